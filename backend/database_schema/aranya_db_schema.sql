@@ -1,6 +1,28 @@
 -- Drop in reverse order due to foreign key dependencies
+DROP MATERIALIZED VIEW IF EXISTS cohort_retention;
+DROP VIEW IF EXISTS quiz_result;
+DROP TABLE IF EXISTS lti_grade_sync;
+DROP TABLE IF EXISTS lti_deep_link;
+DROP TABLE IF EXISTS lti_user;
+DROP TABLE IF EXISTS lti_login_state;
+DROP TABLE IF EXISTS lti_deployment;
+DROP TABLE IF EXISTS lti_platform;
+DROP TABLE IF EXISTS xapi_statement;
+DROP TABLE IF EXISTS badge_assertion;
+DROP TABLE IF EXISTS verifiable_credential;
+DROP TABLE IF EXISTS attestation_job;
+DROP TABLE IF EXISTS attestation_nonce;
+DROP TABLE IF EXISTS attestation_snapshot;
+DROP TABLE IF EXISTS scorm_runtime;
+DROP TABLE IF EXISTS scorm_file;
+DROP TABLE IF EXISTS media;
+DROP TABLE IF EXISTS lesson_video_progress;
+DROP TABLE IF EXISTS learner_heartbeat;
+DROP TABLE IF EXISTS lesson_time;
 DROP TABLE IF EXISTS lesson_completion;
+DROP TABLE IF EXISTS quiz_attempt;
 DROP TABLE IF EXISTS quiz_completion;
+DROP TABLE IF EXISTS quiz_instance;
 DROP TABLE IF EXISTS module_completion;
 DROP TABLE IF EXISTS course_progress;
 DROP TABLE IF EXISTS course_completions;
@@ -9,14 +31,17 @@ DROP TABLE IF EXISTS learner;
 DROP TABLE IF EXISTS creator;
 DROP TABLE IF EXISTS answer_option;
 DROP TABLE IF EXISTS question;
+DROP TABLE IF EXISTS quiz_draw_rule;
 DROP TABLE IF EXISTS quiz;
 DROP TABLE IF EXISTS lesson;
+DROP TABLE IF EXISTS scorm_package;
 DROP TABLE IF EXISTS module;
 DROP TABLE IF EXISTS course;
 
 -- Creator table (must be defined before course)
 CREATE TABLE creator (
-    id TEXT PRIMARY KEY -- Privy ID
+    id TEXT PRIMARY KEY, -- Privy ID
+    created_at TIMESTAMPTZ DEFAULT now()
 );
 
 -- Course table
@@ -26,7 +51,11 @@ CREATE TABLE course (
     description VARCHAR NOT NULL,
     creator_id TEXT NOT NULL REFERENCES creator(id) ON DELETE CASCADE,
     num_learners INT DEFAULT 0,
-    num_completed INT DEFAULT 0
+    num_completed INT DEFAULT 0,
+    creator_nft_address TEXT, -- lowercase hex, set after CourseManager.createCourse
+    learner_nft_address TEXT,
+    nfts_registered_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT now()
 );
 
 
@@ -39,6 +68,16 @@ CREATE TABLE module (
     created_at TIMESTAMPTZ DEFAULT now()
 );
 
+-- Uploaded SCORM 1.2 / 2004 packages, their files are in scorm_file
+CREATE TABLE scorm_package (
+    id BIGSERIAL PRIMARY KEY,
+    title TEXT NOT NULL,
+    scorm_version TEXT NOT NULL, -- 1.2 or 2004
+    launch_path TEXT NOT NULL, -- href of the first SCO with its parameters
+    creator_id TEXT NOT NULL REFERENCES creator(id), -- creator who uploaded the package
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
 -- Lesson table
 CREATE TABLE lesson (
     id BIGSERIAL PRIMARY KEY,
@@ -47,6 +86,9 @@ CREATE TABLE lesson (
     content TEXT,
     video_url TEXT,
     position INT NOT NULL,
+    scorm_package_id BIGINT REFERENCES scorm_package(id), -- SCORM lessons launch the package
+    min_watched_percent DOUBLE PRECISION, -- share of the video to watch before completing, any when NULL
+    video_duration_seconds DOUBLE PRECISION, -- length of the video file, required with min_watched_percent
     created_at TIMESTAMPTZ DEFAULT now()
 );
 
@@ -54,27 +96,56 @@ CREATE TABLE lesson (
 CREATE TABLE quiz (
     id BIGSERIAL PRIMARY KEY,
     module_id BIGINT REFERENCES module(id) ON DELETE CASCADE,
-    course_id BIGINT REFERENCES course(id) ON DELETE CASCADE
+    course_id BIGINT REFERENCES course(id) ON DELETE CASCADE,
+    passing_score_percent DOUBLE PRECISION NOT NULL DEFAULT 70,
+    max_attempts INT, -- unlimited when NULL
+    cooldown_seconds INT NOT NULL DEFAULT 0,
+    scoring_policy TEXT NOT NULL DEFAULT 'best', -- best or latest attempt counts
+    shuffle_answers BOOLEAN NOT NULL DEFAULT TRUE,
+    CHECK ((module_id IS NULL) <> (course_id IS NULL)) -- module quiz or course final exam
+);
+
+-- At most one final exam per course
+CREATE UNIQUE INDEX quiz_final_exam_idx ON quiz (course_id) WHERE course_id IS NOT NULL;
+
+-- Questions drawn from a question bank pool per attempt. Quizzes without rules serve every question
+CREATE TABLE quiz_draw_rule (
+    id BIGSERIAL PRIMARY KEY,
+    quiz_id BIGINT NOT NULL REFERENCES quiz(id) ON DELETE CASCADE,
+    pool TEXT, -- matches question.pool, NULL draws questions without a pool
+    draw_count INT NOT NULL
 );
 
 -- Question table (belongs to quiz)
 CREATE TABLE question (
     id BIGSERIAL PRIMARY KEY,
     quiz_id BIGINT NOT NULL REFERENCES quiz(id) ON DELETE CASCADE,
-    question_text TEXT NOT NULL
+    question_text TEXT NOT NULL,
+    -- single_choice, multiple_choice, true_false, ordering, matching, numeric, short_text
+    question_type TEXT NOT NULL DEFAULT 'single_choice',
+    points DOUBLE PRECISION NOT NULL DEFAULT 1,
+    numeric_answer DOUBLE PRECISION,
+    numeric_tolerance DOUBLE PRECISION NOT NULL DEFAULT 0,
+    case_sensitive BOOLEAN NOT NULL DEFAULT FALSE, -- short_text only
+    pool TEXT -- question bank pool
 );
 
--- Multiple-choice answer options
+-- Answer options. Ordering questions use position as the correct order,
+-- matching questions pair answer_text with match_text, short_text options are accepted answers
 CREATE TABLE answer_option (
     id BIGSERIAL PRIMARY KEY,
     question_id BIGINT NOT NULL REFERENCES question(id) ON DELETE CASCADE,
     answer_text TEXT NOT NULL,
-    is_correct BOOLEAN DEFAULT FALSE
+    is_correct BOOLEAN DEFAULT FALSE,
+    position INT NOT NULL DEFAULT 0,
+    match_text TEXT
 );
 
 -- Learner table
 CREATE TABLE learner (
-    id TEXT PRIMARY KEY -- Privy ID
+    id TEXT PRIMARY KEY, -- Privy ID
+    display_name TEXT, -- printed on certificates, set with a wallet signature
+    created_at TIMESTAMPTZ DEFAULT now()
 );
 
 -- Learner-course many-to-many relationship
@@ -83,10 +154,14 @@ CREATE TABLE learner_course_enrollment (
     course_id BIGINT REFERENCES course(id) ON DELETE CASCADE,
     nft_token_id BIGINT,
     nft_contract_address TEXT,
+    nft_minted_at TIMESTAMPTZ,
     enrolled_at TIMESTAMPTZ DEFAULT now(),
     PRIMARY KEY (learner_id, course_id)
 );
 
+CREATE INDEX learner_course_enrollment_nft_idx
+    ON learner_course_enrollment (nft_contract_address, nft_token_id);
+
 -- Track completed lessons
 CREATE TABLE lesson_completion (
     learner_id TEXT REFERENCES learner(id) ON DELETE CASCADE,
@@ -95,18 +170,74 @@ CREATE TABLE lesson_completion (
     PRIMARY KEY (learner_id, lesson_id)
 );
 
---Track completed quizzes
-CREATE TABLE quiz_completion (
-    id SERIAL PRIMARY KEY,
-    quiz_id BIGINT NOT NULL,
+-- Active time learners spent on lessons, credited from viewer heartbeats
+CREATE TABLE lesson_time (
+    learner_id TEXT REFERENCES learner(id) ON DELETE CASCADE,
+    lesson_id BIGINT REFERENCES lesson(id) ON DELETE CASCADE,
+    active_seconds DOUBLE PRECISION NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (learner_id, lesson_id)
+);
+
+-- Last heartbeat of a learner over all their tabs, so time is only credited once
+CREATE TABLE learner_heartbeat (
+    learner_id TEXT PRIMARY KEY REFERENCES learner(id) ON DELETE CASCADE,
+    last_heartbeat_at TIMESTAMPTZ NOT NULL
+);
+
+-- Playback of lesson videos, the watched intervals are merged and never overlap
+CREATE TABLE lesson_video_progress (
+    learner_id TEXT REFERENCES learner(id) ON DELETE CASCADE,
+    lesson_id BIGINT REFERENCES lesson(id) ON DELETE CASCADE,
+    position_seconds DOUBLE PRECISION NOT NULL DEFAULT 0,
+    duration_seconds DOUBLE PRECISION NOT NULL DEFAULT 0,
+    watched_intervals JSONB NOT NULL DEFAULT '[]', -- [[start, end], ...] in seconds
+    watched_seconds DOUBLE PRECISION NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (learner_id, lesson_id)
+);
+
+-- The questions served for one attempt. Answers are shuffled from the seed
+CREATE TABLE quiz_instance (
+    id BIGSERIAL PRIMARY KEY,
+    quiz_id BIGINT NOT NULL REFERENCES quiz(id) ON DELETE CASCADE,
+    learner_id TEXT NOT NULL,
+    seed BIGINT NOT NULL,
+    question_ids BIGINT[] NOT NULL, -- in the order served
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    submitted_at TIMESTAMPTZ
+);
+
+-- One open instance per learner and quiz
+CREATE UNIQUE INDEX quiz_instance_open_idx
+    ON quiz_instance (quiz_id, learner_id) WHERE submitted_at IS NULL;
+
+-- Every graded quiz submission
+CREATE TABLE quiz_attempt (
+    id BIGSERIAL PRIMARY KEY,
+    quiz_id BIGINT NOT NULL REFERENCES quiz(id) ON DELETE CASCADE,
     learner_id TEXT NOT NULL,
-    score INTEGER,
-    total_questions INTEGER,
-    answers JSONB,
-    completed_at TIMESTAMP DEFAULT now(),
-    UNIQUE(quiz_id, learner_id)
+    instance_id BIGINT NOT NULL UNIQUE REFERENCES quiz_instance(id) ON DELETE CASCADE,
+    attempt_number INT NOT NULL,
+    score DOUBLE PRECISION NOT NULL, -- points earned, with partial credit
+    max_score DOUBLE PRECISION NOT NULL,
+    score_percent DOUBLE PRECISION NOT NULL,
+    total_questions INTEGER NOT NULL,
+    passed BOOLEAN NOT NULL, -- against the passing score at the time of the attempt
+    answers JSONB, -- submitted responses and per question results
+    submitted_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE(quiz_id, learner_id, attempt_number)
 );
 
+-- The attempt that counts for each learner, by the quiz scoring policy
+CREATE VIEW quiz_result AS
+SELECT DISTINCT ON (a.quiz_id, a.learner_id) a.*
+FROM quiz_attempt a
+JOIN quiz q ON q.id = a.quiz_id
+ORDER BY a.quiz_id, a.learner_id,
+    CASE WHEN q.scoring_policy = 'best' THEN a.score_percent END DESC NULLS LAST,
+    a.attempt_number DESC;
+
 -- Track completed modules
 CREATE TABLE module_completion (
     learner_id TEXT REFERENCES learner(id) ON DELETE CASCADE,
@@ -135,4 +266,252 @@ CREATE TABLE course_progress (
     completed BOOLEAN DEFAULT FALSE,
     last_accessed TIMESTAMPTZ DEFAULT now(),
     PRIMARY KEY (learner_id, course_id)
-);
\ No newline at end of file
+);
+
+-- Record of every response served to the FDC verifier, signed with the server key
+CREATE TABLE attestation_snapshot (
+    id BIGSERIAL PRIMARY KEY,
+    endpoint TEXT NOT NULL,
+    request_params JSONB NOT NULL,
+    status_code INT NOT NULL,
+    response_body TEXT NOT NULL,
+    response_hash TEXT NOT NULL, -- hex sha256 of response_body
+    signature TEXT NOT NULL, -- hex ed25519 signature
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- One-time nonces binding an attestation to the wallet that requested it
+CREATE TABLE attestation_nonce (
+    nonce TEXT PRIMARY KEY, -- 0x prefixed bytes32
+    kind TEXT NOT NULL, -- course_creator, enrollment, creator_milestone, learner_milestone
+    course_id BIGINT NOT NULL REFERENCES course(id) ON DELETE CASCADE,
+    wallet TEXT NOT NULL, -- lowercase address that signed the request, learner or course creator
+    learner_id TEXT, -- set for enrollment and learner_milestone
+    milestone INT, -- set for learner_milestone
+    request_signature TEXT NOT NULL UNIQUE, -- wallet signature of the nonce request
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ,
+    used_at TIMESTAMPTZ, -- first query of an attested endpoint with this nonce
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- FDC attestation requests driven by the backend worker, one per proof
+CREATE TABLE attestation_job (
+    id BIGSERIAL PRIMARY KEY,
+    kind TEXT NOT NULL, -- course_creator, enrollment, creator_milestone, learner_milestone
+    course_id BIGINT NOT NULL REFERENCES course(id) ON DELETE CASCADE,
+    learner_id TEXT,
+    milestone INT,
+    nonce TEXT NOT NULL UNIQUE REFERENCES attestation_nonce(nonce) ON DELETE CASCADE,
+    query_params TEXT NOT NULL, -- JSON encoded query params sent by the verifier
+    status TEXT NOT NULL DEFAULT 'pending', -- pending, prepared, submitted, proof_ready, failed
+    abi_encoded_request TEXT,
+    tx_hash TEXT, -- FdcHub request transaction, saved before it is mined
+    round_id BIGINT,
+    proof JSONB, -- {merkleProof, responseHex}
+    attempts INT NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX attestation_job_due_idx ON attestation_job (status, next_attempt_at);
+CREATE INDEX attestation_job_course_idx ON attestation_job (course_id, learner_id);
+
+-- One job in progress per attestation, repeated requests get the running job
+CREATE UNIQUE INDEX attestation_job_in_progress_idx
+    ON attestation_job (kind, course_id, COALESCE(learner_id, ''), COALESCE(milestone, 0))
+    WHERE status IN ('pending', 'prepared', 'submitted');
+
+-- W3C Verifiable Credentials issued on course completion, the id doubles as status list position
+CREATE TABLE verifiable_credential (
+    id BIGSERIAL PRIMARY KEY,
+    learner_id TEXT NOT NULL,
+    course_id BIGINT NOT NULL,
+    jwt TEXT NOT NULL, -- EdDSA signed JWT-VC
+    issued_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    revoked_at TIMESTAMPTZ,
+    revocation_reason TEXT,
+    FOREIGN KEY (learner_id, course_id)
+        REFERENCES course_completion (learner_id, course_id)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX verifiable_credential_learner_idx ON verifiable_credential (learner_id, course_id);
+
+-- At most one active credential per completion, revoked ones are kept for the status list
+CREATE UNIQUE INDEX verifiable_credential_active_idx
+    ON verifiable_credential (learner_id, course_id)
+    WHERE revoked_at IS NULL;
+
+-- Open Badges 3.0 assertions for course completions and creator milestones
+CREATE TABLE badge_assertion (
+    id BIGSERIAL PRIMARY KEY,
+    kind TEXT NOT NULL, -- course_completion, creator_milestone
+    course_id BIGINT NOT NULL REFERENCES course(id) ON DELETE CASCADE,
+    recipient_id TEXT NOT NULL, -- lowercase wallet address
+    milestone INT NOT NULL DEFAULT 0, -- creator milestone, 0 for course completion
+    jwt TEXT NOT NULL, -- EdDSA signed OpenBadgeCredential
+    issued_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (kind, course_id, recipient_id, milestone)
+);
+
+CREATE INDEX badge_assertion_recipient_idx ON badge_assertion (recipient_id);
+
+-- Media imported with course bundles, addressed by content hash and served at /media/{sha256}
+CREATE TABLE media (
+    sha256 TEXT PRIMARY KEY, -- lowercase hex
+    file_name TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    data BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Files of a SCORM package by their path in the package, the content is stored as media
+CREATE TABLE scorm_file (
+    package_id BIGINT NOT NULL REFERENCES scorm_package(id) ON DELETE CASCADE,
+    path TEXT NOT NULL,
+    sha256 TEXT NOT NULL REFERENCES media(sha256),
+    PRIMARY KEY (package_id, path)
+);
+
+-- SCORM data model values (cmi.*) committed by a package for one learner
+CREATE TABLE scorm_runtime (
+    learner_id TEXT REFERENCES learner(id) ON DELETE CASCADE,
+    lesson_id BIGINT REFERENCES lesson(id) ON DELETE CASCADE,
+    cmi JSONB NOT NULL DEFAULT '{}',
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (learner_id, lesson_id)
+);
+
+-- xAPI statements queued for the Learning Record Store. No foreign keys, statements outlive the course
+CREATE TABLE xapi_statement (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(), -- statement id, sent again on retries
+    learner_id TEXT NOT NULL,
+    verb TEXT NOT NULL, -- launched, experienced, completed, passed, failed, scored
+    activity_type TEXT NOT NULL, -- course, lesson, quiz
+    activity_id BIGINT NOT NULL,
+    course_id BIGINT NOT NULL,
+    activity_name TEXT NOT NULL,
+    result JSONB, -- xAPI result of quiz attempts
+    timestamp TIMESTAMPTZ NOT NULL DEFAULT now(),
+    status TEXT NOT NULL DEFAULT 'pending', -- pending, sent, failed
+    attempts INT NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_error TEXT,
+    sent_at TIMESTAMPTZ
+);
+
+CREATE INDEX xapi_statement_due_idx ON xapi_statement (status, next_attempt_at);
+CREATE INDEX xapi_statement_course_idx ON xapi_statement (course_id, learner_id);
+
+-- LTI 1.3 platforms (LMSs) allowed to launch courses, registered with `aranya register-lti-platform`
+CREATE TABLE lti_platform (
+    id BIGSERIAL PRIMARY KEY,
+    issuer TEXT NOT NULL,
+    client_id TEXT NOT NULL, -- the client id the platform assigned to this tool
+    auth_login_url TEXT NOT NULL,
+    auth_token_url TEXT NOT NULL,
+    jwks_url TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (issuer, client_id)
+);
+
+-- Deployments of the tool on a platform, launches from any other are rejected
+CREATE TABLE lti_deployment (
+    platform_id BIGINT NOT NULL REFERENCES lti_platform(id) ON DELETE CASCADE,
+    deployment_id TEXT NOT NULL,
+    PRIMARY KEY (platform_id, deployment_id)
+);
+
+-- Pending OIDC logins, consumed by the launch they start
+CREATE TABLE lti_login_state (
+    state TEXT PRIMARY KEY,
+    nonce TEXT NOT NULL,
+    platform_id BIGINT NOT NULL REFERENCES lti_platform(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Platform users and the learner they are mapped to
+CREATE TABLE lti_user (
+    platform_id BIGINT REFERENCES lti_platform(id) ON DELETE CASCADE,
+    sub TEXT NOT NULL,
+    learner_id TEXT NOT NULL REFERENCES learner(id) ON DELETE CASCADE,
+    PRIMARY KEY (platform_id, sub)
+);
+
+-- Deep linking requests waiting for the instructor to pick a course
+CREATE TABLE lti_deep_link (
+    id TEXT PRIMARY KEY,
+    platform_id BIGINT NOT NULL REFERENCES lti_platform(id) ON DELETE CASCADE,
+    deployment_id TEXT NOT NULL,
+    return_url TEXT NOT NULL,
+    data TEXT, -- opaque value echoed back to the platform
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Gradebook line items fed with the course progress of launched learners
+CREATE TABLE lti_grade_sync (
+    platform_id BIGINT NOT NULL REFERENCES lti_platform(id) ON DELETE CASCADE,
+    lineitem TEXT NOT NULL,
+    sub TEXT NOT NULL,
+    learner_id TEXT NOT NULL REFERENCES learner(id) ON DELETE CASCADE,
+    course_id BIGINT NOT NULL REFERENCES course(id) ON DELETE CASCADE,
+    synced_percent INT, -- last score the platform accepted
+    synced_completed BOOLEAN NOT NULL DEFAULT false,
+    attempts INT NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_error TEXT,
+    synced_at TIMESTAMPTZ,
+    PRIMARY KEY (lineitem, sub)
+);
+
+CREATE INDEX lti_grade_sync_due_idx ON lti_grade_sync (next_attempt_at);
+
+-- Learners grouped by the week they enrolled, with how many were still active and had completed the
+-- course N weeks after enrolling. Only weeks that have elapsed are counted, refreshed by the backend
+CREATE MATERIALIZED VIEW cohort_retention AS
+WITH enrollment_activity AS (
+    SELECT e.course_id, e.enrolled_at, c.completed_at,
+        GREATEST(
+            e.enrolled_at,
+            c.completed_at,
+            (
+                SELECT MAX(lc.completed_at)
+                FROM lesson_completion lc
+                JOIN lesson l ON l.id = lc.lesson_id
+                JOIN module m ON m.id = l.module_id
+                WHERE lc.learner_id = e.learner_id AND m.course_id = e.course_id
+            ),
+            (
+                SELECT MAX(a.submitted_at)
+                FROM quiz_attempt a
+                JOIN quiz q ON q.id = a.quiz_id
+                LEFT JOIN module m ON m.id = q.module_id
+                WHERE a.learner_id = e.learner_id AND COALESCE(q.course_id, m.course_id) = e.course_id
+            )
+        ) AS last_active_at
+    FROM learner_course_enrollment e
+    LEFT JOIN course_completion c ON c.learner_id = e.learner_id AND c.course_id = e.course_id
+    WHERE e.enrolled_at IS NOT NULL
+)
+SELECT course_id,
+    date_trunc('week', enrolled_at)::DATE AS cohort_week,
+    w.week,
+    COUNT(*) AS learners,
+    COUNT(*) FILTER (WHERE last_active_at >= enrolled_at + w.week * INTERVAL '1 week') AS active,
+    COUNT(*) FILTER (WHERE completed_at <= enrolled_at + w.week * INTERVAL '1 week') AS completed,
+    now() AS refreshed_at
+FROM enrollment_activity
+CROSS JOIN generate_series(0, 12) AS w(week)
+WHERE enrolled_at + w.week * INTERVAL '1 week' <= now()
+GROUP BY course_id, cohort_week, w.week;
+
+CREATE UNIQUE INDEX cohort_retention_idx ON cohort_retention (course_id, cohort_week, week);
+
+-- Signed creator requests already accepted, each is accepted once
+CREATE TABLE creator_request (
+    message TEXT PRIMARY KEY, -- the signed message, naming the endpoint and time of the request
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,439 @@
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Cursor, Read, Write};
+use zip::{CompressionMethod, ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use crate::models::bundle::BundleManifest;
+use crate::models::course::{CreateCoursePayload, CreateLessonPayload, CreateModulePayload};
+
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+pub const MANIFEST_FILE: &str = "course.json";
+
+// Largest bundle accepted for import, compressed and unpacked
+pub const MAX_BUNDLE_BYTES: usize = 100 * 1024 * 1024;
+const MAX_UNPACKED_BYTES: u64 = 500 * 1024 * 1024;
+// Media lessons may embed. Types a browser would run, such as HTML or SVG, are refused
+const MEDIA_CONTENT_TYPES: [&str; 13] = [
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "video/mp4",
+    "video/webm",
+    "video/ogg",
+    "video/quicktime",
+    "audio/mpeg",
+    "audio/ogg",
+    "audio/wav",
+    "audio/webm",
+    "application/pdf",
+];
+
+/// Manifest and files of a bundle, by their path in the bundle.
+pub struct Bundle {
+    pub manifest: BundleManifest,
+    pub files: BTreeMap<String, Vec<u8>>,
+}
+
+/// A media file of an imported bundle, stored by its content hash.
+pub struct MediaFile {
+    pub sha256: String,
+    pub file_name: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Writes the manifest and the content and media files as a bundle zip.
+pub fn write_bundle(bundle: &Bundle) -> Result<Vec<u8>, String> {
+    let manifest = serde_json::to_vec_pretty(&bundle.manifest).map_err(|e| e.to_string())?;
+    write_zip(
+        std::iter::once((MANIFEST_FILE, manifest.as_slice()))
+            .chain(bundle.files.iter().map(|(p, d)| (p.as_str(), d.as_slice()))),
+    )
+}
+
+/// Reads a bundle zip, rejecting format versions this backend doesn't know.
+pub fn read_bundle(bytes: &[u8]) -> Result<Bundle, String> {
+    let mut files = read_zip(bytes)?;
+
+    let manifest = files
+        .remove(MANIFEST_FILE)
+        .ok_or_else(|| format!("Bundle has no {}", MANIFEST_FILE))?;
+    let manifest: BundleManifest = serde_json::from_slice(&manifest)
+        .map_err(|e| format!("{} is not a valid manifest: {}", MANIFEST_FILE, e))?;
+    if manifest.format_version != BUNDLE_FORMAT_VERSION {
+        return Err(format!(
+            "Bundle format version {} is not supported, expected {}",
+            manifest.format_version, BUNDLE_FORMAT_VERSION
+        ));
+    }
+
+    Ok(Bundle { manifest, files })
+}
+
+pub fn write_zip<'a>(files: impl Iterator<Item = (&'a str, &'a [u8])>) -> Result<Vec<u8>, String> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (path, data) in files {
+        zip.start_file(path, options).map_err(|e| e.to_string())?;
+        zip.write_all(data).map_err(|e| e.to_string())?;
+    }
+
+    Ok(zip.finish().map_err(|e| e.to_string())?.into_inner())
+}
+
+/// Files of a zip by path, up to the unpacked size limit.
+pub fn read_zip(bytes: &[u8]) -> Result<BTreeMap<String, Vec<u8>>, String> {
+    read_zip_within(bytes, MAX_UNPACKED_BYTES)
+}
+
+fn read_zip_within(bytes: &[u8], max_unpacked: u64) -> Result<BTreeMap<String, Vec<u8>>, String> {
+    let mut archive =
+        ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("Not a zip file: {}", e))?;
+
+    let mut unpacked = 0;
+    let mut files = BTreeMap::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).map_err(|e| e.to_string())?;
+        if file.is_dir() {
+            continue;
+        }
+        if file.enclosed_name().is_none() {
+            return Err(format!("Zip entry {} has an unsafe path", file.name()));
+        }
+        // The declared size can't be trusted, reading stops past the limit
+        let remaining = max_unpacked - unpacked;
+        if file.size() > remaining {
+            return Err("Zip file is too large".to_string());
+        }
+        let mut data = Vec::new();
+        (&mut file)
+            .take(remaining + 1)
+            .read_to_end(&mut data)
+            .map_err(|e| e.to_string())?;
+        unpacked += data.len() as u64;
+        if unpacked > max_unpacked {
+            return Err("Zip file is too large".to_string());
+        }
+        files.insert(file.name().to_string(), data);
+    }
+
+    Ok(files)
+}
+
+/// Checks every file the manifest refers to and builds the payload of the
+/// course. Media paths in lesson content and video URLs are replaced by the
+/// URL the media is served at.
+pub fn bundle_course(
+    bundle: Bundle,
+    creator_id: &str,
+    media_url: impl Fn(&str) -> String,
+) -> Result<(CreateCoursePayload, Vec<MediaFile>), String> {
+    let Bundle {
+        manifest,
+        mut files,
+    } = bundle;
+
+    if manifest.title.trim().is_empty() {
+        return Err("Course title can't be empty".to_string());
+    }
+
+    let mut media = Vec::new();
+    for entry in &manifest.media {
+        let data = files
+            .remove(&entry.path)
+            .ok_or_else(|| format!("Media file {} is missing", entry.path))?;
+        let sha256 = hex::encode(Sha256::digest(&data));
+        if sha256 != entry.sha256.to_lowercase() {
+            return Err(format!("Media file {} does not match its hash", entry.path));
+        }
+        if !is_media_content_type(&entry.content_type) {
+            return Err(format!(
+                "Media file {} has unsupported content type {}",
+                entry.path, entry.content_type
+            ));
+        }
+        // Exported paths start with the hash, the stored name is the original one
+        let file_name = entry.path.rsplit('/').next().unwrap_or_default();
+        let file_name = file_name
+            .strip_prefix(&format!("{}-", &sha256[..12]))
+            .unwrap_or(file_name)
+            .to_string();
+        media.push(MediaFile {
+            sha256,
+            file_name,
+            content_type: entry.content_type.clone(),
+            data,
+        });
+    }
+
+    // Longest paths first, so no path is replaced inside a longer one
+    let mut replacements: Vec<(&str, String)> = manifest
+        .media
+        .iter()
+        .zip(&media)
+        .map(|(entry, file)| (entry.path.as_str(), media_url(&file.sha256)))
+        .collect();
+    replacements.sort_by_key(|(path, _)| std::cmp::Reverse(path.len()));
+    let resolve = |text: String| {
+        replacements
+            .iter()
+            .fold(text, |text, (path, url)| text.replace(path, url))
+    };
+
+    let mut module_positions = HashSet::new();
+    let mut modules = Vec::new();
+    for module in manifest.modules {
+        if !module_positions.insert(module.position) {
+            return Err(format!(
+                "More than one module at position {}",
+                module.position
+            ));
+        }
+
+        let mut lesson_positions = HashSet::new();
+        let mut lessons = Vec::new();
+        for lesson in module.lessons {
+            if !lesson_positions.insert(lesson.position) {
+                return Err(format!(
+                    "Module \"{}\" has more than one lesson at position {}",
+                    module.title, lesson.position
+                ));
+            }
+            let content = files
+                .get(&lesson.content_file)
+                .ok_or_else(|| format!("Lesson content file {} is missing", lesson.content_file))?;
+            let content = String::from_utf8(content.clone())
+                .map_err(|_| format!("Lesson content file {} is not UTF-8", lesson.content_file))?;
+
+            lessons.push(CreateLessonPayload {
+                title: lesson.title,
+                content: resolve(content),
+                video_url: lesson.video_url.map(resolve),
+                position: lesson.position,
+                scorm_package_id: lesson.scorm_package_id,
+                min_watched_percent: lesson.min_watched_percent,
+                video_duration_seconds: lesson.video_duration_seconds,
+            });
+        }
+
+        modules.push(CreateModulePayload {
+            title: module.title,
+            position: module.position,
+            lessons,
+            quiz: module.quiz,
+        });
+    }
+
+    let payload = CreateCoursePayload {
+        title: manifest.title,
+        description: manifest.description,
+        creator_id: creator_id.to_string(),
+        modules,
+        final_exam: manifest.final_exam,
+    };
+
+    Ok((payload, media))
+}
+
+/// Content hashes of the media served at `media_prefix` that a text links to.
+pub fn media_refs(text: &str, media_prefix: &str) -> Vec<String> {
+    text.match_indices(media_prefix)
+        .filter_map(|(i, _)| text.get(i + media_prefix.len()..i + media_prefix.len() + 64))
+        .filter(|hash| hash.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Path of a media file in the bundle, unique by its hash.
+pub fn media_path(sha256: &str, file_name: &str) -> String {
+    let file_name: String = file_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("media/{}-{}", &sha256[..12], file_name)
+}
+
+/// Whether media of this content type may be imported and served.
+pub fn is_media_content_type(content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    MEDIA_CONTENT_TYPES
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(essence))
+}
+
+/// Content type of a media file by its extension.
+pub fn content_type(path: &str) -> &'static str {
+    let extension = path.rsplit('.').next().unwrap_or_default().to_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "js" => "text/javascript",
+        "css" => "text/css",
+        "json" => "application/json",
+        "xml" | "xsd" => "application/xml",
+        "txt" => "text/plain; charset=utf-8",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "ogv" => "video/ogg",
+        "ogg" => "audio/ogg",
+        "mov" => "video/quicktime",
+        "m4v" => "video/mp4",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::sample_bundle;
+
+    fn media_url(sha256: &str) -> String {
+        format!("https://api.aranya.test/media/{}", sha256)
+    }
+
+    #[test]
+    fn export_then_import_keeps_the_course() {
+        let bundle = sample_bundle();
+        let diagram = bundle.manifest.media[0].sha256.clone();
+        let video = bundle.manifest.media[1].sha256.clone();
+
+        let bytes = write_bundle(&bundle).unwrap();
+        let imported = read_bundle(&bytes).unwrap();
+        assert_eq!(imported.files, bundle.files);
+        assert_eq!(
+            serde_json::to_value(&imported.manifest).unwrap(),
+            serde_json::to_value(&bundle.manifest).unwrap()
+        );
+
+        let (payload, media) = bundle_course(imported, "0xcreator", media_url).unwrap();
+        assert_eq!(payload.title, "Rust basics");
+        assert_eq!(payload.creator_id, "0xcreator");
+        assert_eq!(payload.modules.len(), 2);
+        assert!(payload.final_exam.is_some());
+
+        let installing = &payload.modules[0].lessons[1];
+        assert_eq!(
+            installing.content,
+            format!("![Diagram]({})\n\nRun `rustup`.", media_url(&diagram))
+        );
+        assert_eq!(installing.video_url, Some(media_url(&video)));
+        assert_eq!(installing.min_watched_percent, Some(80.0));
+        assert_eq!(installing.video_duration_seconds, Some(12.5));
+
+        let names: Vec<(&str, &str)> = media
+            .iter()
+            .map(|m| (m.file_name.as_str(), m.content_type.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            [("diagram.png", "image/png"), ("intro.mp4", "video/mp4")]
+        );
+        assert_eq!(media[0].data, bundle.files[&bundle.manifest.media[0].path]);
+    }
+
+    #[test]
+    fn malformed_archives_are_rejected() {
+        let manifest = serde_json::to_vec(&sample_bundle().manifest).unwrap();
+        for (files, error) in [
+            (
+                vec![("../../etc/cron.d/job", b"* * * * *".as_slice())],
+                "Zip entry ../../etc/cron.d/job has an unsafe path",
+            ),
+            (
+                vec![("/etc/passwd", b"root".as_slice())],
+                "Zip entry /etc/passwd has an unsafe path",
+            ),
+            (
+                vec![("lessons/1.md", b"# Welcome".as_slice())],
+                "Bundle has no course.json",
+            ),
+            (
+                vec![(MANIFEST_FILE, b"{\"title\": 1}".as_slice())],
+                "course.json is not a valid manifest",
+            ),
+        ] {
+            let e = write_zip(files.into_iter())
+                .and_then(|bytes| read_bundle(&bytes))
+                .err()
+                .unwrap();
+            assert!(e.starts_with(error), "{}", e);
+        }
+
+        let e = read_bundle(b"PK not a zip").err().unwrap();
+        assert!(e.starts_with("Not a zip file"), "{}", e);
+
+        let mut future = sample_bundle().manifest;
+        future.format_version = BUNDLE_FORMAT_VERSION + 1;
+        let future = serde_json::to_vec(&future).unwrap();
+        let e = read_bundle(&write_zip([(MANIFEST_FILE, future.as_slice())].into_iter()).unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(e, "Bundle format version 2 is not supported, expected 1");
+
+        // Every entry counts towards the unpacked size
+        let bytes = write_zip(
+            [
+                (MANIFEST_FILE, manifest.as_slice()),
+                ("lessons/1.md", [b'a'; 600].as_slice()),
+                ("lessons/2.md", [b'b'; 600].as_slice()),
+            ]
+            .into_iter(),
+        )
+        .unwrap();
+        let limit = manifest.len() as u64 + 1000;
+        assert_eq!(
+            read_zip_within(&bytes, limit).err().unwrap(),
+            "Zip file is too large"
+        );
+        assert!(read_zip_within(&bytes, limit + 200).is_ok());
+    }
+
+    #[test]
+    fn media_must_match_the_manifest() {
+        let mut tampered = sample_bundle();
+        let path = tampered.manifest.media[0].path.clone();
+        tampered.files.insert(path.clone(), b"other".to_vec());
+        let e = bundle_course(tampered, "0xcreator", media_url)
+            .err()
+            .unwrap();
+        assert_eq!(e, format!("Media file {} does not match its hash", path));
+
+        let mut missing = sample_bundle();
+        missing.files.remove(&path);
+        let e = bundle_course(missing, "0xcreator", media_url)
+            .err()
+            .unwrap();
+        assert_eq!(e, format!("Media file {} is missing", path));
+
+        let mut html = sample_bundle();
+        html.manifest.media[0].content_type = "text/html".to_string();
+        let e = bundle_course(html, "0xcreator", media_url).err().unwrap();
+        assert_eq!(
+            e,
+            format!("Media file {} has unsupported content type text/html", path)
+        );
+
+        let mut lesson = sample_bundle();
+        lesson.files.remove("lessons/3.md");
+        let e = bundle_course(lesson, "0xcreator", media_url).err().unwrap();
+        assert_eq!(e, "Lesson content file lessons/3.md is missing");
+    }
+}
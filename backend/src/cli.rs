@@ -0,0 +1,283 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+
+use crate::bundle::{read_bundle, read_zip, write_bundle};
+use crate::common_cartridge::write_cartridge;
+use crate::handlers::bundle::{fetch_course_bundle, import_course_bundle};
+use crate::markdown_course::{read_markdown_course, write_markdown_course};
+use crate::state::AppState;
+
+const USAGE: &str = "Usage:
+  aranya                                                  start the server
+  aranya export-course <course id> <bundle.zip>           export a course bundle
+  aranya import-course <bundle.zip> <creator id> [--dry-run]
+                                                          import a course bundle
+  aranya export-markdown <course id> <directory>          export a course as Markdown files
+  aranya import-markdown <directory|zip> <creator id> [--dry-run]
+                                                          import a course from Markdown files
+  aranya export-cartridge <course id> <course.imscc>      export a course as a Common Cartridge
+  aranya register-lti-platform <issuer> <client id> <auth login url> <token url> <jwks url> <deployment id>...
+                                                          allow an LTI 1.3 platform to launch courses";
+
+/// Commands run instead of the server.
+#[derive(Debug)]
+pub enum Command {
+    ExportCourse {
+        course_id: i64,
+        path: String,
+    },
+    ImportCourse {
+        path: String,
+        creator_id: String,
+        dry_run: bool,
+    },
+    ExportMarkdown {
+        course_id: i64,
+        directory: String,
+    },
+    ImportMarkdown {
+        path: String,
+        creator_id: String,
+        dry_run: bool,
+    },
+    ExportCartridge {
+        course_id: i64,
+        path: String,
+    },
+    RegisterLtiPlatform {
+        issuer: String,
+        client_id: String,
+        auth_login_url: String,
+        auth_token_url: String,
+        jwks_url: String,
+        deployment_ids: Vec<String>,
+    },
+}
+
+/// Parses the command line arguments after the program name, `None` starts
+/// the server.
+pub fn parse_args(args: &[String]) -> Result<Option<Command>, String> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        [] => Ok(None),
+        ["export-course", course_id, path] => Ok(Some(Command::ExportCourse {
+            course_id: course_id
+                .parse()
+                .map_err(|_| format!("{} is not a course id\n{}", course_id, USAGE))?,
+            path: path.to_string(),
+        })),
+        ["import-course", path, creator_id, rest @ ..]
+            if rest.is_empty() || rest == ["--dry-run"] =>
+        {
+            Ok(Some(Command::ImportCourse {
+                path: path.to_string(),
+                creator_id: creator_id.to_string(),
+                dry_run: !rest.is_empty(),
+            }))
+        }
+        ["export-markdown", course_id, directory] => Ok(Some(Command::ExportMarkdown {
+            course_id: course_id
+                .parse()
+                .map_err(|_| format!("{} is not a course id\n{}", course_id, USAGE))?,
+            directory: directory.to_string(),
+        })),
+        ["import-markdown", path, creator_id, rest @ ..]
+            if rest.is_empty() || rest == ["--dry-run"] =>
+        {
+            Ok(Some(Command::ImportMarkdown {
+                path: path.to_string(),
+                creator_id: creator_id.to_string(),
+                dry_run: !rest.is_empty(),
+            }))
+        }
+        ["export-cartridge", course_id, path] => Ok(Some(Command::ExportCartridge {
+            course_id: course_id
+                .parse()
+                .map_err(|_| format!("{} is not a course id\n{}", course_id, USAGE))?,
+            path: path.to_string(),
+        })),
+        [
+            "register-lti-platform",
+            issuer,
+            client_id,
+            auth_login_url,
+            auth_token_url,
+            jwks_url,
+            deployment_ids @ ..,
+        ] if !deployment_ids.is_empty() => Ok(Some(Command::RegisterLtiPlatform {
+            issuer: issuer.to_string(),
+            client_id: client_id.to_string(),
+            auth_login_url: auth_login_url.to_string(),
+            auth_token_url: auth_token_url.to_string(),
+            jwks_url: jwks_url.to_string(),
+            deployment_ids: deployment_ids.iter().map(|id| id.to_string()).collect(),
+        })),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+pub async fn run(command: Command, state: &AppState) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::ExportCourse { course_id, path } => {
+            let bundle = fetch_course_bundle(state, course_id)
+                .await
+                .map_err(|(_, e)| e)?;
+            let bundle = write_bundle(&bundle)?;
+            std::fs::write(&path, &bundle)?;
+            println!(
+                "Exported course {} to {} ({} bytes)",
+                course_id,
+                path,
+                bundle.len()
+            );
+        }
+        Command::ImportCourse {
+            path,
+            creator_id,
+            dry_run,
+        } => {
+            let bundle = read_bundle(&std::fs::read(&path)?)?;
+            let imported = import_course_bundle(state, bundle, &creator_id, dry_run)
+                .await
+                .map_err(|(_, e)| e)?;
+            println!("{}", serde_json::to_string_pretty(&imported)?);
+        }
+        Command::ExportMarkdown {
+            course_id,
+            directory,
+        } => {
+            let directory = Path::new(&directory);
+            if directory.exists() && directory.read_dir()?.next().is_some() {
+                return Err(format!("{} is not empty", directory.display()).into());
+            }
+            let bundle = fetch_course_bundle(state, course_id)
+                .await
+                .map_err(|(_, e)| e)?;
+            let files = write_markdown_course(bundle)?;
+            for (path, data) in &files {
+                let path = directory.join(path);
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(path, data)?;
+            }
+            println!(
+                "Exported course {} to {} ({} files)",
+                course_id,
+                directory.display(),
+                files.len()
+            );
+        }
+        Command::ImportMarkdown {
+            path,
+            creator_id,
+            dry_run,
+        } => {
+            let path = Path::new(&path);
+            let files = if path.is_dir() {
+                let mut files = BTreeMap::new();
+                read_directory(path, path, &mut files)?;
+                files
+            } else {
+                read_zip(&std::fs::read(path)?)?
+            };
+            let bundle = read_markdown_course(files)?;
+            let imported = import_course_bundle(state, bundle, &creator_id, dry_run)
+                .await
+                .map_err(|(_, e)| e)?;
+            println!("{}", serde_json::to_string_pretty(&imported)?);
+        }
+        Command::ExportCartridge { course_id, path } => {
+            let bundle = fetch_course_bundle(state, course_id)
+                .await
+                .map_err(|(_, e)| e)?;
+            let (package, issues) = write_cartridge(&bundle)?;
+            std::fs::write(&path, &package)?;
+            for issue in &issues {
+                let action = if issue.skipped { "skipped" } else { "note" };
+                println!("{} ({}): {}", issue.item, action, issue.message);
+            }
+            println!(
+                "Exported course {} to {} ({} bytes)",
+                course_id,
+                path,
+                package.len()
+            );
+        }
+        Command::RegisterLtiPlatform {
+            issuer,
+            client_id,
+            auth_login_url,
+            auth_token_url,
+            jwks_url,
+            deployment_ids,
+        } => {
+            let id: i64 = sqlx::query_scalar(
+                r#"
+                INSERT INTO lti_platform (issuer, client_id, auth_login_url, auth_token_url, jwks_url)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (issuer, client_id) DO UPDATE
+                SET auth_login_url = EXCLUDED.auth_login_url,
+                    auth_token_url = EXCLUDED.auth_token_url,
+                    jwks_url = EXCLUDED.jwks_url
+                RETURNING id
+                "#,
+            )
+            .bind(&issuer)
+            .bind(&client_id)
+            .bind(&auth_login_url)
+            .bind(&auth_token_url)
+            .bind(&jwks_url)
+            .fetch_one(&state.pool)
+            .await?;
+            for deployment_id in &deployment_ids {
+                sqlx::query(
+                    "INSERT INTO lti_deployment (platform_id, deployment_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+                )
+                .bind(id)
+                .bind(deployment_id)
+                .execute(&state.pool)
+                .await?;
+            }
+            println!("Registered LTI platform {} ({} / {})", id, issuer, client_id);
+            println!("  Deployments: {}", deployment_ids.join(", "));
+            println!("Configure the tool on the platform with:");
+            println!("  Login initiation URL: {}", state.public_url("/lti/login"));
+            println!("  Redirect / launch URL: {}", state.public_url("/lti/launch"));
+            println!("  Deep linking URL: {}", state.public_url("/lti/launch"));
+            println!("  Public keyset URL: {}", state.public_url("/lti/jwks"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Files under a directory by their path relative to `root`, leaving out
+/// hidden ones like `.git`.
+fn read_directory(
+    root: &Path,
+    directory: &Path,
+    files: &mut BTreeMap<String, Vec<u8>>,
+) -> Result<(), Box<dyn Error>> {
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        {
+            continue;
+        }
+        if path.is_dir() {
+            read_directory(root, &path, files)?;
+        } else {
+            let relative = path
+                .strip_prefix(root)?
+                .to_string_lossy()
+                .replace('\\', "/");
+            files.insert(relative, std::fs::read(&path)?);
+        }
+    }
+
+    Ok(())
+}
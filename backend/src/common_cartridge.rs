@@ -0,0 +1,716 @@
+use pulldown_cmark::{Options, Parser, html};
+
+use crate::bundle::{Bundle, write_zip};
+use crate::models::bundle::{BundleLesson, BundleModule};
+use crate::models::course::{CreateQuestionPayload, CreateQuizPayload, QuestionType};
+use crate::models::quiz_format::FormatIssue;
+use crate::quiz_format::Issues;
+use crate::quiz_format::qti::{XmlWriter, escape_xml};
+
+const MANIFEST_FILE: &str = "imsmanifest.xml";
+const WEB_RESOURCES: &str = "web_resources/";
+const ASSESSMENT_TYPE: &str = "imsqti_xmlv1p2/imscc_xmlv1p3/assessment";
+const CP_NS: &str = "http://www.imsglobal.org/xsd/imsccv1p3/imscp_v1p1";
+const LOM_NS: &str = "http://ltsc.ieee.org/xsd/imsccv1p3/LOM/manifest";
+const QTI_NS: &str = "http://www.imsglobal.org/xsd/ims_qtiasiv1p2";
+const XSI_NS: &str = "http://www.w3.org/2001/XMLSchema-instance";
+const RESPONSE: &str = "response1";
+
+/// A resource of the cartridge manifest.
+struct Resource {
+    identifier: String,
+    kind: &'static str,
+    // Launched file, only for web content
+    href: Option<String>,
+    files: Vec<String>,
+    dependencies: Vec<String>,
+}
+
+/// An entry of the table of contents, folders have no resource.
+struct Item {
+    identifier: String,
+    title: String,
+    resource: Option<String>,
+    children: Vec<Item>,
+}
+
+/// Writes a course bundle as an IMS Common Cartridge 1.3 package: a folder
+/// per module with a web content page per lesson and a QTI 1.2 assessment
+/// per quiz, then the final exam. Questions and settings Common Cartridge
+/// has no equivalent for are reported as issues.
+pub fn write_cartridge(bundle: &Bundle) -> Result<(Vec<u8>, Vec<FormatIssue>), String> {
+    let manifest = &bundle.manifest;
+    let mut issues = Issues::default();
+    let mut files: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut resources = Vec::new();
+
+    let mut media = Vec::new();
+    for (i, entry) in manifest.media.iter().enumerate() {
+        let data = bundle
+            .files
+            .get(&entry.path)
+            .ok_or_else(|| format!("Media file {} is missing", entry.path))?;
+        let href = format!("{}{}", WEB_RESOURCES, entry.path);
+        let identifier = format!("RES_MEDIA_{}", i + 1);
+        files.push((href.clone(), data.clone()));
+        resources.push(Resource {
+            identifier: identifier.clone(),
+            kind: "webcontent",
+            href: Some(href.clone()),
+            files: vec![href],
+            dependencies: Vec::new(),
+        });
+        media.push((entry.path.as_str(), identifier));
+    }
+    // Longest paths first, so no path is replaced inside a longer one
+    media.sort_by_key(|(path, _)| std::cmp::Reverse(path.len()));
+
+    let mut modules: Vec<&BundleModule> = manifest.modules.iter().collect();
+    modules.sort_by_key(|m| m.position);
+
+    let mut items = Vec::new();
+    let mut lesson_count = 0;
+    let mut quiz_count = 0;
+    for (m, module) in modules.into_iter().enumerate() {
+        let mut children = Vec::new();
+
+        let mut lessons: Vec<&BundleLesson> = module.lessons.iter().collect();
+        lessons.sort_by_key(|l| l.position);
+        for lesson in lessons {
+            lesson_count += 1;
+            let content = bundle
+                .files
+                .get(&lesson.content_file)
+                .ok_or_else(|| format!("Lesson content file {} is missing", lesson.content_file))?;
+            let content = std::str::from_utf8(content)
+                .map_err(|_| format!("Lesson content file {} is not UTF-8", lesson.content_file))?;
+            if lesson.scorm_package_id.is_some() {
+                issues.note(
+                    &lesson.title,
+                    "Lesson launches a SCORM package, only its text is exported",
+                );
+            }
+
+            // Lesson pages are one folder down from the web resources
+            let linked = |text: &str| {
+                media.iter().fold(text.to_string(), |text, (path, _)| {
+                    text.replace(path, &format!("../{}{}", WEB_RESOURCES, path))
+                })
+            };
+            let dependencies = media
+                .iter()
+                .filter(|(path, _)| {
+                    content.contains(path)
+                        || lesson
+                            .video_url
+                            .as_deref()
+                            .is_some_and(|v| v.contains(path))
+                })
+                .map(|(_, identifier)| identifier.clone())
+                .collect();
+
+            let identifier = format!("RES_L{}", lesson_count);
+            let href = format!("lessons/L{}.html", lesson_count);
+            let page = lesson_page(
+                &lesson.title,
+                lesson.video_url.as_deref().map(linked).as_deref(),
+                &linked(content),
+            );
+            files.push((href.clone(), page.into_bytes()));
+            resources.push(Resource {
+                identifier: identifier.clone(),
+                kind: "webcontent",
+                href: Some(href.clone()),
+                files: vec![href],
+                dependencies,
+            });
+            children.push(Item {
+                identifier: format!("ITEM_L{}", lesson_count),
+                title: lesson.title.clone(),
+                resource: Some(identifier),
+                children: Vec::new(),
+            });
+        }
+
+        if let Some(quiz) = &module.quiz {
+            quiz_count += 1;
+            let title = format!("{} quiz", module.title);
+            children.push(add_assessment(
+                quiz,
+                quiz_count,
+                &title,
+                &mut files,
+                &mut resources,
+                &mut issues,
+            ));
+        }
+
+        items.push(Item {
+            identifier: format!("ITEM_M{}", m + 1),
+            title: module.title.clone(),
+            resource: None,
+            children,
+        });
+    }
+
+    if let Some(exam) = &manifest.final_exam {
+        quiz_count += 1;
+        items.push(add_assessment(
+            exam,
+            quiz_count,
+            "Final exam",
+            &mut files,
+            &mut resources,
+            &mut issues,
+        ));
+    }
+
+    let source_id = manifest
+        .source_course_id
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| "IMPORT".to_string());
+    let manifest_xml = write_manifest(
+        &format!("ARANYA_COURSE_{}", source_id),
+        &manifest.title,
+        &manifest.description,
+        &items,
+        &resources,
+    );
+
+    let zip = write_zip(
+        std::iter::once((MANIFEST_FILE, manifest_xml.as_bytes())).chain(
+            files
+                .iter()
+                .map(|(path, data)| (path.as_str(), data.as_slice())),
+        ),
+    )?;
+
+    Ok((zip, issues.into_vec()))
+}
+
+fn add_assessment(
+    quiz: &CreateQuizPayload,
+    number: usize,
+    title: &str,
+    files: &mut Vec<(String, Vec<u8>)>,
+    resources: &mut Vec<Resource>,
+    issues: &mut Issues,
+) -> Item {
+    let identifier = format!("RES_Q{}", number);
+    let href = format!("{}/assessment.xml", identifier);
+    let xml = write_assessment(quiz, &identifier, title, issues);
+    files.push((href.clone(), xml.into_bytes()));
+    resources.push(Resource {
+        identifier: identifier.clone(),
+        kind: ASSESSMENT_TYPE,
+        href: None,
+        files: vec![href],
+        dependencies: Vec::new(),
+    });
+
+    Item {
+        identifier: format!("ITEM_Q{}", number),
+        title: title.to_string(),
+        resource: Some(identifier),
+        children: Vec::new(),
+    }
+}
+
+fn write_manifest(
+    identifier: &str,
+    title: &str,
+    description: &str,
+    items: &[Item],
+    resources: &[Resource],
+) -> String {
+    let mut xml = XmlWriter::new(None);
+    let schema_location = format!(
+        "{} http://www.imsglobal.org/profile/cc/ccv1p3/ccv1p3_imscp_v1p2_v1p0.xsd {} http://www.imsglobal.org/profile/cc/ccv1p3/LOM/ccv1p3_lommanifest_v1p0.xsd",
+        CP_NS, LOM_NS
+    );
+    xml.open(
+        "manifest",
+        &[
+            ("identifier", identifier),
+            ("xmlns", CP_NS),
+            ("xmlns:lomimscc", LOM_NS),
+            ("xmlns:xsi", XSI_NS),
+            ("xsi:schemaLocation", &schema_location),
+        ],
+    );
+
+    xml.open("metadata", &[]);
+    xml.leaf("schema", &[], "IMS Common Cartridge");
+    xml.leaf("schemaversion", &[], "1.3.0");
+    xml.open("lomimscc:lom", &[]);
+    xml.open("lomimscc:general", &[]);
+    xml.open("lomimscc:title", &[]);
+    xml.leaf("lomimscc:string", &[], title);
+    xml.close();
+    xml.open("lomimscc:description", &[]);
+    xml.leaf("lomimscc:string", &[], description);
+    xml.close();
+    xml.close();
+    xml.close();
+    xml.close();
+
+    xml.open("organizations", &[]);
+    xml.open(
+        "organization",
+        &[("identifier", "ORG"), ("structure", "rooted-hierarchy")],
+    );
+    xml.open("item", &[("identifier", "ROOT")]);
+    for item in items {
+        write_item_tree(&mut xml, item);
+    }
+    xml.close();
+    xml.close();
+    xml.close();
+
+    xml.open("resources", &[]);
+    for resource in resources {
+        let mut attrs = vec![
+            ("identifier", resource.identifier.as_str()),
+            ("type", resource.kind),
+        ];
+        if let Some(href) = &resource.href {
+            attrs.push(("href", href));
+        }
+        xml.open("resource", &attrs);
+        for file in &resource.files {
+            xml.empty("file", &[("href", file)]);
+        }
+        for dependency in &resource.dependencies {
+            xml.empty("dependency", &[("identifierref", dependency)]);
+        }
+        xml.close();
+    }
+    xml.close();
+
+    xml.close();
+    xml.finish()
+}
+
+fn write_item_tree(xml: &mut XmlWriter, item: &Item) {
+    let mut attrs = vec![("identifier", item.identifier.as_str())];
+    if let Some(resource) = &item.resource {
+        attrs.push(("identifierref", resource));
+    }
+    xml.open("item", &attrs);
+    xml.leaf("title", &[], &item.title);
+    for child in &item.children {
+        write_item_tree(xml, child);
+    }
+    xml.close();
+}
+
+/// A lesson as a standalone HTML page, Markdown content rendered.
+fn lesson_page(title: &str, video_url: Option<&str>, content: &str) -> String {
+    let mut body = String::new();
+    html::push_html(&mut body, Parser::new_ext(content, Options::all()));
+
+    let video = match video_url {
+        // Media exported with the course plays in place, other videos are embeds
+        Some(url) if url.starts_with("../") => format!(
+            "<p><video controls src=\"{}\" width=\"640\"></video></p>\n",
+            escape_xml(url)
+        ),
+        Some(url) => format!(
+            "<p><iframe src=\"{}\" width=\"640\" height=\"360\" allowfullscreen></iframe></p>\n",
+            escape_xml(url)
+        ),
+        None => String::new(),
+    };
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>\n{video}{body}</body>\n</html>\n",
+        title = escape_xml(title),
+        video = video,
+        body = body
+    )
+}
+
+/// A quiz in the QTI 1.2 profile of Common Cartridge. It has no ordering,
+/// matching or numeric questions with a tolerance, those are skipped.
+fn write_assessment(
+    quiz: &CreateQuizPayload,
+    identifier: &str,
+    title: &str,
+    issues: &mut Issues,
+) -> String {
+    issues.note(
+        title,
+        format!(
+            "Passing score of {}% is not part of Common Cartridge",
+            quiz.passing_score_percent
+        ),
+    );
+    if !quiz.draw_rules.is_empty() {
+        issues.note(
+            title,
+            "Questions are drawn per attempt on Aranya, the cartridge serves every question",
+        );
+    }
+
+    let mut xml = XmlWriter::new(None);
+    let schema_location = format!(
+        "{} http://www.imsglobal.org/profile/cc/ccv1p3/ccv1p3_qtiasiv1p2p1_v1p0.xsd",
+        QTI_NS
+    );
+    xml.open(
+        "questestinterop",
+        &[
+            ("xmlns", QTI_NS),
+            ("xmlns:xsi", XSI_NS),
+            ("xsi:schemaLocation", &schema_location),
+        ],
+    );
+    xml.open("assessment", &[("ident", identifier), ("title", title)]);
+    let max_attempts = quiz
+        .max_attempts
+        .map(|n| n.to_string())
+        .unwrap_or_else(|| "unlimited".to_string());
+    metadata(
+        &mut xml,
+        &[
+            ("cc_profile", "cc.exam.v0p1"),
+            ("qmd_assessmenttype", "Examination"),
+            ("cc_maxattempts", &max_attempts),
+        ],
+    );
+
+    xml.open("section", &[("ident", &format!("{}_S", identifier))]);
+    for (position, question) in quiz.questions.iter().enumerate() {
+        let item = format!("{}, question {}", title, position + 1);
+        write_question(
+            &mut xml,
+            question,
+            &format!("{}_Q{}", identifier, position + 1),
+            &item,
+            issues,
+        );
+    }
+    xml.close();
+
+    xml.close();
+    xml.close();
+    xml.finish()
+}
+
+fn write_question(
+    xml: &mut XmlWriter,
+    question: &CreateQuestionPayload,
+    identifier: &str,
+    item: &str,
+    issues: &mut Issues,
+) {
+    let profile = match question.question_type {
+        QuestionType::SingleChoice => "cc.multiple_choice.v0p1",
+        QuestionType::TrueFalse => "cc.true_false.v0p1",
+        QuestionType::MultipleChoice => {
+            issues.note(item, "Partial credit is not part of Common Cartridge");
+            "cc.multiple_response.v0p1"
+        }
+        QuestionType::ShortText => "cc.fib.v0p1",
+        QuestionType::Numeric if question.numeric_tolerance.unwrap_or_default() == 0.0 => {
+            issues.note(item, "Numeric answer is graded as exact text");
+            "cc.fib.v0p1"
+        }
+        QuestionType::Numeric => {
+            issues.skip(item, "Numeric tolerance is not part of Common Cartridge");
+            return;
+        }
+        QuestionType::Ordering | QuestionType::Matching => {
+            issues.skip(
+                item,
+                "Ordering and matching questions are not part of Common Cartridge",
+            );
+            return;
+        }
+    };
+
+    let points = question.points.to_string();
+    xml.open("item", &[("ident", identifier), ("title", item)]);
+    xml.open("itemmetadata", &[]);
+    metadata(xml, &[("cc_profile", profile), ("cc_weighting", &points)]);
+    xml.close();
+
+    xml.open("presentation", &[]);
+    material(xml, &question.question_text);
+    let answer_id = |i: usize| format!("{}_A{}", identifier, i + 1);
+    let text_answers: Vec<String> = match question.question_type {
+        QuestionType::Numeric => question
+            .numeric_answer
+            .map(|n| n.to_string())
+            .into_iter()
+            .collect(),
+        _ => question
+            .answers
+            .iter()
+            .map(|a| a.answer_text.clone())
+            .collect(),
+    };
+    let fill_in = matches!(
+        question.question_type,
+        QuestionType::ShortText | QuestionType::Numeric
+    );
+    if fill_in {
+        xml.open(
+            "response_str",
+            &[("ident", RESPONSE), ("rcardinality", "Single")],
+        );
+        xml.open("render_fib", &[]);
+        xml.empty(
+            "response_label",
+            &[("ident", "answer1"), ("rshuffle", "No")],
+        );
+    } else {
+        let cardinality = if question.question_type == QuestionType::MultipleChoice {
+            "Multiple"
+        } else {
+            "Single"
+        };
+        xml.open(
+            "response_lid",
+            &[("ident", RESPONSE), ("rcardinality", cardinality)],
+        );
+        xml.open("render_choice", &[]);
+        for (i, answer) in question.answers.iter().enumerate() {
+            xml.open("response_label", &[("ident", &answer_id(i))]);
+            material(xml, &answer.answer_text);
+            xml.close();
+        }
+    }
+    xml.close();
+    xml.close();
+    xml.close();
+
+    xml.open("resprocessing", &[]);
+    xml.open("outcomes", &[]);
+    xml.empty(
+        "decvar",
+        &[
+            ("varname", "SCORE"),
+            ("vartype", "Decimal"),
+            ("minvalue", "0"),
+            ("maxvalue", "100"),
+        ],
+    );
+    xml.close();
+    if fill_in {
+        let case = if question.case_sensitive { "Yes" } else { "No" };
+        for answer in &text_answers {
+            full_score(xml, |xml| {
+                xml.leaf(
+                    "varequal",
+                    &[("respident", RESPONSE), ("case", case)],
+                    answer,
+                )
+            });
+        }
+    } else if question.question_type == QuestionType::MultipleChoice {
+        // All right answers and none of the wrong ones
+        full_score(xml, |xml| {
+            xml.open("and", &[]);
+            for (i, answer) in question.answers.iter().enumerate() {
+                if answer.is_correct {
+                    xml.leaf("varequal", &[("respident", RESPONSE)], &answer_id(i));
+                } else {
+                    xml.open("not", &[]);
+                    xml.leaf("varequal", &[("respident", RESPONSE)], &answer_id(i));
+                    xml.close();
+                }
+            }
+            xml.close();
+        });
+    } else {
+        for (i, answer) in question.answers.iter().enumerate() {
+            if answer.is_correct {
+                full_score(xml, |xml| {
+                    xml.leaf("varequal", &[("respident", RESPONSE)], &answer_id(i))
+                });
+            }
+        }
+    }
+    xml.close();
+
+    xml.close();
+}
+
+fn full_score(xml: &mut XmlWriter, condition: impl FnOnce(&mut XmlWriter)) {
+    xml.open("respcondition", &[("continue", "No")]);
+    xml.open("conditionvar", &[]);
+    condition(xml);
+    xml.close();
+    xml.leaf("setvar", &[("varname", "SCORE"), ("action", "Set")], "100");
+    xml.close();
+}
+
+fn material(xml: &mut XmlWriter, text: &str) {
+    xml.open("material", &[]);
+    xml.leaf("mattext", &[("texttype", "text/plain")], text);
+    xml.close();
+}
+
+fn metadata(xml: &mut XmlWriter, fields: &[(&str, &str)]) {
+    xml.open("qtimetadata", &[]);
+    for (label, entry) in fields {
+        xml.open("qtimetadatafield", &[]);
+        xml.leaf("fieldlabel", &[], label);
+        xml.leaf("fieldentry", &[], entry);
+        xml.close();
+    }
+    xml.close();
+}
+
+#[cfg(test)]
+mod tests {
+    use roxmltree::{Document, Node};
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::bundle::read_zip;
+    use crate::models::course::CreateAnswerOptionPayload;
+    use crate::test_support::sample_bundle;
+
+    fn titles(node: Node) -> Vec<String> {
+        node.children()
+            .filter(|n| n.has_tag_name("item"))
+            .map(|item| {
+                let title = item
+                    .children()
+                    .find(|n| n.has_tag_name("title"))
+                    .and_then(|t| t.text())
+                    .unwrap_or_default();
+                let children = titles(item);
+                if children.is_empty() {
+                    title.to_string()
+                } else {
+                    format!("{} [{}]", title, children.join(", "))
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cartridge_has_every_lesson_quiz_and_media_file() {
+        let bundle = sample_bundle();
+        let diagram = bundle.manifest.media[0].path.clone();
+        let video = bundle.manifest.media[1].path.clone();
+
+        let (package, issues) = write_cartridge(&bundle).unwrap();
+        assert!(issues.iter().all(|issue| !issue.skipped), "{:?}", issues);
+        let files: BTreeMap<String, Vec<u8>> = read_zip(&package).unwrap();
+
+        let manifest = std::str::from_utf8(&files[MANIFEST_FILE]).unwrap();
+        let doc = Document::parse(manifest).unwrap();
+        let root = doc
+            .descendants()
+            .find(|n| n.attribute("identifier") == Some("ROOT"))
+            .unwrap();
+        assert_eq!(
+            titles(root),
+            [
+                "Getting started [Welcome, Installing, Getting started quiz]",
+                "Ownership [Moves]",
+                "Final exam",
+            ]
+        );
+
+        // Every item and dependency points at a resource, every resource file is packaged
+        let resources: Vec<Node> = doc
+            .descendants()
+            .filter(|n| n.has_tag_name("resource"))
+            .collect();
+        let identifiers: Vec<&str> = resources
+            .iter()
+            .filter_map(|r| r.attribute("identifier"))
+            .collect();
+        for reference in doc
+            .descendants()
+            .filter_map(|n| n.attribute("identifierref"))
+        {
+            assert!(identifiers.contains(&reference), "{}", reference);
+        }
+        for href in resources
+            .iter()
+            .flat_map(|r| r.children())
+            .filter_map(|f| f.attribute("href"))
+        {
+            assert!(files.contains_key(href), "{} is not packaged", href);
+        }
+        assert_eq!(files.len(), resources.len() + 1);
+
+        for path in [&diagram, &video] {
+            let href = format!("{}{}", WEB_RESOURCES, path);
+            assert_eq!(files[&href], bundle.files[path]);
+        }
+        let page = std::str::from_utf8(&files["lessons/L2.html"]).unwrap();
+        assert!(page.contains(&format!(
+            "<video controls src=\"../{}{}\"",
+            WEB_RESOURCES, video
+        )));
+        assert!(page.contains(&format!("<img src=\"../{}{}\"", WEB_RESOURCES, diagram)));
+        assert!(page.contains("<code>rustup</code>"));
+
+        let exam = std::str::from_utf8(&files["RES_Q2/assessment.xml"]).unwrap();
+        assert!(Document::parse(exam).is_ok());
+        assert!(exam.contains("Do values have one owner?"));
+    }
+
+    #[test]
+    fn unsupported_questions_are_skipped() {
+        let mut bundle = sample_bundle();
+        let exam = bundle.manifest.final_exam.as_mut().unwrap();
+        exam.questions.push(CreateQuestionPayload {
+            question_text: "Order the steps".to_string(),
+            question_type: QuestionType::Ordering,
+            points: 1.0,
+            answers: ["Parse", "Check"]
+                .into_iter()
+                .map(|answer_text| CreateAnswerOptionPayload {
+                    answer_text: answer_text.to_string(),
+                    is_correct: true,
+                    match_text: None,
+                })
+                .collect(),
+            numeric_answer: None,
+            numeric_tolerance: None,
+            case_sensitive: false,
+            pool: None,
+        });
+
+        let (package, issues) = write_cartridge(&bundle).unwrap();
+        let skipped: Vec<&str> = issues
+            .iter()
+            .filter(|issue| issue.skipped)
+            .map(|issue| issue.item.as_str())
+            .collect();
+        assert_eq!(skipped, ["Final exam, question 2"]);
+
+        let files = read_zip(&package).unwrap();
+        let exam = std::str::from_utf8(&files["RES_Q2/assessment.xml"]).unwrap();
+        assert!(!exam.contains("Order the steps"));
+    }
+
+    #[test]
+    fn incomplete_bundles_are_rejected() {
+        let mut missing_media = sample_bundle();
+        let path = missing_media.manifest.media[0].path.clone();
+        missing_media.files.remove(&path);
+        let e = write_cartridge(&missing_media).err().unwrap();
+        assert_eq!(e, format!("Media file {} is missing", path));
+
+        let mut missing_lesson = sample_bundle();
+        missing_lesson.files.remove("lessons/1.md");
+        let e = write_cartridge(&missing_lesson).err().unwrap();
+        assert_eq!(e, "Lesson content file lessons/1.md is missing");
+
+        let mut binary_lesson = sample_bundle();
+        binary_lesson
+            .files
+            .insert("lessons/1.md".to_string(), vec![0xff, 0xfe]);
+        let e = write_cartridge(&binary_lesson).err().unwrap();
+        assert_eq!(e, "Lesson content file lessons/1.md is not UTF-8");
+    }
+}
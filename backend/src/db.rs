@@ -1,9 +1,9 @@
 use dotenv::dotenv;
 use sqlx::{Pool, Postgres, postgres::PgPoolOptions};
-use std::env;
+use std::{env, error::Error};
 
-pub async fn connect() -> Result<Pool<Postgres>, sqlx::Error> {
+pub async fn connect() -> Result<Pool<Postgres>, Box<dyn Error>> {
     dotenv().ok();
-    let url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
-    PgPoolOptions::new().connect(&url).await
+    let url = env::var("DATABASE_URL").map_err(|_| "DATABASE_URL not set")?;
+    Ok(PgPoolOptions::new().connect(&url).await?)
 }
@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+
+/// Serves finalized proofs, implemented by the DA layer client.
+pub trait DaLayer: Send + Sync {
+    /// Returns `None` while the round is not finalized or the proof is not
+    /// generated yet.
+    fn fetch_proof(
+        &self,
+        voting_round_id: i64,
+        abi_encoded_request: &str,
+    ) -> impl Future<Output = Result<Option<Proof>, String>> + Send;
+}
+
+/// Client for the data availability layer that serves finalized proofs.
+#[derive(Clone)]
+pub struct DaLayerClient {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProofRequest<'a> {
+    voting_round_id: i64,
+    request_bytes: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ProofResponse {
+    response_hex: Option<String>,
+    proof: Option<Vec<String>>,
+}
+
+#[derive(Debug)]
+pub struct Proof {
+    pub response_hex: String,
+    pub merkle_proof: Vec<String>,
+}
+
+impl DaLayerClient {
+    pub fn new(http: reqwest::Client, base_url: &str) -> DaLayerClient {
+        DaLayerClient {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+impl DaLayer for DaLayerClient {
+    async fn fetch_proof(
+        &self,
+        voting_round_id: i64,
+        abi_encoded_request: &str,
+    ) -> Result<Option<Proof>, String> {
+        let response = self
+            .http
+            .post(format!(
+                "{}/api/v1/fdc/proof-by-request-round-raw",
+                self.base_url
+            ))
+            .json(&ProofRequest {
+                voting_round_id,
+                request_bytes: abi_encoded_request,
+            })
+            .send()
+            .await
+            .map_err(|e| format!("DA layer request failed: {}", e))?;
+
+        if response.status().is_server_error() {
+            return Err(format!("DA layer returned status {}", response.status()));
+        }
+        if !response.status().is_success() {
+            return Ok(None);
+        }
+
+        let proof: ProofResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid DA layer response: {}", e))?;
+
+        Ok(match (proof.response_hex, proof.proof) {
+            (Some(response_hex), Some(merkle_proof)) => Some(Proof {
+                response_hex,
+                merkle_proof,
+            }),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fdc::mock::{FAILING_ROUND, FINALIZED_ROUND, mock_da_layer};
+    use crate::test_support::serve;
+
+    #[tokio::test]
+    async fn fetch_proof_returns_finalized_proofs() {
+        let da_layer = DaLayerClient::new(reqwest::Client::new(), &serve(mock_da_layer()).await);
+
+        let proof = da_layer
+            .fetch_proof(FINALIZED_ROUND, "0xabcd")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(proof.response_hex, "0xabcd");
+        assert_eq!(proof.merkle_proof, vec!["0x01", "0x02"]);
+    }
+
+    #[tokio::test]
+    async fn fetch_proof_waits_for_unfinalized_rounds() {
+        let da_layer = DaLayerClient::new(reqwest::Client::new(), &serve(mock_da_layer()).await);
+
+        let proof = da_layer
+            .fetch_proof(FINALIZED_ROUND + 1, "0xabcd")
+            .await
+            .unwrap();
+
+        assert!(proof.is_none());
+    }
+
+    #[tokio::test]
+    async fn fetch_proof_reports_server_errors() {
+        let da_layer = DaLayerClient::new(reqwest::Client::new(), &serve(mock_da_layer()).await);
+
+        let error = da_layer
+            .fetch_proof(FAILING_ROUND, "0xabcd")
+            .await
+            .unwrap_err();
+
+        assert_eq!(error, "DA layer returned status 500 Internal Server Error");
+    }
+}
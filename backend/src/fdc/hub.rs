@@ -0,0 +1,163 @@
+use alloy::{
+    primitives::{Address, Bytes, TxHash, address},
+    providers::{DynProvider, Provider, ProviderBuilder},
+    signers::local::PrivateKeySigner,
+    sol,
+};
+
+sol! {
+    #[sol(rpc)]
+    interface IFlareContractRegistry {
+        function getContractAddressByName(string calldata _name) external view returns (address);
+    }
+
+    #[sol(rpc)]
+    interface IFdcHub {
+        function requestAttestation(bytes calldata _data) external payable;
+    }
+
+    #[sol(rpc)]
+    interface IFdcRequestFeeConfigurations {
+        function getRequestFee(bytes calldata _data) external view returns (uint256);
+    }
+
+    #[sol(rpc)]
+    interface IFlareSystemsManager {
+        function firstVotingRoundStartTs() external view returns (uint64);
+        function votingEpochDurationSeconds() external view returns (uint64);
+    }
+}
+
+const FLARE_CONTRACT_REGISTRY: Address = address!("0xaD67FE66660Fb8dFE9d6b1b4240d8650e30F6019");
+
+/// Sends attestation requests on-chain, implemented by the FdcHub client.
+pub trait FdcHub: Send + Sync {
+    /// Pays the request fee and sends the request, returning the transaction
+    /// hash without waiting for it to be mined.
+    fn send_request(
+        &self,
+        abi_encoded_request: &str,
+    ) -> impl Future<Output = Result<String, String>> + Send;
+
+    /// Whether a sent request was mined and the voting round it landed in.
+    fn request_status(
+        &self,
+        tx_hash: &str,
+    ) -> impl Future<Output = Result<RequestStatus, String>> + Send;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RequestStatus {
+    Pending,
+    Reverted,
+    // Voting round of the block the request was included in
+    Included(i64),
+}
+
+/// Submits attestation requests to the FdcHub and derives their voting round.
+#[derive(Clone)]
+pub struct FdcHubClient {
+    provider: DynProvider,
+}
+
+impl FdcHubClient {
+    pub fn new(rpc_url: &str, private_key: &str) -> Result<FdcHubClient, String> {
+        let signer: PrivateKeySigner = private_key
+            .parse()
+            .map_err(|e| format!("Invalid FDC private key: {}", e))?;
+        let url = rpc_url
+            .parse()
+            .map_err(|e| format!("Invalid RPC url: {}", e))?;
+
+        let provider = ProviderBuilder::new()
+            .wallet(signer)
+            .connect_http(url)
+            .erased();
+
+        Ok(FdcHubClient { provider })
+    }
+
+    async fn contract_address(&self, name: &str) -> Result<Address, String> {
+        IFlareContractRegistry::new(FLARE_CONTRACT_REGISTRY, &self.provider)
+            .getContractAddressByName(name.to_string())
+            .call()
+            .await
+            .map_err(|e| format!("Failed to resolve {}: {}", name, e))
+    }
+}
+
+impl FdcHub for FdcHubClient {
+    async fn send_request(&self, abi_encoded_request: &str) -> Result<String, String> {
+        let request: Bytes = abi_encoded_request
+            .parse()
+            .map_err(|e| format!("Invalid ABI encoded request: {}", e))?;
+
+        let fee_configurations = IFdcRequestFeeConfigurations::new(
+            self.contract_address("FdcRequestFeeConfigurations").await?,
+            &self.provider,
+        );
+        let fee = fee_configurations
+            .getRequestFee(request.clone())
+            .call()
+            .await
+            .map_err(|e| format!("Failed to get request fee: {}", e))?;
+
+        let fdc_hub = IFdcHub::new(self.contract_address("FdcHub").await?, &self.provider);
+        let pending = fdc_hub
+            .requestAttestation(request)
+            .value(fee)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to submit attestation request: {}", e))?;
+
+        Ok(pending.tx_hash().to_string())
+    }
+
+    async fn request_status(&self, tx_hash: &str) -> Result<RequestStatus, String> {
+        let tx_hash: TxHash = tx_hash
+            .parse()
+            .map_err(|e| format!("Invalid transaction hash: {}", e))?;
+
+        let Some(receipt) = self
+            .provider
+            .get_transaction_receipt(tx_hash)
+            .await
+            .map_err(|e| format!("Failed to get transaction receipt: {}", e))?
+        else {
+            return Ok(RequestStatus::Pending);
+        };
+        if !receipt.status() {
+            return Ok(RequestStatus::Reverted);
+        }
+
+        let block_number = receipt
+            .block_number
+            .ok_or_else(|| "Transaction receipt has no block number".to_string())?;
+        let block = self
+            .provider
+            .get_block_by_number(block_number.into())
+            .await
+            .map_err(|e| format!("Failed to get block: {}", e))?
+            .ok_or_else(|| format!("Block {} not found", block_number))?;
+        let block_timestamp = block.header.timestamp;
+
+        let systems_manager = IFlareSystemsManager::new(
+            self.contract_address("FlareSystemsManager").await?,
+            &self.provider,
+        );
+        let first_round_start = systems_manager
+            .firstVotingRoundStartTs()
+            .call()
+            .await
+            .map_err(|e| format!("Failed to get first voting round start: {}", e))?;
+        let epoch_duration = systems_manager
+            .votingEpochDurationSeconds()
+            .call()
+            .await
+            .map_err(|e| format!("Failed to get voting epoch duration: {}", e))?;
+
+        Ok(RequestStatus::Included(
+            ((block_timestamp - first_round_start) / epoch_duration) as i64,
+        ))
+    }
+}
@@ -0,0 +1,100 @@
+use axum::{
+    Json, Router,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+};
+use serde_json::{Value, json};
+use std::sync::{
+    Mutex,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use crate::fdc::hub::{FdcHub, RequestStatus};
+
+pub const API_KEY: &str = "test-api-key";
+pub const FINALIZED_ROUND: i64 = 7;
+pub const FAILING_ROUND: i64 = 9;
+
+/// Web2Json verifier that accepts requests for the public API and answers
+/// with the hex encoded url as the ABI encoded request.
+pub fn mock_verifier(public_api_url: &str) -> Router {
+    let public_api_url = public_api_url.to_string();
+    Router::new().route(
+        "/Web2Json/prepareRequest",
+        post(
+            move |headers: HeaderMap, Json(body): Json<Value>| async move {
+                if headers.get("X-API-KEY").and_then(|v| v.to_str().ok()) != Some(API_KEY) {
+                    return (StatusCode::UNAUTHORIZED, Json(json!({})));
+                }
+
+                let url = body["requestBody"]["url"].as_str().unwrap_or_default();
+                let valid = body["attestationType"]
+                    == format!("0x{:0<64}", hex::encode("Web2Json"))
+                    && body["sourceId"] == format!("0x{:0<64}", hex::encode("PublicWeb2"))
+                    && url.starts_with(&public_api_url);
+                if !valid {
+                    return (StatusCode::OK, Json(json!({ "status": "INVALID" })));
+                }
+
+                (
+                    StatusCode::OK,
+                    Json(json!({
+                        "status": "VALID",
+                        "abiEncodedRequest": format!("0x{}", hex::encode(url)),
+                    })),
+                )
+            },
+        ),
+    )
+}
+
+/// DA layer with a proof for every request in `FINALIZED_ROUND`, failing for
+/// `FAILING_ROUND` and not finalized for any other round.
+pub fn mock_da_layer() -> Router {
+    Router::new().route(
+        "/api/v1/fdc/proof-by-request-round-raw",
+        post(|Json(body): Json<Value>| async move {
+            match body["votingRoundId"].as_i64() {
+                Some(FINALIZED_ROUND) => (
+                    StatusCode::OK,
+                    Json(json!({
+                        "response_hex": body["requestBytes"],
+                        "proof": ["0x01", "0x02"],
+                    })),
+                ),
+                Some(FAILING_ROUND) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({}))),
+                _ => (StatusCode::BAD_REQUEST, Json(json!({}))),
+            }
+        }),
+    )
+}
+
+/// FdcHub that counts the requests it was sent, with a settable receipt status.
+pub struct MockHub {
+    pub sent: AtomicUsize,
+    pub status: Mutex<RequestStatus>,
+}
+
+impl MockHub {
+    pub fn new() -> MockHub {
+        MockHub {
+            sent: AtomicUsize::new(0),
+            status: Mutex::new(RequestStatus::Pending),
+        }
+    }
+
+    pub fn set_status(&self, status: RequestStatus) {
+        *self.status.lock().unwrap() = status;
+    }
+}
+
+impl FdcHub for MockHub {
+    async fn send_request(&self, _abi_encoded_request: &str) -> Result<String, String> {
+        let sent = self.sent.fetch_add(1, Ordering::SeqCst) + 1;
+        Ok(format!("0x{:064x}", sent))
+    }
+
+    async fn request_status(&self, _tx_hash: &str) -> Result<RequestStatus, String> {
+        Ok(*self.status.lock().unwrap())
+    }
+}
@@ -0,0 +1,145 @@
+pub mod da_layer;
+pub mod hub;
+#[cfg(test)]
+pub mod mock;
+pub mod verifier;
+pub mod worker;
+
+use dotenv::dotenv;
+use serde::Serialize;
+use std::env;
+
+/// Endpoints and credentials for the Flare Data Connector. Every URL is
+/// configurable so the clients can be pointed at local mock servers.
+#[derive(Clone, Debug)]
+pub struct FdcConfig {
+    pub verifier_url: String,
+    pub verifier_api_key: String,
+    pub da_layer_url: String,
+    pub rpc_url: String,
+    pub private_key: String,
+    // Public URL of this backend, queried by the Web2Json verifier
+    pub public_api_url: String,
+}
+
+impl FdcConfig {
+    /// `None` when no verifier is configured, attestation jobs then stay pending.
+    pub fn from_env(public_api_url: &str) -> Option<FdcConfig> {
+        dotenv().ok();
+        let verifier_url = env::var("WEB2JSON_VERIFIER_URL")
+            .ok()
+            .filter(|u| !u.is_empty())?;
+        Some(FdcConfig {
+            verifier_url,
+            verifier_api_key: env::var("VERIFIER_API_KEY").unwrap_or_default(),
+            da_layer_url: env::var("DA_LAYER_URL").unwrap_or_default(),
+            rpc_url: env::var("RPC_URL").unwrap_or_default(),
+            private_key: env::var("FDC_PRIVATE_KEY").unwrap_or_default(),
+            public_api_url: public_api_url.to_string(),
+        })
+    }
+}
+
+/// The attestations the contracts accept, one per backend endpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttestationKind {
+    CourseCreator,
+    Enrollment,
+    CreatorMilestone,
+    LearnerMilestone,
+}
+
+impl AttestationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttestationKind::CourseCreator => "course_creator",
+            AttestationKind::Enrollment => "enrollment",
+            AttestationKind::CreatorMilestone => "creator_milestone",
+            AttestationKind::LearnerMilestone => "learner_milestone",
+        }
+    }
+
+    pub fn parse(kind: &str) -> Option<AttestationKind> {
+        match kind {
+            "course_creator" => Some(AttestationKind::CourseCreator),
+            "enrollment" => Some(AttestationKind::Enrollment),
+            "creator_milestone" => Some(AttestationKind::CreatorMilestone),
+            "learner_milestone" => Some(AttestationKind::LearnerMilestone),
+            _ => None,
+        }
+    }
+
+    fn endpoint(&self) -> &'static str {
+        match self {
+            AttestationKind::CourseCreator => "/get-course-creator",
+            AttestationKind::Enrollment => "/is-enrolled",
+            AttestationKind::CreatorMilestone => "/get-num-completed",
+            AttestationKind::LearnerMilestone => "/get-course-progress-percentage",
+        }
+    }
+
+    fn post_process_jq(&self) -> &'static str {
+        match self {
+            AttestationKind::CourseCreator => {
+                "{courseId: .courseId, creatorId: .creatorId, nonce: .nonce, expiresAt: .expiresAt}"
+            }
+            AttestationKind::Enrollment => {
+                "{courseId: .courseId, learnerId: .learnerId, isEnrolled: .isEnrolled, nonce: .nonce, expiresAt: .expiresAt}"
+            }
+            AttestationKind::CreatorMilestone => {
+                "{courseId: .courseId, numCompleted: .numCompleted, nonce: .nonce, expiresAt: .expiresAt}"
+            }
+            AttestationKind::LearnerMilestone => {
+                "{courseId: .courseId, learnerId: .learnerId, progressPercent: .progressPercent, nonce: .nonce, milestone: .milestone, expiresAt: .expiresAt}"
+            }
+        }
+    }
+
+    // Must match the DataTransportObject structs decoded by the contracts
+    fn abi_signature(&self) -> &'static str {
+        match self {
+            AttestationKind::CourseCreator => {
+                r#"{"components": [{"internalType": "uint256", "name": "courseId", "type": "uint256"},{"internalType": "address", "name": "creatorId", "type": "address"}, {"internalType": "bytes32", "name": "nonce", "type": "bytes32"}, {"internalType": "uint256", "name": "expiresAt", "type": "uint256"}],"name": "task","type": "tuple"}"#
+            }
+            AttestationKind::Enrollment => {
+                r#"{"components": [{"internalType": "uint256", "name": "courseId", "type": "uint256"},{"internalType": "address", "name": "learnerId", "type": "address"},{"internalType": "bool", "name": "isEnrolled", "type": "bool"}, {"internalType": "bytes32", "name": "nonce", "type": "bytes32"}, {"internalType": "uint256", "name": "expiresAt", "type": "uint256"}],"name": "task","type": "tuple"}"#
+            }
+            AttestationKind::CreatorMilestone => {
+                r#"{"components": [{"internalType": "uint256", "name": "courseId", "type": "uint256"},{"internalType": "uint256", "name": "numCompleted", "type": "uint256"}, {"internalType": "bytes32", "name": "nonce", "type": "bytes32"}, {"internalType": "uint256", "name": "expiresAt", "type": "uint256"}],"name": "task","type": "tuple"}"#
+            }
+            AttestationKind::LearnerMilestone => {
+                r#"{"components": [{"internalType": "uint256", "name": "courseId", "type": "uint256"}, {"internalType": "address", "name": "learnerId", "type": "address"}, {"internalType": "uint8", "name": "progressPercent", "type": "uint8"}, {"internalType": "bytes32", "name": "nonce", "type": "bytes32"}, {"internalType": "uint8", "name": "milestone", "type": "uint8"}, {"internalType": "uint256", "name": "expiresAt", "type": "uint256"}],"name": "task","type": "tuple"}"#
+            }
+        }
+    }
+
+    /// Builds the Web2Json request body for this attestation. `query_params`
+    /// is the JSON encoded query string the verifier sends to the endpoint.
+    pub fn web2json_request(&self, public_api_url: &str, query_params: String) -> Web2JsonRequest {
+        Web2JsonRequest {
+            url: format!(
+                "{}{}",
+                public_api_url.trim_end_matches('/'),
+                self.endpoint()
+            ),
+            http_method: "GET".to_string(),
+            headers: "{}".to_string(),
+            query_params,
+            body: "{}".to_string(),
+            post_process_jq: self.post_process_jq().to_string(),
+            abi_signature: self.abi_signature().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Web2JsonRequest {
+    pub url: String,
+    pub http_method: String,
+    pub headers: String,
+    pub query_params: String,
+    pub body: String,
+    pub post_process_jq: String,
+    pub abi_signature: String,
+}
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+
+use crate::fdc::Web2JsonRequest;
+
+const ATTESTATION_TYPE: &str = "Web2Json";
+const SOURCE_ID: &str = "PublicWeb2";
+
+/// Prepares attestation requests, implemented by the Web2Json verifier client.
+pub trait Verifier: Send + Sync {
+    /// Returns the ABI encoded request to submit to the FdcHub.
+    fn prepare_request(
+        &self,
+        request: &Web2JsonRequest,
+    ) -> impl Future<Output = Result<String, String>> + Send;
+}
+
+/// Client for the Web2Json verifier `prepareRequest` endpoint.
+#[derive(Clone)]
+pub struct VerifierClient {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PrepareRequest<'a> {
+    attestation_type: String,
+    source_id: String,
+    request_body: &'a Web2JsonRequest,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PrepareResponse {
+    status: String,
+    abi_encoded_request: Option<String>,
+}
+
+impl VerifierClient {
+    pub fn new(http: reqwest::Client, base_url: &str, api_key: &str) -> VerifierClient {
+        VerifierClient {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+        }
+    }
+}
+
+impl Verifier for VerifierClient {
+    async fn prepare_request(&self, request: &Web2JsonRequest) -> Result<String, String> {
+        let response = self
+            .http
+            .post(format!(
+                "{}/{}/prepareRequest",
+                self.base_url, ATTESTATION_TYPE
+            ))
+            .header("X-API-KEY", &self.api_key)
+            .json(&PrepareRequest {
+                attestation_type: to_utf8_hex_string(ATTESTATION_TYPE),
+                source_id: to_utf8_hex_string(SOURCE_ID),
+                request_body: request,
+            })
+            .send()
+            .await
+            .map_err(|e| format!("Verifier request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Verifier returned status {}", response.status()));
+        }
+
+        let prepared: PrepareResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid verifier response: {}", e))?;
+
+        prepared
+            .abi_encoded_request
+            .ok_or_else(|| format!("Verifier rejected request: {}", prepared.status))
+    }
+}
+
+// Hex of the UTF-8 bytes right padded to 32 bytes, as the FDC expects for ids
+fn to_utf8_hex_string(data: &str) -> String {
+    format!("0x{:0<64}", hex::encode(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fdc::AttestationKind;
+    use crate::fdc::mock::{API_KEY, mock_verifier};
+    use crate::test_support::serve;
+
+    const PUBLIC_API_URL: &str = "https://api.aranya.test";
+
+    fn course_creator_request(public_api_url: &str) -> Web2JsonRequest {
+        AttestationKind::CourseCreator.web2json_request(
+            public_api_url,
+            r#"{"courseId":"4","nonce":"0x01"}"#.to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn prepare_request_returns_the_abi_encoded_request() {
+        let base_url = serve(mock_verifier(PUBLIC_API_URL)).await;
+        let verifier =
+            VerifierClient::new(reqwest::Client::new(), &format!("{}/", base_url), API_KEY);
+
+        let abi_encoded_request = verifier
+            .prepare_request(&course_creator_request(PUBLIC_API_URL))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            abi_encoded_request,
+            format!(
+                "0x{}",
+                hex::encode("https://api.aranya.test/get-course-creator")
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn prepare_request_reports_rejected_requests() {
+        let base_url = serve(mock_verifier(PUBLIC_API_URL)).await;
+        let verifier = VerifierClient::new(reqwest::Client::new(), &base_url, API_KEY);
+
+        let error = verifier
+            .prepare_request(&course_creator_request("https://elsewhere.test"))
+            .await
+            .unwrap_err();
+
+        assert_eq!(error, "Verifier rejected request: INVALID");
+    }
+
+    #[tokio::test]
+    async fn prepare_request_sends_the_api_key() {
+        let base_url = serve(mock_verifier(PUBLIC_API_URL)).await;
+        let verifier = VerifierClient::new(reqwest::Client::new(), &base_url, "wrong-key");
+
+        let error = verifier
+            .prepare_request(&course_creator_request(PUBLIC_API_URL))
+            .await
+            .unwrap_err();
+
+        assert_eq!(error, "Verifier returned status 401 Unauthorized");
+    }
+}
@@ -0,0 +1,420 @@
+use chrono::{Duration, Utc};
+use serde_json::{Value, json};
+use sqlx::{Pool, Postgres};
+
+use crate::fdc::{
+    AttestationKind, FdcConfig,
+    da_layer::{DaLayer, DaLayerClient},
+    hub::{FdcHub, FdcHubClient, RequestStatus},
+    verifier::{Verifier, VerifierClient},
+};
+use crate::models::attestation_job::AttestationJobRow;
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_PREPARED: &str = "prepared";
+pub const STATUS_SUBMITTED: &str = "submitted";
+pub const STATUS_PROOF_READY: &str = "proof_ready";
+pub const STATUS_FAILED: &str = "failed";
+
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+const BATCH_SIZE: i64 = 10;
+const MAX_ATTEMPTS: i32 = 12;
+const BASE_BACKOFF_SECONDS: i64 = 10;
+const MAX_BACKOFF_SECONDS: i64 = 300;
+const CLAIM_LEASE_SECONDS: i64 = 300;
+
+// Voting rounds last 90 seconds, the DA layer has no proof before that
+const ROUND_FINALIZATION_SECONDS: i64 = 90;
+
+/// Background worker that moves attestation jobs through
+/// pending -> prepared -> submitted -> proof_ready, retrying with backoff.
+pub struct AttestationWorker<V = VerifierClient, D = DaLayerClient, H = FdcHubClient> {
+    pool: Pool<Postgres>,
+    public_api_url: String,
+    verifier: V,
+    da_layer: D,
+    hub: H,
+}
+
+enum Step {
+    Prepared(String),
+    Sent(String),
+    Reverted(String),
+    Submitted(i64),
+    ProofReady(Value),
+    Waiting,
+}
+
+impl AttestationWorker {
+    pub fn new(pool: Pool<Postgres>, config: &FdcConfig) -> Result<AttestationWorker, String> {
+        let http = reqwest::Client::new();
+
+        Ok(AttestationWorker {
+            pool,
+            public_api_url: config.public_api_url.clone(),
+            verifier: VerifierClient::new(
+                http.clone(),
+                &config.verifier_url,
+                &config.verifier_api_key,
+            ),
+            da_layer: DaLayerClient::new(http, &config.da_layer_url),
+            hub: FdcHubClient::new(&config.rpc_url, &config.private_key)?,
+        })
+    }
+}
+
+impl<V, D, H> AttestationWorker<V, D, H>
+where
+    V: Verifier + 'static,
+    D: DaLayer + 'static,
+    H: FdcHub + 'static,
+{
+    pub fn spawn(self) {
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = self.run_due_jobs().await {
+                    eprintln!("Attestation worker error: {}", e);
+                }
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        });
+    }
+
+    async fn run_due_jobs(&self) -> Result<(), sqlx::Error> {
+        // Claimed jobs are skipped by other workers until the step is recorded
+        // or the lease runs out
+        let jobs: Vec<AttestationJobRow> = sqlx::query_as::<_, AttestationJobRow>(
+            r#"
+            UPDATE attestation_job
+            SET next_attempt_at = $3
+            WHERE id IN (
+                SELECT id
+                FROM attestation_job
+                WHERE status = ANY($1) AND next_attempt_at <= now()
+                ORDER BY next_attempt_at
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, kind, query_params, status, abi_encoded_request, tx_hash, round_id,
+                attempts
+            "#,
+        )
+        .bind(&[STATUS_PENDING, STATUS_PREPARED, STATUS_SUBMITTED][..])
+        .bind(BATCH_SIZE)
+        .bind(Utc::now() + Duration::seconds(CLAIM_LEASE_SECONDS))
+        .fetch_all(&self.pool)
+        .await?;
+
+        for job in jobs {
+            let step = self.advance(&job).await;
+            self.record(&job, step).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn advance(&self, job: &AttestationJobRow) -> Result<Step, String> {
+        let kind = AttestationKind::parse(&job.kind)
+            .ok_or_else(|| format!("Unknown attestation kind {}", job.kind))?;
+
+        match (
+            job.status.as_str(),
+            &job.abi_encoded_request,
+            &job.tx_hash,
+            job.round_id,
+        ) {
+            (STATUS_PENDING, _, _, _) => {
+                let request = kind.web2json_request(&self.public_api_url, job.query_params.clone());
+                let abi_encoded_request = self.verifier.prepare_request(&request).await?;
+                Ok(Step::Prepared(abi_encoded_request))
+            }
+            (STATUS_PREPARED, Some(abi_encoded_request), None, _) => {
+                let tx_hash = self.hub.send_request(abi_encoded_request).await?;
+                println!("Attestation job {} sent in transaction {}", job.id, tx_hash);
+                Ok(Step::Sent(tx_hash))
+            }
+            // Already paid for, only the receipt is polled so the fee is never paid twice
+            (STATUS_PREPARED, Some(_), Some(tx_hash), _) => {
+                match self.hub.request_status(tx_hash).await? {
+                    RequestStatus::Included(round_id) => {
+                        println!("Attestation job {} submitted in round {}", job.id, round_id);
+                        Ok(Step::Submitted(round_id))
+                    }
+                    RequestStatus::Pending => Ok(Step::Waiting),
+                    RequestStatus::Reverted => Ok(Step::Reverted(tx_hash.clone())),
+                }
+            }
+            (STATUS_SUBMITTED, Some(abi_encoded_request), _, Some(round_id)) => {
+                match self
+                    .da_layer
+                    .fetch_proof(round_id, abi_encoded_request)
+                    .await?
+                {
+                    Some(proof) => Ok(Step::ProofReady(json!({
+                        "merkleProof": proof.merkle_proof,
+                        "responseHex": proof.response_hex,
+                    }))),
+                    None => Ok(Step::Waiting),
+                }
+            }
+            _ => Err(format!("Attestation job {} is in an invalid state", job.id)),
+        }
+    }
+
+    async fn record(
+        &self,
+        job: &AttestationJobRow,
+        step: Result<Step, String>,
+    ) -> Result<(), sqlx::Error> {
+        match step {
+            Ok(Step::Prepared(abi_encoded_request)) => {
+                sqlx::query(
+                    r#"
+                    UPDATE attestation_job
+                    SET status = $2, abi_encoded_request = $3, attempts = 0, last_error = NULL,
+                        next_attempt_at = now(), updated_at = now()
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(job.id)
+                .bind(STATUS_PREPARED)
+                .bind(abi_encoded_request)
+                .execute(&self.pool)
+                .await?;
+            }
+            Ok(Step::Sent(tx_hash)) => {
+                sqlx::query(
+                    r#"
+                    UPDATE attestation_job
+                    SET tx_hash = $2, attempts = 0, last_error = NULL, next_attempt_at = now(),
+                        updated_at = now()
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(job.id)
+                .bind(tx_hash)
+                .execute(&self.pool)
+                .await?;
+            }
+            Ok(Step::Reverted(tx_hash)) => {
+                // A reverted request costs no fee, it is sent again on the next attempt
+                sqlx::query("UPDATE attestation_job SET tx_hash = NULL WHERE id = $1")
+                    .bind(job.id)
+                    .execute(&self.pool)
+                    .await?;
+                let error = format!("Attestation request transaction {} reverted", tx_hash);
+                eprintln!("Attestation job {} failed: {}", job.id, error);
+                self.retry(job, Some(error)).await?
+            }
+            Ok(Step::Submitted(round_id)) => {
+                sqlx::query(
+                    r#"
+                    UPDATE attestation_job
+                    SET status = $2, round_id = $3, attempts = 0, last_error = NULL,
+                        next_attempt_at = $4, updated_at = now()
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(job.id)
+                .bind(STATUS_SUBMITTED)
+                .bind(round_id)
+                .bind(Utc::now() + Duration::seconds(ROUND_FINALIZATION_SECONDS))
+                .execute(&self.pool)
+                .await?;
+            }
+            Ok(Step::ProofReady(proof)) => {
+                sqlx::query(
+                    r#"
+                    UPDATE attestation_job
+                    SET status = $2, proof = $3, last_error = NULL, updated_at = now()
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(job.id)
+                .bind(STATUS_PROOF_READY)
+                .bind(proof)
+                .execute(&self.pool)
+                .await?;
+            }
+            Ok(Step::Waiting) => self.retry(job, None).await?,
+            Err(e) => {
+                eprintln!("Attestation job {} failed: {}", job.id, e);
+                self.retry(job, Some(e)).await?
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn retry(
+        &self,
+        job: &AttestationJobRow,
+        error: Option<String>,
+    ) -> Result<(), sqlx::Error> {
+        let attempts = job.attempts + 1;
+        let status = if attempts >= MAX_ATTEMPTS {
+            STATUS_FAILED
+        } else {
+            job.status.as_str()
+        };
+        let error = error.or_else(|| {
+            (status == STATUS_FAILED)
+                .then(|| format!("Proof not available after {} attempts", attempts))
+        });
+
+        sqlx::query(
+            r#"
+            UPDATE attestation_job
+            SET status = $2, attempts = $3, last_error = COALESCE($4, last_error),
+                next_attempt_at = $5, updated_at = now()
+            WHERE id = $1
+            "#,
+        )
+        .bind(job.id)
+        .bind(status)
+        .bind(attempts)
+        .bind(error)
+        .bind(Utc::now() + backoff(attempts))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+fn backoff(attempts: i32) -> Duration {
+    let seconds = BASE_BACKOFF_SECONDS.saturating_mul(1 << attempts.clamp(0, 16));
+    Duration::seconds(seconds.min(MAX_BACKOFF_SECONDS))
+}
+
+/// The longest a job keeps querying the attested endpoint: the backoff before
+/// every retry, the lease of a claim that was dropped and a last voting round.
+pub(crate) fn retry_span() -> Duration {
+    (1..MAX_ATTEMPTS).fold(
+        Duration::seconds(CLAIM_LEASE_SECONDS + ROUND_FINALIZATION_SECONDS),
+        |span, attempts| span + backoff(attempts),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::postgres::PgPoolOptions;
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+    use crate::fdc::mock::{API_KEY, FINALIZED_ROUND, MockHub, mock_da_layer, mock_verifier};
+    use crate::test_support::serve;
+
+    const PUBLIC_API_URL: &str = "https://api.aranya.test";
+
+    async fn worker() -> AttestationWorker<VerifierClient, DaLayerClient, MockHub> {
+        let http = reqwest::Client::new();
+        AttestationWorker {
+            // Steps don't touch the database, only recording them does
+            pool: PgPoolOptions::new()
+                .connect_lazy("postgres://localhost/aranya")
+                .unwrap(),
+            public_api_url: PUBLIC_API_URL.to_string(),
+            verifier: VerifierClient::new(
+                http.clone(),
+                &serve(mock_verifier(PUBLIC_API_URL)).await,
+                API_KEY,
+            ),
+            da_layer: DaLayerClient::new(http, &serve(mock_da_layer()).await),
+            hub: MockHub::new(),
+        }
+    }
+
+    fn job(status: &str) -> AttestationJobRow {
+        AttestationJobRow {
+            id: 1,
+            kind: "enrollment".to_string(),
+            query_params: r#"{"courseId":"4","learnerId":"0xabc","nonce":"0x01"}"#.to_string(),
+            status: status.to_string(),
+            abi_encoded_request: None,
+            tx_hash: None,
+            round_id: None,
+            attempts: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn job_moves_through_every_step() {
+        let worker = worker().await;
+        let mut job = job(STATUS_PENDING);
+
+        let Ok(Step::Prepared(abi_encoded_request)) = worker.advance(&job).await else {
+            panic!("pending job was not prepared");
+        };
+        assert_eq!(
+            abi_encoded_request,
+            format!("0x{}", hex::encode("https://api.aranya.test/is-enrolled"))
+        );
+
+        job.status = STATUS_PREPARED.to_string();
+        job.abi_encoded_request = Some(abi_encoded_request.clone());
+        let Ok(Step::Sent(tx_hash)) = worker.advance(&job).await else {
+            panic!("prepared job was not sent");
+        };
+
+        job.tx_hash = Some(tx_hash);
+        worker
+            .hub
+            .set_status(RequestStatus::Included(FINALIZED_ROUND));
+        let Ok(Step::Submitted(round_id)) = worker.advance(&job).await else {
+            panic!("sent job was not submitted");
+        };
+        assert_eq!(round_id, FINALIZED_ROUND);
+
+        job.status = STATUS_SUBMITTED.to_string();
+        job.round_id = Some(round_id);
+        let Ok(Step::ProofReady(proof)) = worker.advance(&job).await else {
+            panic!("submitted job has no proof");
+        };
+        assert_eq!(
+            proof,
+            json!({ "merkleProof": ["0x01", "0x02"], "responseHex": abi_encoded_request })
+        );
+        assert_eq!(worker.hub.sent.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn sent_request_is_polled_instead_of_paid_again() {
+        let worker = worker().await;
+        let mut job = job(STATUS_PREPARED);
+        job.abi_encoded_request = Some("0xabcd".to_string());
+        job.tx_hash = Some(format!("0x{:064x}", 1));
+
+        assert!(matches!(worker.advance(&job).await, Ok(Step::Waiting)));
+
+        worker.hub.set_status(RequestStatus::Reverted);
+        assert!(matches!(worker.advance(&job).await, Ok(Step::Reverted(_))));
+        assert_eq!(worker.hub.sent.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn submitted_job_waits_for_the_round_to_finalize() {
+        let worker = worker().await;
+        let mut job = job(STATUS_SUBMITTED);
+        job.abi_encoded_request = Some("0xabcd".to_string());
+        job.round_id = Some(FINALIZED_ROUND + 1);
+
+        assert!(matches!(worker.advance(&job).await, Ok(Step::Waiting)));
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        assert_eq!(backoff(1), Duration::seconds(20));
+        assert_eq!(backoff(3), Duration::seconds(80));
+        assert_eq!(
+            backoff(MAX_ATTEMPTS),
+            Duration::seconds(MAX_BACKOFF_SECONDS)
+        );
+    }
+
+    #[test]
+    fn retry_span_covers_every_backoff() {
+        // 20 + 40 + 80 + 160 + 7 * 300 between attempts, plus the lease and a round
+        assert_eq!(retry_span(), Duration::seconds(2400 + 300 + 90));
+    }
+}
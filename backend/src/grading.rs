@@ -0,0 +1,497 @@
+use std::collections::HashSet;
+
+use crate::models::course::{CreateQuestionPayload, CreateQuizPayload, Question, QuestionType};
+use crate::models::progress::{QuestionResponse, QuestionResult};
+
+/// Checks the quiz settings and every question of the quiz.
+pub fn validate_quiz(quiz: &CreateQuizPayload) -> Result<(), String> {
+    if !(0.0..=100.0).contains(&quiz.passing_score_percent) {
+        return Err("Passing score must be between 0 and 100 percent".to_string());
+    }
+    if quiz.max_attempts.is_some_and(|max_attempts| max_attempts < 1) {
+        return Err("Quizzes must allow at least 1 attempt".to_string());
+    }
+    if quiz.cooldown_seconds < 0 {
+        return Err("Quiz cooldown can't be negative".to_string());
+    }
+    // A quiz without questions has a max score of 0 and can never be passed or failed
+    if quiz.questions.is_empty() {
+        return Err("Quizzes must have at least 1 question".to_string());
+    }
+
+    let mut pools = HashSet::new();
+    for rule in &quiz.draw_rules {
+        let pool = rule.pool.as_deref().unwrap_or("default");
+        if !pools.insert(&rule.pool) {
+            return Err(format!("Pool {} has more than one draw rule", pool));
+        }
+
+        let available = quiz.questions.iter().filter(|q| q.pool == rule.pool).count();
+        if rule.count < 1 || rule.count as usize > available {
+            return Err(format!(
+                "Pool {} has {} questions, can't draw {}",
+                pool, available, rule.count
+            ));
+        }
+    }
+
+    quiz.questions.iter().try_for_each(validate_question)
+}
+
+/// Checks a question has what its type needs to be graded.
+pub fn validate_question(question: &CreateQuestionPayload) -> Result<(), String> {
+    let num_answers = question.answers.len();
+    let num_correct = question.answers.iter().filter(|a| a.is_correct).count();
+
+    if !(question.points.is_finite() && question.points > 0.0) {
+        return Err(format!(
+            "Question \"{}\" must be worth more than 0 points",
+            question.question_text
+        ));
+    }
+
+    let valid = match question.question_type {
+        QuestionType::SingleChoice => num_answers >= 2 && num_correct == 1,
+        QuestionType::MultipleChoice => num_answers >= 2 && num_correct >= 1,
+        QuestionType::TrueFalse => num_answers == 2 && num_correct == 1,
+        QuestionType::Ordering => num_answers >= 2,
+        QuestionType::Matching => {
+            num_answers >= 2
+                && question.answers.iter().all(|a| {
+                    a.match_text
+                        .as_deref()
+                        .is_some_and(|m| !m.trim().is_empty())
+                })
+        }
+        QuestionType::Numeric => {
+            question.numeric_answer.is_some_and(f64::is_finite)
+                && question
+                    .numeric_tolerance
+                    .is_none_or(|tolerance| tolerance.is_finite() && tolerance >= 0.0)
+        }
+        QuestionType::ShortText => num_answers >= 1,
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Question \"{}\" is not a valid {} question",
+            question.question_text,
+            question.question_type.as_str()
+        ))
+    }
+}
+
+/// Grades a response, awarding partial credit where the question type
+/// allows it. A missing response scores 0.
+pub fn grade_question(question: &Question, response: Option<&QuestionResponse>) -> QuestionResult {
+    let credit = response.map_or(0.0, |response| credit(question, response));
+
+    QuestionResult {
+        question_id: question.id,
+        credit,
+        points: question.points,
+        earned: credit * question.points,
+        correct: credit >= 1.0,
+    }
+}
+
+// Fraction of the question's points earned, between 0 and 1
+fn credit(question: &Question, response: &QuestionResponse) -> f64 {
+    match question.question_type {
+        QuestionType::SingleChoice | QuestionType::TrueFalse => {
+            match response.selected_answer_ids.as_deref() {
+                Some([selected]) => all_or_nothing(
+                    question
+                        .answers
+                        .iter()
+                        .any(|a| a.id == *selected && a.is_correct),
+                ),
+                _ => 0.0,
+            }
+        }
+        QuestionType::MultipleChoice => {
+            // Each wrong selection cancels out a right one
+            let selected: HashSet<i64> = response
+                .selected_answer_ids
+                .iter()
+                .flatten()
+                .copied()
+                .collect();
+            let num_correct = question.answers.iter().filter(|a| a.is_correct).count();
+            let (right, wrong) = question
+                .answers
+                .iter()
+                .filter(|a| selected.contains(&a.id))
+                .fold((0usize, 0usize), |(right, wrong), a| {
+                    if a.is_correct {
+                        (right + 1, wrong)
+                    } else {
+                        (right, wrong + 1)
+                    }
+                });
+
+            fraction(right.saturating_sub(wrong), num_correct)
+        }
+        QuestionType::Ordering => {
+            let mut correct_order: Vec<_> = question.answers.iter().collect();
+            correct_order.sort_by_key(|a| (a.position, a.id));
+            let submitted = response.ordered_answer_ids.as_deref().unwrap_or_default();
+
+            let in_place = correct_order
+                .iter()
+                .zip(submitted)
+                .filter(|(answer, id)| answer.id == **id)
+                .count();
+
+            fraction(in_place, correct_order.len())
+        }
+        QuestionType::Matching => {
+            let matches = response.matches.as_ref();
+            let matched = question
+                .answers
+                .iter()
+                .filter(|a| {
+                    let submitted = matches.and_then(|m| m.get(&a.id));
+                    match (submitted, &a.match_text) {
+                        (Some(submitted), Some(expected)) => {
+                            normalize(submitted, false) == normalize(expected, false)
+                        }
+                        _ => false,
+                    }
+                })
+                .count();
+
+            fraction(matched, question.answers.len())
+        }
+        QuestionType::Numeric => match (response.numeric_answer, question.numeric_answer) {
+            (Some(submitted), Some(expected)) => {
+                all_or_nothing((submitted - expected).abs() <= question.numeric_tolerance)
+            }
+            _ => 0.0,
+        },
+        QuestionType::ShortText => match &response.text_answer {
+            Some(submitted) => {
+                let submitted = normalize(submitted, question.case_sensitive);
+                all_or_nothing(
+                    question
+                        .answers
+                        .iter()
+                        .any(|a| normalize(&a.answer_text, question.case_sensitive) == submitted),
+                )
+            }
+            None => 0.0,
+        },
+    }
+}
+
+fn all_or_nothing(correct: bool) -> f64 {
+    if correct { 1.0 } else { 0.0 }
+}
+
+fn fraction(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+// Trims and collapses whitespace so spacing differences don't cost points
+fn normalize(text: &str, case_sensitive: bool) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if case_sensitive {
+        text
+    } else {
+        text.to_lowercase()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::models::course::AnswerOption;
+
+    // Answers are (text, is_correct, match_text) and get ids 1, 2, ... in display order
+    fn question(question_type: QuestionType, answers: &[(&str, bool, Option<&str>)]) -> Question {
+        Question {
+            id: 10,
+            question_text: "Question".to_string(),
+            question_type,
+            points: 4.0,
+            answers: answers
+                .iter()
+                .enumerate()
+                .map(|(i, (answer_text, is_correct, match_text))| AnswerOption {
+                    id: i as i64 + 1,
+                    answer_text: answer_text.to_string(),
+                    is_correct: *is_correct,
+                    position: i as i32,
+                    match_text: match_text.map(str::to_string),
+                })
+                .collect(),
+            numeric_answer: None,
+            numeric_tolerance: 0.0,
+            case_sensitive: false,
+            pool: None,
+        }
+    }
+
+    fn response() -> QuestionResponse {
+        QuestionResponse {
+            question_id: 10,
+            selected_answer_ids: None,
+            ordered_answer_ids: None,
+            matches: None,
+            numeric_answer: None,
+            text_answer: None,
+            time_spent_seconds: None,
+        }
+    }
+
+    fn selected(ids: &[i64]) -> QuestionResponse {
+        QuestionResponse {
+            selected_answer_ids: Some(ids.to_vec()),
+            ..response()
+        }
+    }
+
+    fn assert_credits(question: &Question, cases: Vec<(&str, QuestionResponse, f64)>) {
+        for (case, response, credit) in cases {
+            let result = grade_question(question, Some(&response));
+            assert_eq!(result.credit, credit, "{}", case);
+            assert_eq!(result.earned, credit * question.points, "{}", case);
+            assert_eq!(result.correct, credit == 1.0, "{}", case);
+        }
+    }
+
+    #[test]
+    fn single_choice_is_all_or_nothing() {
+        let question = question(
+            QuestionType::SingleChoice,
+            &[("A", false, None), ("B", true, None), ("C", false, None)],
+        );
+        assert_credits(
+            &question,
+            vec![
+                ("right answer", selected(&[2]), 1.0),
+                ("wrong answer", selected(&[1]), 0.0),
+                ("right and wrong answer", selected(&[1, 2]), 0.0),
+                ("nothing selected", selected(&[]), 0.0),
+                ("unknown answer", selected(&[99]), 0.0),
+                ("no selection", response(), 0.0),
+            ],
+        );
+    }
+
+    #[test]
+    fn true_false_is_all_or_nothing() {
+        let question = question(
+            QuestionType::TrueFalse,
+            &[("True", false, None), ("False", true, None)],
+        );
+        assert_credits(
+            &question,
+            vec![
+                ("right answer", selected(&[2]), 1.0),
+                ("wrong answer", selected(&[1]), 0.0),
+                ("both answers", selected(&[1, 2]), 0.0),
+            ],
+        );
+    }
+
+    #[test]
+    fn multiple_choice_wrong_selections_cancel_right_ones() {
+        let question = question(
+            QuestionType::MultipleChoice,
+            &[
+                ("A", true, None),
+                ("B", true, None),
+                ("C", false, None),
+                ("D", false, None),
+            ],
+        );
+        assert_credits(
+            &question,
+            vec![
+                ("every right answer", selected(&[1, 2]), 1.0),
+                ("one of two right answers", selected(&[1]), 0.5),
+                ("duplicate selection counts once", selected(&[1, 1]), 0.5),
+                ("two right and one wrong", selected(&[1, 2, 3]), 0.5),
+                ("one right and one wrong", selected(&[1, 3]), 0.0),
+                (
+                    "more wrong than right clamps at zero",
+                    selected(&[1, 3, 4]),
+                    0.0,
+                ),
+                ("every answer", selected(&[1, 2, 3, 4]), 0.0),
+                ("only wrong answers", selected(&[3, 4]), 0.0),
+                ("no selection", response(), 0.0),
+            ],
+        );
+    }
+
+    #[test]
+    fn ordering_credits_answers_in_place() {
+        let question = question(
+            QuestionType::Ordering,
+            &[
+                ("First", true, None),
+                ("Second", true, None),
+                ("Third", true, None),
+                ("Fourth", true, None),
+            ],
+        );
+        let ordered = |ids: &[i64]| QuestionResponse {
+            ordered_answer_ids: Some(ids.to_vec()),
+            ..response()
+        };
+        assert_credits(
+            &question,
+            vec![
+                ("correct order", ordered(&[1, 2, 3, 4]), 1.0),
+                ("two swapped", ordered(&[1, 2, 4, 3]), 0.5),
+                ("reversed", ordered(&[4, 3, 2, 1]), 0.0),
+                ("shorter submission", ordered(&[1]), 0.25),
+                ("no order", response(), 0.0),
+            ],
+        );
+    }
+
+    #[test]
+    fn matching_credits_each_pair() {
+        let question = question(
+            QuestionType::Matching,
+            &[
+                ("let", false, Some("Binding")),
+                ("fn", false, Some("Function")),
+                ("mod", false, Some("Module")),
+                ("use", false, Some("Import")),
+            ],
+        );
+        let matched = |pairs: &[(i64, &str)]| QuestionResponse {
+            matches: Some(
+                pairs
+                    .iter()
+                    .map(|(id, text)| (*id, text.to_string()))
+                    .collect::<HashMap<_, _>>(),
+            ),
+            ..response()
+        };
+        assert_credits(
+            &question,
+            vec![
+                (
+                    "every pair",
+                    matched(&[
+                        (1, "Binding"),
+                        (2, "Function"),
+                        (3, "Module"),
+                        (4, "Import"),
+                    ]),
+                    1.0,
+                ),
+                (
+                    "case and spacing are ignored",
+                    matched(&[
+                        (1, " binding "),
+                        (2, "FUNCTION"),
+                        (3, "Module"),
+                        (4, "import"),
+                    ]),
+                    1.0,
+                ),
+                (
+                    "three of four pairs",
+                    matched(&[
+                        (1, "Binding"),
+                        (2, "Function"),
+                        (3, "Module"),
+                        (4, "Binding"),
+                    ]),
+                    0.75,
+                ),
+                ("one pair", matched(&[(1, "Binding")]), 0.25),
+                ("unknown answer", matched(&[(99, "Binding")]), 0.0),
+                ("no matches", response(), 0.0),
+            ],
+        );
+    }
+
+    #[test]
+    fn numeric_is_within_tolerance() {
+        let question = Question {
+            numeric_answer: Some(3.5),
+            numeric_tolerance: 0.25,
+            ..question(QuestionType::Numeric, &[])
+        };
+        let answered = |value: f64| QuestionResponse {
+            numeric_answer: Some(value),
+            ..response()
+        };
+        assert_credits(
+            &question,
+            vec![
+                ("exact answer", answered(3.5), 1.0),
+                ("at the lower bound", answered(3.25), 1.0),
+                ("at the upper bound", answered(3.75), 1.0),
+                ("outside the tolerance", answered(3.8), 0.0),
+                ("not a number", answered(f64::NAN), 0.0),
+                ("no answer", response(), 0.0),
+            ],
+        );
+    }
+
+    #[test]
+    fn short_text_accepts_any_listed_answer() {
+        let insensitive = question(
+            QuestionType::ShortText,
+            &[("cargo", true, None), ("the cargo tool", true, None)],
+        );
+        let sensitive = Question {
+            case_sensitive: true,
+            ..question(QuestionType::ShortText, &[("Cargo", true, None)])
+        };
+        let answered = |text: &str| QuestionResponse {
+            text_answer: Some(text.to_string()),
+            ..response()
+        };
+        assert_credits(
+            &insensitive,
+            vec![
+                ("first answer", answered("cargo"), 1.0),
+                (
+                    "other answer with extra spaces",
+                    answered("  the   Cargo tool "),
+                    1.0,
+                ),
+                ("wrong answer", answered("rustup"), 0.0),
+                ("empty answer", answered(""), 0.0),
+                ("no answer", response(), 0.0),
+            ],
+        );
+        assert_credits(
+            &sensitive,
+            vec![
+                ("same case", answered("Cargo"), 1.0),
+                ("other case", answered("cargo"), 0.0),
+            ],
+        );
+    }
+
+    #[test]
+    fn missing_response_scores_zero() {
+        let question = question(
+            QuestionType::SingleChoice,
+            &[("A", true, None), ("B", false, None)],
+        );
+        let result = grade_question(&question, None);
+        assert_eq!(result.credit, 0.0);
+        assert_eq!(result.earned, 0.0);
+        assert_eq!(result.points, 4.0);
+        assert!(!result.correct);
+    }
+}
@@ -0,0 +1,614 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+use chrono::{Duration, Utc};
+use sqlx::{Pool, Postgres};
+use std::collections::HashSet;
+
+use crate::handlers::course::fetch_quiz_questions;
+use crate::handlers::progress::internal_error;
+use crate::item_analysis::{AnalyzedAttempt, StoredAnswers, analyze_question, mean};
+use crate::models::analytics::{
+    AnalyzedAttemptRow, Cohort, CohortRetention, CohortRetentionQuery, CohortRetentionRow,
+    CohortWeek, CourseAnalytics, CourseAnalyticsQuery, CourseSummaryRow, CourseTimeRow,
+    DailyActivity, LessonDropOff, ModuleFunnelStep, QuizAnalytics, QuizAnalyticsQuery,
+    QuizPassRate,
+};
+use crate::signing::recover_eth_address;
+
+// Creator requests older than this, or this far in the future, are rejected
+const CREATOR_REQUEST_WINDOW_SECONDS: i64 = 300;
+const DEFAULT_SERIES_DAYS: i64 = 30;
+const MAX_SERIES_DAYS: i64 = 366;
+const DEFAULT_INACTIVE_DAYS: i64 = 14;
+
+/// Requests only the creator may make. The signed message names the endpoint,
+/// so a signature can't be replayed against another one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CreatorRequest {
+    QuizAnalytics,
+    CourseAnalytics,
+    CohortRetention,
+    ExportProgress,
+    ExportQuiz,
+    ExportCourse,
+    ExportMarkdownCourse,
+    ExportCommonCartridge,
+    ImportCourse,
+    ImportMarkdownCourse,
+    UploadScormPackage,
+}
+
+impl CreatorRequest {
+    pub fn endpoint(&self) -> &'static str {
+        match self {
+            CreatorRequest::QuizAnalytics => "/get-quiz-analytics",
+            CreatorRequest::CourseAnalytics => "/get-course-analytics",
+            CreatorRequest::CohortRetention => "/get-cohort-retention",
+            CreatorRequest::ExportProgress => "/export-progress",
+            CreatorRequest::ExportQuiz => "/export-quiz",
+            CreatorRequest::ExportCourse => "/export-course",
+            CreatorRequest::ExportMarkdownCourse => "/export-markdown-course",
+            CreatorRequest::ExportCommonCartridge => "/export-common-cartridge",
+            CreatorRequest::ImportCourse => "/import-course",
+            CreatorRequest::ImportMarkdownCourse => "/import-markdown-course",
+            CreatorRequest::UploadScormPackage => "/upload-scorm-package",
+        }
+    }
+}
+
+/// Checks a request is signed by the creator of the course.
+pub(crate) async fn authorize_creator(
+    pool: &Pool<Postgres>,
+    request: CreatorRequest,
+    course_id: i64,
+    creator_id: &str,
+    requested_at: i64,
+    signature: &str,
+) -> Result<(), (StatusCode, String)> {
+    let course_creator: String = sqlx::query_scalar("SELECT creator_id FROM course WHERE id = $1")
+        .bind(course_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Course not found".to_string()))?;
+
+    let signer = verify_creator_request(
+        pool,
+        request,
+        Some(course_id),
+        creator_id,
+        requested_at,
+        signature,
+    )
+    .await?;
+    if signer != course_creator.to_lowercase() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the course creator can make this request".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks a creator request is recent, signed by the creator's wallet and not
+/// sent before, and returns the lowercase creator address.
+pub(crate) async fn verify_creator_request(
+    pool: &Pool<Postgres>,
+    request: CreatorRequest,
+    course_id: Option<i64>,
+    creator_id: &str,
+    requested_at: i64,
+    signature: &str,
+) -> Result<String, (StatusCode, String)> {
+    if (Utc::now().timestamp() - requested_at).abs() > CREATOR_REQUEST_WINDOW_SECONDS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Creator request has expired".to_string(),
+        ));
+    }
+
+    let message = creator_request_message(request, course_id, creator_id, requested_at);
+    let signer =
+        recover_eth_address(&message, signature).map_err(|e| (StatusCode::UNAUTHORIZED, e))?;
+    if signer != creator_id.to_lowercase() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Signature is not from the creator".to_string(),
+        ));
+    }
+
+    // Each signed request is accepted once, so one leaked from a URL or a log
+    // can't be sent again
+    sqlx::query("DELETE FROM creator_request WHERE created_at < now() - make_interval(secs => $1)")
+        .bind(2.0 * CREATOR_REQUEST_WINDOW_SECONDS as f64)
+        .execute(pool)
+        .await
+        .map_err(internal_error)?;
+    let inserted =
+        sqlx::query("INSERT INTO creator_request (message) VALUES ($1) ON CONFLICT DO NOTHING")
+            .bind(&message)
+            .execute(pool)
+            .await
+            .map_err(internal_error)?;
+    if inserted.rows_affected() == 0 {
+        return Err((
+            StatusCode::CONFLICT,
+            "Creator request was already sent".to_string(),
+        ));
+    }
+
+    Ok(signer)
+}
+
+/// The message a creator signs for a request, without a course for requests
+/// that create one.
+pub fn creator_request_message(
+    request: CreatorRequest,
+    course_id: Option<i64>,
+    creator_id: &str,
+    requested_at: i64,
+) -> String {
+    let course = course_id
+        .map(|id| format!("Course: {}\n", id))
+        .unwrap_or_default();
+    format!(
+        "Aranya creator request\nEndpoint: {}\n{}Creator: {}\nRequested at: {}",
+        request.endpoint(),
+        course,
+        creator_id.to_lowercase(),
+        requested_at
+    )
+}
+
+/// Course of a module quiz or final exam.
+pub(crate) async fn fetch_quiz_course_id(
+    pool: &Pool<Postgres>,
+    quiz_id: i64,
+) -> Result<i64, (StatusCode, String)> {
+    sqlx::query_scalar(
+        r#"
+        SELECT COALESCE(q.course_id, m.course_id)
+        FROM quiz q
+        LEFT JOIN module m ON m.id = q.module_id
+        WHERE q.id = $1
+        "#,
+    )
+    .bind(quiz_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(internal_error)?
+    .ok_or_else(|| (StatusCode::NOT_FOUND, "Quiz not found".to_string()))
+}
+
+/// Item analysis of a quiz over all attempts, for the course creator.
+pub async fn get_quiz_analytics(
+    State(pool): State<Pool<Postgres>>,
+    Query(params): Query<QuizAnalyticsQuery>,
+) -> Result<Json<QuizAnalytics>, (StatusCode, String)> {
+    let course_id = fetch_quiz_course_id(&pool, params.quiz_id).await?;
+
+    authorize_creator(
+        &pool,
+        CreatorRequest::QuizAnalytics,
+        course_id,
+        &params.creator_id,
+        params.requested_at,
+        &params.signature,
+    )
+    .await?;
+
+    let questions = fetch_quiz_questions(&pool, &[params.quiz_id])
+        .await?
+        .remove(&params.quiz_id)
+        .unwrap_or_default();
+
+    let rows: Vec<AnalyzedAttemptRow> = sqlx::query_as::<_, AnalyzedAttemptRow>(
+        r#"
+        SELECT a.learner_id, a.score_percent, a.passed, i.question_ids, a.answers,
+            i.created_at AS started_at, a.submitted_at
+        FROM quiz_attempt a
+        JOIN quiz_instance i ON i.id = a.instance_id
+        WHERE a.quiz_id = $1
+        "#,
+    )
+    .bind(params.quiz_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    let learners: HashSet<&str> = rows.iter().map(|r| r.learner_id.as_str()).collect();
+    let average_score_percent = mean(rows.iter().map(|r| r.score_percent));
+    let pass_rate = mean(rows.iter().map(|r| if r.passed { 1.0 } else { 0.0 }));
+    let average_duration_seconds = mean(
+        rows.iter()
+            .map(|r| (r.submitted_at - r.started_at).num_milliseconds() as f64 / 1000.0),
+    );
+    let num_learners = learners.len() as i64;
+
+    let attempts: Vec<AnalyzedAttempt> = rows
+        .into_iter()
+        .map(|r| AnalyzedAttempt {
+            score_percent: r.score_percent,
+            question_ids: r.question_ids,
+            // Attempts recorded before answers were stored only count as exposures
+            answers: r
+                .answers
+                .and_then(|answers| serde_json::from_value::<StoredAnswers>(answers).ok())
+                .unwrap_or_default(),
+        })
+        .collect();
+
+    Ok(Json(QuizAnalytics {
+        quiz_id: params.quiz_id,
+        course_id,
+        attempts: attempts.len() as i64,
+        learners: num_learners,
+        average_score_percent,
+        pass_rate,
+        average_duration_seconds,
+        questions: questions
+            .iter()
+            .map(|question| analyze_question(question, &attempts))
+            .collect(),
+    }))
+}
+
+/// Enrollment to completion funnel of a course, for the course creator: module
+/// completions, where inactive learners stopped, quiz pass rates and a daily
+/// series of enrollments and completions.
+pub async fn get_course_analytics(
+    State(pool): State<Pool<Postgres>>,
+    Query(params): Query<CourseAnalyticsQuery>,
+) -> Result<Json<CourseAnalytics>, (StatusCode, String)> {
+    authorize_creator(
+        &pool,
+        CreatorRequest::CourseAnalytics,
+        params.course_id,
+        &params.creator_id,
+        params.requested_at,
+        &params.signature,
+    )
+    .await?;
+
+    let days = params
+        .days
+        .unwrap_or(DEFAULT_SERIES_DAYS)
+        .clamp(1, MAX_SERIES_DAYS);
+    let inactive_since =
+        Utc::now() - Duration::days(params.inactive_days.unwrap_or(DEFAULT_INACTIVE_DAYS).max(0));
+
+    let summary: CourseSummaryRow = sqlx::query_as::<_, CourseSummaryRow>(
+        r#"
+        SELECT COUNT(*) AS enrolled,
+            COUNT(c.learner_id) AS completed,
+            percentile_cont(0.5) WITHIN GROUP (
+                ORDER BY EXTRACT(EPOCH FROM c.completed_at - e.enrolled_at)::DOUBLE PRECISION
+            ) AS median_completion_seconds,
+            COUNT(*) FILTER (
+                WHERE c.learner_id IS NULL AND e.enrolled_at < $2
+                AND NOT EXISTS (
+                    SELECT 1
+                    FROM lesson_completion lc
+                    JOIN lesson l ON l.id = lc.lesson_id
+                    JOIN module m ON m.id = l.module_id
+                    WHERE lc.learner_id = e.learner_id AND m.course_id = e.course_id
+                )
+            ) AS not_started
+        FROM learner_course_enrollment e
+        LEFT JOIN course_completion c
+            ON c.learner_id = e.learner_id AND c.course_id = e.course_id
+        WHERE e.course_id = $1
+        "#,
+    )
+    .bind(params.course_id)
+    .bind(inactive_since)
+    .fetch_one(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    let time: CourseTimeRow = sqlx::query_as::<_, CourseTimeRow>(
+        r#"
+        SELECT COALESCE(ROUND(SUM(seconds)), 0)::BIGINT AS total_time_spent_seconds,
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY seconds) AS median_time_spent_seconds
+        FROM (
+            SELECT t.learner_id, SUM(t.active_seconds) AS seconds
+            FROM lesson_time t
+            JOIN lesson l ON l.id = t.lesson_id
+            JOIN module m ON m.id = l.module_id
+            WHERE m.course_id = $1
+            GROUP BY t.learner_id
+        ) per_learner
+        "#,
+    )
+    .bind(params.course_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    let modules: Vec<ModuleFunnelStep> = sqlx::query_as::<_, ModuleFunnelStep>(
+        r#"
+        SELECT m.id AS module_id, m.title, m.position,
+            COUNT(mc.learner_id) AS completed,
+            COUNT(mc.learner_id)::DOUBLE PRECISION / NULLIF($2::BIGINT, 0) AS completion_rate
+        FROM module m
+        LEFT JOIN module_completion mc ON mc.module_id = m.id
+        WHERE m.course_id = $1
+        GROUP BY m.id
+        ORDER BY m.position
+        "#,
+    )
+    .bind(params.course_id)
+    .bind(summary.enrolled)
+    .fetch_all(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    let lessons: Vec<LessonDropOff> = sqlx::query_as::<_, LessonDropOff>(
+        r#"
+        WITH last_completion AS (
+            SELECT DISTINCT ON (lc.learner_id) lc.learner_id, lc.lesson_id, lc.completed_at
+            FROM lesson_completion lc
+            JOIN lesson l ON l.id = lc.lesson_id
+            JOIN module m ON m.id = l.module_id
+            WHERE m.course_id = $1
+            ORDER BY lc.learner_id, lc.completed_at DESC
+        )
+        SELECT l.id AS lesson_id, m.id AS module_id, l.title,
+            (SELECT COUNT(*) FROM lesson_completion lc WHERE lc.lesson_id = l.id) AS completed,
+            (
+                SELECT COUNT(*)
+                FROM last_completion last
+                WHERE last.lesson_id = l.id AND last.completed_at < $2
+                AND NOT EXISTS (
+                    SELECT 1 FROM course_completion c
+                    WHERE c.learner_id = last.learner_id AND c.course_id = $1
+                )
+            ) AS dropped_off,
+            (SELECT AVG(t.active_seconds) FROM lesson_time t WHERE t.lesson_id = l.id)
+                AS average_time_seconds
+        FROM lesson l
+        JOIN module m ON m.id = l.module_id
+        WHERE m.course_id = $1
+        ORDER BY m.position, l.position
+        "#,
+    )
+    .bind(params.course_id)
+    .bind(inactive_since)
+    .fetch_all(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    let quizzes: Vec<QuizPassRate> = sqlx::query_as::<_, QuizPassRate>(
+        r#"
+        SELECT q.id AS quiz_id, q.module_id,
+            COUNT(a.id) AS attempts,
+            COUNT(a.id) FILTER (WHERE a.passed) AS attempts_passed,
+            COUNT(DISTINCT a.learner_id) AS learners,
+            COUNT(DISTINCT a.learner_id) FILTER (WHERE a.passed) AS learners_passed,
+            COUNT(DISTINCT a.learner_id) FILTER (WHERE a.passed)::DOUBLE PRECISION
+                / NULLIF(COUNT(DISTINCT a.learner_id), 0) AS pass_rate
+        FROM quiz q
+        LEFT JOIN module m ON m.id = q.module_id
+        LEFT JOIN quiz_attempt a ON a.quiz_id = q.id
+        WHERE COALESCE(q.course_id, m.course_id) = $1
+        GROUP BY q.id, m.position
+        ORDER BY m.position NULLS LAST
+        "#,
+    )
+    .bind(params.course_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    let daily: Vec<DailyActivity> = sqlx::query_as::<_, DailyActivity>(
+        r#"
+        SELECT day::DATE AS date,
+            (
+                SELECT COUNT(*) FROM learner_course_enrollment e
+                WHERE e.course_id = $1 AND e.enrolled_at::DATE = day::DATE
+            ) AS enrollments,
+            (
+                SELECT COUNT(*)
+                FROM lesson_completion lc
+                JOIN lesson l ON l.id = lc.lesson_id
+                JOIN module m ON m.id = l.module_id
+                WHERE m.course_id = $1 AND lc.completed_at::DATE = day::DATE
+            ) AS lesson_completions,
+            (
+                SELECT COUNT(*) FROM course_completion c
+                WHERE c.course_id = $1 AND c.completed_at::DATE = day::DATE
+            ) AS completions
+        FROM generate_series(CURRENT_DATE - ($2::INT - 1), CURRENT_DATE, INTERVAL '1 day') AS day
+        ORDER BY day
+        "#,
+    )
+    .bind(params.course_id)
+    .bind(days as i32)
+    .fetch_all(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    Ok(Json(CourseAnalytics {
+        course_id: params.course_id,
+        enrolled: summary.enrolled,
+        completed: summary.completed,
+        completion_rate: (summary.enrolled > 0)
+            .then(|| summary.completed as f64 / summary.enrolled as f64),
+        median_completion_seconds: summary.median_completion_seconds,
+        total_time_spent_seconds: time.total_time_spent_seconds,
+        median_time_spent_seconds: time.median_time_spent_seconds,
+        not_started: summary.not_started,
+        modules,
+        lessons,
+        quizzes,
+        daily,
+    }))
+}
+
+/// Retention of the learners of a course by enrollment week, for the course
+/// creator.
+pub async fn get_cohort_retention(
+    State(pool): State<Pool<Postgres>>,
+    Query(params): Query<CohortRetentionQuery>,
+) -> Result<Json<CohortRetention>, (StatusCode, String)> {
+    authorize_creator(
+        &pool,
+        CreatorRequest::CohortRetention,
+        params.course_id,
+        &params.creator_id,
+        params.requested_at,
+        &params.signature,
+    )
+    .await?;
+
+    let rows: Vec<CohortRetentionRow> = sqlx::query_as::<_, CohortRetentionRow>(
+        r#"
+        SELECT cohort_week, week, learners, active, completed, refreshed_at
+        FROM cohort_retention
+        WHERE course_id = $1
+        ORDER BY cohort_week, week
+        "#,
+    )
+    .bind(params.course_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    Ok(Json(group_cohorts(Some(params.course_id), rows)))
+}
+
+/// Retention by enrollment week over the enrollments of every course.
+pub async fn get_platform_retention(
+    State(pool): State<Pool<Postgres>>,
+) -> Result<Json<CohortRetention>, (StatusCode, String)> {
+    let rows: Vec<CohortRetentionRow> = sqlx::query_as::<_, CohortRetentionRow>(
+        r#"
+        SELECT cohort_week, week,
+            SUM(learners)::BIGINT AS learners,
+            SUM(active)::BIGINT AS active,
+            SUM(completed)::BIGINT AS completed,
+            MAX(refreshed_at) AS refreshed_at
+        FROM cohort_retention
+        GROUP BY cohort_week, week
+        ORDER BY cohort_week, week
+        "#,
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    Ok(Json(group_cohorts(None, rows)))
+}
+
+// Rows are ordered by cohort then week
+fn group_cohorts(course_id: Option<i64>, rows: Vec<CohortRetentionRow>) -> CohortRetention {
+    let refreshed_at = rows.iter().map(|r| r.refreshed_at).max();
+    let rate = |count: i64, learners: i64| (learners > 0).then(|| count as f64 / learners as f64);
+
+    let mut cohorts: Vec<Cohort> = Vec::new();
+    for row in rows {
+        if cohorts
+            .last()
+            .is_none_or(|c| c.cohort_week != row.cohort_week)
+        {
+            cohorts.push(Cohort {
+                cohort_week: row.cohort_week,
+                learners: row.learners,
+                weeks: Vec::new(),
+            });
+        }
+        if let Some(cohort) = cohorts.last_mut() {
+            cohort.weeks.push(CohortWeek {
+                week: row.week,
+                learners: row.learners,
+                active: row.active,
+                completed: row.completed,
+                active_rate: rate(row.active, row.learners),
+                completion_rate: rate(row.completed, row.learners),
+            });
+        }
+    }
+
+    CohortRetention {
+        course_id,
+        refreshed_at,
+        cohorts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::test_support::{personal_sign, wallet_address};
+
+    const CREATOR_SECRET: [u8; 32] = [1; 32];
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures("../../database_schema/aranya_db_schema.sql")
+    )]
+    async fn creator_signature_is_bound_to_one_request(pool: PgPool) {
+        let creator = wallet_address(&CREATOR_SECRET);
+        let course_id: i64 = sqlx::query_scalar(
+            r#"
+            WITH creator AS (INSERT INTO creator (id) VALUES ($1) RETURNING id)
+            INSERT INTO course (title, description, creator_id)
+            SELECT 'Rust', 'Basics', id FROM creator RETURNING id
+            "#,
+        )
+        .bind(&creator)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let now = Utc::now().timestamp();
+        let sign = |request, requested_at| {
+            personal_sign(
+                &CREATOR_SECRET,
+                &creator_request_message(request, Some(course_id), &creator, requested_at),
+            )
+        };
+        let authorize = |request, requested_at, signature: String| {
+            let pool = pool.clone();
+            let creator = creator.clone();
+            async move {
+                authorize_creator(
+                    &pool,
+                    request,
+                    course_id,
+                    &creator,
+                    requested_at,
+                    &signature,
+                )
+                .await
+                .map_err(|(status, _)| status)
+            }
+        };
+
+        let export = sign(CreatorRequest::ExportCourse, now);
+        assert_eq!(
+            authorize(CreatorRequest::ExportCourse, now, export.clone()).await,
+            Ok(())
+        );
+        assert_eq!(
+            authorize(CreatorRequest::ExportCourse, now, export.clone()).await,
+            Err(StatusCode::CONFLICT)
+        );
+        assert_eq!(
+            authorize(CreatorRequest::CourseAnalytics, now, export).await,
+            Err(StatusCode::FORBIDDEN)
+        );
+
+        let stale = now - 2 * CREATOR_REQUEST_WINDOW_SECONDS;
+        let signature = sign(CreatorRequest::CourseAnalytics, stale);
+        assert_eq!(
+            authorize(CreatorRequest::CourseAnalytics, stale, signature).await,
+            Err(StatusCode::BAD_REQUEST)
+        );
+    }
+}
@@ -0,0 +1,503 @@
+use axum::{
+    Json,
+    body::{Body, to_bytes},
+    extract::{FromRef, Query, Request, State},
+    http::{HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Duration, SecondsFormat, SubsecRound, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres};
+use std::collections::BTreeMap;
+
+use crate::fdc::{AttestationKind, worker::retry_span};
+use crate::handlers::progress::{fetch_progress_percent, internal_error};
+use crate::models::attestation::{
+    AttestationNonceRequest, AttestationNonceResponse, AttestationNonceRow, AttestationSnapshot,
+    AttestationSnapshotResponse, PublicKeyResponse, SnapshotQuery,
+};
+use crate::signing::{ServerKey, recover_eth_address};
+
+pub const SNAPSHOT_ID_HEADER: &str = "x-attestation-snapshot-id";
+
+// Time to create the job before its retries start and to submit the proof after them
+const NONCE_SLACK_MINUTES: i64 = 15;
+
+// Maximum clock skew accepted on the signed `requestedAt` of a nonce request
+const NONCE_REQUEST_WINDOW_SECONDS: i64 = 300;
+
+const MAX_LEARNER_MILESTONE: i32 = 4;
+
+#[derive(Clone)]
+pub struct AttestationState {
+    pub pool: Pool<Postgres>,
+    pub key: ServerKey,
+}
+
+impl FromRef<AttestationState> for Pool<Postgres> {
+    fn from_ref(state: &AttestationState) -> Pool<Postgres> {
+        state.pool.clone()
+    }
+}
+
+/// Records every response served to the FDC verifier so the attested value
+/// can be audited later. The snapshot id is returned in a response header.
+pub async fn record_snapshot(
+    State(state): State<AttestationState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, String)> {
+    let endpoint = request.uri().path().to_string();
+    let request_params = Query::<BTreeMap<String, String>>::try_from_uri(request.uri())
+        .map(|Query(params)| params)
+        .unwrap_or_default();
+
+    let response = next.run(request).await;
+    let (mut parts, body) = response.into_parts();
+    let body = to_bytes(body, usize::MAX).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to read response body: {}", e),
+        )
+    })?;
+
+    let request_params = serde_json::to_value(request_params).map_err(internal_error)?;
+    let response_body = String::from_utf8_lossy(&body).into_owned();
+    let response_hash = hex::encode(Sha256::digest(&body));
+    let created_at = Utc::now().trunc_subsecs(6);
+
+    let message = snapshot_message(&endpoint, &request_params, &created_at, &response_hash);
+    let signature = state.key.sign_hex(message.as_bytes());
+
+    let snapshot_id: i64 = sqlx::query_scalar(
+        r#"
+        INSERT INTO attestation_snapshot
+            (endpoint, request_params, status_code, response_body, response_hash, signature, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id
+        "#,
+    )
+    .bind(&endpoint)
+    .bind(&request_params)
+    .bind(parts.status.as_u16() as i32)
+    .bind(&response_body)
+    .bind(&response_hash)
+    .bind(&signature)
+    .bind(created_at)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to record attestation snapshot: {}", e),
+        )
+    })?;
+
+    parts
+        .headers
+        .insert(SNAPSHOT_ID_HEADER, HeaderValue::from(snapshot_id));
+
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
+
+pub async fn get_attestation_snapshot(
+    State(state): State<AttestationState>,
+    Query(params): Query<SnapshotQuery>,
+) -> Result<Json<AttestationSnapshotResponse>, (StatusCode, String)> {
+    let snapshot: Option<AttestationSnapshot> = sqlx::query_as::<_, AttestationSnapshot>(
+        r#"
+        SELECT id, endpoint, request_params, status_code, response_body, response_hash, signature, created_at
+        FROM attestation_snapshot
+        WHERE id = $1
+        "#,
+    )
+    .bind(params.snapshot_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    let Some(snapshot) = snapshot else {
+        return Err((StatusCode::NOT_FOUND, "Snapshot not found".to_string()));
+    };
+
+    let signed_message = snapshot_message(
+        &snapshot.endpoint,
+        &snapshot.request_params,
+        &snapshot.created_at,
+        &snapshot.response_hash,
+    );
+
+    Ok(Json(AttestationSnapshotResponse {
+        snapshot,
+        signed_message,
+        public_key: state.key.public_key_hex(),
+    }))
+}
+
+pub async fn get_attestation_public_key(
+    State(state): State<AttestationState>,
+) -> Json<PublicKeyResponse> {
+    Json(PublicKeyResponse {
+        public_key: state.key.public_key_hex(),
+    })
+}
+
+/// The message signed for a snapshot: endpoint, request params as key-sorted
+/// JSON, RFC 3339 timestamp with microseconds and the hex sha256 of the
+/// response body, separated by newlines.
+fn snapshot_message(
+    endpoint: &str,
+    request_params: &serde_json::Value,
+    created_at: &DateTime<Utc>,
+    response_hash: &str,
+) -> String {
+    format!(
+        "{}\n{}\n{}\n{}",
+        endpoint,
+        request_params,
+        created_at.to_rfc3339_opts(SecondsFormat::Micros, true),
+        response_hash
+    )
+}
+
+/// Issues a nonce for a single attestation. The request must be signed by
+/// the learner wallet for enrollment and learner milestones, and by the course
+/// creator for course creator and creator milestone attestations. Issuing a
+/// new nonce revokes any nonce still outstanding for the same attestation.
+pub async fn request_attestation_nonce(
+    State(pool): State<Pool<Postgres>>,
+    Json(payload): Json<AttestationNonceRequest>,
+) -> Result<Json<AttestationNonceResponse>, (StatusCode, String)> {
+    let kind = AttestationKind::parse(&payload.kind).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            "Unknown attestation kind".to_string(),
+        )
+    })?;
+
+    let milestone = match kind {
+        AttestationKind::LearnerMilestone => Some(
+            payload
+                .milestone
+                .filter(|milestone| (1..=MAX_LEARNER_MILESTONE).contains(milestone))
+                .ok_or_else(|| (StatusCode::BAD_REQUEST, "Invalid milestone".to_string()))?,
+        ),
+        _ => None,
+    };
+
+    if (Utc::now().timestamp() - payload.requested_at).abs() > NONCE_REQUEST_WINDOW_SECONDS {
+        return Err((StatusCode::BAD_REQUEST, "Nonce request has expired".to_string()));
+    }
+
+    let message = nonce_request_message(
+        kind,
+        &payload.wallet,
+        payload.course_id,
+        milestone,
+        payload.requested_at,
+    );
+    let signer = recover_eth_address(&message, &payload.signature)
+        .map_err(|e| (StatusCode::UNAUTHORIZED, e))?;
+    if signer != payload.wallet.to_lowercase() {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            "Signature does not match wallet".to_string(),
+        ));
+    }
+
+    let learner_id = match kind {
+        AttestationKind::CourseCreator | AttestationKind::CreatorMilestone => {
+            let creator_id: Option<String> =
+                sqlx::query_scalar("SELECT creator_id FROM course WHERE id = $1")
+                    .bind(payload.course_id)
+                    .fetch_optional(&pool)
+                    .await
+                    .map_err(internal_error)?;
+            let creator_id = creator_id
+                .ok_or_else(|| (StatusCode::NOT_FOUND, "Course not found".to_string()))?;
+            if creator_id.to_lowercase() != signer {
+                return Err((
+                    StatusCode::FORBIDDEN,
+                    "Wallet is not the course creator".to_string(),
+                ));
+            }
+            None
+        }
+        AttestationKind::Enrollment | AttestationKind::LearnerMilestone => {
+            // Learner ids keep the case they were enrolled with, the attested
+            // endpoints are queried with that id
+            let learner_id: Option<String> = sqlx::query_scalar(
+                "SELECT learner_id FROM learner_course_enrollment WHERE LOWER(learner_id) = $1 AND course_id = $2 LIMIT 1",
+            )
+            .bind(&signer)
+            .bind(payload.course_id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(internal_error)?;
+
+            Some(
+                learner_id.ok_or_else(|| {
+                    (StatusCode::FORBIDDEN, "Learner is not enrolled".to_string())
+                })?,
+            )
+        }
+    };
+
+    if let (Some(learner_id), Some(milestone)) = (&learner_id, milestone) {
+        let progress_percent = fetch_progress_percent(&pool, learner_id, payload.course_id).await?;
+        if i32::from(progress_percent / 25) < milestone {
+            return Err((StatusCode::CONFLICT, "Milestone not reached".to_string()));
+        }
+    }
+
+    let nonce = format!("0x{}", hex::encode(rand::random::<[u8; 32]>()));
+    let expires_at = Utc::now() + nonce_ttl();
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to begin transaction: {}", e),
+        )
+    })?;
+
+    sqlx::query(
+        r#"
+        UPDATE attestation_nonce
+        SET revoked_at = now()
+        WHERE kind = $1 AND course_id = $2 AND wallet = $3 AND milestone IS NOT DISTINCT FROM $4
+            AND revoked_at IS NULL AND expires_at > now()
+        "#,
+    )
+    .bind(kind.as_str())
+    .bind(payload.course_id)
+    .bind(&signer)
+    .bind(milestone)
+    .execute(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO attestation_nonce
+            (nonce, kind, course_id, wallet, learner_id, milestone, expires_at, request_signature)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (request_signature) DO NOTHING
+        "#,
+    )
+    .bind(&nonce)
+    .bind(kind.as_str())
+    .bind(payload.course_id)
+    .bind(&signer)
+    .bind(&learner_id)
+    .bind(milestone)
+    .bind(expires_at)
+    .bind(payload.signature.to_lowercase())
+    .execute(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+
+    if inserted.rows_affected() == 0 {
+        return Err((
+            StatusCode::CONFLICT,
+            "Nonce request signature already used".to_string(),
+        ));
+    }
+
+    tx.commit().await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to commit transaction: {}", e),
+        )
+    })?;
+
+    Ok(Json(AttestationNonceResponse {
+        nonce,
+        kind: kind.as_str().to_string(),
+        course_id: payload.course_id,
+        learner_id,
+        milestone,
+        expires_at: expires_at.timestamp(),
+    }))
+}
+
+/// Looks up a nonce that is neither expired, revoked nor used yet, before an
+/// attestation job is created for it.
+pub(crate) async fn find_outstanding_nonce(
+    pool: &Pool<Postgres>,
+    kind: AttestationKind,
+    nonce: &str,
+    course_id: i64,
+    learner_id: Option<&str>,
+) -> Result<AttestationNonceRow, (StatusCode, String)> {
+    let row: Option<AttestationNonceRow> = sqlx::query_as::<_, AttestationNonceRow>(
+        r#"
+        SELECT nonce, learner_id, milestone, expires_at
+        FROM attestation_nonce
+        WHERE nonce = $1 AND kind = $2 AND course_id = $3 AND LOWER(learner_id) IS NOT DISTINCT FROM LOWER($4)
+            AND revoked_at IS NULL AND used_at IS NULL AND expires_at > now()
+        "#,
+    )
+    .bind(nonce.to_lowercase())
+    .bind(kind.as_str())
+    .bind(course_id)
+    .bind(learner_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(internal_error)?;
+
+    row.ok_or_else(|| {
+        (
+            StatusCode::FORBIDDEN,
+            "Invalid or expired attestation nonce".to_string(),
+        )
+    })
+}
+
+/// Checks the nonce an attested endpoint is queried with and marks it used.
+/// Every FDC attestation provider queries the endpoint independently and the
+/// worker retries the request, so the nonce keeps answering for as long as the
+/// job can retry after its first use.
+pub(crate) async fn consume_nonce(
+    pool: &Pool<Postgres>,
+    kind: AttestationKind,
+    nonce: &str,
+    course_id: i64,
+    learner_id: Option<&str>,
+) -> Result<AttestationNonceRow, (StatusCode, String)> {
+    let row: Option<AttestationNonceRow> = sqlx::query_as::<_, AttestationNonceRow>(
+        r#"
+        UPDATE attestation_nonce
+        SET used_at = COALESCE(used_at, now())
+        WHERE nonce = $1 AND kind = $2 AND course_id = $3 AND LOWER(learner_id) IS NOT DISTINCT FROM LOWER($4)
+            AND revoked_at IS NULL AND expires_at > now()
+            AND (used_at IS NULL OR used_at > now() - make_interval(secs => $5))
+        RETURNING nonce, learner_id, milestone, expires_at
+        "#,
+    )
+    .bind(nonce.to_lowercase())
+    .bind(kind.as_str())
+    .bind(course_id)
+    .bind(learner_id)
+    .bind(retry_span().num_seconds() as f64)
+    .fetch_optional(pool)
+    .await
+    .map_err(internal_error)?;
+
+    row.ok_or_else(|| {
+        (
+            StatusCode::FORBIDDEN,
+            "Invalid, expired or used attestation nonce".to_string(),
+        )
+    })
+}
+
+/// A nonce outlives every retry of its attestation job.
+fn nonce_ttl() -> Duration {
+    retry_span() + Duration::minutes(NONCE_SLACK_MINUTES)
+}
+
+/// The message the learner or course creator signs with `personal_sign` to
+/// request a nonce. The milestone line is only present for learner milestones.
+pub fn nonce_request_message(
+    kind: AttestationKind,
+    wallet: &str,
+    course_id: i64,
+    milestone: Option<i32>,
+    requested_at: i64,
+) -> String {
+    let milestone = milestone
+        .map(|milestone| format!("Milestone: {}\n", milestone))
+        .unwrap_or_default();
+    format!(
+        "Aranya attestation request\nKind: {}\nWallet: {}\nCourse: {}\n{}Requested at: {}",
+        kind.as_str(),
+        wallet.to_lowercase(),
+        course_id,
+        milestone,
+        requested_at
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::handlers::progress::is_enrolled;
+    use crate::models::progress::AttestedEnrollmentQuery;
+    use crate::test_support::{personal_sign, wallet_address};
+
+    const CREATOR_SECRET: [u8; 32] = [1; 32];
+    const LEARNER_SECRET: [u8; 32] = [3; 32];
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures("../../database_schema/aranya_db_schema.sql")
+    )]
+    async fn nonce_matches_the_enrollment_of_a_checksummed_wallet(pool: PgPool) {
+        let wallet = wallet_address(&LEARNER_SECRET);
+        // Enrolled with the mixed case id the wallet reported
+        let enrolled_id = format!("0x{}", wallet[2..].to_uppercase());
+        let course_id: i64 = sqlx::query_scalar(
+            r#"
+            WITH creator AS (
+                INSERT INTO creator (id) VALUES ($1) RETURNING id
+            ), course AS (
+                INSERT INTO course (title, description, creator_id)
+                SELECT 'Rust', 'Basics', id FROM creator RETURNING id
+            ), learner AS (
+                INSERT INTO learner (id) VALUES ($2) RETURNING id
+            )
+            INSERT INTO learner_course_enrollment (learner_id, course_id)
+            SELECT learner.id, course.id FROM learner, course
+            RETURNING course_id
+            "#,
+        )
+        .bind(wallet_address(&CREATOR_SECRET))
+        .bind(&enrolled_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        let requested_at = Utc::now().timestamp();
+        let message = nonce_request_message(
+            AttestationKind::Enrollment,
+            &wallet,
+            course_id,
+            None,
+            requested_at,
+        );
+        let Json(issued) = request_attestation_nonce(
+            State(pool.clone()),
+            Json(AttestationNonceRequest {
+                kind: "enrollment".to_string(),
+                course_id,
+                wallet: wallet.clone(),
+                milestone: None,
+                requested_at,
+                signature: personal_sign(&LEARNER_SECRET, &message),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(issued.learner_id.as_deref(), Some(enrolled_id.as_str()));
+        assert!(issued.expires_at >= (Utc::now() + retry_span()).timestamp());
+
+        // Every provider and every retry of the job gets the same answer
+        for learner_id in [&enrolled_id, &wallet] {
+            let Json(enrollment) = is_enrolled(
+                State(pool.clone()),
+                Query(AttestedEnrollmentQuery {
+                    course_id,
+                    learner_id: learner_id.clone(),
+                    nonce: issued.nonce.clone(),
+                }),
+            )
+            .await
+            .unwrap();
+            assert_eq!(enrollment.nonce, issued.nonce);
+            assert_eq!(enrollment.expires_at, issued.expires_at);
+        }
+    }
+}
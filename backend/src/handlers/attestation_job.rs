@@ -0,0 +1,190 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde_json::json;
+use sqlx::{Pool, Postgres};
+
+use crate::fdc::{
+    AttestationKind,
+    worker::{STATUS_PENDING, STATUS_PREPARED, STATUS_SUBMITTED},
+};
+use crate::handlers::attestation::find_outstanding_nonce;
+use crate::handlers::progress::internal_error;
+use crate::models::attestation_job::{
+    AttestationJob, AttestationJobQuery, AttestationJobsQuery, CreateAttestationJobRequest,
+};
+
+const MAX_JOBS_PER_HOUR: i64 = 5;
+
+/// Creates a job for a nonce issued by `/request-attestation-nonce`. A nonce
+/// gets a single job, and while a job for the same attestation is in progress
+/// it is returned instead of creating another one.
+pub async fn create_attestation_job(
+    State(pool): State<Pool<Postgres>>,
+    Json(payload): Json<CreateAttestationJobRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let kind = AttestationKind::parse(&payload.kind).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            "Unknown attestation kind".to_string(),
+        )
+    })?;
+
+    let learner_id = match kind {
+        AttestationKind::Enrollment | AttestationKind::LearnerMilestone => Some(
+            payload
+                .learner_id
+                .clone()
+                .ok_or_else(|| (StatusCode::BAD_REQUEST, "learnerId is required".to_string()))?,
+        ),
+        AttestationKind::CourseCreator | AttestationKind::CreatorMilestone => None,
+    };
+
+    let nonce = payload
+        .nonce
+        .as_deref()
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, "nonce is required".to_string()))?;
+    let nonce =
+        find_outstanding_nonce(&pool, kind, nonce, payload.course_id, learner_id.as_deref())
+            .await?;
+    // The id the learner enrolled with, whatever case the request used
+    let learner_id = nonce.learner_id.clone();
+
+    // Query params are sent as strings, the verifier's jq output is ABI encoded
+    let query_params = match &learner_id {
+        Some(learner_id) => json!({
+            "learnerId": learner_id,
+            "courseId": payload.course_id.to_string(),
+            "nonce": nonce.nonce,
+        }),
+        None => json!({ "courseId": payload.course_id.to_string(), "nonce": nonce.nonce }),
+    };
+
+    // Every job makes the backend wallet pay the FdcHub fee
+    let recent_jobs: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*)
+        FROM attestation_job
+        WHERE kind = $1 AND course_id = $2 AND learner_id IS NOT DISTINCT FROM $3
+            AND created_at > now() - make_interval(hours => 1)
+        "#,
+    )
+    .bind(kind.as_str())
+    .bind(payload.course_id)
+    .bind(&learner_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    if recent_jobs >= MAX_JOBS_PER_HOUR {
+        return Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            "Too many attestation jobs, try again later".to_string(),
+        ));
+    }
+
+    let job: Option<AttestationJob> = sqlx::query_as::<_, AttestationJob>(
+        r#"
+        INSERT INTO attestation_job
+            (kind, course_id, learner_id, milestone, nonce, query_params, status)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT DO NOTHING
+        RETURNING id, kind, course_id, learner_id, milestone, status, round_id, proof, attempts,
+            last_error, created_at, updated_at
+        "#,
+    )
+    .bind(kind.as_str())
+    .bind(payload.course_id)
+    .bind(&learner_id)
+    .bind(nonce.milestone)
+    .bind(&nonce.nonce)
+    .bind(query_params.to_string())
+    .bind(STATUS_PENDING)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to create attestation job: {}", e),
+        )
+    })?;
+
+    if let Some(job) = job {
+        return Ok((StatusCode::CREATED, Json(job)));
+    }
+
+    // The nonce already has a job or the same attestation is in progress
+    let job: AttestationJob = sqlx::query_as::<_, AttestationJob>(
+        r#"
+        SELECT id, kind, course_id, learner_id, milestone, status, round_id, proof, attempts,
+            last_error, created_at, updated_at
+        FROM attestation_job
+        WHERE nonce = $1
+            OR (kind = $2 AND course_id = $3 AND learner_id IS NOT DISTINCT FROM $4
+                AND milestone IS NOT DISTINCT FROM $5 AND status = ANY($6))
+        ORDER BY id DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(&nonce.nonce)
+    .bind(kind.as_str())
+    .bind(payload.course_id)
+    .bind(&learner_id)
+    .bind(nonce.milestone)
+    .bind(&[STATUS_PENDING, STATUS_PREPARED, STATUS_SUBMITTED][..])
+    .fetch_one(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    Ok((StatusCode::OK, Json(job)))
+}
+
+pub async fn get_attestation_job(
+    State(pool): State<Pool<Postgres>>,
+    Query(params): Query<AttestationJobQuery>,
+) -> Result<Json<AttestationJob>, (StatusCode, String)> {
+    let job: Option<AttestationJob> = sqlx::query_as::<_, AttestationJob>(
+        r#"
+        SELECT id, kind, course_id, learner_id, milestone, status, round_id, proof, attempts,
+            last_error, created_at, updated_at
+        FROM attestation_job
+        WHERE id = $1
+        "#,
+    )
+    .bind(params.job_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    job.map(Json).ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            "Attestation job not found".to_string(),
+        )
+    })
+}
+
+pub async fn get_attestation_jobs(
+    State(pool): State<Pool<Postgres>>,
+    Query(params): Query<AttestationJobsQuery>,
+) -> Result<Json<Vec<AttestationJob>>, (StatusCode, String)> {
+    let jobs: Vec<AttestationJob> = sqlx::query_as::<_, AttestationJob>(
+        r#"
+        SELECT id, kind, course_id, learner_id, milestone, status, round_id, proof, attempts,
+            last_error, created_at, updated_at
+        FROM attestation_job
+        WHERE course_id = $1 AND ($2::TEXT IS NULL OR learner_id = $2)
+        ORDER BY id DESC
+        "#,
+    )
+    .bind(params.course_id)
+    .bind(&params.learner_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    Ok(Json(jobs))
+}
@@ -0,0 +1,485 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use chrono::{SecondsFormat, Utc};
+use serde_json::{Value, json};
+use sqlx::{Pool, Postgres};
+
+use crate::handlers::credential::{CredentialState, VC_JWT_TYPE, wallet_did};
+use crate::handlers::nft::{CREATOR_MILESTONE_THRESHOLDS, MILESTONE_IMAGES, MILESTONE_NAMES};
+use crate::handlers::progress::internal_error;
+use crate::models::badge::{
+    BadgeAssertionPath, BadgeAssertionResponse, BadgeAssertionRow, BadgeCourseRow, BadgesQuery,
+    CourseAchievementPath, CreatorMilestoneAchievementPath, VerifyBadgeRequest,
+    VerifyBadgeResponse,
+};
+
+const OB_CONTEXT: &str = "https://purl.imsglobal.org/spec/ob/v3p0/context-3.0.3.json";
+const VC_CONTEXT: &str = "https://www.w3.org/2018/credentials/v1";
+
+const KIND_COURSE_COMPLETION: &str = "course_completion";
+const KIND_CREATOR_MILESTONE: &str = "creator_milestone";
+
+// The seed milestone is reached on course creation and gets no badge
+const FIRST_CREATOR_BADGE_MILESTONE: usize = 1;
+
+async fn fetch_badge_course(
+    pool: &Pool<Postgres>,
+    course_id: i64,
+) -> Result<BadgeCourseRow, (StatusCode, String)> {
+    sqlx::query_as::<_, BadgeCourseRow>(
+        r#"
+        SELECT c.title, c.description, c.creator_id,
+            (SELECT COUNT(*) FROM module m WHERE m.course_id = c.id) AS module_count,
+            (SELECT COUNT(*) FROM lesson l JOIN module m ON l.module_id = m.id
+                WHERE m.course_id = c.id) AS lesson_count,
+            (SELECT COUNT(*) FROM quiz q JOIN module m ON q.module_id = m.id
+                WHERE m.course_id = c.id) AS module_quiz_count,
+            (SELECT q.passing_score_percent FROM quiz q WHERE q.course_id = c.id)
+                AS final_exam_passing_score
+        FROM course c
+        WHERE c.id = $1
+        "#,
+    )
+    .bind(course_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(internal_error)?
+    .ok_or_else(|| (StatusCode::NOT_FOUND, "Course not found".to_string()))
+}
+
+fn issuer_profile(state: &CredentialState) -> Value {
+    json!({
+        "id": state.key.did(),
+        "type": ["Profile"],
+        "name": "Aranya",
+        "url": state.public_url("/"),
+    })
+}
+
+fn course_achievement(state: &CredentialState, course_id: i64, course: &BadgeCourseRow) -> Value {
+    json!({
+        "id": state.public_url(&format!("/badges/achievements/course/{}", course_id)),
+        "type": ["Achievement"],
+        "achievementType": "Course",
+        "name": course.title,
+        "description": course.description,
+        "criteria": {
+            "narrative": completion_criteria(course),
+        },
+        "image": {
+            "id": MILESTONE_IMAGES[MILESTONE_IMAGES.len() - 1],
+            "type": "Image",
+        },
+        "creator": issuer_profile(state),
+    })
+}
+
+/// What `complete_course` requires of a learner, in words: every lesson and
+/// module quiz of every module, then the final exam if the course has one.
+fn completion_criteria(course: &BadgeCourseRow) -> String {
+    let count =
+        |n: i64, one: &str, many: &str| format!("{} {}", n, if n == 1 { one } else { many });
+
+    let lessons = format!(
+        "Complete all {} of the {} of \"{}\" on Aranya",
+        count(course.lesson_count, "lesson", "lessons"),
+        count(course.module_count, "module", "modules"),
+        course.title
+    );
+    let quizzes = (course.module_quiz_count > 0).then(|| {
+        format!(
+            "pass {}",
+            count(course.module_quiz_count, "module quiz", "module quizzes")
+        )
+    });
+    let final_exam = course.final_exam_passing_score.map(|passing_score| {
+        format!(
+            "pass the final exam with a score of at least {}%",
+            passing_score
+        )
+    });
+
+    match (quizzes, final_exam) {
+        (Some(quizzes), Some(final_exam)) => {
+            format!("{}, {} and {}.", lessons, quizzes, final_exam)
+        }
+        (Some(requirement), None) | (None, Some(requirement)) => {
+            format!("{} and {}.", lessons, requirement)
+        }
+        (None, None) => format!("{}.", lessons),
+    }
+}
+
+fn creator_milestone_achievement(
+    state: &CredentialState,
+    course_id: i64,
+    course: &BadgeCourseRow,
+    milestone: usize,
+) -> Value {
+    let threshold = CREATOR_MILESTONE_THRESHOLDS[milestone];
+    json!({
+        "id": state.public_url(&format!(
+            "/badges/achievements/course/{}/creator-milestone/{}",
+            course_id, milestone
+        )),
+        "type": ["Achievement"],
+        "achievementType": "Achievement",
+        "name": format!("{} - Teacher Tree - {}", course.title, MILESTONE_NAMES[milestone]),
+        "description": format!(
+            "Awarded to the creator of \"{}\" when {} learners completed the course.",
+            course.title, threshold
+        ),
+        "criteria": {
+            "narrative": format!("Have {} learners complete \"{}\" on Aranya.", threshold, course.title),
+        },
+        "image": {
+            "id": MILESTONE_IMAGES[milestone],
+            "type": "Image",
+        },
+        "creator": issuer_profile(state),
+    })
+}
+
+fn is_creator_badge_milestone(milestone: usize) -> bool {
+    (FIRST_CREATOR_BADGE_MILESTONE..CREATOR_MILESTONE_THRESHOLDS.len()).contains(&milestone)
+}
+
+/// Issues an OpenBadgeCredential for `achievement` unless the recipient
+/// already holds one.
+async fn issue_badge(
+    state: &CredentialState,
+    kind: &str,
+    course_id: i64,
+    recipient_id: &str,
+    milestone: i32,
+    achievement: Value,
+) -> Result<(), (StatusCode, String)> {
+    let exists: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM badge_assertion
+            WHERE kind = $1 AND course_id = $2 AND recipient_id = $3 AND milestone = $4
+        )
+        "#,
+    )
+    .bind(kind)
+    .bind(course_id)
+    .bind(recipient_id.to_lowercase())
+    .bind(milestone)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    if exists {
+        return Ok(());
+    }
+
+    // The id is part of the signed assertion, so it is reserved before inserting
+    let assertion_id: i64 =
+        sqlx::query_scalar("SELECT nextval(pg_get_serial_sequence('badge_assertion', 'id'))")
+            .fetch_one(&state.pool)
+            .await
+            .map_err(internal_error)?;
+
+    let issued_at = Utc::now();
+    let assertion_url = state.public_url(&format!("/badges/assertions/{}", assertion_id));
+    let subject = wallet_did(recipient_id);
+
+    let claims = json!({
+        "iss": state.key.did(),
+        "sub": subject,
+        "jti": assertion_url,
+        "nbf": issued_at.timestamp(),
+        "iat": issued_at.timestamp(),
+        "vc": {
+            "@context": [VC_CONTEXT, OB_CONTEXT],
+            "id": assertion_url,
+            "type": ["VerifiableCredential", "OpenBadgeCredential"],
+            "issuer": issuer_profile(state),
+            "issuanceDate": issued_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            "name": achievement["name"],
+            "credentialSubject": {
+                "id": subject,
+                "type": ["AchievementSubject"],
+                "achievement": achievement,
+            },
+        },
+    });
+    let jwt = state.key.sign_jwt(VC_JWT_TYPE, &claims);
+
+    sqlx::query(
+        r#"
+        INSERT INTO badge_assertion (id, kind, course_id, recipient_id, milestone, jwt, issued_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (kind, course_id, recipient_id, milestone) DO NOTHING
+        "#,
+    )
+    .bind(assertion_id)
+    .bind(kind)
+    .bind(course_id)
+    .bind(recipient_id.to_lowercase())
+    .bind(milestone)
+    .bind(&jwt)
+    .bind(issued_at)
+    .execute(&state.pool)
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to insert badge assertion: {}", e),
+        )
+    })?;
+
+    Ok(())
+}
+
+/// Issues the course completion badge to the learner and any creator
+/// milestone badges the new completion count unlocks.
+pub(crate) async fn issue_completion_badges(
+    state: &CredentialState,
+    learner_id: &str,
+    course_id: i64,
+) -> Result<(), (StatusCode, String)> {
+    let course = fetch_badge_course(&state.pool, course_id).await?;
+
+    issue_badge(
+        state,
+        KIND_COURSE_COMPLETION,
+        course_id,
+        learner_id,
+        0,
+        course_achievement(state, course_id, &course),
+    )
+    .await?;
+
+    let num_completed: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM course_completion WHERE course_id = $1")
+            .bind(course_id)
+            .fetch_one(&state.pool)
+            .await
+            .map_err(internal_error)?;
+
+    let reached = CREATOR_MILESTONE_THRESHOLDS
+        .iter()
+        .enumerate()
+        .skip(FIRST_CREATOR_BADGE_MILESTONE)
+        .take_while(|(_, threshold)| num_completed >= **threshold);
+
+    for (milestone, _) in reached {
+        issue_badge(
+            state,
+            KIND_CREATOR_MILESTONE,
+            course_id,
+            &course.creator_id,
+            milestone as i32,
+            creator_milestone_achievement(state, course_id, &course, milestone),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn get_course_achievement(
+    State(state): State<CredentialState>,
+    Path(path): Path<CourseAchievementPath>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    let course = fetch_badge_course(&state.pool, path.course_id).await?;
+
+    let mut achievement = course_achievement(&state, path.course_id, &course);
+    achievement["@context"] = json!([OB_CONTEXT]);
+
+    Ok(Json(achievement))
+}
+
+pub async fn get_creator_milestone_achievement(
+    State(state): State<CredentialState>,
+    Path(path): Path<CreatorMilestoneAchievementPath>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    if !is_creator_badge_milestone(path.milestone) {
+        return Err((StatusCode::NOT_FOUND, "Milestone not found".to_string()));
+    }
+
+    let course = fetch_badge_course(&state.pool, path.course_id).await?;
+
+    let mut achievement =
+        creator_milestone_achievement(&state, path.course_id, &course, path.milestone);
+    achievement["@context"] = json!([OB_CONTEXT]);
+
+    Ok(Json(achievement))
+}
+
+pub async fn get_badge_issuer(State(state): State<CredentialState>) -> Json<Value> {
+    let mut profile = issuer_profile(&state);
+    profile["@context"] = json!([OB_CONTEXT]);
+
+    Json(profile)
+}
+
+fn assertion_response(
+    state: &CredentialState,
+    row: BadgeAssertionRow,
+) -> Result<BadgeAssertionResponse, (StatusCode, String)> {
+    let claims = state
+        .key
+        .verify_jwt(&row.jwt)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(BadgeAssertionResponse {
+        id: state.public_url(&format!("/badges/assertions/{}", row.id)),
+        kind: row.kind,
+        course_id: row.course_id,
+        recipient_id: row.recipient_id,
+        milestone: row.milestone,
+        jwt: row.jwt,
+        credential: claims["vc"].clone(),
+        issued_at: row.issued_at,
+    })
+}
+
+pub async fn get_badge_assertion(
+    State(state): State<CredentialState>,
+    Path(path): Path<BadgeAssertionPath>,
+) -> Result<Json<BadgeAssertionResponse>, (StatusCode, String)> {
+    let row: BadgeAssertionRow = sqlx::query_as::<_, BadgeAssertionRow>(
+        r#"
+        SELECT id, kind, course_id, recipient_id, milestone, jwt, issued_at
+        FROM badge_assertion
+        WHERE id = $1
+        "#,
+    )
+    .bind(path.assertion_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(internal_error)?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            "Badge assertion not found".to_string(),
+        )
+    })?;
+
+    Ok(Json(assertion_response(&state, row)?))
+}
+
+pub async fn get_badges(
+    State(state): State<CredentialState>,
+    Query(params): Query<BadgesQuery>,
+) -> Result<Json<Vec<BadgeAssertionResponse>>, (StatusCode, String)> {
+    let rows: Vec<BadgeAssertionRow> = sqlx::query_as::<_, BadgeAssertionRow>(
+        r#"
+        SELECT id, kind, course_id, recipient_id, milestone, jwt, issued_at
+        FROM badge_assertion
+        WHERE recipient_id = $1
+        ORDER BY issued_at DESC
+        "#,
+    )
+    .bind(params.recipient_id.to_lowercase())
+    .fetch_all(&state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    let badges = rows
+        .into_iter()
+        .map(|row| assertion_response(&state, row))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Json(badges))
+}
+
+/// Checks the signature and issuer of a badge JWT and that the assertion
+/// was issued by this backend.
+pub async fn verify_badge(
+    State(state): State<CredentialState>,
+    Json(payload): Json<VerifyBadgeRequest>,
+) -> Result<Json<VerifyBadgeResponse>, (StatusCode, String)> {
+    let invalid = |error: &str, credential: Option<Value>| {
+        Json(VerifyBadgeResponse {
+            valid: false,
+            credential,
+            error: Some(error.to_string()),
+        })
+    };
+
+    let claims = match state.key.verify_jwt(payload.jwt.trim()) {
+        Ok(claims) => claims,
+        Err(e) => return Ok(invalid(&e, None)),
+    };
+    let credential = Some(claims["vc"].clone());
+
+    if claims["iss"] != state.key.did().as_str() {
+        return Ok(invalid("Unknown issuer", credential));
+    }
+
+    let assertion_id = claims["jti"]
+        .as_str()
+        .and_then(|url| url.rsplit('/').next())
+        .and_then(|id| id.parse::<i64>().ok());
+    let Some(assertion_id) = assertion_id else {
+        return Ok(invalid("Missing assertion id", credential));
+    };
+
+    let exists: bool =
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM badge_assertion WHERE id = $1)")
+            .bind(assertion_id)
+            .fetch_one(&state.pool)
+            .await
+            .map_err(internal_error)?;
+
+    if !exists {
+        return Ok(invalid("Badge assertion not found", credential));
+    }
+
+    Ok(Json(VerifyBadgeResponse {
+        valid: true,
+        credential,
+        error: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn course(module_quiz_count: i64, final_exam_passing_score: Option<f64>) -> BadgeCourseRow {
+        BadgeCourseRow {
+            title: "Rust basics".to_string(),
+            description: "Ownership & borrowing".to_string(),
+            creator_id: "0xabc".to_string(),
+            module_count: 2,
+            lesson_count: 3,
+            module_quiz_count,
+            final_exam_passing_score,
+        }
+    }
+
+    #[test]
+    fn criteria_list_every_requirement_of_the_course() {
+        for (course, narrative) in [
+            (
+                course(0, None),
+                "Complete all 3 lessons of the 2 modules of \"Rust basics\" on Aranya.",
+            ),
+            (
+                course(1, None),
+                "Complete all 3 lessons of the 2 modules of \"Rust basics\" on Aranya \
+                 and pass 1 module quiz.",
+            ),
+            (
+                course(0, Some(80.0)),
+                "Complete all 3 lessons of the 2 modules of \"Rust basics\" on Aranya \
+                 and pass the final exam with a score of at least 80%.",
+            ),
+            (
+                course(2, Some(72.5)),
+                "Complete all 3 lessons of the 2 modules of \"Rust basics\" on Aranya, \
+                 pass 2 module quizzes and pass the final exam with a score of at least 72.5%.",
+            ),
+        ] {
+            assert_eq!(completion_criteria(&course), narrative);
+        }
+    }
+}
@@ -0,0 +1,563 @@
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use chrono::Utc;
+use sqlx::{Pool, Postgres, Transaction};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::bundle::{
+    BUNDLE_FORMAT_VERSION, Bundle, MediaFile, bundle_course, is_media_content_type, media_path,
+    media_refs, read_bundle, write_bundle,
+};
+use crate::handlers::analytics::{CreatorRequest, authorize_creator, verify_creator_request};
+use crate::handlers::course::{fetch_quiz_questions, insert_course, validate_course};
+use crate::handlers::progress::internal_error;
+use crate::models::bundle::{
+    BundleLesson, BundleManifest, BundleMedia, BundleModule, BundleQuizRow, ExportCourseQuery,
+    ImportCourseQuery, ImportedCourse, MediaPath, MediaRow,
+};
+use crate::models::course::{
+    CreateDrawRulePayload, CreateQuizPayload, LessonRow, ModuleRow, ScoringPolicy,
+};
+use crate::quiz_format::question_payload;
+use crate::state::AppState;
+
+/// Downloads a course as a bundle, for the course creator only since quizzes
+/// include their answer key.
+pub async fn export_course(
+    State(state): State<AppState>,
+    Query(params): Query<ExportCourseQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    authorize_creator(
+        &state.pool,
+        CreatorRequest::ExportCourse,
+        params.course_id,
+        &params.creator_id,
+        params.requested_at,
+        &params.signature,
+    )
+    .await?;
+
+    let bundle = fetch_course_bundle(&state, params.course_id).await?;
+    let bundle = write_bundle(&bundle).map_err(internal_error)?;
+    let disposition = format!(
+        "attachment; filename=\"aranya-course-{}.zip\"",
+        params.course_id
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        bundle,
+    ))
+}
+
+/// Creates a course from a bundle uploaded as the request body, signed by the
+/// creator. Nothing is written unless the whole bundle is valid.
+pub async fn import_course(
+    State(state): State<AppState>,
+    Query(params): Query<ImportCourseQuery>,
+    body: Bytes,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let creator_id = verify_creator_request(
+        &state.pool,
+        CreatorRequest::ImportCourse,
+        None,
+        &params.creator_id,
+        params.requested_at,
+        &params.signature,
+    )
+    .await?;
+
+    let bundle = read_bundle(&body).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let imported = import_course_bundle(&state, bundle, &creator_id, params.dry_run).await?;
+    let status = if params.dry_run {
+        StatusCode::OK
+    } else {
+        StatusCode::CREATED
+    };
+
+    Ok((status, Json(imported)))
+}
+
+/// Serves media imported with a course bundle. It is only ever embedded by
+/// lessons, so browsers are told not to open or sniff it as a page.
+pub async fn get_media(
+    State(pool): State<Pool<Postgres>>,
+    Path(params): Path<MediaPath>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let media: MediaRow = sqlx::query_as::<_, MediaRow>(
+        "SELECT sha256, file_name, content_type, data FROM media WHERE sha256 = $1",
+    )
+    .bind(params.sha256.to_lowercase())
+    .fetch_optional(&pool)
+    .await
+    .map_err(internal_error)?
+    .ok_or_else(|| (StatusCode::NOT_FOUND, "Media not found".to_string()))?;
+
+    // Media stored before the allowlist is served as plain bytes
+    let content_type = if is_media_content_type(&media.content_type) {
+        media.content_type
+    } else {
+        "application/octet-stream".to_string()
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", media.file_name),
+            ),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+            (
+                header::CACHE_CONTROL,
+                "public, max-age=31536000, immutable".to_string(),
+            ),
+        ],
+        media.data,
+    ))
+}
+
+/// The bundle of a course, with the media it links to.
+pub(crate) async fn fetch_course_bundle(
+    state: &AppState,
+    course_id: i64,
+) -> Result<Bundle, (StatusCode, String)> {
+    let pool = &state.pool;
+    let (title, description): (String, String) =
+        sqlx::query_as("SELECT title, COALESCE(description, '') FROM course WHERE id = $1")
+            .bind(course_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(internal_error)?
+            .ok_or_else(|| (StatusCode::NOT_FOUND, "Course not found".to_string()))?;
+
+    let modules: Vec<ModuleRow> = sqlx::query_as::<_, ModuleRow>(
+        "SELECT id, course_id, title, position FROM module WHERE course_id = $1 ORDER BY position, id",
+    )
+    .bind(course_id)
+    .fetch_all(pool)
+    .await
+    .map_err(internal_error)?;
+    let module_ids: Vec<i64> = modules.iter().map(|m| m.id).collect();
+
+    let lessons: Vec<LessonRow> = sqlx::query_as::<_, LessonRow>(
+        r#"
+        SELECT id, module_id, title, COALESCE(content, '') AS content, video_url, position,
+            scorm_package_id, min_watched_percent, video_duration_seconds
+        FROM lesson
+        WHERE module_id = ANY($1)
+        ORDER BY position, id
+        "#,
+    )
+    .bind(&module_ids)
+    .fetch_all(pool)
+    .await
+    .map_err(internal_error)?;
+
+    let mut quizzes = fetch_quiz_payloads(pool, &module_ids, course_id).await?;
+
+    // Media imported earlier is linked by URL, the bundle carries the files instead
+    let media_prefix = state.public_url("/media/");
+    let mut hashes: Vec<String> = lessons
+        .iter()
+        .flat_map(|l| {
+            let mut refs = media_refs(&l.content, &media_prefix);
+            refs.extend(media_refs(
+                l.video_url.as_deref().unwrap_or_default(),
+                &media_prefix,
+            ));
+            refs
+        })
+        .collect();
+    hashes.sort();
+    hashes.dedup();
+
+    let media_rows: Vec<MediaRow> = sqlx::query_as::<_, MediaRow>(
+        "SELECT sha256, file_name, content_type, data FROM media WHERE sha256 = ANY($1) ORDER BY sha256",
+    )
+    .bind(&hashes)
+    .fetch_all(pool)
+    .await
+    .map_err(internal_error)?;
+
+    let replacements: Vec<(String, String)> = media_rows
+        .iter()
+        .map(|media| {
+            (
+                format!("{}{}", media_prefix, media.sha256),
+                media_path(&media.sha256, &media.file_name),
+            )
+        })
+        .collect();
+    let to_bundle_paths = |text: &str| {
+        replacements
+            .iter()
+            .fold(text.to_string(), |text, (url, path)| {
+                text.replace(url, path)
+            })
+    };
+
+    let mut files = BTreeMap::new();
+    let mut lessons_by_module: HashMap<i64, Vec<BundleLesson>> = HashMap::new();
+    for lesson in &lessons {
+        let content_file = format!("lessons/{}.md", lesson.id);
+        files.insert(
+            content_file.clone(),
+            to_bundle_paths(&lesson.content).into_bytes(),
+        );
+        lessons_by_module
+            .entry(lesson.module_id)
+            .or_default()
+            .push(BundleLesson {
+                source_id: Some(lesson.id),
+                title: lesson.title.clone(),
+                position: lesson.position,
+                content_file,
+                video_url: lesson.video_url.as_deref().map(to_bundle_paths),
+                scorm_package_id: lesson.scorm_package_id,
+                min_watched_percent: lesson.min_watched_percent,
+                video_duration_seconds: lesson.video_duration_seconds,
+            });
+    }
+
+    let mut media = Vec::new();
+    for row in media_rows {
+        let path = media_path(&row.sha256, &row.file_name);
+        media.push(BundleMedia {
+            path: path.clone(),
+            content_type: row.content_type,
+            sha256: row.sha256,
+        });
+        files.insert(path, row.data);
+    }
+
+    let manifest = BundleManifest {
+        format_version: BUNDLE_FORMAT_VERSION,
+        exported_at: Utc::now(),
+        source_course_id: Some(course_id),
+        title,
+        description,
+        modules: modules
+            .iter()
+            .map(|m| BundleModule {
+                source_id: Some(m.id),
+                title: m.title.clone(),
+                position: m.position,
+                lessons: lessons_by_module.remove(&m.id).unwrap_or_default(),
+                quiz: quizzes.remove(&Some(m.id)),
+            })
+            .collect(),
+        final_exam: quizzes.remove(&None),
+        media,
+    };
+
+    Ok(Bundle { manifest, files })
+}
+
+/// Validates a bundle and creates its course, unless it's a dry run.
+pub(crate) async fn import_course_bundle(
+    state: &AppState,
+    bundle: Bundle,
+    creator_id: &str,
+    dry_run: bool,
+) -> Result<ImportedCourse, (StatusCode, String)> {
+    let source_ids: Vec<(Option<i64>, Vec<Option<i64>>)> = bundle
+        .manifest
+        .modules
+        .iter()
+        .map(|m| (m.source_id, m.lessons.iter().map(|l| l.source_id).collect()))
+        .collect();
+
+    let (payload, media) = bundle_course(bundle, creator_id, |sha256| {
+        state.public_url(&format!("/media/{}", sha256))
+    })
+    .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    validate_course(&payload).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let mut imported = ImportedCourse {
+        title: payload.title.clone(),
+        modules: payload.modules.len(),
+        lessons: payload.modules.iter().map(|m| m.lessons.len()).sum(),
+        questions: payload
+            .modules
+            .iter()
+            .filter_map(|m| m.quiz.as_ref())
+            .chain(&payload.final_exam)
+            .map(|q| q.questions.len())
+            .sum(),
+        media: media.len(),
+        ..Default::default()
+    };
+    if dry_run {
+        return Ok(imported);
+    }
+
+    let mut tx = state.pool.begin().await.map_err(internal_error)?;
+    for file in &media {
+        insert_media(&mut tx, file).await?;
+    }
+    let inserted = insert_course(&mut tx, &payload).await?;
+    tx.commit().await.map_err(internal_error)?;
+
+    imported.course_id = Some(inserted.course_id);
+    for ((module_source, lesson_sources), (module_id, lesson_ids)) in source_ids
+        .into_iter()
+        .zip(inserted.module_ids.into_iter().zip(inserted.lesson_ids))
+    {
+        if let Some(source) = module_source {
+            imported.module_ids.insert(source, module_id);
+        }
+        for (source, lesson_id) in lesson_sources.into_iter().zip(lesson_ids) {
+            if let Some(source) = source {
+                imported.lesson_ids.insert(source, lesson_id);
+            }
+        }
+    }
+
+    Ok(imported)
+}
+
+/// Stores a media file once by its content hash.
+pub(crate) async fn insert_media(
+    tx: &mut Transaction<'_, Postgres>,
+    file: &MediaFile,
+) -> Result<(), (StatusCode, String)> {
+    sqlx::query(
+        r#"
+        INSERT INTO media (sha256, file_name, content_type, data)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (sha256) DO NOTHING
+        "#,
+    )
+    .bind(&file.sha256)
+    .bind(&file.file_name)
+    .bind(&file.content_type)
+    .bind(&file.data)
+    .execute(&mut **tx)
+    .await
+    .map_err(internal_error)?;
+
+    Ok(())
+}
+
+/// Quizzes of a course with their full question bank and settings, by module
+/// and `None` for the final exam.
+pub(crate) async fn fetch_quiz_payloads(
+    pool: &Pool<Postgres>,
+    module_ids: &[i64],
+    course_id: i64,
+) -> Result<HashMap<Option<i64>, CreateQuizPayload>, (StatusCode, String)> {
+    let quizzes: Vec<BundleQuizRow> = sqlx::query_as::<_, BundleQuizRow>(
+        r#"
+        SELECT id, module_id, passing_score_percent, max_attempts, cooldown_seconds,
+            scoring_policy, shuffle_answers
+        FROM quiz
+        WHERE module_id = ANY($1) OR course_id = $2
+        "#,
+    )
+    .bind(module_ids)
+    .bind(course_id)
+    .fetch_all(pool)
+    .await
+    .map_err(internal_error)?;
+    let quiz_ids: Vec<i64> = quizzes.iter().map(|q| q.id).collect();
+
+    let mut questions = fetch_quiz_questions(pool, &quiz_ids).await?;
+    let rules: Vec<(i64, Option<String>, i32)> = sqlx::query_as(
+        "SELECT quiz_id, pool, draw_count FROM quiz_draw_rule WHERE quiz_id = ANY($1) ORDER BY id",
+    )
+    .bind(&quiz_ids)
+    .fetch_all(pool)
+    .await
+    .map_err(internal_error)?;
+    let mut draw_rules: HashMap<i64, Vec<CreateDrawRulePayload>> = HashMap::new();
+    for (quiz_id, pool, count) in rules {
+        draw_rules
+            .entry(quiz_id)
+            .or_default()
+            .push(CreateDrawRulePayload { pool, count });
+    }
+
+    let mut payloads = HashMap::new();
+    for quiz in quizzes {
+        let scoring_policy = ScoringPolicy::parse(&quiz.scoring_policy).ok_or_else(|| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unknown scoring policy {}", quiz.scoring_policy),
+            )
+        })?;
+        let payload = CreateQuizPayload {
+            questions: questions
+                .remove(&quiz.id)
+                .unwrap_or_default()
+                .iter()
+                .map(question_payload)
+                .collect(),
+            passing_score_percent: quiz.passing_score_percent,
+            max_attempts: quiz.max_attempts,
+            cooldown_seconds: quiz.cooldown_seconds,
+            scoring_policy,
+            draw_rules: draw_rules.remove(&quiz.id).unwrap_or_default(),
+            shuffle_answers: quiz.shuffle_answers,
+        };
+        payloads.insert(quiz.module_id, payload);
+    }
+
+    Ok(payloads)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::response::Response;
+    use sha2::{Digest, Sha256};
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::handlers::analytics::creator_request_message;
+    use crate::test_support::{personal_sign, wallet_address};
+
+    const CREATOR_SECRET: [u8; 32] = [1; 32];
+
+    // A one lesson course embedding one media file
+    fn bundle_zip(media_path: &str, content_type: &str, data: &[u8]) -> Bytes {
+        let manifest = BundleManifest {
+            format_version: BUNDLE_FORMAT_VERSION,
+            exported_at: Utc::now(),
+            source_course_id: None,
+            title: "Rust".to_string(),
+            description: "Basics".to_string(),
+            modules: vec![BundleModule {
+                source_id: Some(1),
+                title: "Intro".to_string(),
+                position: 1,
+                lessons: vec![BundleLesson {
+                    source_id: Some(2),
+                    title: "Ownership".to_string(),
+                    position: 1,
+                    content_file: "lessons/2.md".to_string(),
+                    video_url: None,
+                    scorm_package_id: None,
+                    min_watched_percent: None,
+                    video_duration_seconds: None,
+                }],
+                quiz: None,
+            }],
+            final_exam: None,
+            media: vec![BundleMedia {
+                path: media_path.to_string(),
+                content_type: content_type.to_string(),
+                sha256: hex::encode(Sha256::digest(data)),
+            }],
+        };
+        let files = BTreeMap::from([
+            (
+                "lessons/2.md".to_string(),
+                format!("![diagram]({})", media_path).into_bytes(),
+            ),
+            (media_path.to_string(), data.to_vec()),
+        ]);
+        Bytes::from(write_bundle(&Bundle { manifest, files }).unwrap())
+    }
+
+    async fn import(
+        state: &AppState,
+        request: CreatorRequest,
+        requested_at: i64,
+        body: Bytes,
+    ) -> Result<Response, (StatusCode, String)> {
+        let creator_id = wallet_address(&CREATOR_SECRET);
+        let signature = personal_sign(
+            &CREATOR_SECRET,
+            &creator_request_message(request, None, &creator_id, requested_at),
+        );
+        import_course(
+            State(state.clone()),
+            Query(ImportCourseQuery {
+                creator_id,
+                requested_at,
+                signature,
+                dry_run: false,
+            }),
+            body,
+        )
+        .await
+        .map(IntoResponse::into_response)
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures("../../database_schema/aranya_db_schema.sql")
+    )]
+    async fn import_is_signed_and_media_is_an_attachment(pool: PgPool) {
+        let state = AppState {
+            pool: pool.clone(),
+            public_api_url: "https://api.aranya.test".to_string(),
+        };
+        let png = b"\x89PNG\r\n\x1a\n";
+        // A signature is accepted once, every attempt signs its own request time
+        let now = Utc::now().timestamp();
+
+        let (status, _) = import(
+            &state,
+            CreatorRequest::ImportMarkdownCourse,
+            now - 2,
+            bundle_zip("media/diagram.png", "image/png", png),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let svg = b"<svg xmlns=\"http://www.w3.org/2000/svg\"><script>alert(1)</script></svg>";
+        let (status, _) = import(
+            &state,
+            CreatorRequest::ImportCourse,
+            now - 1,
+            bundle_zip("media/diagram.svg", "image/svg+xml", svg),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let response = import(
+            &state,
+            CreatorRequest::ImportCourse,
+            now,
+            bundle_zip("media/diagram.png", "image/png", png),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let creator: String = sqlx::query_scalar("SELECT creator_id FROM course")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(creator, wallet_address(&CREATOR_SECRET));
+
+        let media = get_media(
+            State(pool),
+            Path(MediaPath {
+                sha256: hex::encode(Sha256::digest(png)),
+            }),
+        )
+        .await
+        .unwrap()
+        .into_response();
+        let headers = media.headers();
+        assert_eq!(headers[header::CONTENT_TYPE], "image/png");
+        assert!(
+            headers[header::CONTENT_DISPOSITION]
+                .to_str()
+                .unwrap()
+                .starts_with("attachment")
+        );
+        assert_eq!(headers[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+    }
+}
@@ -0,0 +1,202 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres};
+
+use crate::handlers::progress::internal_error;
+use crate::models::certificate::{
+    CertificateQuery, CertificateRow, CertificateVerification, SetDisplayNameRequest,
+};
+use crate::pdf::{CertificateData, render_certificate};
+use crate::signing::recover_eth_address;
+use crate::state::AppState;
+
+const MAX_DISPLAY_NAME_LENGTH: usize = 64;
+
+// Maximum clock skew accepted on the signed `requestedAt` of a display name change
+const DISPLAY_NAME_REQUEST_WINDOW_SECONDS: i64 = 300;
+
+async fn fetch_certificate_data(
+    state: &AppState,
+    learner_id: &str,
+    course_id: i64,
+) -> Result<CertificateData, (StatusCode, String)> {
+    let row: CertificateRow = sqlx::query_as::<_, CertificateRow>(
+        r#"
+        SELECT
+            c.title AS course_title,
+            c.creator_id,
+            l.display_name,
+            cc.completed_at,
+            e.nft_token_id,
+            e.nft_contract_address
+        FROM course_completion cc
+        JOIN course c ON c.id = cc.course_id
+        JOIN learner l ON l.id = cc.learner_id
+        JOIN learner_course_enrollment e
+            ON e.learner_id = cc.learner_id AND e.course_id = cc.course_id
+        WHERE cc.learner_id = $1 AND cc.course_id = $2
+        "#,
+    )
+    .bind(learner_id)
+    .bind(course_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(internal_error)?
+    .ok_or_else(|| (StatusCode::NOT_FOUND, "Course not completed".to_string()))?;
+
+    let completed_at = row.completed_at.ok_or_else(|| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Course completion has no date".to_string(),
+        )
+    })?;
+
+    Ok(CertificateData {
+        learner_id: learner_id.to_string(),
+        display_name: row.display_name,
+        course_title: row.course_title,
+        creator_id: row.creator_id,
+        completed_at,
+        nft_token_id: row.nft_token_id,
+        nft_contract_address: row.nft_contract_address,
+        verification_url: state.public_url(&format!(
+            "/verify-certificate?learnerId={}&courseId={}",
+            learner_id, course_id
+        )),
+    })
+}
+
+/// Renders the completion certificate PDF. The same completion always
+/// renders to identical bytes.
+pub async fn get_certificate(
+    State(state): State<AppState>,
+    Query(params): Query<CertificateQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let data = fetch_certificate_data(&state, &params.learner_id, params.course_id).await?;
+    let pdf = render_certificate(&data).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    let disposition = format!(
+        "attachment; filename=\"aranya-certificate-{}-{}.pdf\"",
+        params.course_id, params.learner_id
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        pdf,
+    ))
+}
+
+/// Target of the certificate QR code, confirms the completion and returns
+/// the hash the certificate PDF must have.
+pub async fn verify_certificate(
+    State(state): State<AppState>,
+    Query(params): Query<CertificateQuery>,
+) -> Result<Json<CertificateVerification>, (StatusCode, String)> {
+    let data = fetch_certificate_data(&state, &params.learner_id, params.course_id).await?;
+    let pdf = render_certificate(&data).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    let credential_id: Option<i64> = sqlx::query_scalar(
+        r#"
+        SELECT id FROM verifiable_credential
+        WHERE learner_id = $1 AND course_id = $2 AND revoked_at IS NULL
+        ORDER BY id DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(&params.learner_id)
+    .bind(params.course_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    Ok(Json(CertificateVerification {
+        learner_id: data.learner_id,
+        display_name: data.display_name,
+        course_id: params.course_id,
+        course_title: data.course_title,
+        creator_id: data.creator_id,
+        completed_at: data.completed_at,
+        nft_token_id: data.nft_token_id,
+        credential_id,
+        certificate_hash: hex::encode(Sha256::digest(&pdf)),
+    }))
+}
+
+/// Sets the name printed on certificates, signed by the learner wallet.
+pub async fn set_learner_display_name(
+    State(pool): State<Pool<Postgres>>,
+    Json(payload): Json<SetDisplayNameRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let display_name = payload
+        .display_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty());
+
+    if display_name.is_some_and(|name| name.chars().count() > MAX_DISPLAY_NAME_LENGTH) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Display name must be at most {} characters",
+                MAX_DISPLAY_NAME_LENGTH
+            ),
+        ));
+    }
+
+    if (Utc::now().timestamp() - payload.requested_at).abs() > DISPLAY_NAME_REQUEST_WINDOW_SECONDS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Display name request has expired".to_string(),
+        ));
+    }
+
+    let message = display_name_message(
+        &payload.learner_id,
+        display_name.unwrap_or_default(),
+        payload.requested_at,
+    );
+    let signer = recover_eth_address(&message, &payload.signature)
+        .map_err(|e| (StatusCode::UNAUTHORIZED, e))?;
+    if signer != payload.learner_id.to_lowercase() {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            "Signature does not match learner".to_string(),
+        ));
+    }
+
+    let result = sqlx::query("UPDATE learner SET display_name = $2 WHERE id = $1")
+        .bind(&payload.learner_id)
+        .bind(display_name)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to update display name: {}", e),
+            )
+        })?;
+
+    if result.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "Learner not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn display_name_message(learner_id: &str, display_name: &str, requested_at: i64) -> String {
+    format!(
+        "Aranya display name\nLearner: {}\nDisplay name: {}\nRequested at: {}",
+        learner_id.to_lowercase(),
+        display_name,
+        requested_at
+    )
+}
@@ -0,0 +1,42 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+use crate::common_cartridge::write_cartridge;
+use crate::handlers::analytics::{CreatorRequest, authorize_creator};
+use crate::handlers::bundle::fetch_course_bundle;
+use crate::handlers::progress::internal_error;
+use crate::models::bundle::ExportCourseQuery;
+use crate::models::common_cartridge::ExportedCartridge;
+use crate::state::AppState;
+
+/// Exports a course as an IMS Common Cartridge 1.3 package for loading into
+/// another LMS, for the course creator only since quizzes include their
+/// answer key.
+pub async fn export_common_cartridge(
+    State(state): State<AppState>,
+    Query(params): Query<ExportCourseQuery>,
+) -> Result<Json<ExportedCartridge>, (StatusCode, String)> {
+    authorize_creator(
+        &state.pool,
+        CreatorRequest::ExportCommonCartridge,
+        params.course_id,
+        &params.creator_id,
+        params.requested_at,
+        &params.signature,
+    )
+    .await?;
+
+    let bundle = fetch_course_bundle(&state, params.course_id).await?;
+    let (package, issues) = write_cartridge(&bundle).map_err(internal_error)?;
+
+    Ok(Json(ExportedCartridge {
+        course_id: params.course_id,
+        file_name: format!("aranya-course-{}.imscc", params.course_id),
+        content: STANDARD.encode(package),
+        issues,
+    }))
+}
@@ -4,18 +4,26 @@ use axum::{
     http::StatusCode,
     response::IntoResponse,
 };
+use rand::seq::SliceRandom;
 use serde_json::json;
 use sqlx::{Pool, Postgres, Row, Transaction};
-use std::{collections::HashMap, convert::TryInto};
+use std::collections::HashMap;
 
+use crate::fdc::AttestationKind;
+use crate::grading::validate_quiz;
+use crate::handlers::attestation::consume_nonce;
 use crate::models::course::{
-    AnswerOption, AnswerOptionRow, CountsResponse, Course, CourseCreatorResponse, CoursePreview, CourseQuery, CourseRow, CreateCoursePayload, CreatedCourse, EnrolledCourse, JoinCourseRequest, LearnerId, Lesson, LessonRow, Module, ModuleRow, NumCompletedResponse, Question, QuestionRow, Quiz, QuizRow, UserCoursesResponse, UserQuery
+    AnswerOption, AnswerOptionRow, AttestedCourseQuery, Course, CourseCreatorResponse, CoursePreview, CourseQuery, CourseRow, CreateAnswerOptionPayload, CreateCoursePayload, CreateLessonPayload, CreateQuizPayload, CreatedCourse, EnrolledCourse, InsertedCourse, JoinCourseRequest, LearnerId, Lesson, LessonRow, Module, ModuleRow, NumCompletedResponse, Question, QuestionRow, QuestionType, Quiz, QuizRow, ResumePosition, ScoringPolicy, UserCoursesResponse, UserQuery
 };
+use crate::video_progress::{is_video_file, resume_position};
+use crate::xapi::{Activity, Verb, enqueue_statement};
 
 pub async fn create_course(
     State(pool): State<Pool<Postgres>>,
     Json(payload): Json<CreateCoursePayload>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
+    validate_course(&payload).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
     let mut tx = pool.begin().await.map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -23,9 +31,81 @@ pub async fn create_course(
         )
     })?;
 
+    let inserted = insert_course(&mut tx, &payload).await?;
+
+    tx.commit().await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to commit transaction: {}", e),
+        )
+    })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({ "message": "Course created successfully", "course_id": inserted.course_id })),
+    ))
+}
+
+/// Checks every lesson and quiz of a course before anything is written.
+pub(crate) fn validate_course(payload: &CreateCoursePayload) -> Result<(), String> {
+    payload
+        .modules
+        .iter()
+        .flat_map(|m| &m.lessons)
+        .try_for_each(validate_lesson)?;
+    payload
+        .modules
+        .iter()
+        .filter_map(|m| m.quiz.as_ref())
+        .chain(&payload.final_exam)
+        .try_for_each(validate_quiz)
+}
+
+fn validate_lesson(lesson: &CreateLessonPayload) -> Result<(), String> {
+    if let Some(duration) = lesson.video_duration_seconds {
+        if lesson.video_url.is_none() {
+            return Err(format!(
+                "Lesson \"{}\" has a video duration but no video",
+                lesson.title
+            ));
+        }
+        if !(duration.is_finite() && duration > 0.0) {
+            return Err(format!(
+                "Lesson \"{}\" video duration must be positive",
+                lesson.title
+            ));
+        }
+    }
+    match lesson.min_watched_percent {
+        Some(_) if lesson.video_url.is_none() => Err(format!(
+            "Lesson \"{}\" has a minimum watched percentage but no video",
+            lesson.title
+        )),
+        Some(percent) if !(percent > 0.0 && percent <= 100.0) => Err(format!(
+            "Lesson \"{}\" minimum watched percentage must be above 0 and at most 100",
+            lesson.title
+        )),
+        // Only video files report progress, embedded players never would
+        Some(_) if !lesson.video_url.as_deref().is_some_and(is_video_file) => Err(format!(
+            "Lesson \"{}\" can only require a watched percentage of a video file",
+            lesson.title
+        )),
+        Some(_) if lesson.video_duration_seconds.is_none() => Err(format!(
+            "Lesson \"{}\" needs the video duration to require a watched percentage",
+            lesson.title
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Inserts a validated course with its modules, lessons and quizzes.
+pub(crate) async fn insert_course(
+    tx: &mut Transaction<'_, Postgres>,
+    payload: &CreateCoursePayload,
+) -> Result<InsertedCourse, (StatusCode, String)> {
     sqlx::query("INSERT INTO creator (id) VALUES ($1) ON CONFLICT DO NOTHING")
         .bind(&payload.creator_id)
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
@@ -35,93 +115,68 @@ pub async fn create_course(
     .bind(&payload.title)
     .bind(&payload.creator_id)
     .bind(&payload.description)
-    .fetch_one(&mut *tx)
+    .fetch_one(&mut **tx)
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    for module in &payload.modules {
-        let position_i32: i32 = module.position.try_into().map_err(|_| {
-            (
-                StatusCode::BAD_REQUEST,
-                format!("Position {} is too large for an i32", module.position),
-            )
-        })?;
+    let mut inserted = InsertedCourse {
+        course_id,
+        module_ids: Vec::new(),
+        lesson_ids: Vec::new(),
+    };
 
+    for module in &payload.modules {
         let module_id: i64 = sqlx::query_scalar(
             "INSERT INTO module (title, course_id, position) VALUES ($1, $2, $3) RETURNING id",
         )
         .bind(&module.title)
         .bind(course_id)
-        .bind(position_i32)
-        .fetch_one(&mut *tx)
+        .bind(module.position)
+        .fetch_one(&mut **tx)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
+        let mut lesson_ids = Vec::new();
         for lesson in &module.lessons {
-            let position_i32: i32 = lesson.position.try_into().map_err(|_| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    format!("Position {} is too large for an i32", module.position),
-                )
-            })?;
-
-            sqlx::query(
-                "INSERT INTO lesson (title, content, video_url, module_id, position) VALUES ($1, $2, $3, $4, $5)",
+            let lesson_id: i64 = sqlx::query_scalar(
+                "INSERT INTO lesson (title, content, video_url, module_id, position, scorm_package_id, min_watched_percent, video_duration_seconds) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id",
             )
             .bind(&lesson.title)
             .bind(&lesson.content)
             .bind(&lesson.video_url)
             .bind(module_id)
-            .bind(position_i32)
-            .execute(&mut *tx)
+            .bind(lesson.position)
+            .bind(lesson.scorm_package_id)
+            .bind(lesson.min_watched_percent)
+            .bind(lesson.video_duration_seconds)
+            .fetch_one(&mut **tx)
             .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            .map_err(|e| match e.as_database_error().and_then(|d| d.constraint()) {
+                Some("lesson_scorm_package_id_fkey") => (
+                    StatusCode::BAD_REQUEST,
+                    format!(
+                        "SCORM package {} does not exist",
+                        lesson.scorm_package_id.unwrap_or_default()
+                    ),
+                ),
+                _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            })?;
+            lesson_ids.push(lesson_id);
         }
 
         if let Some(quiz) = &module.quiz {
-            let quiz_id: i64 =
-                sqlx::query_scalar("INSERT INTO quiz (module_id) VALUES ($1) RETURNING id")
-                    .bind(module_id)
-                    .fetch_one(&mut *tx)
-                    .await
-                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-            for question in &quiz.questions {
-                let question_id: i64 = sqlx::query_scalar(
-                    "INSERT INTO question (question_text, quiz_id) VALUES ($1, $2) RETURNING id",
-                )
-                .bind(&question.question_text)
-                .bind(quiz_id)
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-                for answer in &question.answers {
-                    sqlx::query(
-                    "INSERT INTO answer_option (answer_text, is_correct, question_id) VALUES ($1, $2, $3)",
-                )
-                .bind(&answer.answer_text)
-                .bind(answer.is_correct)
-                .bind(question_id)
-                .execute(&mut *tx)
-                .await
-                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-                }
-            }
+            insert_quiz(tx, Some(module_id), None, quiz).await?;
         }
+
+        inserted.module_ids.push(module_id);
+        inserted.lesson_ids.push(lesson_ids);
     }
 
-    tx.commit().await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to commit transaction: {}", e),
-        )
-    })?;
+    if let Some(final_exam) = &payload.final_exam {
+        insert_quiz(tx, None, Some(course_id), final_exam).await?;
+    }
 
-    Ok((
-        StatusCode::CREATED,
-        Json(json!({ "message": "Course created successfully", "course_id": course_id })),
-    ))
+    Ok(inserted)
 }
 
 pub async fn enroll(
@@ -141,12 +196,24 @@ pub async fn enroll(
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    sqlx::query(
-        "INSERT INTO learner_course_enrollment (learner_id, course_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",).bind(&payload.learner_id).bind(&payload.course_id)
+    let enrolled = sqlx::query(
+        "INSERT INTO learner_course_enrollment (learner_id, course_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",).bind(&payload.learner_id).bind(payload.course_id)
     .execute(&mut *tx)
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
+    if enrolled.rows_affected() > 0 {
+        enqueue_statement(
+            &mut *tx,
+            &payload.learner_id,
+            Verb::Launched,
+            Activity::Course(payload.course_id),
+            None,
+        )
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+
     tx.commit().await.map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -274,7 +341,7 @@ pub async fn get_learners_by_course(
         WHERE course_id = $1
         "#,
     )
-    .bind(&params.course_id)
+    .bind(params.course_id)
     .fetch_all(&pool)
     .await
     .map_err(|err| {
@@ -289,8 +356,17 @@ pub async fn get_learners_by_course(
 
 pub async fn get_num_completed(
     State(pool): State<Pool<Postgres>>,
-    Query(params): Query<CourseQuery>,
+    Query(params): Query<AttestedCourseQuery>,
 ) -> Result<Json<NumCompletedResponse>, (StatusCode, String)> {
+    let nonce = consume_nonce(
+        &pool,
+        AttestationKind::CreatorMilestone,
+        &params.nonce,
+        params.course_id,
+        None,
+    )
+    .await?;
+
     let num_completed: i64 = sqlx::query_scalar(
         r#"
         SELECT COUNT(*)::bigint AS num_completed
@@ -298,7 +374,7 @@ pub async fn get_num_completed(
         WHERE course_id = $1
         "#,
     )
-    .bind(&params.course_id)
+    .bind(params.course_id)
     .fetch_one(&pool)
     .await
     .map_err(|e| {
@@ -311,6 +387,8 @@ pub async fn get_num_completed(
     Ok(Json(NumCompletedResponse {
         course_id: params.course_id,
         num_completed,
+        nonce: nonce.nonce,
+        expires_at: nonce.expires_at.timestamp(),
     }))
 }
 
@@ -332,7 +410,7 @@ pub async fn get_course(
     WHERE id = $1
     "#,
     )
-    .bind(&params.course_id)
+    .bind(params.course_id)
     .fetch_one(&pool)
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
@@ -344,7 +422,7 @@ pub async fn get_course(
     WHERE course_id = $1
     "#,
     )
-    .bind(&params.course_id)
+    .bind(params.course_id)
     .fetch_all(&pool)
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
@@ -353,7 +431,8 @@ pub async fn get_course(
 
     let lesson_rows: Vec<LessonRow> = sqlx::query_as::<_, LessonRow>(
         r#"
-    SELECT id, module_id, title, content, video_url, position
+    SELECT id, module_id, title, content, video_url, position, scorm_package_id,
+        min_watched_percent, video_duration_seconds
     FROM lesson
     WHERE module_id = ANY($1)
     "#,
@@ -365,40 +444,18 @@ pub async fn get_course(
 
     let quiz_rows: Vec<QuizRow> = sqlx::query_as::<_, QuizRow>(
         r#"
-    SELECT id, module_id
+    SELECT id, module_id, course_id, passing_score_percent, max_attempts, cooldown_seconds,
+        scoring_policy,
+        COALESCE(
+            (SELECT SUM(draw_count) FROM quiz_draw_rule WHERE quiz_id = quiz.id),
+            (SELECT COUNT(*) FROM question WHERE quiz_id = quiz.id)
+        )::BIGINT AS question_count
     FROM quiz
-    WHERE module_id = ANY($1)
+    WHERE module_id = ANY($1) OR course_id = $2
     "#,
     )
     .bind(&module_ids)
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    let quiz_ids: Vec<i64> = quiz_rows.iter().map(|q| q.id).collect();
-
-    let question_rows: Vec<QuestionRow> = sqlx::query_as::<_, QuestionRow>(
-        r#"
-    SELECT id, quiz_id, question_text
-    FROM question
-    WHERE quiz_id = ANY($1)
-    "#,
-    )
-    .bind(&quiz_ids)
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    let question_ids: Vec<i64> = question_rows.iter().map(|q| q.id).collect();
-
-    let answer_rows: Vec<AnswerOptionRow> = sqlx::query_as::<_, AnswerOptionRow>(
-        r#"
-    SELECT id, question_id, answer_text, is_correct
-    FROM answer_option
-    WHERE question_id = ANY($1)
-    "#,
-    )
-    .bind(&question_ids)
+    .bind(params.course_id)
     .fetch_all(&pool)
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
@@ -410,42 +467,31 @@ pub async fn get_course(
         )
     })?;
 
-    let mut answers_by_question: HashMap<i64, Vec<AnswerOption>> = HashMap::new();
-    for a in answer_rows {
-        let answer = AnswerOption {
-            id: a.id,
-            question_id: a.question_id,
-            answer_text: a.answer_text,
-            is_correct: a.is_correct,
-        };
-        answers_by_question
-            .entry(a.question_id)
-            .or_default()
-            .push(answer);
-    }
-
-    let mut questions_by_quiz: HashMap<i64, Vec<Question>> = HashMap::new();
-    for q in question_rows {
-        let question = Question {
-            id: q.id,
-            quiz_id: q.quiz_id,
-            question_text: q.question_text,
-            answers: answers_by_question.remove(&q.id).unwrap_or_default(),
-        };
-        questions_by_quiz
-            .entry(q.quiz_id)
-            .or_default()
-            .push(question);
-    }
-
     let mut quiz_by_module: HashMap<i64, Quiz> = HashMap::new();
+    let mut final_exam: Option<Quiz> = None;
     for q in quiz_rows {
+        let scoring_policy = ScoringPolicy::parse(&q.scoring_policy).ok_or_else(|| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unknown scoring policy {}", q.scoring_policy),
+            )
+        })?;
         let quiz: Quiz = Quiz {
             id: q.id,
             module_id: q.module_id,
-            questions: questions_by_quiz.remove(&q.id).unwrap_or_default(),
+            course_id: q.course_id,
+            passing_score_percent: q.passing_score_percent,
+            max_attempts: q.max_attempts,
+            cooldown_seconds: q.cooldown_seconds,
+            scoring_policy,
+            question_count: q.question_count,
         };
-        quiz_by_module.insert(q.module_id, quiz);
+        match q.module_id {
+            Some(module_id) => {
+                quiz_by_module.insert(module_id, quiz);
+            }
+            None => final_exam = Some(quiz),
+        }
     }
 
     let mut lessons_by_module: HashMap<i64, Vec<Lesson>> = HashMap::new();
@@ -457,6 +503,9 @@ pub async fn get_course(
             content: l.content,
             video_url: l.video_url,
             position: l.position,
+            scorm_package_id: l.scorm_package_id,
+            min_watched_percent: l.min_watched_percent,
+            video_duration_seconds: l.video_duration_seconds,
         };
         lessons_by_module
             .entry(l.module_id)
@@ -476,6 +525,11 @@ pub async fn get_course(
         })
         .collect();
 
+    let resume = match &params.learner_id {
+        Some(learner_id) => fetch_resume_position(&pool, learner_id, params.course_id).await?,
+        None => None,
+    };
+
     let course = Course {
         id: course_row.id,
         title: course_row.title,
@@ -484,15 +538,216 @@ pub async fn get_course(
         num_learners: course_row.num_learners,
         num_completed: course_row.num_completed,
         modules,
+        final_exam,
+        resume,
     };
 
     Ok((StatusCode::OK, Json(course)))
 }
 
+/// Position in the video of the course the learner watched last.
+async fn fetch_resume_position(
+    pool: &Pool<Postgres>,
+    learner_id: &str,
+    course_id: i64,
+) -> Result<Option<ResumePosition>, (StatusCode, String)> {
+    let row: Option<(i64, i64, f64, f64)> = sqlx::query_as(
+        r#"
+    SELECT p.lesson_id, l.module_id, p.position_seconds, p.duration_seconds
+    FROM lesson_video_progress p
+    JOIN lesson l ON l.id = p.lesson_id
+    JOIN module m ON m.id = l.module_id
+    WHERE p.learner_id = $1 AND m.course_id = $2
+    ORDER BY p.updated_at DESC
+    LIMIT 1
+    "#,
+    )
+    .bind(learner_id)
+    .bind(course_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(row.map(
+        |(lesson_id, module_id, position, duration)| ResumePosition {
+            lesson_id,
+            module_id,
+            position_seconds: resume_position(position, duration),
+        },
+    ))
+}
+
+/// Loads the question bank of each quiz with the answers in position order.
+pub(crate) async fn fetch_quiz_questions(
+    pool: &Pool<Postgres>,
+    quiz_ids: &[i64],
+) -> Result<HashMap<i64, Vec<Question>>, (StatusCode, String)> {
+    let question_rows: Vec<QuestionRow> = sqlx::query_as::<_, QuestionRow>(
+        r#"
+    SELECT id, quiz_id, question_text, question_type, points, numeric_answer, numeric_tolerance,
+        case_sensitive, pool
+    FROM question
+    WHERE quiz_id = ANY($1)
+    ORDER BY id
+    "#,
+    )
+    .bind(quiz_ids)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let question_ids: Vec<i64> = question_rows.iter().map(|q| q.id).collect();
+
+    let answer_rows: Vec<AnswerOptionRow> = sqlx::query_as::<_, AnswerOptionRow>(
+        r#"
+    SELECT id, question_id, answer_text, is_correct, position, match_text
+    FROM answer_option
+    WHERE question_id = ANY($1)
+    ORDER BY position, id
+    "#,
+    )
+    .bind(&question_ids)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut answers_by_question: HashMap<i64, Vec<AnswerOption>> = HashMap::new();
+    for a in answer_rows {
+        let answer = AnswerOption {
+            id: a.id,
+            answer_text: a.answer_text,
+            is_correct: a.is_correct,
+            position: a.position,
+            match_text: a.match_text,
+        };
+        answers_by_question
+            .entry(a.question_id)
+            .or_default()
+            .push(answer);
+    }
+
+    let mut questions_by_quiz: HashMap<i64, Vec<Question>> = HashMap::new();
+    for q in question_rows {
+        let question_type = QuestionType::parse(&q.question_type).ok_or_else(|| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unknown question type {}", q.question_type),
+            )
+        })?;
+        let question = Question {
+            id: q.id,
+            question_text: q.question_text,
+            question_type,
+            points: q.points,
+            answers: answers_by_question.remove(&q.id).unwrap_or_default(),
+            numeric_answer: q.numeric_answer,
+            numeric_tolerance: q.numeric_tolerance,
+            case_sensitive: q.case_sensitive,
+            pool: q.pool,
+        };
+        questions_by_quiz
+            .entry(q.quiz_id)
+            .or_default()
+            .push(question);
+    }
+
+    Ok(questions_by_quiz)
+}
+
+/// Inserts a module quiz, or a course final exam when `course_id` is set.
+async fn insert_quiz(
+    tx: &mut Transaction<'_, Postgres>,
+    module_id: Option<i64>,
+    course_id: Option<i64>,
+    quiz: &CreateQuizPayload,
+) -> Result<i64, (StatusCode, String)> {
+    let quiz_id: i64 = sqlx::query_scalar(
+        r#"
+        INSERT INTO quiz
+            (module_id, course_id, passing_score_percent, max_attempts, cooldown_seconds, scoring_policy, shuffle_answers)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id
+        "#,
+    )
+    .bind(module_id)
+    .bind(course_id)
+    .bind(quiz.passing_score_percent)
+    .bind(quiz.max_attempts)
+    .bind(quiz.cooldown_seconds)
+    .bind(quiz.scoring_policy.as_str())
+    .bind(quiz.shuffle_answers)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    for rule in &quiz.draw_rules {
+        sqlx::query("INSERT INTO quiz_draw_rule (quiz_id, pool, draw_count) VALUES ($1, $2, $3)")
+            .bind(quiz_id)
+            .bind(&rule.pool)
+            .bind(rule.count)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+
+    for question in &quiz.questions {
+        let question_id: i64 = sqlx::query_scalar(
+            r#"
+            INSERT INTO question
+                (question_text, quiz_id, question_type, points, numeric_answer, numeric_tolerance, case_sensitive, pool)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id
+            "#,
+        )
+        .bind(&question.question_text)
+        .bind(quiz_id)
+        .bind(question.question_type.as_str())
+        .bind(question.points)
+        .bind(question.numeric_answer)
+        .bind(question.numeric_tolerance.unwrap_or(0.0))
+        .bind(question.case_sensitive)
+        .bind(&question.pool)
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+        // Ids of ordering answers would give the order away if inserted in it
+        let mut answers: Vec<(usize, &CreateAnswerOptionPayload)> =
+            question.answers.iter().enumerate().collect();
+        if question.question_type == QuestionType::Ordering {
+            answers.shuffle(&mut rand::thread_rng());
+        }
+        for (position, answer) in answers {
+            sqlx::query(
+                "INSERT INTO answer_option (answer_text, is_correct, question_id, position, match_text) VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(&answer.answer_text)
+            .bind(answer.is_correct)
+            .bind(question_id)
+            .bind(position as i32)
+            .bind(&answer.match_text)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        }
+    }
+
+    Ok(quiz_id)
+}
+
 pub async fn get_course_creator(
     State(pool): State<Pool<Postgres>>,
-    Query(params): Query<CourseQuery>,
+    Query(params): Query<AttestedCourseQuery>,
 ) -> Result<Json<CourseCreatorResponse>, (StatusCode, String)> {
+    let nonce = consume_nonce(
+        &pool,
+        AttestationKind::CourseCreator,
+        &params.nonce,
+        params.course_id,
+        None,
+    )
+    .await?;
+
     let result: Option<(String,)> = sqlx::query_as::<_, (String,)>(
         r#"
         SELECT
@@ -501,7 +756,7 @@ pub async fn get_course_creator(
         WHERE id = $1
         "#,
     )
-    .bind(&params.course_id)
+    .bind(params.course_id)
     .fetch_optional(&pool)
     .await
     .map_err(|e| {
@@ -515,6 +770,8 @@ pub async fn get_course_creator(
         Some((creator_id,)) => Ok(Json(CourseCreatorResponse {
             course_id: params.course_id,
             creator_id,
+            nonce: nonce.nonce,
+            expires_at: nonce.expires_at.timestamp(),
         })),
         None => Err((StatusCode::NOT_FOUND, "Course not found".to_string())),
     }
@@ -625,31 +882,30 @@ pub async fn get_user_courses(
     ))
 }
 
-pub async fn get_counts(
-    State(pool): State<Pool<Postgres>>,
-) -> Result<Json<CountsResponse>, (StatusCode, String)> {
-    let num_learners: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM learner")
-        .fetch_one(&pool)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("DB error (learners): {}", e),
-            )
-        })?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn video_lesson(video_url: &str, duration: Option<f64>) -> CreateLessonPayload {
+        CreateLessonPayload {
+            title: "Ownership".to_string(),
+            content: String::new(),
+            video_url: Some(video_url.to_string()),
+            position: 1,
+            scorm_package_id: None,
+            min_watched_percent: Some(80.0),
+            video_duration_seconds: duration,
+        }
+    }
 
-    let num_courses: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM course")
-        .fetch_one(&pool)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("DB error (courses): {}", e),
-            )
-        })?;
+    #[test]
+    fn watched_percentage_needs_a_video_file_with_its_duration() {
+        let file = "https://cdn.example.com/ownership.mp4";
+        let embed = "https://www.youtube.com/embed/abc123";
 
-    Ok(Json(CountsResponse {
-        num_learners: num_learners.0,
-        num_courses: num_courses.0,
-    }))
+        assert!(validate_lesson(&video_lesson(file, Some(120.0))).is_ok());
+        assert!(validate_lesson(&video_lesson(file, None)).is_err());
+        assert!(validate_lesson(&video_lesson(file, Some(0.0))).is_err());
+        assert!(validate_lesson(&video_lesson(embed, Some(120.0))).is_err());
+    }
 }
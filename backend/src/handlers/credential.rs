@@ -0,0 +1,557 @@
+use axum::{
+    Json,
+    extract::{FromRef, Path, Query, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, SecondsFormat, Utc};
+use flate2::{Compression, write::GzEncoder};
+use serde_json::{Value, json};
+use sqlx::{Pool, Postgres};
+use std::io::Write;
+
+use crate::handlers::progress::internal_error;
+use crate::models::credential::{
+    CredentialPath, CredentialQuery, CredentialResponse, CredentialRow, RevokeCredentialRequest,
+    StatusListPath, VerifyCredentialRequest, VerifyCredentialResponse,
+};
+use crate::signing::{ServerKey, recover_eth_address};
+
+// Minimum bitstring length recommended by the status list spec, for herd privacy
+const STATUS_LIST_LENGTH: i64 = 131_072;
+
+// Coston2, the chain the learner wallets live on
+const CHAIN_ID: u64 = 114;
+
+pub(crate) const VC_JWT_TYPE: &str = "vc+jwt";
+pub(crate) const VC_JWT_CONTENT_TYPE: &str = "application/vc+jwt";
+
+#[derive(Clone)]
+pub struct CredentialState {
+    pub pool: Pool<Postgres>,
+    pub key: ServerKey,
+    // Public URL of this backend, used in credential and status list ids
+    pub issuer_url: String,
+}
+
+impl FromRef<CredentialState> for Pool<Postgres> {
+    fn from_ref(state: &CredentialState) -> Pool<Postgres> {
+        state.pool.clone()
+    }
+}
+
+impl CredentialState {
+    /// Absolute URL of `path` on this backend.
+    pub(crate) fn public_url(&self, path: &str) -> String {
+        format!("{}{}", self.issuer_url.trim_end_matches('/'), path)
+    }
+
+    fn credential_url(&self, credential_id: i64) -> String {
+        self.public_url(&format!("/credentials/{}", credential_id))
+    }
+
+    fn status_list_url(&self, list_id: i64) -> String {
+        self.public_url(&format!("/credentials/status/{}", list_id))
+    }
+}
+
+/// did:pkh identifier of a learner or creator wallet.
+pub(crate) fn wallet_did(address: &str) -> String {
+    format!("did:pkh:eip155:{}:{}", CHAIN_ID, address.to_lowercase())
+}
+
+/// Issues a course completion credential as a JWT-VC, unless the learner
+/// already holds one. Returns the credential id, and fails with 409 once the
+/// credential was revoked so completing the course again doesn't reissue it.
+pub(crate) async fn issue_completion_credential(
+    state: &CredentialState,
+    learner_id: &str,
+    course_id: i64,
+) -> Result<i64, (StatusCode, String)> {
+    // The active credential when there is one, else the last revoked one
+    let existing: Option<(i64, bool)> = sqlx::query_as(
+        r#"
+        SELECT id, revoked_at IS NOT NULL
+        FROM verifiable_credential
+        WHERE learner_id = $1 AND course_id = $2
+        ORDER BY revoked_at IS NULL DESC, id DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(learner_id)
+    .bind(course_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    match existing {
+        Some((_, true)) => {
+            return Err((
+                StatusCode::CONFLICT,
+                "The credential for this course was revoked".to_string(),
+            ));
+        }
+        Some((credential_id, false)) => return Ok(credential_id),
+        None => {}
+    }
+
+    let (title, description, completed_at): (String, String, Option<DateTime<Utc>>) =
+        sqlx::query_as(
+            r#"
+            SELECT c.title, c.description, cc.completed_at
+            FROM course_completion cc
+            JOIN course c ON c.id = cc.course_id
+            WHERE cc.learner_id = $1 AND cc.course_id = $2
+            "#,
+        )
+        .bind(learner_id)
+        .bind(course_id)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Course not completed".to_string()))?;
+
+    // The id is part of the signed credential, so it is reserved before inserting
+    let credential_id: i64 =
+        sqlx::query_scalar("SELECT nextval(pg_get_serial_sequence('verifiable_credential', 'id'))")
+            .fetch_one(&state.pool)
+            .await
+            .map_err(internal_error)?;
+
+    let issued_at = Utc::now();
+    let completed_at = completed_at.unwrap_or(issued_at);
+    let subject = wallet_did(learner_id);
+    let list_id = credential_id / STATUS_LIST_LENGTH;
+    let list_index = credential_id % STATUS_LIST_LENGTH;
+
+    let claims = json!({
+        "iss": state.key.did(),
+        "sub": subject,
+        "jti": state.credential_url(credential_id),
+        "nbf": issued_at.timestamp(),
+        "iat": issued_at.timestamp(),
+        "vc": {
+            "@context": [
+                "https://www.w3.org/2018/credentials/v1",
+                "https://w3id.org/vc/status-list/2021/v1"
+            ],
+            "type": ["VerifiableCredential", "CourseCompletionCredential"],
+            "issuer": state.key.did(),
+            "issuanceDate": issued_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            "credentialSubject": {
+                "id": subject,
+                "walletAddress": learner_id.to_lowercase(),
+                "course": {
+                    "id": course_id.to_string(),
+                    "title": title,
+                    "description": description,
+                },
+                "completionDate": completed_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            },
+            "credentialStatus": {
+                "id": format!("{}#{}", state.status_list_url(list_id), list_index),
+                "type": "StatusList2021Entry",
+                "statusPurpose": "revocation",
+                "statusListIndex": list_index.to_string(),
+                "statusListCredential": state.status_list_url(list_id),
+            },
+        },
+    });
+    let jwt = state.key.sign_jwt(VC_JWT_TYPE, &claims);
+
+    let inserted: Option<i64> = sqlx::query_scalar(
+        r#"
+        INSERT INTO verifiable_credential (id, learner_id, course_id, jwt, issued_at)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (learner_id, course_id) WHERE revoked_at IS NULL DO NOTHING
+        RETURNING id
+        "#,
+    )
+    .bind(credential_id)
+    .bind(learner_id)
+    .bind(course_id)
+    .bind(&jwt)
+    .bind(issued_at)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to insert credential: {}", e),
+        )
+    })?;
+
+    if let Some(credential_id) = inserted {
+        return Ok(credential_id);
+    }
+
+    // A concurrent completion issued the credential first
+    sqlx::query_scalar(
+        r#"
+        SELECT id FROM verifiable_credential
+        WHERE learner_id = $1 AND course_id = $2 AND revoked_at IS NULL
+        "#,
+    )
+    .bind(learner_id)
+    .bind(course_id)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(internal_error)
+}
+
+pub async fn get_credential(
+    State(state): State<CredentialState>,
+    Query(params): Query<CredentialQuery>,
+) -> Result<Json<CredentialResponse>, (StatusCode, String)> {
+    let row: Option<CredentialRow> = sqlx::query_as::<_, CredentialRow>(
+        r#"
+        SELECT id, learner_id, course_id, jwt, issued_at, revoked_at, revocation_reason
+        FROM verifiable_credential
+        WHERE learner_id = $1 AND course_id = $2
+        ORDER BY id DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(&params.learner_id)
+    .bind(params.course_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    let row = row.ok_or_else(|| (StatusCode::NOT_FOUND, "Credential not found".to_string()))?;
+    let claims = state
+        .key
+        .verify_jwt(&row.jwt)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(CredentialResponse {
+        id: row.id,
+        learner_id: row.learner_id,
+        course_id: row.course_id,
+        jwt: row.jwt,
+        credential: claims["vc"].clone(),
+        issued_at: row.issued_at,
+        revoked_at: row.revoked_at,
+        revocation_reason: row.revocation_reason,
+    }))
+}
+
+/// Serves the credential JWT at the URL used as its `jti`.
+pub async fn get_credential_jwt(
+    State(pool): State<Pool<Postgres>>,
+    Path(path): Path<CredentialPath>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let jwt: Option<String> =
+        sqlx::query_scalar("SELECT jwt FROM verifiable_credential WHERE id = $1")
+            .bind(path.credential_id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(internal_error)?;
+
+    let jwt = jwt.ok_or_else(|| (StatusCode::NOT_FOUND, "Credential not found".to_string()))?;
+
+    Ok(([(header::CONTENT_TYPE, VC_JWT_CONTENT_TYPE)], jwt))
+}
+
+pub async fn verify_credential(
+    State(state): State<CredentialState>,
+    Json(payload): Json<VerifyCredentialRequest>,
+) -> Result<Json<VerifyCredentialResponse>, (StatusCode, String)> {
+    let issuer = state.key.did();
+    let invalid = |error: String, credential: Option<Value>| VerifyCredentialResponse {
+        valid: false,
+        revoked: false,
+        issuer: issuer.clone(),
+        credential,
+        error: Some(error),
+    };
+
+    let claims = match state.key.verify_jwt(payload.jwt.trim()) {
+        Ok(claims) => claims,
+        Err(e) => return Ok(Json(invalid(e, None))),
+    };
+    let credential = Some(claims["vc"].clone());
+
+    if claims["iss"] != issuer.as_str() {
+        return Ok(Json(invalid("Unknown issuer".to_string(), credential)));
+    }
+    if claims["nbf"]
+        .as_i64()
+        .is_some_and(|nbf| nbf > Utc::now().timestamp())
+    {
+        return Ok(Json(invalid(
+            "Credential is not valid yet".to_string(),
+            credential,
+        )));
+    }
+
+    let status = &claims["vc"]["credentialStatus"];
+    let list_id = status["statusListCredential"]
+        .as_str()
+        .and_then(|url| url.rsplit('/').next())
+        .and_then(|id| id.parse::<i64>().ok());
+    let list_index = status["statusListIndex"]
+        .as_str()
+        .and_then(|index| index.parse::<i64>().ok());
+    let (Some(list_id), Some(list_index)) = (list_id, list_index) else {
+        return Ok(Json(invalid(
+            "Missing credential status".to_string(),
+            credential,
+        )));
+    };
+
+    let revoked_at: Option<Option<DateTime<Utc>>> =
+        sqlx::query_scalar("SELECT revoked_at FROM verifiable_credential WHERE id = $1")
+            .bind(list_id * STATUS_LIST_LENGTH + list_index)
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(internal_error)?;
+
+    let Some(revoked_at) = revoked_at else {
+        return Ok(Json(invalid(
+            "Credential not found".to_string(),
+            credential,
+        )));
+    };
+    let revoked = revoked_at.is_some();
+
+    Ok(Json(VerifyCredentialResponse {
+        valid: !revoked,
+        revoked,
+        issuer,
+        credential,
+        error: revoked.then(|| "Credential has been revoked".to_string()),
+    }))
+}
+
+/// Revokes a credential. Only the course creator can revoke, proven by a
+/// wallet signature over the revocation message.
+pub async fn revoke_credential(
+    State(pool): State<Pool<Postgres>>,
+    Json(payload): Json<RevokeCredentialRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let creator_id: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT c.creator_id
+        FROM verifiable_credential vc
+        JOIN course c ON c.id = vc.course_id
+        WHERE vc.id = $1
+        "#,
+    )
+    .bind(payload.credential_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    let creator_id =
+        creator_id.ok_or_else(|| (StatusCode::NOT_FOUND, "Credential not found".to_string()))?;
+
+    let message = revocation_message(payload.credential_id, &payload.creator_id);
+    let signer = recover_eth_address(&message, &payload.signature)
+        .map_err(|e| (StatusCode::UNAUTHORIZED, e))?;
+    if signer != payload.creator_id.to_lowercase() || signer != creator_id.to_lowercase() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the course creator can revoke credentials".to_string(),
+        ));
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE verifiable_credential
+        SET revoked_at = now(), revocation_reason = $2
+        WHERE id = $1 AND revoked_at IS NULL
+        "#,
+    )
+    .bind(payload.credential_id)
+    .bind(&payload.reason)
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to revoke credential: {}", e),
+        )
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Serves the revocation status list as a signed StatusList2021Credential.
+pub async fn get_status_list(
+    State(state): State<CredentialState>,
+    Path(path): Path<StatusListPath>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let revoked_ids: Vec<i64> = sqlx::query_scalar(
+        r#"
+        SELECT id FROM verifiable_credential
+        WHERE revoked_at IS NOT NULL AND id / $1 = $2
+        "#,
+    )
+    .bind(STATUS_LIST_LENGTH)
+    .bind(path.list_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    let mut bitstring = vec![0u8; STATUS_LIST_LENGTH as usize / 8];
+    for id in revoked_ids {
+        let index = (id % STATUS_LIST_LENGTH) as usize;
+        bitstring[index / 8] |= 0x80 >> (index % 8);
+    }
+
+    let encode_error = |e: std::io::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to encode status list: {}", e),
+        )
+    };
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&bitstring).map_err(encode_error)?;
+    let encoded_list = URL_SAFE_NO_PAD.encode(encoder.finish().map_err(encode_error)?);
+
+    let issued_at = Utc::now();
+    let list_url = state.status_list_url(path.list_id);
+    let claims = json!({
+        "iss": state.key.did(),
+        "sub": format!("{}#list", list_url),
+        "jti": list_url,
+        "nbf": issued_at.timestamp(),
+        "iat": issued_at.timestamp(),
+        "vc": {
+            "@context": [
+                "https://www.w3.org/2018/credentials/v1",
+                "https://w3id.org/vc/status-list/2021/v1"
+            ],
+            "type": ["VerifiableCredential", "StatusList2021Credential"],
+            "issuer": state.key.did(),
+            "issuanceDate": issued_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            "credentialSubject": {
+                "id": format!("{}#list", list_url),
+                "type": "StatusList2021",
+                "statusPurpose": "revocation",
+                "encodedList": encoded_list,
+            },
+        },
+    });
+
+    Ok((
+        [(header::CONTENT_TYPE, VC_JWT_CONTENT_TYPE)],
+        state.key.sign_jwt(VC_JWT_TYPE, &claims),
+    ))
+}
+
+pub fn revocation_message(credential_id: i64, creator_id: &str) -> String {
+    format!(
+        "Aranya credential revocation\nCredential: {}\nCreator: {}",
+        credential_id,
+        creator_id.to_lowercase()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::response::IntoResponse;
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::handlers::progress::complete_course;
+    use crate::models::progress::CourseCompleteRequest;
+    use crate::test_support::{personal_sign, wallet_address};
+
+    const CREATOR_SECRET: [u8; 32] = [1; 32];
+    const LEARNER: &str = "0xabc";
+
+    // A one module course the enrolled learner has completed every module of
+    async fn completed_modules(pool: &PgPool) -> i64 {
+        let creator = wallet_address(&CREATOR_SECRET);
+        sqlx::query("INSERT INTO creator (id) VALUES ($1)")
+            .bind(&creator)
+            .execute(pool)
+            .await
+            .unwrap();
+        let course_id: i64 = sqlx::query_scalar(
+            "INSERT INTO course (title, description, creator_id) VALUES ('Rust', 'Basics', $1) RETURNING id",
+        )
+        .bind(&creator)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"
+            WITH module AS (
+                INSERT INTO module (course_id, title, position) VALUES ($1, 'Intro', 1) RETURNING id
+            ), learner AS (
+                INSERT INTO learner (id) VALUES ($2) RETURNING id
+            ), enrollment AS (
+                INSERT INTO learner_course_enrollment (learner_id, course_id) SELECT id, $1 FROM learner
+            )
+            INSERT INTO module_completion (learner_id, module_id) SELECT $2, id FROM module
+            "#,
+        )
+        .bind(course_id)
+        .bind(LEARNER)
+        .execute(pool)
+        .await
+        .unwrap();
+        course_id
+    }
+
+    async fn complete(state: &CredentialState, course_id: i64) -> Result<(), (StatusCode, String)> {
+        complete_course(
+            State(state.clone()),
+            Json(CourseCompleteRequest {
+                learner_id: LEARNER.to_string(),
+                course_id,
+            }),
+        )
+        .await
+        .map(|response| assert_eq!(response.into_response().status(), StatusCode::CREATED))
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures("../../database_schema/aranya_db_schema.sql")
+    )]
+    async fn revoked_credential_is_not_reissued(pool: PgPool) {
+        let state = CredentialState {
+            pool: pool.clone(),
+            key: ServerKey::from_hex(&hex::encode([2u8; 32])).unwrap(),
+            issuer_url: "https://api.aranya.test".to_string(),
+        };
+        let course_id = completed_modules(&pool).await;
+
+        complete(&state, course_id).await.unwrap();
+        complete(&state, course_id).await.unwrap();
+        let credential_id: i64 = sqlx::query_scalar("SELECT id FROM verifiable_credential")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        let creator_id = wallet_address(&CREATOR_SECRET);
+        revoke_credential(
+            State(pool.clone()),
+            Json(RevokeCredentialRequest {
+                credential_id,
+                signature: personal_sign(
+                    &CREATOR_SECRET,
+                    &revocation_message(credential_id, &creator_id),
+                ),
+                creator_id,
+                reason: Some("Plagiarism".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+
+        let (status, _) = complete(&state, course_id).await.unwrap_err();
+        assert_eq!(status, StatusCode::CONFLICT);
+        let credentials: Vec<(i64, bool)> =
+            sqlx::query_as("SELECT id, revoked_at IS NOT NULL FROM verifiable_credential")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(credentials, vec![(credential_id, true)]);
+    }
+}
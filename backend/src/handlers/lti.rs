@@ -0,0 +1,779 @@
+use axum::{
+    Form, Json,
+    extract::{Query, State},
+    http::{
+        HeaderMap, HeaderValue, StatusCode,
+        header::{COOKIE, SET_COOKIE},
+    },
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use chrono::Utc;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres};
+
+use crate::handlers::progress::internal_error;
+use crate::lti::{
+    AGS_ENDPOINT_CLAIM, AGS_SCORE_SCOPE, CONTENT_ITEMS_CLAIM, CUSTOM_CLAIM,
+    DEEP_LINKING_DATA_CLAIM, DEEP_LINKING_REQUEST, DEEP_LINKING_RESPONSE,
+    DEEP_LINKING_SETTINGS_CLAIM, DEPLOYMENT_ID_CLAIM, LTI_VERSION, LtiConfig, MESSAGE_TYPE_CLAIM,
+    RESOURCE_LINK_REQUEST, TARGET_LINK_URI_CLAIM, VERSION_CLAIM,
+    token::{validate_id_token, verify_jwt},
+};
+use crate::models::lti::{
+    DeepLinkRow, DeepLinkSelectForm, LtiLaunchForm, LtiLoginParams, LtiPlatformRow,
+};
+use crate::quiz_format::qti::escape_xml;
+use crate::xapi::{Activity, Verb, enqueue_statement};
+
+// Login states expire after 10 minutes, in the database and in the browser
+const LOGIN_STATE_MAX_AGE_SECONDS: i64 = 600;
+const LOGIN_STATE_COOKIE_PREFIX: &str = "lti_state_";
+
+#[derive(Clone)]
+pub struct LtiState {
+    pub pool: Pool<Postgres>,
+    pub config: LtiConfig,
+    pub http: reqwest::Client,
+}
+
+/// Public key set platforms verify deep linking responses and AGS client
+/// assertions with.
+pub async fn get_lti_jwks(State(state): State<LtiState>) -> Json<Value> {
+    Json(state.config.key.jwks())
+}
+
+pub async fn lti_login(
+    State(state): State<LtiState>,
+    Query(params): Query<LtiLoginParams>,
+) -> Result<Response, (StatusCode, String)> {
+    start_login(&state, params).await
+}
+
+pub async fn lti_login_form(
+    State(state): State<LtiState>,
+    Form(params): Form<LtiLoginParams>,
+) -> Result<Response, (StatusCode, String)> {
+    start_login(&state, params).await
+}
+
+/// OIDC third party login initiation: sends the browser back to the
+/// platform with a single use state and nonce, the platform then posts the
+/// id_token to `/lti/launch`. The state is also set as a cookie, so a launch
+/// is only accepted in the browser that started the login.
+async fn start_login(
+    state: &LtiState,
+    params: LtiLoginParams,
+) -> Result<Response, (StatusCode, String)> {
+    let platforms: Vec<LtiPlatformRow> = sqlx::query_as::<_, LtiPlatformRow>(
+        r#"
+        SELECT id, issuer, client_id, auth_login_url, auth_token_url, jwks_url
+        FROM lti_platform
+        WHERE issuer = $1 AND ($2::TEXT IS NULL OR client_id = $2)
+        "#,
+    )
+    .bind(&params.iss)
+    .bind(&params.client_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(internal_error)?;
+    let [platform] = &platforms[..] else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("No single LTI platform is registered for {}", params.iss),
+        ));
+    };
+
+    let login_state = hex::encode(rand::random::<[u8; 16]>());
+    let nonce = hex::encode(rand::random::<[u8; 16]>());
+    sqlx::query("DELETE FROM lti_login_state WHERE created_at < now() - make_interval(secs => $1)")
+        .bind(LOGIN_STATE_MAX_AGE_SECONDS as f64)
+        .execute(&state.pool)
+        .await
+        .map_err(internal_error)?;
+    sqlx::query("INSERT INTO lti_login_state (state, nonce, platform_id) VALUES ($1, $2, $3)")
+        .bind(&login_state)
+        .bind(&nonce)
+        .bind(platform.id)
+        .execute(&state.pool)
+        .await
+        .map_err(internal_error)?;
+
+    let launch_url = state.config.launch_url();
+    let mut query = vec![
+        ("scope", "openid"),
+        ("response_type", "id_token"),
+        ("response_mode", "form_post"),
+        ("prompt", "none"),
+        ("client_id", platform.client_id.as_str()),
+        ("redirect_uri", launch_url.as_str()),
+        ("login_hint", params.login_hint.as_str()),
+        ("state", login_state.as_str()),
+        ("nonce", nonce.as_str()),
+    ];
+    if let Some(hint) = &params.lti_message_hint {
+        query.push(("lti_message_hint", hint));
+    }
+    let url = reqwest::Url::parse_with_params(&platform.auth_login_url, &query)
+        .map_err(|e| internal_error(format!("Invalid platform login URL: {}", e)))?;
+
+    Ok((
+        [(
+            SET_COOKIE,
+            login_state_cookie(&login_state, LOGIN_STATE_MAX_AGE_SECONDS),
+        )],
+        Redirect::to(url.as_str()),
+    )
+        .into_response())
+}
+
+/// Receives the id_token of a launch. Resource link launches enroll the
+/// platform user and open the course, deep linking launches show the course
+/// picker.
+pub async fn lti_launch(
+    State(state): State<LtiState>,
+    headers: HeaderMap,
+    Form(form): Form<LtiLaunchForm>,
+) -> Result<Response, (StatusCode, String)> {
+    // Without this, a login started by an attacker could be completed in the victim's browser
+    if login_state_from_cookie(&headers, &form.state) != Some(form.state.as_str()) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            "Login state does not match this browser".to_string(),
+        ));
+    }
+
+    let login: Option<(String, i64)> = sqlx::query_as(
+        r#"
+        DELETE FROM lti_login_state
+        WHERE state = $1 AND created_at >= now() - make_interval(secs => $2)
+        RETURNING nonce, platform_id
+        "#,
+    )
+    .bind(&form.state)
+    .bind(LOGIN_STATE_MAX_AGE_SECONDS as f64)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(internal_error)?;
+    let (nonce, platform_id) = login.ok_or_else(|| {
+        (
+            StatusCode::UNAUTHORIZED,
+            "Unknown or expired login state".to_string(),
+        )
+    })?;
+    let platform = fetch_platform(&state.pool, platform_id).await?;
+
+    let jwks: Value = state
+        .http
+        .get(&platform.jwks_url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| {
+            (
+                StatusCode::BAD_GATEWAY,
+                format!("Platform keys unavailable: {}", e),
+            )
+        })?
+        .json()
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::BAD_GATEWAY,
+                format!("Invalid platform keys: {}", e),
+            )
+        })?;
+    let claims = verify_jwt(&form.id_token, &jwks)
+        .and_then(|claims| {
+            validate_id_token(&claims, &platform, &nonce, Utc::now().timestamp())?;
+            Ok(claims)
+        })
+        .map_err(|e| (StatusCode::UNAUTHORIZED, e))?;
+
+    let deployment_id = claims[DEPLOYMENT_ID_CLAIM].as_str().unwrap_or_default();
+    let deployment_registered: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM lti_deployment WHERE platform_id = $1 AND deployment_id = $2)",
+    )
+    .bind(platform.id)
+    .bind(deployment_id)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(internal_error)?;
+    if !deployment_registered {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            format!(
+                "Deployment {} is not registered for this platform",
+                deployment_id
+            ),
+        ));
+    }
+
+    let mut response = match claims[MESSAGE_TYPE_CLAIM].as_str() {
+        Some(RESOURCE_LINK_REQUEST) => launch_course(&state, &platform, &claims).await?,
+        Some(DEEP_LINKING_REQUEST) => show_course_picker(&state, &platform, &claims).await?,
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Unsupported LTI message type".to_string(),
+            ));
+        }
+    };
+    response
+        .headers_mut()
+        .append(SET_COOKIE, login_state_cookie(&form.state, 0));
+
+    Ok(response)
+}
+
+/// Answers a deep linking request with the picked course as a resource link
+/// with a gradebook column, posted back to the platform.
+pub async fn lti_deep_link(
+    State(state): State<LtiState>,
+    Form(form): Form<DeepLinkSelectForm>,
+) -> Result<Html<String>, (StatusCode, String)> {
+    let deep_link: DeepLinkRow = sqlx::query_as::<_, DeepLinkRow>(
+        r#"
+        DELETE FROM lti_deep_link
+        WHERE id = $1 AND created_at >= now() - INTERVAL '1 hour'
+        RETURNING platform_id, deployment_id, return_url, data
+        "#,
+    )
+    .bind(&form.deep_link_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(internal_error)?
+    .ok_or_else(|| {
+        (
+            StatusCode::UNAUTHORIZED,
+            "Unknown or expired deep linking request".to_string(),
+        )
+    })?;
+    let platform = fetch_platform(&state.pool, deep_link.platform_id).await?;
+    let title: String = sqlx::query_scalar("SELECT title FROM course WHERE id = $1")
+        .bind(form.course_id)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Course not found".to_string()))?;
+
+    let now = Utc::now().timestamp();
+    let mut claims = json!({
+        "iss": platform.client_id,
+        "aud": platform.issuer,
+        "iat": now,
+        "exp": now + 600,
+        "nonce": hex::encode(rand::random::<[u8; 16]>()),
+        MESSAGE_TYPE_CLAIM: DEEP_LINKING_RESPONSE,
+        VERSION_CLAIM: LTI_VERSION,
+        DEPLOYMENT_ID_CLAIM: deep_link.deployment_id,
+        CONTENT_ITEMS_CLAIM: [{
+            "type": "ltiResourceLink",
+            "title": title,
+            "url": state.config.launch_url(),
+            "custom": { "course_id": form.course_id.to_string() },
+            "lineItem": { "scoreMaximum": 100, "label": title },
+        }],
+    });
+    if let Some(data) = deep_link.data {
+        claims[DEEP_LINKING_DATA_CLAIM] = json!(data);
+    }
+
+    Ok(Html(form_post_page(
+        &deep_link.return_url,
+        &[("JWT", &state.config.key.sign_jwt(&claims))],
+    )))
+}
+
+async fn launch_course(
+    state: &LtiState,
+    platform: &LtiPlatformRow,
+    claims: &Value,
+) -> Result<Response, (StatusCode, String)> {
+    let sub = claims["sub"]
+        .as_str()
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                "Anonymous launches are not supported".to_string(),
+            )
+        })?;
+    let course_id = launched_course_id(claims).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            "Launch has no course_id custom parameter".to_string(),
+        )
+    })?;
+    let course_exists: bool =
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM course WHERE id = $1)")
+            .bind(course_id)
+            .fetch_one(&state.pool)
+            .await
+            .map_err(internal_error)?;
+    if !course_exists {
+        return Err((StatusCode::NOT_FOUND, "Course not found".to_string()));
+    }
+
+    let learner_id = lti_learner_id(&platform.issuer, sub);
+    let name = claims["name"].as_str().map(str::to_string).or_else(|| {
+        let parts: Vec<&str> = [&claims["given_name"], &claims["family_name"]]
+            .iter()
+            .filter_map(|n| n.as_str())
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(" "))
+    });
+
+    let mut tx = state.pool.begin().await.map_err(internal_error)?;
+    sqlx::query(
+        r#"
+        INSERT INTO learner (id, display_name) VALUES ($1, $2)
+        ON CONFLICT (id) DO UPDATE SET display_name = COALESCE(learner.display_name, EXCLUDED.display_name)
+        "#,
+    )
+    .bind(&learner_id)
+    .bind(&name)
+    .execute(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+    sqlx::query(
+        "INSERT INTO lti_user (platform_id, sub, learner_id) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING",
+    )
+    .bind(platform.id)
+    .bind(sub)
+    .bind(&learner_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+
+    let enrolled = sqlx::query(
+        "INSERT INTO learner_course_enrollment (learner_id, course_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+    )
+    .bind(&learner_id)
+    .bind(course_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+    if enrolled.rows_affected() > 0 {
+        enqueue_statement(
+            &mut *tx,
+            &learner_id,
+            Verb::Launched,
+            Activity::Course(course_id),
+            None,
+        )
+        .await
+        .map_err(internal_error)?;
+    }
+
+    // Progress is pushed to the gradebook column when the platform grants scores
+    let ags = &claims[AGS_ENDPOINT_CLAIM];
+    let can_post_scores = ags["scope"]
+        .as_array()
+        .is_some_and(|scopes| scopes.iter().any(|s| s == AGS_SCORE_SCOPE));
+    if let Some(lineitem) = ags["lineitem"].as_str().filter(|_| can_post_scores) {
+        sqlx::query(
+            r#"
+            INSERT INTO lti_grade_sync (platform_id, lineitem, sub, learner_id, course_id)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (lineitem, sub) DO NOTHING
+            "#,
+        )
+        .bind(platform.id)
+        .bind(lineitem)
+        .bind(sub)
+        .bind(&learner_id)
+        .bind(course_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+    }
+    tx.commit().await.map_err(internal_error)?;
+
+    let url = reqwest::Url::parse_with_params(
+        &format!("{}/view/{}", state.config.webapp_url, course_id),
+        &[("ltiLearnerId", &learner_id)],
+    )
+    .map_err(internal_error)?;
+
+    Ok(Redirect::to(url.as_str()).into_response())
+}
+
+async fn show_course_picker(
+    state: &LtiState,
+    platform: &LtiPlatformRow,
+    claims: &Value,
+) -> Result<Response, (StatusCode, String)> {
+    let settings = &claims[DEEP_LINKING_SETTINGS_CLAIM];
+    let return_url = settings["deep_link_return_url"].as_str().ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            "Deep linking request has no return URL".to_string(),
+        )
+    })?;
+    let accepts_links = settings["accept_types"]
+        .as_array()
+        .is_none_or(|types| types.iter().any(|t| t == "ltiResourceLink"));
+    if !accepts_links {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Platform does not accept resource links".to_string(),
+        ));
+    }
+
+    let deep_link_id = hex::encode(rand::random::<[u8; 16]>());
+    sqlx::query(
+        r#"
+        INSERT INTO lti_deep_link (id, platform_id, deployment_id, return_url, data)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+    )
+    .bind(&deep_link_id)
+    .bind(platform.id)
+    .bind(claims[DEPLOYMENT_ID_CLAIM].as_str())
+    .bind(return_url)
+    .bind(settings["data"].as_str())
+    .execute(&state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    let courses: Vec<(i64, String, String)> =
+        sqlx::query_as("SELECT id, title, description FROM course ORDER BY title, id")
+            .fetch_all(&state.pool)
+            .await
+            .map_err(internal_error)?;
+
+    let action = format!("{}/lti/deep-link", state.config.public_api_url);
+    let items: String = courses
+        .iter()
+        .map(|(id, title, description)| {
+            format!(
+                r#"<li><form method="post" action="{action}"><input type="hidden" name="deep_link_id" value="{deep_link_id}"><input type="hidden" name="course_id" value="{id}"><button type="submit">{title}</button><p>{description}</p></form></li>"#,
+                action = escape_xml(&action),
+                title = escape_xml(title),
+                description = escape_xml(description),
+            )
+        })
+        .collect();
+
+    Ok(Html(format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Add an Aranya course</title>\n</head>\n<body>\n<h1>Add an Aranya course</h1>\n<ul>{}</ul>\n</body>\n</html>\n",
+        items
+    ))
+    .into_response())
+}
+
+async fn fetch_platform(
+    pool: &Pool<Postgres>,
+    platform_id: i64,
+) -> Result<LtiPlatformRow, (StatusCode, String)> {
+    sqlx::query_as::<_, LtiPlatformRow>(
+        r#"
+        SELECT id, issuer, client_id, auth_login_url, auth_token_url, jwks_url
+        FROM lti_platform
+        WHERE id = $1
+        "#,
+    )
+    .bind(platform_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(internal_error)?
+    .ok_or_else(|| (StatusCode::NOT_FOUND, "LTI platform not found".to_string()))
+}
+
+// The course_id custom parameter set by deep linking, or a courseId in the target link
+fn launched_course_id(claims: &Value) -> Option<i64> {
+    let custom = &claims[CUSTOM_CLAIM]["course_id"];
+    custom
+        .as_i64()
+        .or_else(|| custom.as_str().and_then(|id| id.trim().parse().ok()))
+        .or_else(|| {
+            let target = reqwest::Url::parse(claims[TARGET_LINK_URI_CLAIM].as_str()?).ok()?;
+            target
+                .query_pairs()
+                .find(|(name, _)| name == "courseId")
+                .and_then(|(_, id)| id.parse().ok())
+        })
+}
+
+// One cookie per login, so logins started in several frames don't overwrite
+// each other. The launch is a cross-site form post, hence SameSite=None.
+fn login_state_cookie(login_state: &str, max_age_seconds: i64) -> HeaderValue {
+    HeaderValue::from_str(&format!(
+        "{}{}={}; Path=/lti/launch; Max-Age={}; HttpOnly; Secure; SameSite=None",
+        LOGIN_STATE_COOKIE_PREFIX, login_state, login_state, max_age_seconds
+    ))
+    .expect("Login states are hex")
+}
+
+fn login_state_from_cookie<'a>(headers: &'a HeaderMap, login_state: &str) -> Option<&'a str> {
+    let name = format!("{}{}", LOGIN_STATE_COOKIE_PREFIX, login_state);
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(cookie_name, _)| *cookie_name == name)
+        .map(|(_, value)| value)
+}
+
+/// Learner id of a platform user, stable across launches and courses.
+fn lti_learner_id(issuer: &str, sub: &str) -> String {
+    let digest = Sha256::digest(format!("{}\n{}", issuer, sub));
+    format!("lti:{}", &hex::encode(digest)[..40])
+}
+
+// A page that posts `fields` to `action` as soon as it loads
+fn form_post_page(action: &str, fields: &[(&str, &str)]) -> String {
+    let inputs: String = fields
+        .iter()
+        .map(|(name, value)| {
+            format!(
+                r#"<input type="hidden" name="{}" value="{}">"#,
+                escape_xml(name),
+                escape_xml(value)
+            )
+        })
+        .collect();
+    format!(
+        "<!DOCTYPE html>\n<html>\n<body onload=\"document.forms[0].submit()\">\n<form method=\"post\" action=\"{}\">{}<noscript><button type=\"submit\">Continue</button></noscript></form>\n</body>\n</html>\n",
+        escape_xml(action),
+        inputs
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::header::LOCATION;
+    use sqlx::PgPool;
+    use std::{collections::HashMap, sync::Arc};
+
+    use super::*;
+    use crate::lti::key::ToolKey;
+    use crate::lti::mock::{CLIENT_ID, ISSUER, MockPlatform, TOOL_KEY_PEM, mock_platform};
+    use crate::test_support::serve;
+
+    const DEPLOYMENT_ID: &str = "deployment-1";
+
+    // The tool, a platform registered with one deployment and a course to launch
+    async fn setup(pool: &PgPool) -> (LtiState, Arc<MockPlatform>, i64) {
+        let tool_key = ToolKey::from_pem(TOOL_KEY_PEM).unwrap();
+        let platform = MockPlatform::new(&tool_key);
+        let row = MockPlatform::row(&serve(mock_platform(platform.clone())).await);
+        sqlx::query(
+            r#"
+            WITH platform AS (
+                INSERT INTO lti_platform (issuer, client_id, auth_login_url, auth_token_url, jwks_url)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING id
+            )
+            INSERT INTO lti_deployment (platform_id, deployment_id) SELECT id, $6 FROM platform
+            "#,
+        )
+        .bind(&row.issuer)
+        .bind(&row.client_id)
+        .bind(&row.auth_login_url)
+        .bind(&row.auth_token_url)
+        .bind(&row.jwks_url)
+        .bind(DEPLOYMENT_ID)
+        .execute(pool)
+        .await
+        .unwrap();
+        let course_id: i64 = sqlx::query_scalar(
+            r#"
+            WITH creator AS (INSERT INTO creator (id) VALUES ('0xcreator') RETURNING id)
+            INSERT INTO course (title, description, creator_id)
+            SELECT 'Rust', 'Basics', id FROM creator
+            RETURNING id
+            "#,
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+
+        let state = LtiState {
+            pool: pool.clone(),
+            config: LtiConfig {
+                key: tool_key,
+                public_api_url: "https://api.aranya.test".to_string(),
+                webapp_url: "https://aranya.test".to_string(),
+            },
+            http: reqwest::Client::new(),
+        };
+        (state, platform, course_id)
+    }
+
+    // Starts a login and returns its state, its nonce and the cookie the browser keeps
+    async fn login(state: &LtiState) -> (String, String, String) {
+        let response = start_login(
+            state,
+            LtiLoginParams {
+                iss: ISSUER.to_string(),
+                login_hint: "learner-sub".to_string(),
+                lti_message_hint: None,
+                client_id: Some(CLIENT_ID.to_string()),
+            },
+        )
+        .await
+        .unwrap();
+
+        let location = response.headers()[LOCATION].to_str().unwrap();
+        let query: HashMap<String, String> = reqwest::Url::parse(location)
+            .unwrap()
+            .query_pairs()
+            .into_owned()
+            .collect();
+        let set_cookie = response.headers()[SET_COOKIE].to_str().unwrap();
+        assert!(set_cookie.contains("HttpOnly; Secure; SameSite=None"));
+        let cookie = set_cookie.split(';').next().unwrap().to_string();
+
+        (query["state"].clone(), query["nonce"].clone(), cookie)
+    }
+
+    fn id_token(
+        platform: &MockPlatform,
+        nonce: &str,
+        deployment_id: &str,
+        course_id: i64,
+    ) -> String {
+        let now = Utc::now().timestamp();
+        platform.key.sign_jwt(&json!({
+            "iss": ISSUER,
+            "aud": CLIENT_ID,
+            "sub": "learner-sub",
+            "iat": now,
+            "exp": now + 300,
+            "nonce": nonce,
+            MESSAGE_TYPE_CLAIM: RESOURCE_LINK_REQUEST,
+            VERSION_CLAIM: LTI_VERSION,
+            DEPLOYMENT_ID_CLAIM: deployment_id,
+            CUSTOM_CLAIM: { "course_id": course_id.to_string() },
+        }))
+    }
+
+    async fn launch(
+        state: &LtiState,
+        cookie: Option<&str>,
+        login_state: &str,
+        id_token: String,
+    ) -> Result<Response, (StatusCode, String)> {
+        let mut headers = HeaderMap::new();
+        if let Some(cookie) = cookie {
+            headers.insert(COOKIE, HeaderValue::from_str(cookie).unwrap());
+        }
+        lti_launch(
+            State(state.clone()),
+            headers,
+            Form(LtiLaunchForm {
+                id_token,
+                state: login_state.to_string(),
+            }),
+        )
+        .await
+    }
+
+    async fn enrollments(pool: &PgPool) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM learner_course_enrollment")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures("../../database_schema/aranya_db_schema.sql")
+    )]
+    async fn launch_in_the_login_browser_enrolls_the_learner(pool: PgPool) {
+        let (state, platform, course_id) = setup(&pool).await;
+        let (login_state, nonce, cookie) = login(&state).await;
+
+        let response = launch(
+            &state,
+            Some(&format!("other=1; {}", cookie)),
+            &login_state,
+            id_token(&platform, &nonce, DEPLOYMENT_ID, course_id),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert!(
+            response.headers()[LOCATION]
+                .to_str()
+                .unwrap()
+                .starts_with(&format!(
+                    "https://aranya.test/view/{}?ltiLearnerId=lti%3A",
+                    course_id
+                ))
+        );
+        // The cookie is cleared along with the login state
+        let set_cookie = response.headers()[SET_COOKIE].to_str().unwrap();
+        assert!(set_cookie.starts_with(&format!("{}; ", cookie)));
+        assert!(set_cookie.contains("Max-Age=0"));
+        assert_eq!(enrollments(&pool).await, 1);
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures("../../database_schema/aranya_db_schema.sql")
+    )]
+    async fn launch_in_another_browser_is_rejected(pool: PgPool) {
+        let (state, platform, course_id) = setup(&pool).await;
+        let (login_state, nonce, cookie) = login(&state).await;
+        let (_, _, other_cookie) = login(&state).await;
+
+        for cookie in [
+            None,
+            Some(other_cookie.as_str()),
+            Some(&format!("{}=forged", cookie.split('=').next().unwrap())),
+        ] {
+            let (status, message) = launch(
+                &state,
+                cookie,
+                &login_state,
+                id_token(&platform, &nonce, DEPLOYMENT_ID, course_id),
+            )
+            .await
+            .unwrap_err();
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+            assert_eq!(message, "Login state does not match this browser");
+        }
+        assert_eq!(enrollments(&pool).await, 0);
+
+        // The login is left for the browser that started it
+        launch(
+            &state,
+            Some(&cookie),
+            &login_state,
+            id_token(&platform, &nonce, DEPLOYMENT_ID, course_id),
+        )
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures("../../database_schema/aranya_db_schema.sql")
+    )]
+    async fn launch_from_an_unregistered_deployment_is_rejected(pool: PgPool) {
+        let (state, platform, course_id) = setup(&pool).await;
+        let (login_state, nonce, cookie) = login(&state).await;
+
+        let (status, message) = launch(
+            &state,
+            Some(&cookie),
+            &login_state,
+            id_token(&platform, &nonce, "deployment-2", course_id),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            message,
+            "Deployment deployment-2 is not registered for this platform"
+        );
+        assert_eq!(enrollments(&pool).await, 0);
+    }
+}
@@ -0,0 +1,86 @@
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Query, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+
+use crate::bundle::{read_zip, write_zip};
+use crate::handlers::analytics::{CreatorRequest, authorize_creator, verify_creator_request};
+use crate::handlers::bundle::{fetch_course_bundle, import_course_bundle};
+use crate::handlers::progress::internal_error;
+use crate::markdown_course::{read_markdown_course, write_markdown_course};
+use crate::models::bundle::{ExportCourseQuery, ImportCourseQuery};
+use crate::state::AppState;
+
+/// Downloads a course as a zip of Markdown files, for the course creator
+/// only since quizzes include their answer key.
+pub async fn export_markdown_course(
+    State(state): State<AppState>,
+    Query(params): Query<ExportCourseQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    authorize_creator(
+        &state.pool,
+        CreatorRequest::ExportMarkdownCourse,
+        params.course_id,
+        &params.creator_id,
+        params.requested_at,
+        &params.signature,
+    )
+    .await?;
+
+    let bundle = fetch_course_bundle(&state, params.course_id).await?;
+    let files = write_markdown_course(bundle).map_err(internal_error)?;
+    let root = format!("aranya-course-{}", params.course_id);
+    let paths: Vec<String> = files
+        .keys()
+        .map(|path| format!("{}/{}", root, path))
+        .collect();
+    let zip = write_zip(
+        paths
+            .iter()
+            .map(String::as_str)
+            .zip(files.values().map(Vec::as_slice)),
+    )
+    .map_err(internal_error)?;
+    let disposition = format!("attachment; filename=\"{}.zip\"", root);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        zip,
+    ))
+}
+
+/// Creates a course from a zip of Markdown files uploaded as the request
+/// body, signed by the creator. Nothing is written unless every file is valid.
+pub async fn import_markdown_course(
+    State(state): State<AppState>,
+    Query(params): Query<ImportCourseQuery>,
+    body: Bytes,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let creator_id = verify_creator_request(
+        &state.pool,
+        CreatorRequest::ImportMarkdownCourse,
+        None,
+        &params.creator_id,
+        params.requested_at,
+        &params.signature,
+    )
+    .await?;
+
+    let bundle = read_zip(&body)
+        .and_then(read_markdown_course)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let imported = import_course_bundle(&state, bundle, &creator_id, params.dry_run).await?;
+    let status = if params.dry_run {
+        StatusCode::OK
+    } else {
+        StatusCode::CREATED
+    };
+
+    Ok((status, Json(imported)))
+}
@@ -0,0 +1,447 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::Utc;
+use serde_json::{Value, json};
+use sqlx::{Pool, Postgres};
+
+use crate::handlers::progress::{fetch_progress_percent, internal_error};
+use crate::models::nft::{
+    CreatorNftRow, LearnerNftRow, NftAttribute, NftMetadata, NftMetadataPath,
+    RegisterCourseNftsRequest, RegisterLearnerNftRequest,
+};
+use crate::signing::recover_eth_address;
+
+// Tree images per milestone, shared by the learner and teacher trees
+pub(crate) const MILESTONE_IMAGES: [&str; 5] = [
+    "ipfs://bafybeifisopzpx72vywf5nmepdhiwn5kuszihlzti4nnolim7dkgprynv4",
+    "ipfs://bafybeigt6xdlnpozt7cljq6dbmwbe4ooxxm32275xgerwcmsozwtlyatym",
+    "ipfs://bafybeihhaucf6zdgwetp4yvwhnn2xrn2mdxgdzg5j4bdcbknmn7vehej7m",
+    "ipfs://bafybeihjbgjajxlt5ntifquuyt33csmz4c6tocawjlc73ifaz4blxlsjee",
+    "ipfs://bafybeihbyrcsvb6zi3mc2yamg4afoiz2hzw3fxnq366z3ebjqk4pcm5xia",
+];
+
+pub(crate) const MILESTONE_NAMES: [&str; 5] = ["Seed", "Germination", "Seedling", "Sapling", "Mature Tree"];
+
+// Must match the thresholds CourseManager was deployed with
+pub(crate) const CREATOR_MILESTONE_THRESHOLDS: [i64; 5] = [0, 1, 2, 3, 500];
+
+// CreatorNFT clones only ever mint a single token
+const CREATOR_TOKEN_ID: i64 = 1;
+
+// Maximum clock skew accepted on the signed `requestedAt` of an NFT registration
+const REGISTER_REQUEST_WINDOW_SECONDS: i64 = 300;
+
+/// Registers the NFT contracts cloned for a course, signed by its creator.
+pub async fn register_course_nfts(
+    State(pool): State<Pool<Postgres>>,
+    Json(payload): Json<RegisterCourseNftsRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    check_request_time(payload.requested_at)?;
+    let creator_id: String = sqlx::query_scalar("SELECT creator_id FROM course WHERE id = $1")
+        .bind(payload.course_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Course not found".to_string()))?;
+    let message = course_nfts_message(
+        payload.course_id,
+        &payload.creator_nft_address,
+        &payload.learner_nft_address,
+        payload.requested_at,
+    );
+    let signer = recover_eth_address(&message, &payload.signature)
+        .map_err(|e| (StatusCode::UNAUTHORIZED, e))?;
+    if signer != creator_id.to_lowercase() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the course creator can register its NFTs".to_string(),
+        ));
+    }
+
+    let result = sqlx::query(
+        r#"
+        UPDATE course
+        SET creator_nft_address = $1, learner_nft_address = $2,
+            nfts_registered_at = COALESCE(nfts_registered_at, now())
+        WHERE id = $3
+        "#,
+    )
+    .bind(payload.creator_nft_address.to_lowercase())
+    .bind(payload.learner_nft_address.to_lowercase())
+    .bind(payload.course_id)
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to register course NFTs: {}", e),
+        )
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "Course not found".to_string()));
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({ "message": "Course NFTs registered", "course_id": payload.course_id })),
+    ))
+}
+
+/// Registers the LearnerNFT minted on enrollment, signed by the learner.
+pub async fn register_learner_nft(
+    State(pool): State<Pool<Postgres>>,
+    Json(payload): Json<RegisterLearnerNftRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    check_request_time(payload.requested_at)?;
+    let message = learner_nft_message(
+        &payload.learner_id,
+        payload.course_id,
+        payload.nft_token_id,
+        payload.requested_at,
+    );
+    let signer = recover_eth_address(&message, &payload.signature)
+        .map_err(|e| (StatusCode::UNAUTHORIZED, e))?;
+    if signer != payload.learner_id.to_lowercase() {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            "Signature does not match learner".to_string(),
+        ));
+    }
+
+    let result = sqlx::query(
+        r#"
+        UPDATE learner_course_enrollment e
+        SET nft_token_id = $1, nft_contract_address = c.learner_nft_address,
+            nft_minted_at = COALESCE(e.nft_minted_at, now())
+        FROM course c
+        WHERE c.id = e.course_id AND e.learner_id = $2 AND e.course_id = $3
+        "#,
+    )
+    .bind(payload.nft_token_id)
+    .bind(&payload.learner_id)
+    .bind(payload.course_id)
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to register learner NFT: {}", e),
+        )
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "Enrollment not found".to_string()));
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({ "message": "Learner NFT registered", "course_id": payload.course_id })),
+    ))
+}
+
+fn check_request_time(requested_at: i64) -> Result<(), (StatusCode, String)> {
+    if (Utc::now().timestamp() - requested_at).abs() > REGISTER_REQUEST_WINDOW_SECONDS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "NFT registration request has expired".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub fn course_nfts_message(
+    course_id: i64,
+    creator_nft_address: &str,
+    learner_nft_address: &str,
+    requested_at: i64,
+) -> String {
+    format!(
+        "Aranya course NFTs\nCourse: {}\nCreatorNFT: {}\nLearnerNFT: {}\nRequested at: {}",
+        course_id,
+        creator_nft_address.to_lowercase(),
+        learner_nft_address.to_lowercase(),
+        requested_at
+    )
+}
+
+pub fn learner_nft_message(
+    learner_id: &str,
+    course_id: i64,
+    nft_token_id: i64,
+    requested_at: i64,
+) -> String {
+    format!(
+        "Aranya learner NFT\nLearner: {}\nCourse: {}\nToken: {}\nRequested at: {}",
+        learner_id.to_lowercase(),
+        course_id,
+        nft_token_id,
+        requested_at
+    )
+}
+
+pub async fn get_nft_metadata(
+    State(pool): State<Pool<Postgres>>,
+    Path(params): Path<NftMetadataPath>,
+) -> Result<Json<NftMetadata>, (StatusCode, String)> {
+    let contract = params.contract.to_lowercase();
+
+    let learner_row: Option<LearnerNftRow> = sqlx::query_as::<_, LearnerNftRow>(
+        r#"
+        SELECT
+            e.learner_id,
+            c.id AS course_id,
+            c.title AS course_title,
+            cc.completed_at
+        FROM learner_course_enrollment e
+        JOIN course c ON c.id = e.course_id
+        LEFT JOIN course_completion cc
+            ON cc.learner_id = e.learner_id AND cc.course_id = e.course_id
+        WHERE e.nft_contract_address = $1 AND e.nft_token_id = $2
+        "#,
+    )
+    .bind(&contract)
+    .bind(params.token_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    if let Some(row) = learner_row {
+        return learner_metadata(&pool, row).await.map(Json);
+    }
+
+    if params.token_id != CREATOR_TOKEN_ID {
+        return Err((StatusCode::NOT_FOUND, "Token not found".to_string()));
+    }
+
+    let creator_row: Option<CreatorNftRow> = sqlx::query_as::<_, CreatorNftRow>(
+        r#"
+        SELECT
+            c.id AS course_id,
+            c.title AS course_title,
+            c.creator_id,
+            (
+                SELECT COUNT(*)::BIGINT
+                FROM learner_course_enrollment e
+                WHERE e.course_id = c.id
+            ) AS num_learners,
+            (
+                SELECT COUNT(*)::BIGINT
+                FROM course_completion cc
+                WHERE cc.course_id = c.id
+            ) AS num_completed
+        FROM course c
+        WHERE c.creator_nft_address = $1
+        "#,
+    )
+    .bind(&contract)
+    .fetch_optional(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    match creator_row {
+        Some(row) => Ok(Json(creator_metadata(row))),
+        None => Err((StatusCode::NOT_FOUND, "Token not found".to_string())),
+    }
+}
+
+async fn learner_metadata(
+    pool: &Pool<Postgres>,
+    row: LearnerNftRow,
+) -> Result<NftMetadata, (StatusCode, String)> {
+    let progress_percent = fetch_progress_percent(pool, &row.learner_id, row.course_id).await?;
+
+    let (completed_modules, total_modules): (i64, i64) = sqlx::query_as(
+        r#"
+        SELECT
+            COUNT(mc.module_id)::BIGINT,
+            COUNT(m.id)::BIGINT
+        FROM module m
+        LEFT JOIN module_completion mc
+            ON mc.module_id = m.id AND mc.learner_id = $1
+        WHERE m.course_id = $2
+        "#,
+    )
+    .bind(&row.learner_id)
+    .bind(row.course_id)
+    .fetch_one(pool)
+    .await
+    .map_err(internal_error)?;
+
+    // Same rule as LearnerNFT.updateMilestone
+    let milestone = (progress_percent / 25) as usize;
+
+    let description = match milestone {
+        0 => format!("Represents the beginning of the course \"{}\".", row.course_title),
+        4 => format!(
+            "Represents completion of 100% of the modules in the course \"{}\".",
+            row.course_title
+        ),
+        m => format!(
+            "Represents completing {}% of the modules in the course \"{}\".",
+            m * 25,
+            row.course_title
+        ),
+    };
+
+    let mut attributes = vec![
+        attribute("Course", json!(row.course_title)),
+        attribute("Course ID", json!(row.course_id)),
+        attribute("Milestone", json!(milestone)),
+        NftAttribute {
+            trait_type: "Progress".to_string(),
+            value: json!(progress_percent),
+            display_type: Some("boost_percentage".to_string()),
+        },
+        attribute("Modules Completed", json!(completed_modules)),
+        attribute("Total Modules", json!(total_modules)),
+    ];
+
+    if let Some(completed_at) = row.completed_at {
+        attributes.push(NftAttribute {
+            trait_type: "Completion Date".to_string(),
+            value: json!(completed_at.timestamp()),
+            display_type: Some("date".to_string()),
+        });
+    }
+
+    Ok(NftMetadata {
+        name: format!(
+            "{} - Learner Tree - {}",
+            row.course_title, MILESTONE_NAMES[milestone]
+        ),
+        description,
+        image: MILESTONE_IMAGES[milestone].to_string(),
+        attributes,
+    })
+}
+
+fn creator_metadata(row: CreatorNftRow) -> NftMetadata {
+    // Highest threshold reached, matching contracts/metadata/teacher
+    let milestone = CREATOR_MILESTONE_THRESHOLDS
+        .iter()
+        .rposition(|threshold| row.num_completed >= *threshold)
+        .unwrap_or(0);
+
+    let description = if milestone == 0 {
+        format!("Represents the creation of the course \"{}\".", row.course_title)
+    } else {
+        format!(
+            "Represents {}+ learner completions of the course \"{}\".",
+            CREATOR_MILESTONE_THRESHOLDS[milestone], row.course_title
+        )
+    };
+
+    NftMetadata {
+        name: format!(
+            "{} - Teacher Tree - {}",
+            row.course_title, MILESTONE_NAMES[milestone]
+        ),
+        description,
+        image: MILESTONE_IMAGES[milestone].to_string(),
+        attributes: vec![
+            attribute("Course", json!(row.course_title)),
+            attribute("Course ID", json!(row.course_id)),
+            attribute("Creator", json!(row.creator_id)),
+            attribute("Milestone", json!(milestone)),
+            attribute("Learners", json!(row.num_learners)),
+            attribute("Completions", json!(row.num_completed)),
+        ],
+    }
+}
+
+fn attribute(trait_type: &str, value: Value) -> NftAttribute {
+    NftAttribute {
+        trait_type: trait_type.to_string(),
+        value,
+        display_type: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::test_support::{personal_sign, wallet_address};
+
+    const CREATOR_SECRET: [u8; 32] = [1; 32];
+    const LEARNER_SECRET: [u8; 32] = [3; 32];
+    const CREATOR_NFT: &str = "0x00000000000000000000000000000000000000c1";
+    const LEARNER_NFT: &str = "0x00000000000000000000000000000000000000e1";
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures("../../database_schema/aranya_db_schema.sql")
+    )]
+    async fn registrations_are_signed_by_creator_and_learner(pool: PgPool) {
+        let learner_id = wallet_address(&LEARNER_SECRET);
+        let course_id: i64 = sqlx::query_scalar(
+            r#"
+            WITH creator AS (
+                INSERT INTO creator (id) VALUES ($1) RETURNING id
+            ), course AS (
+                INSERT INTO course (title, description, creator_id)
+                SELECT 'Rust', 'Basics', id FROM creator RETURNING id
+            ), learner AS (
+                INSERT INTO learner (id) VALUES ($2) RETURNING id
+            )
+            INSERT INTO learner_course_enrollment (learner_id, course_id)
+            SELECT learner.id, course.id FROM learner, course
+            RETURNING course_id
+            "#,
+        )
+        .bind(wallet_address(&CREATOR_SECRET))
+        .bind(&learner_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let now = Utc::now().timestamp();
+
+        let register_course = |secret: [u8; 32]| {
+            let message = course_nfts_message(course_id, CREATOR_NFT, LEARNER_NFT, now);
+            register_course_nfts(
+                State(pool.clone()),
+                Json(RegisterCourseNftsRequest {
+                    course_id,
+                    creator_nft_address: CREATOR_NFT.to_string(),
+                    learner_nft_address: LEARNER_NFT.to_string(),
+                    requested_at: now,
+                    signature: personal_sign(&secret, &message),
+                }),
+            )
+        };
+        let (status, _) = register_course(LEARNER_SECRET).await.err().unwrap();
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert!(register_course(CREATOR_SECRET).await.is_ok());
+
+        let register_learner = |signed_token_id: i64| {
+            let message = learner_nft_message(&learner_id, course_id, signed_token_id, now);
+            register_learner_nft(
+                State(pool.clone()),
+                Json(RegisterLearnerNftRequest {
+                    learner_id: learner_id.clone(),
+                    course_id,
+                    nft_token_id: 7,
+                    requested_at: now,
+                    signature: personal_sign(&LEARNER_SECRET, &message),
+                }),
+            )
+        };
+        let (status, _) = register_learner(8).await.err().unwrap();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(register_learner(7).await.is_ok());
+
+        let contract: String = sqlx::query_scalar(
+            "SELECT nft_contract_address FROM learner_course_enrollment WHERE nft_token_id = 7",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(contract, LEARNER_NFT);
+    }
+}
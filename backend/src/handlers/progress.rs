@@ -4,22 +4,44 @@ use axum::{
     http::StatusCode,
     response::IntoResponse,
 };
+use chrono::{DateTime, Duration, Utc};
 use serde_json::json;
-use sqlx::{Pool, Postgres, Row, postgres::PgRow};
+use sqlx::{Pool, Postgres, Row, postgres::PgRow, types::Json as SqlJson};
+use std::collections::HashMap;
 
+use crate::fdc::AttestationKind;
+use crate::grading::grade_question;
+use crate::handlers::attestation::consume_nonce;
+use crate::handlers::badge::issue_completion_badges;
+use crate::handlers::course::fetch_quiz_questions;
+use crate::handlers::credential::{CredentialState, issue_completion_credential};
+use crate::models::course::{DrawRuleRow, Question, ScoringPolicy};
 use crate::models::progress::{
-    CompleteQuizPayload, CompletedLessonsQuery, CompletedLessonsResponse, CourseCompleteRequest,
+    AttestedEnrollmentQuery, AttestedEnrollmentResponse, AttestedProgressQuery, CompleteQuizPayload, CompletedLessonsQuery, CompletedLessonsResponse, CourseCompleteRequest,
     CourseProgressPercentage, CourseProgressQuery, CourseProgressResponse, CourseProgressSummary,
     EnrollmentQuery, EnrollmentResponse, LearnerQuery, LessonCompleteRequest,
-    ModuleCompleteRequest,
+    LessonHeartbeatRequest, LessonHeartbeatResponse, LessonVideoRow,
+    ModuleCompleteRequest, QuestionResponse, QuestionResult, QuizAttempt, QuizAttemptsQuery,
+    QuizAttemptsResponse, QuizGradeResponse, QuizInstance, QuizInstanceRow, QuizSettingsRow,
+    StartQuizAttemptPayload, VideoProgress, VideoProgressQuery, VideoProgressRequest,
+    VideoProgressRow,
 };
+use crate::question_pool::{draw_questions, serve_question};
+use crate::video_progress::{
+    duration_matches, max_new_watched_seconds, merge_watched, resume_position, watched_percent,
+    watched_seconds,
+};
+use crate::xapi::{Activity, Verb, enqueue_statement, statement::quiz_result};
 
-pub async fn is_enrolled(
+// Heartbeats further apart than this mean the learner was idle or left
+const HEARTBEAT_IDLE_SECONDS: f64 = 60.0;
+
+pub async fn get_enrollment(
     State(pool): State<Pool<Postgres>>,
     Query(params): Query<EnrollmentQuery>,
 ) -> Result<Json<EnrollmentResponse>, (StatusCode, String)> {
     println! {"is enrolled {:?}", params};
-    let exists = sqlx::query_scalar::<_, bool>( r#" SELECT EXISTS( SELECT 1 FROM learner_course_enrollment WHERE course_id = $1 AND learner_id = $2 ) "#, ) .bind(&params.course_id) .bind(&params.learner_id) .fetch_one(&pool) .await .map_err(|err| { ( StatusCode::INTERNAL_SERVER_ERROR, format!("Database query failed: {}", err), ) })?;
+    let exists = sqlx::query_scalar::<_, bool>( r#" SELECT EXISTS( SELECT 1 FROM learner_course_enrollment WHERE course_id = $1 AND learner_id = $2 ) "#, ) .bind(params.course_id) .bind(&params.learner_id) .fetch_one(&pool) .await .map_err(|err| { ( StatusCode::INTERNAL_SERVER_ERROR, format!("Database query failed: {}", err), ) })?;
 
     println!("is enrolled? {}", exists);
     println!("course id? {}", &params.course_id);
@@ -31,11 +53,63 @@ pub async fn is_enrolled(
     }))
 }
 
+pub async fn is_enrolled(
+    State(pool): State<Pool<Postgres>>,
+    Query(params): Query<AttestedEnrollmentQuery>,
+) -> Result<Json<AttestedEnrollmentResponse>, (StatusCode, String)> {
+    let nonce = consume_nonce(
+        &pool,
+        AttestationKind::Enrollment,
+        &params.nonce,
+        params.course_id,
+        Some(&params.learner_id),
+    )
+    .await?;
+
+    let Json(enrollment) = get_enrollment(
+        State(pool),
+        Query(EnrollmentQuery {
+            course_id: params.course_id,
+            learner_id: params.learner_id,
+        }),
+    )
+    .await?;
+
+    Ok(Json(AttestedEnrollmentResponse {
+        course_id: enrollment.course_id,
+        learner_id: enrollment.learner_id,
+        is_enrolled: enrollment.is_enrolled,
+        nonce: nonce.nonce,
+        expires_at: nonce.expires_at.timestamp(),
+    }))
+}
+
 pub async fn complete_lesson(
     State(pool): State<Pool<Postgres>>,
     Json(payload): Json<LessonCompleteRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
-    sqlx::query(
+    let mut tx = pool.begin().await.map_err(internal_error)?;
+
+    let video = fetch_lesson_video(&mut *tx, &payload.learner_id, payload.lesson_id).await?;
+    if let (Some(_), Some(required)) = (&video.video_url, video.min_watched_percent) {
+        let watched = watched_percent(
+            video.watched_seconds.unwrap_or_default(),
+            video.duration_seconds.unwrap_or_default(),
+        )
+        .unwrap_or_default();
+        if watched < required {
+            return Err((
+                StatusCode::CONFLICT,
+                format!(
+                    "Watched {:.0}% of the video, {:.0}% required",
+                    watched.floor(),
+                    required
+                ),
+            ));
+        }
+    }
+
+    let completed = sqlx::query(
         r#"
         INSERT INTO lesson_completion (learner_id, lesson_id)
         VALUES ($1, $2)
@@ -43,8 +117,8 @@ pub async fn complete_lesson(
         "#,
     )
     .bind(&payload.learner_id)
-    .bind(&payload.lesson_id)
-    .execute(&pool)
+    .bind(payload.lesson_id)
+    .execute(&mut *tx)
     .await
     .map_err(|e| {
         (
@@ -53,12 +127,260 @@ pub async fn complete_lesson(
         )
     })?;
 
+    if completed.rows_affected() > 0 {
+        enqueue_statement(
+            &mut *tx,
+            &payload.learner_id,
+            Verb::Experienced,
+            Activity::Lesson(payload.lesson_id),
+            None,
+        )
+        .await
+        .map_err(internal_error)?;
+    }
+    tx.commit().await.map_err(internal_error)?;
+
     Ok((
         StatusCode::CREATED,
         Json(json!({ "message": "Lesson marked complete" })),
     ))
 }
 
+/// Credits the time since the learner's previous heartbeat to the lesson.
+/// Heartbeats of all tabs share one clock per learner, so time spent with
+/// several tabs open is counted once, and gaps longer than
+/// `HEARTBEAT_IDLE_SECONDS` are not counted.
+pub async fn record_lesson_heartbeat(
+    State(pool): State<Pool<Postgres>>,
+    Json(payload): Json<LessonHeartbeatRequest>,
+) -> Result<Json<LessonHeartbeatResponse>, (StatusCode, String)> {
+    let mut tx = pool.begin().await.map_err(internal_error)?;
+    check_lesson_enrollment(&mut *tx, &payload.learner_id, payload.lesson_id).await?;
+
+    // Locked so heartbeats of concurrent tabs are credited one after the other
+    let previous: Option<DateTime<Utc>> = sqlx::query_scalar(
+        "SELECT last_heartbeat_at FROM learner_heartbeat WHERE learner_id = $1 FOR UPDATE",
+    )
+    .bind(&payload.learner_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+    let now = Utc::now();
+    let credited_seconds = previous
+        .map(|previous| (now - previous).num_milliseconds() as f64 / 1000.0)
+        .filter(|seconds| *seconds > 0.0 && *seconds <= HEARTBEAT_IDLE_SECONDS)
+        .unwrap_or(0.0);
+
+    sqlx::query(
+        r#"
+        INSERT INTO learner_heartbeat (learner_id, last_heartbeat_at) VALUES ($1, $2)
+        ON CONFLICT (learner_id) DO UPDATE
+        SET last_heartbeat_at = GREATEST(learner_heartbeat.last_heartbeat_at, EXCLUDED.last_heartbeat_at)
+        "#,
+    )
+    .bind(&payload.learner_id)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+    let active_seconds: f64 = sqlx::query_scalar(
+        r#"
+        INSERT INTO lesson_time (learner_id, lesson_id, active_seconds) VALUES ($1, $2, $3)
+        ON CONFLICT (learner_id, lesson_id) DO UPDATE
+        SET active_seconds = lesson_time.active_seconds + EXCLUDED.active_seconds, updated_at = now()
+        RETURNING active_seconds
+        "#,
+    )
+    .bind(&payload.learner_id)
+    .bind(payload.lesson_id)
+    .bind(credited_seconds)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+    tx.commit().await.map_err(internal_error)?;
+
+    Ok(Json(LessonHeartbeatResponse {
+        lesson_id: payload.lesson_id,
+        credited_seconds,
+        time_spent_seconds: active_seconds.round() as i64,
+    }))
+}
+
+/// Records the playback position and merges the intervals played since the
+/// last report into the watched intervals of the lesson video.
+pub async fn record_video_progress(
+    State(pool): State<Pool<Postgres>>,
+    Json(payload): Json<VideoProgressRequest>,
+) -> Result<Json<VideoProgress>, (StatusCode, String)> {
+    if !(payload.duration_seconds.is_finite() && payload.duration_seconds > 0.0) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Video duration must be positive".to_string(),
+        ));
+    }
+    let mut tx = pool.begin().await.map_err(internal_error)?;
+    check_lesson_enrollment(&mut *tx, &payload.learner_id, payload.lesson_id).await?;
+
+    let lesson_duration: Option<f64> =
+        sqlx::query_scalar("SELECT video_duration_seconds FROM lesson WHERE id = $1")
+            .bind(payload.lesson_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(internal_error)?;
+    if lesson_duration.is_some_and(|duration| !duration_matches(payload.duration_seconds, duration))
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            "Reported video duration doesn't match the lesson video".to_string(),
+        ));
+    }
+
+    // Locked so reports from concurrent tabs don't drop each other's intervals
+    let previous: Option<VideoProgressRow> = sqlx::query_as::<_, VideoProgressRow>(
+        r#"
+        SELECT watched_intervals, duration_seconds,
+            EXTRACT(EPOCH FROM now() - updated_at)::DOUBLE PRECISION AS seconds_since_update
+        FROM lesson_video_progress
+        WHERE learner_id = $1 AND lesson_id = $2
+        FOR UPDATE
+        "#,
+    )
+    .bind(&payload.learner_id)
+    .bind(payload.lesson_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+
+    // The lesson's duration is authoritative, without one the first reported
+    // duration is kept so later reports can't shrink the video
+    let (watched, reported_duration, seconds_since_last_report) = match previous {
+        Some(previous) => (
+            previous.watched_intervals.0,
+            previous.duration_seconds,
+            Some(previous.seconds_since_update),
+        ),
+        None => (Vec::new(), payload.duration_seconds, None),
+    };
+    let duration = lesson_duration.unwrap_or(reported_duration);
+    let intervals = merge_watched(
+        &watched,
+        &payload.watched,
+        duration,
+        max_new_watched_seconds(seconds_since_last_report),
+    );
+    let position = payload.position_seconds.clamp(0.0, duration);
+
+    sqlx::query(
+        r#"
+        INSERT INTO lesson_video_progress
+            (learner_id, lesson_id, position_seconds, duration_seconds, watched_intervals, watched_seconds)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (learner_id, lesson_id) DO UPDATE
+        SET position_seconds = EXCLUDED.position_seconds,
+            duration_seconds = EXCLUDED.duration_seconds,
+            watched_intervals = EXCLUDED.watched_intervals,
+            watched_seconds = EXCLUDED.watched_seconds,
+            updated_at = now()
+        "#,
+    )
+    .bind(&payload.learner_id)
+    .bind(payload.lesson_id)
+    .bind(position)
+    .bind(duration)
+    .bind(SqlJson(&intervals))
+    .bind(watched_seconds(&intervals))
+    .execute(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+    tx.commit().await.map_err(internal_error)?;
+
+    fetch_video_progress(&pool, &payload.learner_id, payload.lesson_id).await
+}
+
+pub async fn get_video_progress(
+    State(pool): State<Pool<Postgres>>,
+    Query(params): Query<VideoProgressQuery>,
+) -> Result<Json<VideoProgress>, (StatusCode, String)> {
+    fetch_video_progress(&pool, &params.learner_id, params.lesson_id).await
+}
+
+async fn fetch_video_progress(
+    pool: &Pool<Postgres>,
+    learner_id: &str,
+    lesson_id: i64,
+) -> Result<Json<VideoProgress>, (StatusCode, String)> {
+    let video = fetch_lesson_video(pool, learner_id, lesson_id).await?;
+    let position = video.position_seconds.unwrap_or_default();
+    let duration = video.duration_seconds.unwrap_or_default();
+    let watched = video.watched_seconds.unwrap_or_default();
+
+    Ok(Json(VideoProgress {
+        lesson_id,
+        resume_position_seconds: resume_position(position, duration),
+        duration_seconds: duration,
+        watched_seconds: watched,
+        watched_percent: watched_percent(watched, duration),
+        min_watched_percent: video.min_watched_percent,
+    }))
+}
+
+async fn fetch_lesson_video<'e, E>(
+    executor: E,
+    learner_id: &str,
+    lesson_id: i64,
+) -> Result<LessonVideoRow, (StatusCode, String)>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_as::<_, LessonVideoRow>(
+        r#"
+        SELECT l.video_url, l.min_watched_percent, p.position_seconds,
+            COALESCE(l.video_duration_seconds, p.duration_seconds) AS duration_seconds,
+            p.watched_seconds
+        FROM lesson l
+        LEFT JOIN lesson_video_progress p ON p.lesson_id = l.id AND p.learner_id = $1
+        WHERE l.id = $2
+        "#,
+    )
+    .bind(learner_id)
+    .bind(lesson_id)
+    .fetch_optional(executor)
+    .await
+    .map_err(internal_error)?
+    .ok_or((StatusCode::NOT_FOUND, "Lesson not found".to_string()))
+}
+
+/// Fails with 404 unless the learner is enrolled in the course of the lesson.
+async fn check_lesson_enrollment<'e, E>(
+    executor: E,
+    learner_id: &str,
+    lesson_id: i64,
+) -> Result<(), (StatusCode, String)>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let enrolled: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS(
+            SELECT 1
+            FROM lesson l
+            JOIN module m ON m.id = l.module_id
+            JOIN learner_course_enrollment e ON e.course_id = m.course_id
+            WHERE l.id = $1 AND e.learner_id = $2
+        )
+        "#,
+    )
+    .bind(lesson_id)
+    .bind(learner_id)
+    .fetch_one(executor)
+    .await
+    .map_err(internal_error)?;
+    if !enrolled {
+        return Err((StatusCode::NOT_FOUND, "Enrollment not found".to_string()));
+    }
+    Ok(())
+}
+
 pub async fn complete_module(
     State(pool): State<Pool<Postgres>>,
     Json(payload): Json<ModuleCompleteRequest>,
@@ -72,7 +394,7 @@ pub async fn complete_module(
 
     let total_lessons: i64 =
         sqlx::query_scalar(r#"SELECT COUNT(*) FROM lesson WHERE module_id = $1"#)
-            .bind(&payload.module_id)
+            .bind(payload.module_id)
             .fetch_one(&mut *tx)
             .await
             .map_err(|e| {
@@ -91,7 +413,7 @@ pub async fn complete_module(
         "#,
     )
     .bind(&payload.learner_id)
-    .bind(&payload.module_id)
+    .bind(payload.module_id)
     .fetch_one(&mut *tx)
     .await
     .map_err(|e| {
@@ -108,6 +430,37 @@ pub async fn complete_module(
         ));
     }
 
+    let quiz_passed: bool = sqlx::query_scalar(
+        r#"
+        SELECT NOT EXISTS (
+            SELECT 1
+            FROM quiz q
+            WHERE q.module_id = $2
+            AND NOT EXISTS (
+                SELECT 1 FROM quiz_result qr
+                WHERE qr.quiz_id = q.id AND qr.learner_id = $1 AND qr.passed
+            )
+        )
+        "#,
+    )
+    .bind(&payload.learner_id)
+    .bind(payload.module_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to check quiz result: {}", e),
+        )
+    })?;
+
+    if !quiz_passed {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Module quiz has not been passed".to_string(),
+        ));
+    }
+
     sqlx::query(
         r#"
         INSERT INTO module_completion (learner_id, module_id)
@@ -116,7 +469,7 @@ pub async fn complete_module(
         "#,
     )
     .bind(&payload.learner_id)
-    .bind(&payload.module_id)
+    .bind(payload.module_id)
     .execute(&mut *tx)
     .await
     .map_err(|e| {
@@ -128,7 +481,7 @@ pub async fn complete_module(
 
     let total_modules: i64 =
         sqlx::query_scalar(r#"SELECT COUNT(*) FROM module WHERE course_id = $1"#)
-            .bind(&payload.course_id)
+            .bind(payload.course_id)
             .fetch_one(&mut *tx)
             .await
             .map_err(|e| {
@@ -147,7 +500,7 @@ pub async fn complete_module(
         "#,
     )
     .bind(&payload.learner_id)
-    .bind(&payload.course_id)
+    .bind(payload.course_id)
     .fetch_one(&mut *tx)
     .await
     .map_err(|e| {
@@ -166,22 +519,26 @@ pub async fn complete_module(
         )
     })?;
 
+    let final_exam_passed =
+        fetch_final_exam_passed(&pool, &payload.learner_id, payload.course_id).await?;
+
     Ok((
         StatusCode::CREATED,
         Json(json!({
             "module_completed": true,
-            "course_completed": all_modules_completed
+            "course_completed": all_modules_completed && final_exam_passed != Some(false)
         })),
     ))
 }
 
 pub async fn complete_course(
-    State(pool): State<Pool<Postgres>>,
+    State(state): State<CredentialState>,
     Json(payload): Json<CourseCompleteRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let pool = &state.pool;
     let total_modules: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM module WHERE course_id = $1")
-        .bind(&payload.course_id)
-        .fetch_one(&pool)
+        .bind(payload.course_id)
+        .fetch_one(pool)
         .await
         .map_err(|e| {
             (
@@ -198,8 +555,8 @@ pub async fn complete_course(
         "#,
     )
     .bind(&payload.learner_id)
-    .bind(&payload.course_id)
-    .fetch_one(&pool)
+    .bind(payload.course_id)
+    .fetch_one(pool)
     .await
     .map_err(|e| {
         (
@@ -215,12 +572,20 @@ pub async fn complete_course(
         ));
     }
 
-    sqlx::query(
+    if fetch_final_exam_passed(pool, &payload.learner_id, payload.course_id).await? == Some(false) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Final exam has not been passed".to_string(),
+        ));
+    }
+
+    let mut tx = pool.begin().await.map_err(internal_error)?;
+    let completed = sqlx::query(
         "INSERT INTO course_completion (learner_id, course_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
     )
     .bind(&payload.learner_id)
-    .bind(&payload.course_id)
-    .execute(&pool)
+    .bind(payload.course_id)
+    .execute(&mut *tx)
     .await
     .map_err(|e| {
         (
@@ -229,42 +594,428 @@ pub async fn complete_course(
         )
     })?;
 
+    if completed.rows_affected() > 0 {
+        enqueue_statement(
+            &mut *tx,
+            &payload.learner_id,
+            Verb::Completed,
+            Activity::Course(payload.course_id),
+            None,
+        )
+        .await
+        .map_err(internal_error)?;
+    }
+    tx.commit().await.map_err(internal_error)?;
+
+    let credential_id =
+        issue_completion_credential(&state, &payload.learner_id, payload.course_id).await?;
+    issue_completion_badges(&state, &payload.learner_id, payload.course_id).await?;
+
     Ok((
         StatusCode::CREATED,
         Json(json!({
-            "course_completed": true
+            "course_completed": true,
+            "credential_id": credential_id
         })),
     ))
 }
 
+/// Serves the learner's open quiz instance, or draws a new one from the
+/// question bank if the attempt limit and cooldown allow another attempt.
+pub async fn start_quiz_attempt(
+    State(pool): State<Pool<Postgres>>,
+    Json(payload): Json<StartQuizAttemptPayload>,
+) -> Result<Json<QuizInstance>, (StatusCode, String)> {
+    let settings = fetch_quiz_settings(&pool, payload.quiz_id).await?;
+    check_quiz_enrollment(&pool, &payload.learner_id, payload.quiz_id).await?;
+    let questions = fetch_quiz_questions(&pool, &[payload.quiz_id])
+        .await?
+        .remove(&payload.quiz_id)
+        .unwrap_or_default();
+
+    let instance = match fetch_open_quiz_instance(&pool, payload.quiz_id, &payload.learner_id).await? {
+        Some(instance) => instance,
+        None => {
+            let attempts = fetch_quiz_attempts(&pool, payload.quiz_id, &payload.learner_id).await?;
+            check_attempt_allowed(&settings, &attempts)?;
+
+            let rules: Vec<DrawRuleRow> = sqlx::query_as::<_, DrawRuleRow>(
+                "SELECT pool, draw_count FROM quiz_draw_rule WHERE quiz_id = $1 ORDER BY id",
+            )
+            .bind(payload.quiz_id)
+            .fetch_all(&pool)
+            .await
+            .map_err(internal_error)?;
+
+            let seed: i64 = rand::random();
+            let question_ids = draw_questions(&questions, &rules, seed);
+
+            // A concurrent request may open an instance first, that one is served instead
+            sqlx::query(
+                r#"
+                INSERT INTO quiz_instance (quiz_id, learner_id, seed, question_ids)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (quiz_id, learner_id) WHERE submitted_at IS NULL DO NOTHING
+                "#,
+            )
+            .bind(payload.quiz_id)
+            .bind(&payload.learner_id)
+            .bind(seed)
+            .bind(&question_ids)
+            .execute(&pool)
+            .await
+            .map_err(internal_error)?;
+
+            fetch_open_quiz_instance(&pool, payload.quiz_id, &payload.learner_id)
+                .await?
+                .ok_or_else(|| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Failed to open quiz instance".to_string(),
+                    )
+                })?
+        }
+    };
+
+    let questions_by_id: HashMap<i64, &Question> = questions.iter().map(|q| (q.id, q)).collect();
+    let served = instance
+        .question_ids
+        .iter()
+        .filter_map(|id| questions_by_id.get(id))
+        .map(|question| serve_question(question, instance.seed, settings.shuffle_answers))
+        .collect();
+
+    Ok(Json(QuizInstance {
+        instance_id: instance.id,
+        quiz_id: instance.quiz_id,
+        learner_id: instance.learner_id,
+        created_at: instance.created_at,
+        questions: served,
+    }))
+}
+
+/// Grades the submitted responses against the questions of the served
+/// instance and records them as a new attempt.
 pub async fn complete_quiz(
     State(pool): State<Pool<Postgres>>,
     Json(payload): Json<CompleteQuizPayload>,
-) -> Result<StatusCode, (StatusCode, String)> {
-    sqlx::query(
+) -> Result<Json<QuizGradeResponse>, (StatusCode, String)> {
+    let settings = fetch_quiz_settings(&pool, payload.quiz_id).await?;
+    check_quiz_enrollment(&pool, &payload.learner_id, payload.quiz_id).await?;
+
+    let instance = sqlx::query_as::<_, QuizInstanceRow>(
         r#"
-        INSERT INTO quiz_completion (quiz_id, learner_id, score, total_questions)
-        VALUES ($1, $2, $3, $4)
-        ON CONFLICT (quiz_id, learner_id) DO UPDATE
-        SET score = EXCLUDED.score,
-            total_questions = EXCLUDED.total_questions,
-            completed_at = now()
+        SELECT id, quiz_id, learner_id, seed, question_ids, created_at, submitted_at
+        FROM quiz_instance
+        WHERE id = $1 AND quiz_id = $2 AND learner_id = $3
         "#,
     )
+    .bind(payload.instance_id)
     .bind(payload.quiz_id)
     .bind(&payload.learner_id)
-    .bind(payload.score)
-    .bind(payload.total_questions)
-    .execute(&pool)
+    .fetch_optional(&pool)
     .await
-    .map_err(|e| {
+    .map_err(internal_error)?
+    .ok_or_else(|| (StatusCode::NOT_FOUND, "Quiz instance not found".to_string()))?;
+
+    if instance.submitted_at.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            "Quiz instance already submitted".to_string(),
+        ));
+    }
+
+    let mut attempts = fetch_quiz_attempts(&pool, payload.quiz_id, &payload.learner_id).await?;
+    check_attempt_allowed(&settings, &attempts)?;
+
+    let mut questions_by_id: HashMap<i64, Question> = fetch_quiz_questions(&pool, &[payload.quiz_id])
+        .await?
+        .remove(&payload.quiz_id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|q| (q.id, q))
+        .collect();
+    let questions: Vec<Question> = instance
+        .question_ids
+        .iter()
+        .filter_map(|id| questions_by_id.remove(id))
+        .collect();
+
+    let responses: HashMap<i64, &QuestionResponse> = payload
+        .responses
+        .iter()
+        .map(|response| (response.question_id, response))
+        .collect();
+    let results: Vec<QuestionResult> = questions
+        .iter()
+        .map(|question| grade_question(question, responses.get(&question.id).copied()))
+        .collect();
+
+    let score: f64 = results.iter().map(|r| r.earned).sum();
+    let max_score: f64 = results.iter().map(|r| r.points).sum();
+    let score_percent = if max_score > 0.0 { score * 100.0 / max_score } else { 0.0 };
+    let total_questions = questions.len() as i32;
+    let passed = score_percent >= settings.passing_score_percent;
+    let attempt_number = attempts.len() as i32 + 1;
+
+    let answers = json!({
+        "responses": payload.responses,
+        "results": results,
+    });
+
+    let mut tx = pool.begin().await.map_err(internal_error)?;
+
+    sqlx::query_scalar::<_, i64>(
+        "UPDATE quiz_instance SET submitted_at = now() WHERE id = $1 AND submitted_at IS NULL RETURNING id",
+    )
+    .bind(instance.id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(internal_error)?
+    .ok_or_else(|| {
+        (
+            StatusCode::CONFLICT,
+            "Quiz instance already submitted".to_string(),
+        )
+    })?;
+
+    // A concurrent submission takes the same attempt number and loses on the unique key
+    let attempt: QuizAttempt = sqlx::query_as::<_, QuizAttempt>(
+        r#"
+        INSERT INTO quiz_attempt
+            (quiz_id, learner_id, instance_id, attempt_number, score, max_score, score_percent, total_questions, passed, answers)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        ON CONFLICT (quiz_id, learner_id, attempt_number) DO NOTHING
+        RETURNING id, attempt_number, score, max_score, score_percent, total_questions, passed, submitted_at
+        "#,
+    )
+    .bind(payload.quiz_id)
+    .bind(&payload.learner_id)
+    .bind(instance.id)
+    .bind(attempt_number)
+    .bind(score)
+    .bind(max_score)
+    .bind(score_percent)
+    .bind(total_questions)
+    .bind(passed)
+    .bind(answers)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(internal_error)?
+    .ok_or_else(|| {
+        (
+            StatusCode::CONFLICT,
+            "Another attempt was submitted at the same time".to_string(),
+        )
+    })?;
+
+    let result = quiz_result(score, max_score, score_percent, passed);
+    let outcome = if passed { Verb::Passed } else { Verb::Failed };
+    for verb in [Verb::Scored, outcome] {
+        enqueue_statement(
+            &mut *tx,
+            &payload.learner_id,
+            verb,
+            Activity::Quiz(payload.quiz_id),
+            Some(result.clone()),
+        )
+        .await
+        .map_err(internal_error)?;
+    }
+
+    tx.commit().await.map_err(internal_error)?;
+
+    let attempt_id = attempt.id;
+    attempts.push(attempt);
+    let (attempts_remaining, next_attempt_at) = attempt_limits(&settings, &attempts);
+    let quiz_passed = fetch_quiz_result(&pool, payload.quiz_id, &payload.learner_id)
+        .await?
+        .is_some_and(|(_, passed)| passed);
+
+    Ok(Json(QuizGradeResponse {
+        quiz_id: payload.quiz_id,
+        attempt_id,
+        attempt_number,
+        score,
+        max_score,
+        score_percent,
+        total_questions,
+        passed,
+        passing_score_percent: settings.passing_score_percent,
+        quiz_passed,
+        attempts_remaining,
+        next_attempt_at,
+        results,
+    }))
+}
+
+/// Lists a learner's attempts at a quiz, with the attempt that counts and
+/// when the next attempt is allowed.
+pub async fn get_quiz_attempts(
+    State(pool): State<Pool<Postgres>>,
+    Query(params): Query<QuizAttemptsQuery>,
+) -> Result<Json<QuizAttemptsResponse>, (StatusCode, String)> {
+    let settings = fetch_quiz_settings(&pool, params.quiz_id).await?;
+    let attempts = fetch_quiz_attempts(&pool, params.quiz_id, &params.learner_id).await?;
+    let result = fetch_quiz_result(&pool, params.quiz_id, &params.learner_id).await?;
+    let (attempts_remaining, next_attempt_at) = attempt_limits(&settings, &attempts);
+
+    let scoring_policy = ScoringPolicy::parse(&settings.scoring_policy).ok_or_else(|| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
-            format!("DB error: {}", e),
+            format!("Unknown scoring policy {}", settings.scoring_policy),
         )
     })?;
 
-    Ok(StatusCode::OK)
+    Ok(Json(QuizAttemptsResponse {
+        quiz_id: params.quiz_id,
+        learner_id: params.learner_id,
+        passing_score_percent: settings.passing_score_percent,
+        max_attempts: settings.max_attempts,
+        cooldown_seconds: settings.cooldown_seconds,
+        scoring_policy,
+        attempts,
+        result_attempt_id: result.map(|(attempt_id, _)| attempt_id),
+        quiz_passed: result.is_some_and(|(_, passed)| passed),
+        attempts_remaining,
+        next_attempt_at,
+    }))
+}
+
+async fn fetch_quiz_settings(
+    pool: &Pool<Postgres>,
+    quiz_id: i64,
+) -> Result<QuizSettingsRow, (StatusCode, String)> {
+    sqlx::query_as::<_, QuizSettingsRow>(
+        r#"
+        SELECT passing_score_percent, max_attempts, cooldown_seconds, scoring_policy, shuffle_answers
+        FROM quiz
+        WHERE id = $1
+        "#,
+    )
+    .bind(quiz_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(internal_error)?
+    .ok_or_else(|| (StatusCode::NOT_FOUND, "Quiz not found".to_string()))
+}
+
+/// Fails with 404 unless the learner is enrolled in the course of the
+/// module quiz or final exam.
+async fn check_quiz_enrollment(
+    pool: &Pool<Postgres>,
+    learner_id: &str,
+    quiz_id: i64,
+) -> Result<(), (StatusCode, String)> {
+    let enrolled: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS(
+            SELECT 1
+            FROM quiz q
+            LEFT JOIN module m ON m.id = q.module_id
+            JOIN learner_course_enrollment e ON e.course_id = COALESCE(q.course_id, m.course_id)
+            WHERE q.id = $1 AND e.learner_id = $2
+        )
+        "#,
+    )
+    .bind(quiz_id)
+    .bind(learner_id)
+    .fetch_one(pool)
+    .await
+    .map_err(internal_error)?;
+    if !enrolled {
+        return Err((StatusCode::NOT_FOUND, "Enrollment not found".to_string()));
+    }
+    Ok(())
+}
+
+async fn fetch_quiz_attempts(
+    pool: &Pool<Postgres>,
+    quiz_id: i64,
+    learner_id: &str,
+) -> Result<Vec<QuizAttempt>, (StatusCode, String)> {
+    sqlx::query_as::<_, QuizAttempt>(
+        r#"
+        SELECT id, attempt_number, score, max_score, score_percent, total_questions, passed, submitted_at
+        FROM quiz_attempt
+        WHERE quiz_id = $1 AND learner_id = $2
+        ORDER BY attempt_number
+        "#,
+    )
+    .bind(quiz_id)
+    .bind(learner_id)
+    .fetch_all(pool)
+    .await
+    .map_err(internal_error)
+}
+
+async fn fetch_open_quiz_instance(
+    pool: &Pool<Postgres>,
+    quiz_id: i64,
+    learner_id: &str,
+) -> Result<Option<QuizInstanceRow>, (StatusCode, String)> {
+    sqlx::query_as::<_, QuizInstanceRow>(
+        r#"
+        SELECT id, quiz_id, learner_id, seed, question_ids, created_at, submitted_at
+        FROM quiz_instance
+        WHERE quiz_id = $1 AND learner_id = $2 AND submitted_at IS NULL
+        "#,
+    )
+    .bind(quiz_id)
+    .bind(learner_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(internal_error)
+}
+
+// The attempt id and passed flag of the attempt that counts
+async fn fetch_quiz_result(
+    pool: &Pool<Postgres>,
+    quiz_id: i64,
+    learner_id: &str,
+) -> Result<Option<(i64, bool)>, (StatusCode, String)> {
+    sqlx::query_as::<_, (i64, bool)>(
+        "SELECT id, passed FROM quiz_result WHERE quiz_id = $1 AND learner_id = $2",
+    )
+    .bind(quiz_id)
+    .bind(learner_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(internal_error)
+}
+
+fn check_attempt_allowed(
+    settings: &QuizSettingsRow,
+    attempts: &[QuizAttempt],
+) -> Result<(), (StatusCode, String)> {
+    match attempt_limits(settings, attempts) {
+        (Some(0), _) => Err((
+            StatusCode::CONFLICT,
+            "No quiz attempts remaining".to_string(),
+        )),
+        (_, Some(next_attempt_at)) => Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            format!("Next quiz attempt allowed at {}", next_attempt_at.to_rfc3339()),
+        )),
+        _ => Ok(()),
+    }
+}
+
+// Attempts left, if limited, and the end of the cooldown, if still running
+fn attempt_limits(
+    settings: &QuizSettingsRow,
+    attempts: &[QuizAttempt],
+) -> (Option<i32>, Option<DateTime<Utc>>) {
+    let attempts_remaining = settings
+        .max_attempts
+        .map(|max_attempts| (max_attempts - attempts.len() as i32).max(0));
+
+    let next_attempt_at = attempts
+        .last()
+        .map(|attempt| attempt.submitted_at + Duration::seconds(settings.cooldown_seconds.into()))
+        .filter(|next_attempt_at| *next_attempt_at > Utc::now() && attempts_remaining != Some(0));
+
+    (attempts_remaining, next_attempt_at)
 }
 
 pub async fn get_completed_lesson_ids(
@@ -280,7 +1031,7 @@ pub async fn get_completed_lesson_ids(
         WHERE m.course_id = $1 AND lc.learner_id = $2
         "#,
     )
-    .bind(&params.course_id)
+    .bind(params.course_id)
     .bind(&params.learner_id)
     .fetch_all(&pool)
     .await
@@ -337,10 +1088,10 @@ pub async fn get_course_progress(
     let completed_quiz_ids: Vec<i64> = sqlx::query_scalar(
         r#"
         SELECT q.id
-        FROM quiz_completion qc
-        JOIN quiz q ON qc.quiz_id = q.id
-        JOIN module m ON q.module_id = m.id
-        WHERE qc.learner_id = $1 AND m.course_id = $2
+        FROM quiz_result qr
+        JOIN quiz q ON qr.quiz_id = q.id
+        LEFT JOIN module m ON q.module_id = m.id
+        WHERE qr.learner_id = $1 AND COALESCE(q.course_id, m.course_id) = $2 AND qr.passed
         "#,
     )
     .bind(learner_id)
@@ -383,15 +1134,26 @@ pub async fn get_course_progress(
         0.0
     };
 
-    let module_progress = if total_modules > 0 {
-        completed_module_ids.len() as f32 / total_modules as f32
+    let final_exam_passed = fetch_final_exam_passed(&pool, learner_id, course_id).await?;
+    let (total_units, completed_units) = module_units(
+        total_modules,
+        completed_module_ids.len() as i64,
+        final_exam_passed,
+    );
+
+    let module_progress = if total_units > 0 {
+        completed_units as f32 / total_units as f32
     } else {
         0.0
     };
 
     let progress_percent = ((lesson_progress + module_progress) / 2.0) * 100.0;
 
-    let course_completed = completed_module_ids.len() as i64 == total_modules;
+    let course_completed = completed_units == total_units;
+    let time_spent_seconds = fetch_time_spent(&pool, learner_id)
+        .await?
+        .remove(&course_id)
+        .unwrap_or(0);
 
     Ok(Json(CourseProgressResponse {
         completed_lesson_ids,
@@ -399,13 +1161,50 @@ pub async fn get_course_progress(
         completed_module_ids,
         progress_percent,
         course_completed,
+        time_spent_seconds,
     }))
 }
 
 pub async fn get_course_progress_percentage(
     State(pool): State<Pool<Postgres>>,
-    Query(params): Query<CourseProgressQuery>,
+    Query(params): Query<AttestedProgressQuery>,
 ) -> Result<Json<CourseProgressPercentage>, (StatusCode, String)> {
+    let nonce = consume_nonce(
+        &pool,
+        AttestationKind::LearnerMilestone,
+        &params.nonce,
+        params.course_id,
+        Some(&params.learner_id),
+    )
+    .await?;
+    // Always set for learner milestone nonces
+    let milestone = nonce.milestone.unwrap_or_default();
+
+    let progress_percent =
+        fetch_progress_percent(&pool, &params.learner_id, params.course_id).await?;
+
+    if i32::from(progress_percent / 25) < milestone {
+        return Err((
+            StatusCode::CONFLICT,
+            "Milestone not reached".to_string(),
+        ));
+    }
+
+    Ok(Json(CourseProgressPercentage {
+        course_id: params.course_id,
+        learner_id: params.learner_id,
+        progress_percent,
+        nonce: nonce.nonce,
+        milestone,
+        expires_at: nonce.expires_at.timestamp(),
+    }))
+}
+
+pub(crate) async fn fetch_progress_percent(
+    pool: &Pool<Postgres>,
+    learner_id: &str,
+    course_id: i64,
+) -> Result<u8, (StatusCode, String)> {
     let total_lessons: i64 = sqlx::query_scalar(
         r#"
         SELECT COUNT(*)
@@ -414,8 +1213,8 @@ pub async fn get_course_progress_percentage(
         WHERE m.course_id = $1
         "#,
     )
-    .bind(&params.course_id)
-    .fetch_one(&pool)
+    .bind(course_id)
+    .fetch_one(pool)
     .await
     .map_err(internal_error)?;
 
@@ -428,9 +1227,9 @@ pub async fn get_course_progress_percentage(
         WHERE lc.learner_id = $1 AND m.course_id = $2
         "#,
     )
-    .bind(&params.learner_id)
-    .bind(&params.course_id)
-    .fetch_one(&pool)
+    .bind(learner_id)
+    .bind(course_id)
+    .fetch_one(pool)
     .await
     .map_err(internal_error)?;
 
@@ -441,8 +1240,8 @@ pub async fn get_course_progress_percentage(
         WHERE course_id = $1
         "#,
     )
-    .bind(&params.course_id)
-    .fetch_one(&pool)
+    .bind(course_id)
+    .fetch_one(pool)
     .await
     .map_err(internal_error)?;
 
@@ -454,9 +1253,9 @@ pub async fn get_course_progress_percentage(
         AND module_id IN (SELECT id FROM module WHERE course_id = $2)
         "#,
     )
-    .bind(&params.learner_id)
-    .bind(&params.course_id)
-    .fetch_one(&pool)
+    .bind(learner_id)
+    .bind(course_id)
+    .fetch_one(pool)
     .await
     .map_err(internal_error)?;
 
@@ -466,8 +1265,12 @@ pub async fn get_course_progress_percentage(
         0.0
     };
 
-    let module_progress = if total_modules > 0 {
-        completed_modules as f32 / total_modules as f32
+    let final_exam_passed = fetch_final_exam_passed(pool, learner_id, course_id).await?;
+    let (total_units, completed_units) =
+        module_units(total_modules, completed_modules, final_exam_passed);
+
+    let module_progress = if total_units > 0 {
+        completed_units as f32 / total_units as f32
     } else {
         0.0
     };
@@ -476,14 +1279,65 @@ pub async fn get_course_progress_percentage(
 
     let progress_percent: u8 = raw_progress.clamp(0.0, 100.0) as u8;
 
-    Ok(Json(CourseProgressPercentage {
-        course_id: params.course_id,
-        learner_id: params.learner_id,
-        progress_percent,
-    }))
+    Ok(progress_percent)
+}
+
+/// Whether the learner passed the course final exam, `None` when the course
+/// has no final exam.
+pub(crate) async fn fetch_final_exam_passed(
+    pool: &Pool<Postgres>,
+    learner_id: &str,
+    course_id: i64,
+) -> Result<Option<bool>, (StatusCode, String)> {
+    sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM quiz_result qr
+            WHERE qr.quiz_id = q.id AND qr.learner_id = $1 AND qr.passed
+        )
+        FROM quiz q
+        WHERE q.course_id = $2
+        "#,
+    )
+    .bind(learner_id)
+    .bind(course_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(internal_error)
 }
 
-fn internal_error<E: std::fmt::Display>(e: E) -> (StatusCode, String) {
+// The final exam counts as one more module towards progress
+fn module_units(total_modules: i64, completed_modules: i64, final_exam_passed: Option<bool>) -> (i64, i64) {
+    match final_exam_passed {
+        Some(passed) => (total_modules + 1, completed_modules + i64::from(passed)),
+        None => (total_modules, completed_modules),
+    }
+}
+
+/// Active time of a learner on the lessons of each course, in whole seconds.
+pub(crate) async fn fetch_time_spent(
+    pool: &Pool<Postgres>,
+    learner_id: &str,
+) -> Result<HashMap<i64, i64>, (StatusCode, String)> {
+    let rows: Vec<(i64, i64)> = sqlx::query_as(
+        r#"
+        SELECT m.course_id, ROUND(SUM(t.active_seconds))::BIGINT
+        FROM lesson_time t
+        JOIN lesson l ON l.id = t.lesson_id
+        JOIN module m ON m.id = l.module_id
+        WHERE t.learner_id = $1
+        GROUP BY m.course_id
+        "#,
+    )
+    .bind(learner_id)
+    .fetch_all(pool)
+    .await
+    .map_err(internal_error)?;
+
+    Ok(rows.into_iter().collect())
+}
+
+pub(crate) fn internal_error<E: std::fmt::Display>(e: E) -> (StatusCode, String) {
     (
         StatusCode::INTERNAL_SERVER_ERROR,
         format!("Database error: {}", e),
@@ -513,7 +1367,7 @@ pub async fn get_all_course_progress(
         LEFT JOIN lesson l ON l.module_id = m.id
         LEFT JOIN lesson_completion lc ON lc.lesson_id = l.id AND lc.learner_id = $1
         LEFT JOIN quiz q ON q.module_id = m.id
-        LEFT JOIN quiz_completion qc ON qc.quiz_id = q.id AND qc.learner_id = $1
+        LEFT JOIN quiz_result qc ON qc.quiz_id = q.id AND qc.learner_id = $1 AND qc.passed
         LEFT JOIN module_completion mc ON mc.module_id = m.id AND mc.learner_id = $1
         WHERE ce.learner_id = $1
         "#,
@@ -523,6 +1377,26 @@ pub async fn get_all_course_progress(
     .await
     .map_err(internal_error)?;
 
+    // Course id -> final exam quiz id and whether it's passed
+    let final_exams: HashMap<i64, (i64, bool)> = sqlx::query_as::<_, (i64, i64, bool)>(
+        r#"
+        SELECT q.course_id, q.id, EXISTS (
+            SELECT 1 FROM quiz_result qr
+            WHERE qr.quiz_id = q.id AND qr.learner_id = $1 AND qr.passed
+        )
+        FROM quiz q
+        JOIN learner_course_enrollment ce ON ce.course_id = q.course_id
+        WHERE ce.learner_id = $1
+        "#,
+    )
+    .bind(learner_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(internal_error)?
+    .into_iter()
+    .map(|(course_id, quiz_id, passed)| (course_id, (quiz_id, passed)))
+    .collect();
+
     use std::collections::{HashMap, HashSet};
 
     let mut summaries: HashMap<i64, CourseProgressSummary> = HashMap::new();
@@ -549,36 +1423,34 @@ pub async fn get_all_course_progress(
                 completed_module_ids: vec![],
                 progress_percent: 0.0,
                 course_completed: false,
+                time_spent_seconds: 0,
             });
 
         if let Some(lid) = lesson_id {
             total_lessons.entry(course_id).or_default().insert(lid);
-            if lesson_completed {
-                if !entry.completed_lesson_ids.contains(&lid) {
-                    entry.completed_lesson_ids.push(lid);
-                }
+            if lesson_completed && !entry.completed_lesson_ids.contains(&lid) {
+                entry.completed_lesson_ids.push(lid);
             }
         }
 
-        if let Some(qid) = quiz_id {
-            if quiz_completed {
-                if !entry.completed_quiz_ids.contains(&qid) {
-                    entry.completed_quiz_ids.push(qid);
-                }
-            }
+        if let Some(qid) = quiz_id
+            && quiz_completed
+            && !entry.completed_quiz_ids.contains(&qid)
+        {
+            entry.completed_quiz_ids.push(qid);
         }
 
         if let Some(mid) = module_id {
             total_modules.entry(course_id).or_default().insert(mid);
-            if module_completed {
-                if !entry.completed_module_ids.contains(&mid) {
-                    entry.completed_module_ids.push(mid);
-                }
+            if module_completed && !entry.completed_module_ids.contains(&mid) {
+                entry.completed_module_ids.push(mid);
             }
         }
     }
 
+    let time_spent = fetch_time_spent(&pool, learner_id).await?;
     for summary in summaries.values_mut() {
+        summary.time_spent_seconds = time_spent.get(&summary.course_id).copied().unwrap_or(0);
         let lesson_total = total_lessons
             .get(&summary.course_id)
             .map(|s| s.len())
@@ -594,15 +1466,94 @@ pub async fn get_all_course_progress(
             0.0
         };
 
-        let module_progress = if module_total > 0 {
-            summary.completed_module_ids.len() as f32 / module_total as f32
+        let final_exam = final_exams.get(&summary.course_id);
+        if let Some((quiz_id, true)) = final_exam {
+            summary.completed_quiz_ids.push(*quiz_id);
+        }
+        let (total_units, completed_units) = module_units(
+            module_total as i64,
+            summary.completed_module_ids.len() as i64,
+            final_exam.map(|(_, passed)| *passed),
+        );
+
+        let module_progress = if total_units > 0 {
+            completed_units as f32 / total_units as f32
         } else {
             0.0
         };
 
         summary.progress_percent = ((lesson_progress + module_progress) / 2.0) * 100.0;
-        summary.course_completed = summary.completed_module_ids.len() == module_total;
+        summary.course_completed = completed_units == total_units;
     }
 
     Ok(Json(summaries.into_values().collect()))
 }
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+
+    const LEARNER: &str = "0xabc";
+
+    // A lesson with a two minute video file the enrolled learner must watch
+    async fn video_lesson(pool: &PgPool) -> i64 {
+        sqlx::query_scalar(
+            r#"
+            WITH creator AS (
+                INSERT INTO creator (id) VALUES ('0xcreator') RETURNING id
+            ), course AS (
+                INSERT INTO course (title, description, creator_id)
+                SELECT 'Rust', 'Basics', id FROM creator RETURNING id
+            ), module AS (
+                INSERT INTO module (course_id, title, position) SELECT id, 'Intro', 1 FROM course
+                RETURNING id
+            ), learner AS (
+                INSERT INTO learner (id) VALUES ($1) RETURNING id
+            ), enrollment AS (
+                INSERT INTO learner_course_enrollment (learner_id, course_id)
+                SELECT learner.id, course.id FROM learner, course
+            )
+            INSERT INTO lesson
+                (module_id, title, content, video_url, position, min_watched_percent, video_duration_seconds)
+            SELECT id, 'Ownership', '', 'https://cdn.example.com/ownership.mp4', 1, 80, 120
+            FROM module
+            RETURNING id
+            "#,
+        )
+        .bind(LEARNER)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    fn report(lesson_id: i64, duration_seconds: f64) -> Json<VideoProgressRequest> {
+        Json(VideoProgressRequest {
+            learner_id: LEARNER.to_string(),
+            lesson_id,
+            position_seconds: 0.0,
+            duration_seconds,
+            watched: Vec::new(),
+        })
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures("../../database_schema/aranya_db_schema.sql")
+    )]
+    async fn video_duration_comes_from_the_lesson(pool: PgPool) {
+        let lesson_id = video_lesson(&pool).await;
+
+        let (status, _) = record_video_progress(State(pool.clone()), report(lesson_id, 1.0))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let Json(progress) = record_video_progress(State(pool.clone()), report(lesson_id, 120.3))
+            .await
+            .unwrap();
+        assert_eq!(progress.duration_seconds, 120.0);
+        assert_eq!(progress.watched_percent, Some(0.0));
+    }
+}
@@ -0,0 +1,152 @@
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use futures_util::TryStreamExt;
+use sqlx::{Pool, Postgres};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::handlers::analytics::{CreatorRequest, authorize_creator};
+use crate::handlers::progress::internal_error;
+use crate::models::progress_export::{
+    ExportProgressQuery, ExportQuizRow, ProgressExportFormat, ProgressExportRow,
+};
+use crate::progress_export::{ProgressColumns, ProgressWriter};
+
+// Chunks encoded ahead of a slow client
+const CHUNK_BUFFER: usize = 4;
+
+/// Streams the progress of every learner enrolled in a course as CSV or
+/// Parquet, for the course creator.
+pub async fn export_progress(
+    State(pool): State<Pool<Postgres>>,
+    Query(params): Query<ExportProgressQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    authorize_creator(
+        &pool,
+        CreatorRequest::ExportProgress,
+        params.course_id,
+        &params.creator_id,
+        params.requested_at,
+        &params.signature,
+    )
+    .await?;
+
+    let quizzes: Vec<ExportQuizRow> = sqlx::query_as::<_, ExportQuizRow>(
+        r#"
+        SELECT q.id, q.course_id IS NOT NULL AS final_exam
+        FROM quiz q
+        LEFT JOIN module m ON m.id = q.module_id
+        WHERE COALESCE(q.course_id, m.course_id) = $1
+        ORDER BY m.position NULLS LAST, q.id
+        "#,
+    )
+    .bind(params.course_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    let (lessons_total, modules_total): (i64, i64) = sqlx::query_as(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM lesson l JOIN module m ON m.id = l.module_id WHERE m.course_id = $1),
+            (SELECT COUNT(*) FROM module WHERE course_id = $1)
+        "#,
+    )
+    .bind(params.course_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    let quiz_ids: Vec<i64> = quizzes.iter().map(|q| q.id).collect();
+    let columns = ProgressColumns::new(&quizzes, lessons_total, modules_total);
+    let writer = ProgressWriter::new(params.format, columns).map_err(internal_error)?;
+
+    let (tx, rx) = mpsc::channel(CHUNK_BUFFER);
+    tokio::spawn(async move {
+        let result = stream_progress(&pool, params.course_id, &quiz_ids, writer, &tx).await;
+        let chunk = result.map_err(|e| {
+            eprintln!(
+                "Progress export of course {} failed: {}",
+                params.course_id, e
+            );
+            std::io::Error::other(e)
+        });
+        let _ = tx.send(chunk).await;
+    });
+
+    let (content_type, extension) = match params.format {
+        ProgressExportFormat::Csv => ("text/csv; charset=utf-8", "csv"),
+        ProgressExportFormat::Parquet => ("application/vnd.apache.parquet", "parquet"),
+    };
+    let disposition = format!(
+        "attachment; filename=\"aranya-course-{}-progress.{}\"",
+        params.course_id, extension
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        Body::from_stream(ReceiverStream::new(rx)),
+    ))
+}
+
+// Sends chunks as rows arrive and returns the end of the file. Stops early,
+// without an error, once the client has gone away
+async fn stream_progress(
+    pool: &Pool<Postgres>,
+    course_id: i64,
+    quiz_ids: &[i64],
+    mut writer: ProgressWriter,
+    tx: &mpsc::Sender<Result<Vec<u8>, std::io::Error>>,
+) -> Result<Vec<u8>, String> {
+    let mut rows = sqlx::query_as::<_, ProgressExportRow>(
+        r#"
+        SELECT e.learner_id, l.display_name, e.enrolled_at,
+            (
+                SELECT COUNT(*)
+                FROM lesson_completion lc
+                JOIN lesson ls ON ls.id = lc.lesson_id
+                JOIN module m ON m.id = ls.module_id
+                WHERE lc.learner_id = e.learner_id AND m.course_id = e.course_id
+            ) AS lessons_completed,
+            (
+                SELECT COUNT(*)
+                FROM module_completion mc
+                JOIN module m ON m.id = mc.module_id
+                WHERE mc.learner_id = e.learner_id AND m.course_id = e.course_id
+            ) AS modules_completed,
+            ARRAY(
+                SELECT r.score_percent
+                FROM unnest($2::BIGINT[]) WITH ORDINALITY AS q(id, n)
+                LEFT JOIN quiz_result r ON r.quiz_id = q.id AND r.learner_id = e.learner_id
+                ORDER BY q.n
+            ) AS quiz_scores,
+            c.completed_at
+        FROM learner_course_enrollment e
+        JOIN learner l ON l.id = e.learner_id
+        LEFT JOIN course_completion c
+            ON c.learner_id = e.learner_id AND c.course_id = e.course_id
+        WHERE e.course_id = $1
+        ORDER BY e.enrolled_at, e.learner_id
+        "#,
+    )
+    .bind(course_id)
+    .bind(quiz_ids)
+    .fetch(pool);
+
+    while let Some(row) = rows.try_next().await.map_err(|e| e.to_string())? {
+        if let Some(chunk) = writer.write(row)?
+            && tx.send(Ok(chunk)).await.is_err()
+        {
+            return Ok(Vec::new());
+        }
+    }
+
+    writer.finish()
+}
@@ -0,0 +1,84 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use sqlx::{Pool, Postgres};
+
+use crate::handlers::analytics::{CreatorRequest, authorize_creator, fetch_quiz_course_id};
+use crate::handlers::course::fetch_quiz_questions;
+use crate::models::course::CreateQuestionPayload;
+use crate::models::quiz_format::{
+    ExportQuizQuery, ExportedQuiz, ImportQuizPayload, ImportedQuiz, QuizFormat,
+};
+use crate::quiz_format::{
+    gift::{export_gift, import_gift},
+    qti::{export_qti, import_qti},
+    question_payload,
+};
+
+/// Converts a GIFT or QTI question bank into questions for a create course
+/// payload, reporting what could not be carried over per item.
+pub async fn import_quiz(
+    Json(payload): Json<ImportQuizPayload>,
+) -> Result<Json<ImportedQuiz>, (StatusCode, String)> {
+    let imported = match payload.format {
+        QuizFormat::Gift => import_gift(&payload.content),
+        QuizFormat::Qti => {
+            import_qti(&payload.content).map_err(|e| (StatusCode::BAD_REQUEST, e))?
+        }
+    };
+
+    Ok(Json(imported))
+}
+
+/// Exports the question bank of a quiz, for the course creator only since it
+/// includes the answer key.
+pub async fn export_quiz(
+    State(pool): State<Pool<Postgres>>,
+    Query(params): Query<ExportQuizQuery>,
+) -> Result<Json<ExportedQuiz>, (StatusCode, String)> {
+    let course_id = fetch_quiz_course_id(&pool, params.quiz_id).await?;
+    authorize_creator(
+        &pool,
+        CreatorRequest::ExportQuiz,
+        course_id,
+        &params.creator_id,
+        params.requested_at,
+        &params.signature,
+    )
+    .await?;
+
+    let questions: Vec<CreateQuestionPayload> = fetch_quiz_questions(&pool, &[params.quiz_id])
+        .await?
+        .remove(&params.quiz_id)
+        .unwrap_or_default()
+        .iter()
+        .map(question_payload)
+        .collect();
+
+    let (file_name, content, issues) = match params.format {
+        QuizFormat::Gift => {
+            let (text, issues) = export_gift(&questions);
+            (format!("quiz-{}.txt", params.quiz_id), text, issues)
+        }
+        QuizFormat::Qti => {
+            let (package, issues) = export_qti(&questions, params.qti_version)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+            (
+                format!("quiz-{}-qti.zip", params.quiz_id),
+                STANDARD.encode(package),
+                issues,
+            )
+        }
+    };
+
+    Ok(Json(ExportedQuiz {
+        quiz_id: params.quiz_id,
+        format: params.format,
+        file_name,
+        content,
+        issues,
+    }))
+}
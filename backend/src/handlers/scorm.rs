@@ -0,0 +1,430 @@
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{StatusCode, header},
+    response::{Html, IntoResponse},
+};
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres, types::Json as SqlJson};
+
+use crate::bundle::{MediaFile, content_type, read_zip};
+use crate::handlers::analytics::{CreatorRequest, verify_creator_request};
+use crate::handlers::bundle::insert_media;
+use crate::handlers::progress::internal_error;
+use crate::models::scorm::{
+    CommitScormPayload, CommittedScorm, ScormContentPath, ScormLessonRow, ScormPackage,
+    ScormPackagePath, ScormRuntime, ScormRuntimeQuery, UploadScormQuery,
+};
+use crate::scorm::manifest::read_package;
+use crate::scorm::runtime::{self, CmiValues};
+use crate::scorm::{ScormVersion, package_content_type};
+use crate::xapi::{Activity, Verb, enqueue_statement};
+
+const LAUNCHER: &str = include_str!("../scorm/launcher.html");
+
+/// Stores a SCORM 1.2 or 2004 package zip uploaded as the request body,
+/// signed by the creator. Lessons created with its id launch it instead of
+/// showing their content.
+pub async fn upload_scorm_package(
+    State(pool): State<Pool<Postgres>>,
+    Query(params): Query<UploadScormQuery>,
+    body: Bytes,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let creator_id = verify_creator_request(
+        &pool,
+        CreatorRequest::UploadScormPackage,
+        None,
+        &params.creator_id,
+        params.requested_at,
+        &params.signature,
+    )
+    .await?;
+
+    let (manifest, files) = read_zip(&body)
+        .and_then(read_package)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let mut tx = pool.begin().await.map_err(internal_error)?;
+    sqlx::query("INSERT INTO creator (id) VALUES ($1) ON CONFLICT DO NOTHING")
+        .bind(&creator_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+    let package_id: i64 = sqlx::query_scalar(
+        "INSERT INTO scorm_package (title, scorm_version, launch_path, creator_id) VALUES ($1, $2, $3, $4) RETURNING id",
+    )
+    .bind(&manifest.title)
+    .bind(manifest.version.as_str())
+    .bind(&manifest.launch_path)
+    .bind(&creator_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+
+    // Files are stored as media, so packages uploaded twice share their content
+    let file_count = files.len();
+    for (path, data) in files {
+        let file = MediaFile {
+            sha256: hex::encode(Sha256::digest(&data)),
+            file_name: path.rsplit('/').next().unwrap_or_default().to_string(),
+            content_type: content_type(&path).to_string(),
+            data,
+        };
+        insert_media(&mut tx, &file).await?;
+        sqlx::query("INSERT INTO scorm_file (package_id, path, sha256) VALUES ($1, $2, $3)")
+            .bind(package_id)
+            .bind(&path)
+            .bind(&file.sha256)
+            .execute(&mut *tx)
+            .await
+            .map_err(internal_error)?;
+    }
+    tx.commit().await.map_err(internal_error)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ScormPackage {
+            id: package_id,
+            title: manifest.title,
+            scorm_version: manifest.version,
+            launch_path: manifest.launch_path,
+            files: file_count,
+        }),
+    ))
+}
+
+/// Page the lesson frame opens: it provides the SCORM API to the package,
+/// served from the same origin, and loads the package in a frame.
+pub async fn launch_scorm_package(
+    State(pool): State<Pool<Postgres>>,
+    Path(path): Path<ScormPackagePath>,
+    Query(params): Query<ScormRuntimeQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let runtime = fetch_scorm_runtime(&pool, &params.learner_id, params.lesson_id).await?;
+    if runtime.package_id != path.package_id {
+        return Err((
+            StatusCode::NOT_FOUND,
+            "Lesson does not launch this package".to_string(),
+        ));
+    }
+
+    // Keeps a learner id from closing the script element
+    let runtime = serde_json::to_string(&runtime)
+        .map_err(internal_error)?
+        .replace('<', "\\u003c");
+    Ok(Html(LAUNCHER.replace("/*RUNTIME*/null", &runtime)))
+}
+
+/// Serves a file of a package, relative links between files resolve here.
+/// The type comes from the file's extension, never from what was uploaded.
+pub async fn get_scorm_content(
+    State(pool): State<Pool<Postgres>>,
+    Path(path): Path<ScormContentPath>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let data: Vec<u8> = sqlx::query_scalar(
+        r#"
+        SELECT media.data
+        FROM scorm_file
+        JOIN media ON media.sha256 = scorm_file.sha256
+        WHERE scorm_file.package_id = $1 AND scorm_file.path = $2
+        "#,
+    )
+    .bind(path.package_id)
+    .bind(&path.path)
+    .fetch_optional(&pool)
+    .await
+    .map_err(internal_error)?
+    .ok_or_else(|| (StatusCode::NOT_FOUND, "File not found".to_string()))?;
+
+    let (content_type, disposition) = match package_content_type(&path.path) {
+        Some(content_type) => (content_type, "inline"),
+        None => ("application/octet-stream", "attachment"),
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CONTENT_DISPOSITION, disposition),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+            (header::CACHE_CONTROL, "public, max-age=31536000, immutable"),
+        ],
+        data,
+    ))
+}
+
+pub async fn get_scorm_runtime(
+    State(pool): State<Pool<Postgres>>,
+    Query(params): Query<ScormRuntimeQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let runtime = fetch_scorm_runtime(&pool, &params.learner_id, params.lesson_id).await?;
+    Ok(Json(runtime))
+}
+
+/// Stores the values a package set and marks the lesson complete once the
+/// package reports it completed or passed.
+pub async fn commit_scorm_runtime(
+    State(pool): State<Pool<Postgres>>,
+    Json(payload): Json<CommitScormPayload>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let mut tx = pool.begin().await.map_err(internal_error)?;
+    let lesson = fetch_scorm_lesson(&mut *tx, payload.lesson_id).await?;
+    let version = lesson_version(&lesson)?;
+
+    let is_enrolled: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM learner_course_enrollment WHERE learner_id = $1 AND course_id = $2)",
+    )
+    .bind(&payload.learner_id)
+    .bind(lesson.course_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+    if !is_enrolled {
+        return Err((StatusCode::FORBIDDEN, "Learner is not enrolled".to_string()));
+    }
+
+    let stored: Option<SqlJson<CmiValues>> = sqlx::query_scalar(
+        "SELECT cmi FROM scorm_runtime WHERE learner_id = $1 AND lesson_id = $2 FOR UPDATE",
+    )
+    .bind(&payload.learner_id)
+    .bind(payload.lesson_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+    let mut cmi = stored.map(|s| s.0).unwrap_or_default();
+
+    runtime::commit(version, &mut cmi, payload.values, payload.finished)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO scorm_runtime (learner_id, lesson_id, cmi)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (learner_id, lesson_id) DO UPDATE SET cmi = EXCLUDED.cmi, updated_at = now()
+        "#,
+    )
+    .bind(&payload.learner_id)
+    .bind(payload.lesson_id)
+    .bind(SqlJson(&cmi))
+    .execute(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+
+    let lesson_completed = runtime::is_completed(version, &cmi);
+    if lesson_completed {
+        let completed = sqlx::query(
+            r#"
+            INSERT INTO lesson_completion (learner_id, lesson_id)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(&payload.learner_id)
+        .bind(payload.lesson_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        if completed.rows_affected() > 0 {
+            enqueue_statement(
+                &mut *tx,
+                &payload.learner_id,
+                Verb::Experienced,
+                Activity::Lesson(payload.lesson_id),
+                None,
+            )
+            .await
+            .map_err(internal_error)?;
+        }
+    }
+    tx.commit().await.map_err(internal_error)?;
+
+    Ok(Json(CommittedScorm { lesson_completed }))
+}
+
+async fn fetch_scorm_runtime(
+    pool: &Pool<Postgres>,
+    learner_id: &str,
+    lesson_id: i64,
+) -> Result<ScormRuntime, (StatusCode, String)> {
+    let lesson = fetch_scorm_lesson(pool, lesson_id).await?;
+    let version = lesson_version(&lesson)?;
+
+    let stored: Option<SqlJson<CmiValues>> = sqlx::query_scalar(
+        "SELECT cmi FROM scorm_runtime WHERE learner_id = $1 AND lesson_id = $2",
+    )
+    .bind(learner_id)
+    .bind(lesson_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(internal_error)?;
+    let display_name: Option<String> =
+        sqlx::query_scalar("SELECT display_name FROM learner WHERE id = $1")
+            .bind(learner_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(internal_error)?
+            .flatten();
+    let lesson_completed: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM lesson_completion WHERE learner_id = $1 AND lesson_id = $2)",
+    )
+    .bind(learner_id)
+    .bind(lesson_id)
+    .fetch_one(pool)
+    .await
+    .map_err(internal_error)?;
+
+    let values = runtime::launch_values(
+        version,
+        stored.map(|s| s.0),
+        learner_id,
+        display_name.as_deref().unwrap_or(learner_id),
+    );
+
+    Ok(ScormRuntime {
+        learner_id: learner_id.to_string(),
+        lesson_id,
+        package_id: lesson.package_id,
+        scorm_version: version,
+        launch_path: lesson.launch_path,
+        values,
+        lesson_completed,
+    })
+}
+
+async fn fetch_scorm_lesson<'e, E>(
+    executor: E,
+    lesson_id: i64,
+) -> Result<ScormLessonRow, (StatusCode, String)>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_as::<_, ScormLessonRow>(
+        r#"
+        SELECT scorm_package.id AS package_id, scorm_package.scorm_version,
+            scorm_package.launch_path, module.course_id
+        FROM lesson
+        JOIN scorm_package ON scorm_package.id = lesson.scorm_package_id
+        JOIN module ON module.id = lesson.module_id
+        WHERE lesson.id = $1
+        "#,
+    )
+    .bind(lesson_id)
+    .fetch_optional(executor)
+    .await
+    .map_err(internal_error)?
+    .ok_or_else(|| (StatusCode::NOT_FOUND, "SCORM lesson not found".to_string()))
+}
+
+fn lesson_version(lesson: &ScormLessonRow) -> Result<ScormVersion, (StatusCode, String)> {
+    ScormVersion::parse(&lesson.scorm_version)
+        .ok_or_else(|| internal_error(format!("Unknown SCORM version {}", lesson.scorm_version)))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::response::Response;
+    use chrono::Utc;
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::bundle::write_zip;
+    use crate::handlers::analytics::creator_request_message;
+    use crate::test_support::{personal_sign, wallet_address};
+
+    const CREATOR_SECRET: [u8; 32] = [1; 32];
+    const MANIFEST: &str = r#"<manifest identifier="intro">
+  <metadata><schemaversion>1.2</schemaversion></metadata>
+  <organizations default="org">
+    <organization identifier="org">
+      <title>Intro</title>
+      <item identifier="item" identifierref="sco"><title>Intro</title></item>
+    </organization>
+  </organizations>
+  <resources>
+    <resource identifier="sco" type="webcontent" adlcp:scormtype="sco" href="index.html"
+      xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_rootv1p2"/>
+  </resources>
+</manifest>"#;
+
+    async fn upload(pool: &PgPool, signer: &[u8; 32]) -> Result<Response, (StatusCode, String)> {
+        let creator_id = wallet_address(&CREATOR_SECRET);
+        let requested_at = Utc::now().timestamp();
+        let signature = personal_sign(
+            signer,
+            &creator_request_message(
+                CreatorRequest::UploadScormPackage,
+                None,
+                &creator_id,
+                requested_at,
+            ),
+        );
+        let package = write_zip(
+            [
+                ("imsmanifest.xml", MANIFEST.as_bytes()),
+                ("index.html", b"<html></html>".as_slice()),
+                ("payload.exe", b"MZ".as_slice()),
+            ]
+            .into_iter(),
+        )
+        .unwrap();
+        upload_scorm_package(
+            State(pool.clone()),
+            Query(UploadScormQuery {
+                creator_id,
+                requested_at,
+                signature,
+            }),
+            Bytes::from(package),
+        )
+        .await
+        .map(IntoResponse::into_response)
+    }
+
+    async fn content(pool: &PgPool, package_id: i64, path: &str) -> Response {
+        get_scorm_content(
+            State(pool.clone()),
+            Path(ScormContentPath {
+                package_id,
+                path: path.to_string(),
+            }),
+        )
+        .await
+        .unwrap()
+        .into_response()
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures("../../database_schema/aranya_db_schema.sql")
+    )]
+    async fn upload_is_signed_and_files_are_served_by_extension(pool: PgPool) {
+        let (status, _) = upload(&pool, &[2; 32]).await.unwrap_err();
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let response = upload(&pool, &CREATOR_SECRET).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let (package_id, creator_id): (i64, String) =
+            sqlx::query_as("SELECT id, creator_id FROM scorm_package")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(creator_id, wallet_address(&CREATOR_SECRET));
+
+        let page = content(&pool, package_id, "index.html").await;
+        assert_eq!(
+            page.headers()[header::CONTENT_TYPE],
+            "text/html; charset=utf-8"
+        );
+        assert_eq!(page.headers()[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+
+        let download = content(&pool, package_id, "payload.exe").await;
+        assert_eq!(
+            download.headers()[header::CONTENT_TYPE],
+            "application/octet-stream"
+        );
+        assert_eq!(
+            download.headers()[header::CONTENT_DISPOSITION],
+            "attachment"
+        );
+    }
+}
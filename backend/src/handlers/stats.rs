@@ -0,0 +1,33 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+use chrono::Utc;
+use std::sync::Arc;
+
+use crate::models::stats::{PlatformStats, PlatformStatsQuery};
+use crate::stats::StatsService;
+
+/// Platform-wide totals, activity and growth over a time range.
+pub async fn get_platform_stats(
+    State(stats): State<Arc<StatsService>>,
+    Query(params): Query<PlatformStatsQuery>,
+) -> Result<Json<PlatformStats>, (StatusCode, String)> {
+    if params
+        .from
+        .is_some_and(|from| from >= params.to.unwrap_or_else(Utc::now))
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "from must be before to".to_string(),
+        ));
+    }
+
+    stats.stats(params).await.map(Json).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("DB error: {}", e),
+        )
+    })
+}
@@ -0,0 +1,34 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+use sqlx::{Pool, Postgres};
+
+use crate::handlers::progress::internal_error;
+use crate::models::xapi::{XapiStatementRow, XapiStatementsQuery};
+
+/// Lists the statements queued for a course or enrollment, newest first,
+/// with their delivery status.
+pub async fn get_xapi_statements(
+    State(pool): State<Pool<Postgres>>,
+    Query(params): Query<XapiStatementsQuery>,
+) -> Result<Json<Vec<XapiStatementRow>>, (StatusCode, String)> {
+    let statements: Vec<XapiStatementRow> = sqlx::query_as::<_, XapiStatementRow>(
+        r#"
+        SELECT id::TEXT, learner_id, verb, activity_type, activity_id, course_id, activity_name,
+            result, timestamp, status, attempts, last_error, sent_at
+        FROM xapi_statement
+        WHERE course_id = $1 AND ($2::TEXT IS NULL OR learner_id = $2)
+        ORDER BY timestamp DESC
+        LIMIT 500
+        "#,
+    )
+    .bind(params.course_id)
+    .bind(&params.learner_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    Ok(Json(statements))
+}
@@ -0,0 +1,290 @@
+use serde::Deserialize;
+
+use crate::models::analytics::{AnswerOptionAnalytics, QuestionAnalytics};
+use crate::models::course::{Question, QuestionType};
+use crate::models::progress::{QuestionResponse, QuestionResult};
+
+// Share of attempts in each of the upper and lower groups of the discrimination index
+const DISCRIMINATION_GROUP_SHARE: f64 = 0.27;
+
+/// Responses and results as stored in `quiz_attempt.answers`.
+#[derive(Debug, Default, Deserialize)]
+pub struct StoredAnswers {
+    #[serde(default)]
+    pub responses: Vec<QuestionResponse>,
+    #[serde(default)]
+    pub results: Vec<QuestionResult>,
+}
+
+pub struct AnalyzedAttempt {
+    pub score_percent: f64,
+    // Questions served in the attempt
+    pub question_ids: Vec<i64>,
+    pub answers: StoredAnswers,
+}
+
+struct Exposure<'a> {
+    score_percent: f64,
+    credit: f64,
+    response: Option<&'a QuestionResponse>,
+}
+
+/// Computes difficulty, discrimination, timing and distractor statistics of a
+/// question over the attempts it was served in.
+pub fn analyze_question(question: &Question, attempts: &[AnalyzedAttempt]) -> QuestionAnalytics {
+    let mut exposures: Vec<Exposure> = attempts
+        .iter()
+        .filter(|a| a.question_ids.contains(&question.id))
+        .map(|a| Exposure {
+            score_percent: a.score_percent,
+            credit: a
+                .answers
+                .results
+                .iter()
+                .find(|r| r.question_id == question.id)
+                .map_or(0.0, |r| r.credit),
+            response: a
+                .answers
+                .responses
+                .iter()
+                .find(|r| r.question_id == question.id),
+        })
+        .collect();
+    exposures.sort_by(|a, b| a.score_percent.total_cmp(&b.score_percent));
+
+    let num_exposures = exposures.len();
+    let responses: Vec<&QuestionResponse> = exposures.iter().filter_map(|e| e.response).collect();
+
+    let answer_options = match question.question_type {
+        QuestionType::SingleChoice | QuestionType::MultipleChoice | QuestionType::TrueFalse => {
+            question
+                .answers
+                .iter()
+                .map(|answer| {
+                    let selections = responses
+                        .iter()
+                        .filter(|r| {
+                            r.selected_answer_ids
+                                .as_ref()
+                                .is_some_and(|ids| ids.contains(&answer.id))
+                        })
+                        .count();
+                    AnswerOptionAnalytics {
+                        answer_id: answer.id,
+                        answer_text: answer.answer_text.clone(),
+                        is_correct: answer.is_correct,
+                        selections: selections as i64,
+                        selection_rate: ratio(selections, num_exposures),
+                    }
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+
+    QuestionAnalytics {
+        question_id: question.id,
+        question_text: question.question_text.clone(),
+        question_type: question.question_type,
+        pool: question.pool.clone(),
+        exposures: num_exposures as i64,
+        responses: responses.len() as i64,
+        p_value: mean(exposures.iter().map(|e| e.credit)),
+        discrimination_index: discrimination_index(&exposures),
+        average_time_seconds: mean(
+            responses
+                .iter()
+                .filter_map(|r| r.time_spent_seconds)
+                .filter(|seconds| seconds.is_finite() && *seconds >= 0.0),
+        ),
+        answer_options,
+    }
+}
+
+// Exposures must be sorted by attempt score
+fn discrimination_index(exposures: &[Exposure]) -> Option<f64> {
+    let n = exposures.len();
+    if n < 2 {
+        return None;
+    }
+
+    let group_size = ((n as f64 * DISCRIMINATION_GROUP_SHARE).round() as usize).clamp(1, n / 2);
+    let lower = mean(exposures[..group_size].iter().map(|e| e.credit))?;
+    let upper = mean(exposures[n - group_size..].iter().map(|e| e.credit))?;
+
+    Some(upper - lower)
+}
+
+pub fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0usize), |(sum, count), value| {
+        (sum + value, count + 1)
+    });
+    (count > 0).then(|| sum / count as f64)
+}
+
+fn ratio(numerator: usize, denominator: usize) -> Option<f64> {
+    (denominator > 0).then(|| numerator as f64 / denominator as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::course::AnswerOption;
+
+    const QUESTION_ID: i64 = 5;
+
+    fn question() -> Question {
+        Question {
+            id: QUESTION_ID,
+            question_text: "Pick one".to_string(),
+            question_type: QuestionType::SingleChoice,
+            points: 2.0,
+            answers: [(51, true), (52, false), (53, false)]
+                .into_iter()
+                .map(|(id, is_correct)| AnswerOption {
+                    id,
+                    answer_text: format!("Answer {}", id),
+                    is_correct,
+                    position: id as i32,
+                    match_text: None,
+                })
+                .collect(),
+            numeric_answer: None,
+            numeric_tolerance: 0.0,
+            case_sensitive: false,
+            pool: None,
+        }
+    }
+
+    // An attempt that served the question and selected `selected`
+    fn attempt(
+        score_percent: f64,
+        selected: i64,
+        time_spent_seconds: Option<f64>,
+    ) -> AnalyzedAttempt {
+        let credit = if selected == 51 { 1.0 } else { 0.0 };
+        AnalyzedAttempt {
+            score_percent,
+            question_ids: vec![1, QUESTION_ID],
+            answers: StoredAnswers {
+                responses: vec![QuestionResponse {
+                    question_id: QUESTION_ID,
+                    selected_answer_ids: Some(vec![selected]),
+                    ordered_answer_ids: None,
+                    matches: None,
+                    numeric_answer: None,
+                    text_answer: None,
+                    time_spent_seconds,
+                }],
+                results: vec![QuestionResult {
+                    question_id: QUESTION_ID,
+                    credit,
+                    points: 2.0,
+                    earned: credit * 2.0,
+                    correct: credit == 1.0,
+                }],
+            },
+        }
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("value is missing");
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn difficulty_and_discrimination_of_ten_attempts() {
+        // By score: 10 52, 20 52, 30 51, 40 53, 50-100 51. The 27% groups are
+        // the 3 lowest and 3 highest scores, 1/3 and 3/3 right
+        let attempts = [
+            attempt(70.0, 51, Some(30.0)),
+            attempt(10.0, 52, Some(10.0)),
+            attempt(100.0, 51, Some(20.0)),
+            attempt(40.0, 53, Some(40.0)),
+            attempt(30.0, 51, Some(50.0)),
+            attempt(90.0, 51, None),
+            attempt(20.0, 52, Some(30.0)),
+            attempt(60.0, 51, Some(f64::NAN)),
+            attempt(50.0, 51, Some(-5.0)),
+            attempt(80.0, 51, Some(30.0)),
+        ];
+
+        let analytics = analyze_question(&question(), &attempts);
+
+        assert_eq!(analytics.exposures, 10);
+        assert_eq!(analytics.responses, 10);
+        assert_close(analytics.p_value, 0.7);
+        assert_close(analytics.discrimination_index, 1.0 - 1.0 / 3.0);
+        // 30 + 10 + 20 + 40 + 50 + 30 + 30 over 7 valid timings
+        assert_close(analytics.average_time_seconds, 30.0);
+
+        let options: Vec<(i64, i64)> = analytics
+            .answer_options
+            .iter()
+            .map(|o| (o.answer_id, o.selections))
+            .collect();
+        assert_eq!(options, [(51, 7), (52, 2), (53, 1)]);
+        assert_close(analytics.answer_options[1].selection_rate, 0.2);
+    }
+
+    #[test]
+    fn zero_variance_has_no_discrimination() {
+        for selected in [51, 52] {
+            let attempts: Vec<AnalyzedAttempt> = (1..=4)
+                .map(|n| attempt(n as f64 * 25.0, selected, None))
+                .collect();
+
+            let analytics = analyze_question(&question(), &attempts);
+            let credit = if selected == 51 { 1.0 } else { 0.0 };
+            assert_close(analytics.p_value, credit);
+            assert_close(analytics.discrimination_index, 0.0);
+            assert_eq!(analytics.average_time_seconds, None);
+        }
+    }
+
+    #[test]
+    fn single_attempt_has_no_discrimination() {
+        let analytics = analyze_question(&question(), &[attempt(80.0, 51, Some(12.0))]);
+
+        assert_eq!(analytics.exposures, 1);
+        assert_close(analytics.p_value, 1.0);
+        assert_eq!(analytics.discrimination_index, None);
+        assert_close(analytics.answer_options[0].selection_rate, 1.0);
+
+        let analytics = analyze_question(&question(), &[]);
+        assert_eq!(analytics.exposures, 0);
+        assert_eq!(analytics.p_value, None);
+        assert_eq!(analytics.discrimination_index, None);
+        assert_eq!(analytics.answer_options[0].selection_rate, None);
+    }
+
+    #[test]
+    fn only_attempts_serving_the_question_count() {
+        let not_served = AnalyzedAttempt {
+            question_ids: vec![1],
+            ..attempt(100.0, 51, Some(5.0))
+        };
+        // Served but left unanswered, it scores 0
+        let unanswered = AnalyzedAttempt {
+            answers: StoredAnswers::default(),
+            ..attempt(0.0, 51, None)
+        };
+
+        let analytics = analyze_question(
+            &question(),
+            &[not_served, unanswered, attempt(50.0, 51, Some(8.0))],
+        );
+
+        assert_eq!(analytics.exposures, 2);
+        assert_eq!(analytics.responses, 1);
+        assert_close(analytics.p_value, 0.5);
+        assert_close(analytics.discrimination_index, 1.0);
+        assert_close(analytics.average_time_seconds, 8.0);
+        assert_close(analytics.answer_options[0].selection_rate, 0.5);
+    }
+}
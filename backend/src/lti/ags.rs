@@ -0,0 +1,219 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+use crate::lti::{AGS_SCORE_SCOPE, key::ToolKey};
+use crate::models::lti::LtiPlatformRow;
+
+const CLIENT_ASSERTION_TYPE: &str = "urn:ietf:params:oauth:client-assertion-type:jwt-bearer";
+const SCORE_CONTENT_TYPE: &str = "application/vnd.ims.lis.v1.score+json";
+
+/// Client for the Assignment and Grade Services score endpoint. Access
+/// tokens are requested with a signed client assertion and reused per
+/// platform until they expire.
+pub struct AgsClient {
+    http: reqwest::Client,
+    key: ToolKey,
+    tokens: Mutex<HashMap<i64, (String, DateTime<Utc>)>>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<i64>,
+}
+
+impl AgsClient {
+    pub fn new(http: reqwest::Client, key: ToolKey) -> AgsClient {
+        AgsClient {
+            http,
+            key,
+            tokens: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Posts a score to a line item, the result of the learner in the gradebook column.
+    pub async fn post_score(
+        &self,
+        platform: &LtiPlatformRow,
+        lineitem: &str,
+        score: &Value,
+    ) -> Result<(), String> {
+        let token = self.access_token(platform).await?;
+        let response = self
+            .http
+            .post(scores_url(lineitem))
+            .bearer_auth(&token)
+            .header(reqwest::header::CONTENT_TYPE, SCORE_CONTENT_TYPE)
+            .body(score.to_string())
+            .send()
+            .await
+            .map_err(|e| format!("Score request failed: {}", e))?;
+
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+            self.tokens.lock().await.remove(&platform.id);
+        }
+        if !response.status().is_success() {
+            return Err(format!(
+                "Platform returned status {} for the score",
+                response.status()
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn access_token(&self, platform: &LtiPlatformRow) -> Result<String, String> {
+        if let Some((token, expires_at)) = self.tokens.lock().await.get(&platform.id)
+            && *expires_at > Utc::now()
+        {
+            return Ok(token.clone());
+        }
+
+        let now = Utc::now().timestamp();
+        let assertion = self.key.sign_jwt(&json!({
+            "iss": platform.client_id,
+            "sub": platform.client_id,
+            "aud": platform.auth_token_url,
+            "iat": now,
+            "exp": now + 300,
+            "jti": hex::encode(rand::random::<[u8; 16]>()),
+        }));
+        let response = self
+            .http
+            .post(&platform.auth_token_url)
+            .form(&[
+                ("grant_type", "client_credentials"),
+                ("client_assertion_type", CLIENT_ASSERTION_TYPE),
+                ("client_assertion", &assertion),
+                ("scope", AGS_SCORE_SCOPE),
+            ])
+            .send()
+            .await
+            .map_err(|e| format!("Token request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!(
+                "Platform returned status {} for the token",
+                response.status()
+            ));
+        }
+        let token: TokenResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid token response: {}", e))?;
+
+        // Renewed a minute early so it does not expire in flight
+        let expires_at = Utc::now() + Duration::seconds(token.expires_in.unwrap_or(3600) - 60);
+        self.tokens
+            .lock()
+            .await
+            .insert(platform.id, (token.access_token.clone(), expires_at));
+
+        Ok(token.access_token)
+    }
+}
+
+// Line item URLs may carry a query string, scores go under their path
+fn scores_url(lineitem: &str) -> String {
+    match lineitem.split_once('?') {
+        Some((path, query)) => format!("{}/scores?{}", path.trim_end_matches('/'), query),
+        None => format!("{}/scores", lineitem.trim_end_matches('/')),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, atomic::Ordering};
+
+    use super::*;
+    use crate::lti::mock::{LINEITEM_PATH, MockPlatform, TOOL_KEY_PEM, mock_platform};
+    use crate::test_support::serve;
+
+    // The tool client, the platform, its registration and a line item on it
+    async fn platform() -> (AgsClient, Arc<MockPlatform>, LtiPlatformRow, String) {
+        let tool_key = ToolKey::from_pem(TOOL_KEY_PEM).unwrap();
+        let platform = MockPlatform::new(&tool_key);
+        let base_url = serve(mock_platform(platform.clone())).await;
+        (
+            AgsClient::new(reqwest::Client::new(), tool_key),
+            platform,
+            MockPlatform::row(&base_url),
+            format!("{}{}", base_url, LINEITEM_PATH),
+        )
+    }
+
+    fn score(percent: i32) -> Value {
+        json!({
+            "userId": "learner-sub",
+            "scoreGiven": percent,
+            "scoreMaximum": 100,
+            "activityProgress": "InProgress",
+            "gradingProgress": "Pending",
+        })
+    }
+
+    #[tokio::test]
+    async fn post_score_authenticates_with_a_signed_assertion() {
+        let (ags, platform, row, lineitem) = platform().await;
+
+        ags.post_score(&row, &lineitem, &score(40)).await.unwrap();
+
+        assert_eq!(*platform.scores.lock().unwrap(), vec![score(40)]);
+        assert_eq!(platform.tokens_issued.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn access_token_is_reused_until_the_platform_rejects_it() {
+        let (ags, platform, row, lineitem) = platform().await;
+
+        ags.post_score(&row, &lineitem, &score(40)).await.unwrap();
+        ags.post_score(&row, &lineitem, &score(60)).await.unwrap();
+        assert_eq!(platform.tokens_issued.load(Ordering::SeqCst), 1);
+
+        *platform.valid_token.lock().unwrap() = None;
+        let error = ags
+            .post_score(&row, &lineitem, &score(80))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error,
+            "Platform returned status 401 Unauthorized for the score"
+        );
+
+        ags.post_score(&row, &lineitem, &score(80)).await.unwrap();
+        assert_eq!(platform.tokens_issued.load(Ordering::SeqCst), 2);
+        assert_eq!(platform.scores.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn token_request_signed_with_another_key_is_refused() {
+        let (_, platform, row, lineitem) = platform().await;
+        let ags = AgsClient::new(reqwest::Client::new(), platform.key.clone());
+
+        let error = ags
+            .post_score(&row, &lineitem, &score(40))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error,
+            "Platform returned status 400 Bad Request for the token"
+        );
+        assert!(platform.scores.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn scores_go_under_the_lineitem_path() {
+        assert_eq!(
+            scores_url("https://lms.test/lineitems/1/"),
+            "https://lms.test/lineitems/1/scores"
+        );
+        assert_eq!(
+            scores_url("https://lms.test/lineitems/1?type=course"),
+            "https://lms.test/lineitems/1/scores?type=course"
+        );
+    }
+}
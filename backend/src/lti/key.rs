@@ -0,0 +1,73 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use rsa::{
+    RsaPrivateKey, RsaPublicKey,
+    pkcs1::DecodeRsaPrivateKey,
+    pkcs1v15::SigningKey,
+    pkcs8::{DecodePrivateKey, EncodePublicKey},
+    signature::{SignatureEncoding, Signer},
+    traits::PublicKeyParts,
+};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+/// RSA key the tool signs its messages to platforms with, RS256 being the
+/// only algorithm LTI 1.3 requires platforms to accept.
+#[derive(Clone)]
+pub struct ToolKey {
+    signing_key: SigningKey<Sha256>,
+    public_key: RsaPublicKey,
+    kid: String,
+}
+
+impl ToolKey {
+    /// Reads a PKCS#8 or PKCS#1 PEM private key.
+    pub fn from_pem(pem: &str) -> Result<ToolKey, String> {
+        let private_key = RsaPrivateKey::from_pkcs8_pem(pem)
+            .or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem))
+            .map_err(|e| format!("Invalid RSA private key: {}", e))?;
+        let public_key = private_key.to_public_key();
+
+        // Stable across restarts, platforms cache the JWKS by kid
+        let der = public_key
+            .to_public_key_der()
+            .map_err(|e| format!("Invalid RSA public key: {}", e))?;
+        let kid = hex::encode(&Sha256::digest(der.as_bytes())[..16]);
+
+        Ok(ToolKey {
+            signing_key: SigningKey::new(private_key),
+            public_key,
+            kid,
+        })
+    }
+
+    /// The public key set served at `/lti/jwks`.
+    pub fn jwks(&self) -> Value {
+        json!({
+            "keys": [{
+                "kty": "RSA",
+                "alg": "RS256",
+                "use": "sig",
+                "kid": self.kid,
+                "n": URL_SAFE_NO_PAD.encode(self.public_key.n().to_bytes_be()),
+                "e": URL_SAFE_NO_PAD.encode(self.public_key.e().to_bytes_be()),
+            }]
+        })
+    }
+
+    /// Signs `claims` as a compact RS256 JWT.
+    pub fn sign_jwt(&self, claims: &Value) -> String {
+        let header = json!({ "alg": "RS256", "typ": "JWT", "kid": self.kid });
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature = self.signing_key.sign(signing_input.as_bytes());
+
+        format!(
+            "{}.{}",
+            signing_input,
+            URL_SAFE_NO_PAD.encode(signature.to_bytes())
+        )
+    }
+}
@@ -0,0 +1,62 @@
+pub mod ags;
+pub mod key;
+#[cfg(test)]
+pub mod mock;
+pub mod token;
+pub mod worker;
+
+use dotenv::dotenv;
+use std::env;
+
+use key::ToolKey;
+
+pub const MESSAGE_TYPE_CLAIM: &str = "https://purl.imsglobal.org/spec/lti/claim/message_type";
+pub const VERSION_CLAIM: &str = "https://purl.imsglobal.org/spec/lti/claim/version";
+pub const DEPLOYMENT_ID_CLAIM: &str = "https://purl.imsglobal.org/spec/lti/claim/deployment_id";
+pub const TARGET_LINK_URI_CLAIM: &str = "https://purl.imsglobal.org/spec/lti/claim/target_link_uri";
+pub const CUSTOM_CLAIM: &str = "https://purl.imsglobal.org/spec/lti/claim/custom";
+pub const DEEP_LINKING_SETTINGS_CLAIM: &str =
+    "https://purl.imsglobal.org/spec/lti-dl/claim/deep_linking_settings";
+pub const CONTENT_ITEMS_CLAIM: &str = "https://purl.imsglobal.org/spec/lti-dl/claim/content_items";
+pub const DEEP_LINKING_DATA_CLAIM: &str = "https://purl.imsglobal.org/spec/lti-dl/claim/data";
+pub const AGS_ENDPOINT_CLAIM: &str = "https://purl.imsglobal.org/spec/lti-ags/claim/endpoint";
+pub const AGS_SCORE_SCOPE: &str = "https://purl.imsglobal.org/spec/lti-ags/scope/score";
+
+pub const RESOURCE_LINK_REQUEST: &str = "LtiResourceLinkRequest";
+pub const DEEP_LINKING_REQUEST: &str = "LtiDeepLinkingRequest";
+pub const DEEP_LINKING_RESPONSE: &str = "LtiDeepLinkingResponse";
+pub const LTI_VERSION: &str = "1.3.0";
+
+/// Tool side of LTI 1.3: the key platforms verify our messages with, and
+/// where launched learners are sent.
+#[derive(Clone)]
+pub struct LtiConfig {
+    pub key: ToolKey,
+    // Public URL of this backend, platforms redirect to /lti/launch there
+    pub public_api_url: String,
+    pub webapp_url: String,
+}
+
+impl LtiConfig {
+    /// `None` when `LTI_PRIVATE_KEY_PATH` is not set, the tool is then disabled.
+    pub fn from_env(public_api_url: &str) -> Option<LtiConfig> {
+        dotenv().ok();
+        let key_path = env::var("LTI_PRIVATE_KEY_PATH")
+            .ok()
+            .filter(|p| !p.is_empty())?;
+        let pem = std::fs::read_to_string(&key_path).expect("LTI_PRIVATE_KEY_PATH is not readable");
+
+        Some(LtiConfig {
+            key: ToolKey::from_pem(&pem).expect("LTI_PRIVATE_KEY_PATH is not an RSA private key"),
+            public_api_url: public_api_url.trim_end_matches('/').to_string(),
+            webapp_url: env::var("LTI_WEBAPP_URL")
+                .unwrap_or_else(|_| "http://localhost:3000".to_string())
+                .trim_end_matches('/')
+                .to_string(),
+        })
+    }
+
+    pub fn launch_url(&self) -> String {
+        format!("{}/lti/launch", self.public_api_url)
+    }
+}
@@ -0,0 +1,219 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use rsa::{
+    BigUint, RsaPublicKey,
+    pkcs1v15::{Signature, VerifyingKey},
+    signature::Verifier,
+};
+use serde_json::Value;
+use sha2::Sha256;
+
+use crate::lti::{DEPLOYMENT_ID_CLAIM, LTI_VERSION, VERSION_CLAIM};
+use crate::models::lti::LtiPlatformRow;
+use crate::signing::decode_jwt_part;
+
+// Clock skew allowed between the platform and this backend
+const LEEWAY_SECONDS: i64 = 60;
+
+/// Verifies an RS256 JWT against a platform key set and returns its claims.
+/// Tokens are matched to a key by `kid`, or to the only key of the set.
+pub fn verify_jwt(jwt: &str, jwks: &Value) -> Result<Value, String> {
+    let parts: Vec<&str> = jwt.split('.').collect();
+    let [header, claims, signature] = parts[..] else {
+        return Err("Malformed JWT".to_string());
+    };
+
+    let header = decode_jwt_part(header)?;
+    if header["alg"] != "RS256" {
+        return Err("Unsupported JWT algorithm".to_string());
+    }
+
+    let keys = jwks["keys"]
+        .as_array()
+        .ok_or_else(|| "Platform key set has no keys".to_string())?;
+    let key = match header["kid"].as_str() {
+        Some(kid) => keys.iter().find(|k| k["kid"] == kid),
+        None if keys.len() == 1 => keys.first(),
+        None => None,
+    }
+    .ok_or_else(|| "No platform key matches the JWT".to_string())?;
+
+    let public_key = RsaPublicKey::new(key_part(key, "n")?, key_part(key, "e")?)
+        .map_err(|e| format!("Invalid platform key: {}", e))?;
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|_| "Malformed JWT".to_string())
+        .and_then(|s| Signature::try_from(s.as_slice()).map_err(|e| e.to_string()))?;
+    VerifyingKey::<Sha256>::new(public_key)
+        .verify(format!("{}.{}", parts[0], claims).as_bytes(), &signature)
+        .map_err(|_| "Invalid JWT signature".to_string())?;
+
+    decode_jwt_part(claims)
+}
+
+/// Checks that an id_token was issued by the platform to this tool for the
+/// login it answers, and is an LTI 1.3 message.
+pub fn validate_id_token(
+    claims: &Value,
+    platform: &LtiPlatformRow,
+    nonce: &str,
+    now: i64,
+) -> Result<(), String> {
+    if claims["iss"] != platform.issuer.as_str() {
+        return Err("id_token issuer does not match the platform".to_string());
+    }
+
+    // With several audiences the authorized party must be this tool
+    let audience_ok = match &claims["aud"] {
+        Value::String(aud) => *aud == platform.client_id,
+        Value::Array(auds) => {
+            auds.iter().any(|a| *a == platform.client_id.as_str())
+                && (auds.len() == 1 || claims["azp"] == platform.client_id.as_str())
+        }
+        _ => false,
+    };
+    if !audience_ok {
+        return Err("id_token was not issued to this tool".to_string());
+    }
+    if claims["azp"].is_string() && claims["azp"] != platform.client_id.as_str() {
+        return Err("id_token was not issued to this tool".to_string());
+    }
+
+    let exp = claims["exp"]
+        .as_i64()
+        .ok_or_else(|| "id_token has no expiry".to_string())?;
+    let iat = claims["iat"]
+        .as_i64()
+        .ok_or_else(|| "id_token has no issue time".to_string())?;
+    if exp + LEEWAY_SECONDS < now {
+        return Err("id_token has expired".to_string());
+    }
+    if iat - LEEWAY_SECONDS > now {
+        return Err("id_token is issued in the future".to_string());
+    }
+
+    if claims["nonce"] != nonce {
+        return Err("id_token nonce does not match the login".to_string());
+    }
+    if claims[VERSION_CLAIM] != LTI_VERSION {
+        return Err("Only LTI 1.3.0 messages are supported".to_string());
+    }
+    if claims[DEPLOYMENT_ID_CLAIM]
+        .as_str()
+        .is_none_or(|d| d.is_empty())
+    {
+        return Err("id_token has no deployment id".to_string());
+    }
+
+    Ok(())
+}
+
+fn key_part(key: &Value, name: &str) -> Result<BigUint, String> {
+    let bytes = key[name]
+        .as_str()
+        .and_then(|v| URL_SAFE_NO_PAD.decode(v).ok())
+        .ok_or_else(|| format!("Platform key has no valid {}", name))?;
+    Ok(BigUint::from_bytes_be(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::lti::key::ToolKey;
+    use crate::lti::mock::{CLIENT_ID, ISSUER, MockPlatform, TOOL_KEY_PEM, mock_platform};
+    use crate::test_support::serve;
+
+    const NONCE: &str = "login-nonce";
+    const NOW: i64 = 1_800_000_000;
+
+    fn id_token_claims() -> Value {
+        json!({
+            "iss": ISSUER,
+            "aud": CLIENT_ID,
+            "sub": "learner-sub",
+            "iat": NOW,
+            "exp": NOW + 300,
+            "nonce": NONCE,
+            VERSION_CLAIM: LTI_VERSION,
+            DEPLOYMENT_ID_CLAIM: "deployment-1",
+        })
+    }
+
+    // The platform key set, fetched the way a launch fetches it
+    async fn platform_jwks() -> (Value, std::sync::Arc<MockPlatform>, LtiPlatformRow) {
+        let platform = MockPlatform::new(&ToolKey::from_pem(TOOL_KEY_PEM).unwrap());
+        let row = MockPlatform::row(&serve(mock_platform(platform.clone())).await);
+        let jwks = reqwest::get(&row.jwks_url)
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        (jwks, platform, row)
+    }
+
+    #[tokio::test]
+    async fn id_token_signed_by_the_platform_is_accepted() {
+        let (jwks, platform, row) = platform_jwks().await;
+        let id_token = platform.key.sign_jwt(&id_token_claims());
+
+        let claims = verify_jwt(&id_token, &jwks).unwrap();
+
+        assert_eq!(claims, id_token_claims());
+        validate_id_token(&claims, &row, NONCE, NOW).unwrap();
+    }
+
+    #[tokio::test]
+    async fn id_token_signed_by_another_key_is_rejected() {
+        let (jwks, _, _) = platform_jwks().await;
+        let id_token = ToolKey::from_pem(TOOL_KEY_PEM)
+            .unwrap()
+            .sign_jwt(&id_token_claims());
+
+        assert_eq!(
+            verify_jwt(&id_token, &jwks).unwrap_err(),
+            "No platform key matches the JWT"
+        );
+    }
+
+    #[tokio::test]
+    async fn tampered_id_token_is_rejected() {
+        let (jwks, platform, _) = platform_jwks().await;
+        let id_token = platform.key.sign_jwt(&id_token_claims());
+        let mut other_claims = id_token_claims();
+        other_claims["sub"] = json!("someone-else");
+        let other_token = platform.key.sign_jwt(&other_claims);
+
+        // Claims of one token with the signature of the other
+        let parts: Vec<&str> = id_token.split('.').collect();
+        let other_parts: Vec<&str> = other_token.split('.').collect();
+        let tampered = format!("{}.{}.{}", parts[0], other_parts[1], parts[2]);
+
+        assert_eq!(
+            verify_jwt(&tampered, &jwks).unwrap_err(),
+            "Invalid JWT signature"
+        );
+    }
+
+    #[tokio::test]
+    async fn id_token_for_another_login_or_tool_is_rejected() {
+        let (_, _, row) = platform_jwks().await;
+
+        assert_eq!(
+            validate_id_token(&id_token_claims(), &row, "other-nonce", NOW).unwrap_err(),
+            "id_token nonce does not match the login"
+        );
+        assert_eq!(
+            validate_id_token(&id_token_claims(), &row, NONCE, NOW + 600).unwrap_err(),
+            "id_token has expired"
+        );
+
+        let mut claims = id_token_claims();
+        claims["aud"] = json!([CLIENT_ID, "other-tool"]);
+        assert_eq!(
+            validate_id_token(&claims, &row, NONCE, NOW).unwrap_err(),
+            "id_token was not issued to this tool"
+        );
+    }
+}
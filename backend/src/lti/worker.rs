@@ -0,0 +1,166 @@
+use chrono::{Duration, SecondsFormat, Utc};
+use serde_json::json;
+use sqlx::{Pool, Postgres};
+
+use crate::handlers::progress::fetch_progress_percent;
+use crate::lti::{LtiConfig, ags::AgsClient};
+use crate::models::lti::{LtiGradeSyncRow, LtiPlatformRow};
+
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+const BATCH_SIZE: i64 = 50;
+// How often the progress of a synced learner is checked for changes
+const CHECK_INTERVAL_SECONDS: i64 = 60;
+const BASE_BACKOFF_SECONDS: i64 = 10;
+const MAX_BACKOFF_SECONDS: i64 = 3600;
+
+/// Background worker that pushes the course progress of learners launched
+/// from a platform to its gradebook whenever it changes, retrying with
+/// backoff while the platform is unreachable.
+pub struct LtiGradeWorker {
+    pool: Pool<Postgres>,
+    ags: AgsClient,
+}
+
+impl LtiGradeWorker {
+    pub fn new(pool: Pool<Postgres>, config: &LtiConfig) -> LtiGradeWorker {
+        LtiGradeWorker {
+            pool,
+            ags: AgsClient::new(reqwest::Client::new(), config.key.clone()),
+        }
+    }
+
+    pub fn spawn(self) {
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = self.sync_due_grades().await {
+                    eprintln!("LTI grade worker error: {}", e);
+                }
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        });
+    }
+
+    async fn sync_due_grades(&self) -> Result<(), sqlx::Error> {
+        let rows: Vec<LtiGradeSyncRow> = sqlx::query_as::<_, LtiGradeSyncRow>(
+            r#"
+            SELECT platform_id, lineitem, sub, learner_id, course_id, synced_percent,
+                synced_completed, attempts
+            FROM lti_grade_sync
+            WHERE next_attempt_at <= now()
+            ORDER BY next_attempt_at
+            LIMIT $1
+            "#,
+        )
+        .bind(BATCH_SIZE)
+        .fetch_all(&self.pool)
+        .await?;
+
+        for row in rows {
+            let percent =
+                match fetch_progress_percent(&self.pool, &row.learner_id, row.course_id).await {
+                    Ok(percent) => i32::from(percent),
+                    Err((_, e)) => {
+                        self.retry(&row, e).await?;
+                        continue;
+                    }
+                };
+            let completed: bool = sqlx::query_scalar(
+                "SELECT EXISTS(SELECT 1 FROM course_completion WHERE learner_id = $1 AND course_id = $2)",
+            )
+            .bind(&row.learner_id)
+            .bind(row.course_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+            if row.synced_percent == Some(percent) && row.synced_completed == completed {
+                self.schedule(&row, Duration::seconds(CHECK_INTERVAL_SECONDS))
+                    .await?;
+                continue;
+            }
+
+            let platform: LtiPlatformRow = sqlx::query_as::<_, LtiPlatformRow>(
+                r#"
+                SELECT id, issuer, client_id, auth_login_url, auth_token_url, jwks_url
+                FROM lti_platform
+                WHERE id = $1
+                "#,
+            )
+            .bind(row.platform_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+            let score = json!({
+                "userId": row.sub,
+                "scoreGiven": percent,
+                "scoreMaximum": 100,
+                "activityProgress": if completed { "Completed" } else { "InProgress" },
+                "gradingProgress": "FullyGraded",
+                "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            });
+            match self.ags.post_score(&platform, &row.lineitem, &score).await {
+                Ok(()) => {
+                    sqlx::query(
+                        r#"
+                        UPDATE lti_grade_sync
+                        SET synced_percent = $3, synced_completed = $4, attempts = 0,
+                            last_error = NULL, synced_at = now(), next_attempt_at = $5
+                        WHERE lineitem = $1 AND sub = $2
+                        "#,
+                    )
+                    .bind(&row.lineitem)
+                    .bind(&row.sub)
+                    .bind(percent)
+                    .bind(completed)
+                    .bind(Utc::now() + Duration::seconds(CHECK_INTERVAL_SECONDS))
+                    .execute(&self.pool)
+                    .await?;
+                }
+                Err(e) => {
+                    eprintln!("LTI grade for {} not synced: {}", row.learner_id, e);
+                    self.retry(&row, e).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn schedule(&self, row: &LtiGradeSyncRow, delay: Duration) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE lti_grade_sync SET next_attempt_at = $3 WHERE lineitem = $1 AND sub = $2",
+        )
+        .bind(&row.lineitem)
+        .bind(&row.sub)
+        .bind(Utc::now() + delay)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Grades keep being retried, the platform may come back after any outage
+    async fn retry(&self, row: &LtiGradeSyncRow, error: String) -> Result<(), sqlx::Error> {
+        let attempts = row.attempts + 1;
+        sqlx::query(
+            r#"
+            UPDATE lti_grade_sync
+            SET attempts = $3, last_error = $4, next_attempt_at = $5
+            WHERE lineitem = $1 AND sub = $2
+            "#,
+        )
+        .bind(&row.lineitem)
+        .bind(&row.sub)
+        .bind(attempts)
+        .bind(error)
+        .bind(Utc::now() + backoff(attempts))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+fn backoff(attempts: i32) -> Duration {
+    let seconds = BASE_BACKOFF_SECONDS.saturating_mul(1 << attempts.clamp(0, 16));
+    Duration::seconds(seconds.min(MAX_BACKOFF_SECONDS))
+}
@@ -1,28 +1,125 @@
+mod bundle;
+mod cli;
+mod common_cartridge;
 mod db;
+mod fdc;
+mod grading;
 mod handlers;
+mod item_analysis;
+mod lti;
+mod markdown_course;
 mod models;
+mod pdf;
+mod progress_export;
+mod question_pool;
+mod quiz_format;
+mod retention;
 mod routes;
+mod scorm;
+mod signing;
+mod state;
+mod stats;
+#[cfg(test)]
+mod test_support;
+mod video_progress;
+mod xapi;
 
 use axum::Router;
-use std::{error::Error, net::SocketAddr};
+use std::{env, error::Error, net::SocketAddr, sync::Arc};
 use tokio::net::TcpListener;
 use tower_http::cors::{Any, CorsLayer};
 
-use routes::{course::course_routes, progress::progress_routes};
+use fdc::{FdcConfig, worker::AttestationWorker};
+use handlers::{credential::CredentialState, lti::LtiState};
+use lti::{LtiConfig, worker::LtiGradeWorker};
+use routes::{
+    analytics::analytics_routes, attestation::attestation_routes,
+    attestation_job::attestation_job_routes, badge::badge_routes, bundle::bundle_routes,
+    certificate::certificate_routes, course::course_routes, credential::credential_routes,
+    lti::lti_routes, nft::nft_routes, progress::progress_routes,
+    progress_export::progress_export_routes, quiz_format::quiz_format_routes, scorm::scorm_routes,
+    stats::stats_routes, xapi::xapi_routes,
+};
+use retention::RetentionRefresher;
+use signing::ServerKey;
+use state::AppState;
+use stats::StatsService;
+use xapi::{XapiConfig, worker::XapiWorker};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let pool = db::connect().await.expect("DB connect failed");
+    let command = cli::parse_args(&std::env::args().skip(1).collect::<Vec<_>>())?;
+
+    let pool = db::connect().await?;
+    let public_api_url =
+        env::var("PUBLIC_API_URL").unwrap_or_else(|_| "http://localhost:4000".to_string());
+
+    let app_state = AppState {
+        pool: pool.clone(),
+        public_api_url: public_api_url.clone(),
+    };
+
+    if let Some(command) = command {
+        return cli::run(command, &app_state).await;
+    }
+
+    // Only the server signs credentials and attestation snapshots
+    let key = ServerKey::from_env()?;
+    let credential_state = CredentialState {
+        pool: pool.clone(),
+        key: key.clone(),
+        issuer_url: public_api_url.clone(),
+    };
+
+    match FdcConfig::from_env(&public_api_url) {
+        Some(fdc_config) => match AttestationWorker::new(pool.clone(), &fdc_config) {
+            Ok(worker) => worker.spawn(),
+            Err(e) => eprintln!("Attestation worker disabled: {}", e),
+        },
+        None => println!("WEB2JSON_VERIFIER_URL not set, attestation jobs stay pending"),
+    }
+
+    RetentionRefresher::new(pool.clone()).spawn();
+
+    match XapiConfig::from_env(&public_api_url) {
+        Some(xapi_config) => XapiWorker::new(pool.clone(), &xapi_config).spawn(),
+        None => println!("XAPI_LRS_ENDPOINT not set, xAPI statements stay queued"),
+    }
 
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
         .allow_headers(Any);
 
-    let app = Router::new()
+    let mut app = Router::new()
         .merge(course_routes(pool.clone()))
-        .merge(progress_routes(pool.clone()))
-        .layer(cors);
+        .merge(stats_routes(Arc::new(StatsService::from_env(pool.clone()))))
+        .merge(progress_routes(pool.clone(), credential_state.clone()))
+        .merge(progress_export_routes(pool.clone()))
+        .merge(attestation_routes(pool.clone(), key.clone()))
+        .merge(attestation_job_routes(pool.clone()))
+        .merge(nft_routes(pool.clone()))
+        .merge(analytics_routes(pool.clone()))
+        .merge(quiz_format_routes(pool.clone()))
+        .merge(scorm_routes(pool.clone()))
+        .merge(xapi_routes(pool.clone()))
+        .merge(credential_routes(credential_state.clone()))
+        .merge(badge_routes(credential_state))
+        .merge(bundle_routes(app_state.clone()))
+        .merge(certificate_routes(app_state));
+
+    match LtiConfig::from_env(&public_api_url) {
+        Some(lti_config) => {
+            LtiGradeWorker::new(pool.clone(), &lti_config).spawn();
+            app = app.merge(lti_routes(LtiState {
+                pool: pool.clone(),
+                config: lti_config,
+                http: reqwest::Client::new(),
+            }));
+        }
+        None => println!("LTI_PRIVATE_KEY_PATH not set, LTI launches are disabled"),
+    }
+    let app = app.layer(cors);
 
     let addr = SocketAddr::from(([127, 0, 0, 1], 4000));
     println!("Listening on {}", addr);
@@ -0,0 +1,433 @@
+use chrono::Utc;
+use serde::{Serialize, de::DeserializeOwned};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use crate::bundle::{BUNDLE_FORMAT_VERSION, Bundle, content_type};
+use crate::models::bundle::{BundleLesson, BundleManifest, BundleMedia, BundleModule};
+use crate::models::markdown_course::{CourseFrontMatter, LessonFrontMatter, ModuleFrontMatter};
+
+pub const COURSE_FILE: &str = "course.md";
+pub const MODULE_FILE: &str = "module.md";
+const MEDIA_DIR: &str = "media/";
+
+/// Reads a course authored as Markdown files:
+///
+/// ```text
+/// course.md                  title and final exam, the body is the description
+/// 01-basics/module.md        optional title, position and quiz of the module
+/// 01-basics/01-welcome.md    a lesson, the body is its content
+/// media/diagram.png          linked from lessons as ../media/diagram.png
+/// ```
+///
+/// Modules and lessons are ordered by their name unless their front matter
+/// sets a position. Everything may sit in a single root folder.
+pub fn read_markdown_course(files: BTreeMap<String, Vec<u8>>) -> Result<Bundle, String> {
+    let mut files = strip_root_folder(
+        files
+            .into_iter()
+            .map(|(path, data)| (normalize_path(&path), data))
+            .filter(|(path, _)| !path.split('/').any(|part| part.starts_with('.')))
+            .collect(),
+    );
+
+    let course = files
+        .remove(COURSE_FILE)
+        .ok_or_else(|| format!("{} is missing", COURSE_FILE))?;
+    let (course, description): (CourseFrontMatter, String) = parse_markdown(COURSE_FILE, &course)?
+        .ok_or_else(|| format!("{} has no front matter", COURSE_FILE))?;
+
+    let mut bundle_files = BTreeMap::new();
+    let mut media = Vec::new();
+    let mut module_files: BTreeMap<&str, Vec<(&str, &str)>> = BTreeMap::new();
+    for (path, data) in &files {
+        if path.starts_with(MEDIA_DIR) {
+            media.push(BundleMedia {
+                path: path.clone(),
+                content_type: content_type(path).to_string(),
+                sha256: hex::encode(Sha256::digest(data)),
+            });
+            bundle_files.insert(path.clone(), data.clone());
+        } else if let Some((folder, name)) = path.split_once('/') {
+            // Anything besides the lessons, like notes or drafts, is left out
+            if name.ends_with(".md") && !name.contains('/') {
+                module_files.entry(folder).or_default().push((name, path));
+            }
+        }
+    }
+
+    let mut folders: Vec<_> = module_files.into_iter().collect();
+    folders.sort_by_key(|(folder, _)| order_key(folder));
+
+    let mut modules = Vec::new();
+    for (index, (folder, mut names)) in folders.into_iter().enumerate() {
+        let module = match names.iter().position(|(name, _)| *name == MODULE_FILE) {
+            Some(i) => {
+                let (_, path) = names.remove(i);
+                parse_markdown::<ModuleFrontMatter>(path, &files[path])?
+                    .map(|(front, _)| front)
+                    .unwrap_or_default()
+            }
+            None => ModuleFrontMatter::default(),
+        };
+
+        names.sort_by_key(|(name, _)| order_key(name));
+        let mut lessons = Vec::new();
+        for (index, (name, path)) in names.into_iter().enumerate() {
+            let text =
+                std::str::from_utf8(&files[path]).map_err(|_| format!("{} is not UTF-8", path))?;
+            let (lesson, content) = parse_markdown::<LessonFrontMatter>(path, &files[path])?
+                .unwrap_or_else(|| (LessonFrontMatter::default(), text.to_string()));
+
+            bundle_files.insert(path.to_string(), from_lesson_links(&content).into_bytes());
+            lessons.push(BundleLesson {
+                source_id: lesson.source_id,
+                title: lesson
+                    .title
+                    .unwrap_or_else(|| humanize(name.trim_end_matches(".md"))),
+                position: lesson.position.unwrap_or(index as i32 + 1),
+                content_file: path.to_string(),
+                video_url: lesson.video_url.as_deref().map(from_lesson_links),
+                scorm_package_id: lesson.scorm_package_id,
+                min_watched_percent: lesson.min_watched_percent,
+                video_duration_seconds: lesson.video_duration_seconds,
+            });
+        }
+
+        modules.push(BundleModule {
+            source_id: module.source_id,
+            title: module.title.unwrap_or_else(|| humanize(folder)),
+            position: module.position.unwrap_or(index as i32 + 1),
+            lessons,
+            quiz: module.quiz,
+        });
+    }
+
+    let manifest = BundleManifest {
+        format_version: BUNDLE_FORMAT_VERSION,
+        exported_at: Utc::now(),
+        source_course_id: course.source_id,
+        title: course.title,
+        description,
+        modules,
+        final_exam: course.final_exam,
+        media,
+    };
+
+    Ok(Bundle {
+        manifest,
+        files: bundle_files,
+    })
+}
+
+/// Writes a bundle in the layout `read_markdown_course` reads, with folders
+/// and files numbered in the order of their position.
+pub fn write_markdown_course(bundle: Bundle) -> Result<BTreeMap<String, Vec<u8>>, String> {
+    let Bundle {
+        manifest,
+        files: bundle_files,
+    } = bundle;
+    let mut files = BTreeMap::new();
+
+    let course = CourseFrontMatter {
+        title: manifest.title,
+        source_id: manifest.source_course_id,
+        final_exam: manifest.final_exam,
+    };
+    files.insert(
+        COURSE_FILE.to_string(),
+        write_markdown(&course, &manifest.description)?,
+    );
+
+    // Lessons are one folder down from the media
+    let media_paths: Vec<&str> = manifest.media.iter().map(|m| m.path.as_str()).collect();
+    let to_lesson_links = |text: &str| {
+        media_paths.iter().fold(text.to_string(), |text, path| {
+            text.replace(path, &format!("../{}", path))
+        })
+    };
+
+    let mut modules = manifest.modules;
+    modules.sort_by_key(|m| m.position);
+    for (index, module) in modules.into_iter().enumerate() {
+        let folder = format!("{:02}-{}", index + 1, slug(&module.title));
+        let mut lessons = module.lessons;
+        lessons.sort_by_key(|l| l.position);
+        let front = ModuleFrontMatter {
+            title: Some(module.title),
+            position: unless_in_order(module.position, index),
+            source_id: module.source_id,
+            quiz: module.quiz,
+        };
+        files.insert(
+            format!("{}/{}", folder, MODULE_FILE),
+            write_markdown(&front, "")?,
+        );
+
+        for (index, lesson) in lessons.into_iter().enumerate() {
+            let content = bundle_files
+                .get(&lesson.content_file)
+                .ok_or_else(|| format!("Lesson content file {} is missing", lesson.content_file))?;
+            let content = std::str::from_utf8(content)
+                .map_err(|_| format!("Lesson content file {} is not UTF-8", lesson.content_file))?;
+            let front = LessonFrontMatter {
+                title: Some(lesson.title.clone()),
+                position: unless_in_order(lesson.position, index),
+                source_id: lesson.source_id,
+                video_url: lesson.video_url.as_deref().map(to_lesson_links),
+                scorm_package_id: lesson.scorm_package_id,
+                min_watched_percent: lesson.min_watched_percent,
+                video_duration_seconds: lesson.video_duration_seconds,
+            };
+            files.insert(
+                format!("{}/{:02}-{}.md", folder, index + 1, slug(&lesson.title)),
+                write_markdown(&front, &to_lesson_links(content))?,
+            );
+        }
+    }
+
+    for media in &manifest.media {
+        let data = bundle_files
+            .get(&media.path)
+            .ok_or_else(|| format!("Media file {} is missing", media.path))?;
+        files.insert(media.path.clone(), data.clone());
+    }
+
+    Ok(files)
+}
+
+/// Front matter and body of a Markdown file, `None` without front matter.
+fn parse_markdown<T: DeserializeOwned>(
+    path: &str,
+    data: &[u8],
+) -> Result<Option<(T, String)>, String> {
+    let text = std::str::from_utf8(data).map_err(|_| format!("{} is not UTF-8", path))?;
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let Some(rest) = text
+        .strip_prefix("---\n")
+        .or_else(|| text.strip_prefix("---\r\n"))
+    else {
+        return Ok(None);
+    };
+
+    let mut yaml_len = 0;
+    let mut body = None;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            body = Some(&rest[yaml_len + line.len()..]);
+            break;
+        }
+        yaml_len += line.len();
+    }
+    let body = body.ok_or_else(|| format!("{} has no end to its front matter", path))?;
+
+    let front = serde_yaml_ng::from_str(&rest[..yaml_len])
+        .map_err(|e| format!("{} has invalid front matter: {}", path, e))?;
+    Ok(Some((front, body.trim().to_string())))
+}
+
+fn write_markdown<T: Serialize>(front: &T, body: &str) -> Result<Vec<u8>, String> {
+    // Unset fields are left out rather than written as null
+    let mut value = serde_yaml_ng::to_value(front).map_err(|e| e.to_string())?;
+    strip_nulls(&mut value);
+    let yaml = serde_yaml_ng::to_string(&value).map_err(|e| e.to_string())?;
+
+    let body = body.trim();
+    let text = if body.is_empty() {
+        format!("---\n{}---\n", yaml)
+    } else {
+        format!("---\n{}---\n\n{}\n", yaml, body)
+    };
+    Ok(text.into_bytes())
+}
+
+fn strip_nulls(value: &mut serde_yaml_ng::Value) {
+    match value {
+        serde_yaml_ng::Value::Mapping(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(strip_nulls);
+        }
+        serde_yaml_ng::Value::Sequence(items) => items.iter_mut().for_each(strip_nulls),
+        _ => {}
+    }
+}
+
+fn normalize_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    path.trim_start_matches("./")
+        .trim_start_matches('/')
+        .to_string()
+}
+
+/// Drops the folder every file is in, as when a course folder is zipped.
+fn strip_root_folder(files: BTreeMap<String, Vec<u8>>) -> BTreeMap<String, Vec<u8>> {
+    if files.contains_key(COURSE_FILE) {
+        return files;
+    }
+    let Some(root) = files
+        .keys()
+        .find_map(|path| path.strip_suffix(&format!("/{}", COURSE_FILE)))
+        .filter(|root| !root.contains('/'))
+        .map(|root| format!("{}/", root))
+    else {
+        return files;
+    };
+    if !files.keys().all(|path| path.starts_with(&root)) {
+        return files;
+    }
+
+    files
+        .into_iter()
+        .map(|(path, data)| (path[root.len()..].to_string(), data))
+        .collect()
+}
+
+/// Media links are relative to the lesson file in the folder, and to the
+/// course in the bundle.
+fn from_lesson_links(text: &str) -> String {
+    text.replace(&format!("../{}", MEDIA_DIR), MEDIA_DIR)
+}
+
+// Numbered names in the order of their number, so 10 comes after 9
+fn order_key(name: &str) -> (u64, String) {
+    let digits: String = name.chars().take_while(char::is_ascii_digit).collect();
+    (digits.parse().unwrap_or(u64::MAX), name.to_string())
+}
+
+fn unless_in_order(position: i32, index: usize) -> Option<i32> {
+    (position != index as i32 + 1).then_some(position)
+}
+
+/// Title from a file or folder name, `02-getting_started` → `Getting started`.
+fn humanize(name: &str) -> String {
+    let name =
+        name.trim_start_matches(|c: char| c.is_ascii_digit() || matches!(c, '-' | '_' | '.' | ' '));
+    let name = name.replace(['-', '_'], " ");
+    let mut chars = name.trim().chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => "Untitled".to_string(),
+    }
+}
+
+fn slug(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.len() >= 40 {
+            break;
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "untitled".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle::{read_zip, write_zip};
+    use crate::test_support::sample_bundle;
+
+    fn zipped(files: &BTreeMap<String, Vec<u8>>) -> Vec<u8> {
+        write_zip(files.iter().map(|(p, d)| (p.as_str(), d.as_slice()))).unwrap()
+    }
+
+    // Lessons are stored under their Markdown path and media ordered by path
+    fn comparable(manifest: &BundleManifest) -> serde_json::Value {
+        let mut manifest = serde_json::to_value(manifest).unwrap();
+        manifest["exportedAt"] = serde_json::Value::Null;
+        for module in manifest["modules"].as_array_mut().unwrap() {
+            for lesson in module["lessons"].as_array_mut().unwrap() {
+                lesson["contentFile"] = serde_json::Value::Null;
+            }
+        }
+        let media = manifest["media"].as_array_mut().unwrap();
+        media.sort_by_key(|m| m["path"].as_str().unwrap_or_default().to_string());
+        manifest
+    }
+
+    #[test]
+    fn export_then_import_keeps_the_course() {
+        let bundle = sample_bundle();
+        let expected = comparable(&bundle.manifest);
+        let lesson_2 = bundle.files["lessons/2.md"].clone();
+        let diagram = bundle.manifest.media[0].path.clone();
+
+        let files = write_markdown_course(bundle).unwrap();
+        let lesson_file = "01-getting-started/02-installing.md";
+        let lesson = std::str::from_utf8(&files[lesson_file]).unwrap();
+        assert!(lesson.contains(&format!("](../{})", diagram)), "{}", lesson);
+
+        let imported = read_markdown_course(read_zip(&zipped(&files)).unwrap()).unwrap();
+        assert_eq!(comparable(&imported.manifest), expected);
+
+        assert_eq!(imported.files[lesson_file], lesson_2);
+        assert_eq!(imported.files[&diagram], files[&diagram]);
+    }
+
+    #[test]
+    fn course_in_a_root_folder_is_read() {
+        let files: BTreeMap<String, Vec<u8>> = write_markdown_course(sample_bundle())
+            .unwrap()
+            .into_iter()
+            .map(|(path, data)| (format!("rust-basics/{}", path), data))
+            .chain([("rust-basics/.git/HEAD".to_string(), b"ref".to_vec())])
+            .collect();
+
+        let imported = read_markdown_course(files).unwrap();
+        assert_eq!(imported.manifest.title, "Rust basics");
+        assert_eq!(imported.manifest.modules.len(), 2);
+    }
+
+    #[test]
+    fn malformed_folders_are_rejected() {
+        let course = "---\ntitle: Rust basics\n---\n\nOwnership".as_bytes();
+        for (files, error) in [
+            (
+                vec![("01-basics/01-welcome.md", "Hello".as_bytes())],
+                "course.md is missing",
+            ),
+            (
+                vec![(COURSE_FILE, "# No front matter".as_bytes())],
+                "course.md has no front matter",
+            ),
+            (
+                vec![(COURSE_FILE, "---\ntitle: Rust basics\n".as_bytes())],
+                "course.md has no end to its front matter",
+            ),
+            (
+                vec![(COURSE_FILE, "---\ntitle: [unclosed\n---\n".as_bytes())],
+                "course.md has invalid front matter",
+            ),
+            (
+                vec![
+                    (COURSE_FILE, course),
+                    ("01-basics/01-welcome.md", b"\xff\xfe"),
+                ],
+                "01-basics/01-welcome.md is not UTF-8",
+            ),
+            (
+                vec![
+                    (COURSE_FILE, course),
+                    ("../01-basics/01-welcome.md", b"Hello"),
+                ],
+                "Zip entry ../01-basics/01-welcome.md has an unsafe path",
+            ),
+        ] {
+            let e = write_zip(files.into_iter())
+                .and_then(|bytes| read_zip(&bytes))
+                .and_then(read_markdown_course)
+                .err()
+                .unwrap();
+            assert!(e.starts_with(error), "{}", e);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+
+use crate::models::course::QuestionType;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuizAnalyticsQuery {
+    pub quiz_id: i64,
+    pub creator_id: String,
+    pub requested_at: i64,
+    // Creator wallet signature of the creator request message for the quiz's course
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuizAnalytics {
+    pub quiz_id: i64,
+    pub course_id: i64,
+    pub attempts: i64,
+    pub learners: i64,
+    pub average_score_percent: Option<f64>,
+    pub pass_rate: Option<f64>,
+    pub average_duration_seconds: Option<f64>,
+    pub questions: Vec<QuestionAnalytics>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuestionAnalytics {
+    pub question_id: i64,
+    pub question_text: String,
+    pub question_type: QuestionType,
+    pub pool: Option<String>,
+    // Attempts the question was served in
+    pub exposures: i64,
+    pub responses: i64,
+    // Average credit, the share of the points learners earn. Low values are hard questions
+    pub p_value: Option<f64>,
+    // Upper minus lower 27% group average credit, by attempt score
+    pub discrimination_index: Option<f64>,
+    pub average_time_seconds: Option<f64>,
+    pub answer_options: Vec<AnswerOptionAnalytics>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnswerOptionAnalytics {
+    pub answer_id: i64,
+    pub answer_text: String,
+    pub is_correct: bool,
+    pub selections: i64,
+    // Share of exposures the option was selected in
+    pub selection_rate: Option<f64>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AnalyzedAttemptRow {
+    pub learner_id: String,
+    pub score_percent: f64,
+    pub passed: bool,
+    pub question_ids: Vec<i64>,
+    pub answers: Option<Value>,
+    pub started_at: DateTime<Utc>,
+    pub submitted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CourseAnalyticsQuery {
+    pub course_id: i64,
+    pub creator_id: String,
+    pub requested_at: i64,
+    pub signature: String,
+    // Length of the daily series ending today, 30 by default
+    pub days: Option<i64>,
+    // Learners without a lesson completion for this long have dropped off, 14 by default
+    pub inactive_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CourseAnalytics {
+    pub course_id: i64,
+    pub enrolled: i64,
+    pub completed: i64,
+    pub completion_rate: Option<f64>,
+    // From enrollment to course completion
+    pub median_completion_seconds: Option<f64>,
+    // Active time on the lessons, from lesson heartbeats
+    pub total_time_spent_seconds: i64,
+    // Among learners with any active time
+    pub median_time_spent_seconds: Option<f64>,
+    // Inactive learners who never completed a lesson
+    pub not_started: i64,
+    pub modules: Vec<ModuleFunnelStep>,
+    pub lessons: Vec<LessonDropOff>,
+    pub quizzes: Vec<QuizPassRate>,
+    pub daily: Vec<DailyActivity>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct CourseSummaryRow {
+    pub enrolled: i64,
+    pub completed: i64,
+    pub median_completion_seconds: Option<f64>,
+    pub not_started: i64,
+}
+
+#[derive(Debug, FromRow)]
+pub struct CourseTimeRow {
+    pub total_time_spent_seconds: i64,
+    pub median_time_spent_seconds: Option<f64>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleFunnelStep {
+    pub module_id: i64,
+    pub title: String,
+    pub position: i32,
+    pub completed: i64,
+    // Share of enrolled learners who completed the module
+    pub completion_rate: Option<f64>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct LessonDropOff {
+    pub lesson_id: i64,
+    pub module_id: i64,
+    pub title: String,
+    pub completed: i64,
+    // Inactive learners, without the course completed, whose last completed lesson is this one
+    pub dropped_off: i64,
+    // Among learners with any active time on the lesson
+    pub average_time_seconds: Option<f64>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct QuizPassRate {
+    pub quiz_id: i64,
+    // None for the final exam
+    pub module_id: Option<i64>,
+    pub attempts: i64,
+    pub attempts_passed: i64,
+    pub learners: i64,
+    pub learners_passed: i64,
+    // Share of learners who attempted the quiz and passed it at least once
+    pub pass_rate: Option<f64>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyActivity {
+    pub date: NaiveDate,
+    pub enrollments: i64,
+    pub lesson_completions: i64,
+    pub completions: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CohortRetentionQuery {
+    pub course_id: i64,
+    pub creator_id: String,
+    pub requested_at: i64,
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CohortRetention {
+    // None for the platform-wide retention
+    pub course_id: Option<i64>,
+    // When the cohorts were last computed, None before the first enrollment
+    pub refreshed_at: Option<DateTime<Utc>>,
+    pub cohorts: Vec<Cohort>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cohort {
+    // Monday of the enrollment week
+    pub cohort_week: NaiveDate,
+    pub learners: i64,
+    pub weeks: Vec<CohortWeek>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CohortWeek {
+    // Weeks since enrollment, only those that have elapsed for the cohort
+    pub week: i32,
+    pub learners: i64,
+    // Learners with activity at least `week` weeks after enrolling
+    pub active: i64,
+    pub completed: i64,
+    pub active_rate: Option<f64>,
+    pub completion_rate: Option<f64>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct CohortRetentionRow {
+    pub cohort_week: NaiveDate,
+    pub week: i32,
+    pub learners: i64,
+    pub active: i64,
+    pub completed: i64,
+    pub refreshed_at: DateTime<Utc>,
+}
@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotQuery {
+    pub snapshot_id: i64,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationSnapshot {
+    pub id: i64,
+    pub endpoint: String,
+    pub request_params: Value,
+    pub status_code: i32,
+    pub response_body: String,
+    pub response_hash: String,
+    pub signature: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationSnapshotResponse {
+    #[serde(flatten)]
+    pub snapshot: AttestationSnapshot,
+    pub signed_message: String,
+    pub public_key: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicKeyResponse {
+    pub public_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationNonceRequest {
+    pub kind: String,
+    pub course_id: i64,
+    // Learner for enrollment and learner milestones, course creator otherwise
+    pub wallet: String,
+    pub milestone: Option<i32>,
+    pub requested_at: i64,
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationNonceResponse {
+    pub nonce: String,
+    pub kind: String,
+    pub course_id: i64,
+    pub learner_id: Option<String>,
+    pub milestone: Option<i32>,
+    pub expires_at: i64,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AttestationNonceRow {
+    pub nonce: String,
+    pub learner_id: Option<String>,
+    pub milestone: Option<i32>,
+    pub expires_at: DateTime<Utc>,
+}
@@ -0,0 +1,55 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAttestationJobRequest {
+    pub kind: String,
+    pub course_id: i64,
+    pub learner_id: Option<String>,
+    pub nonce: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationJobQuery {
+    pub job_id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationJobsQuery {
+    pub course_id: i64,
+    pub learner_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationJob {
+    pub id: i64,
+    pub kind: String,
+    pub course_id: i64,
+    pub learner_id: Option<String>,
+    pub milestone: Option<i32>,
+    pub status: String,
+    pub round_id: Option<i64>,
+    pub proof: Option<Value>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AttestationJobRow {
+    pub id: i64,
+    pub kind: String,
+    pub query_params: String,
+    pub status: String,
+    pub abi_encoded_request: Option<String>,
+    pub tx_hash: Option<String>,
+    pub round_id: Option<i64>,
+    pub attempts: i32,
+}
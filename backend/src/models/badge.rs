@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+
+#[derive(Debug, Deserialize)]
+pub struct CourseAchievementPath {
+    pub course_id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatorMilestoneAchievementPath {
+    pub course_id: i64,
+    pub milestone: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BadgeAssertionPath {
+    pub assertion_id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BadgesQuery {
+    pub recipient_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyBadgeRequest {
+    pub jwt: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyBadgeResponse {
+    pub valid: bool,
+    pub credential: Option<Value>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct BadgeAssertionRow {
+    pub id: i64,
+    pub kind: String,
+    pub course_id: i64,
+    pub recipient_id: String,
+    pub milestone: i32,
+    pub jwt: String,
+    pub issued_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BadgeAssertionResponse {
+    pub id: String,
+    pub kind: String,
+    pub course_id: i64,
+    pub recipient_id: String,
+    pub milestone: i32,
+    pub jwt: String,
+    pub credential: Value,
+    pub issued_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct BadgeCourseRow {
+    pub title: String,
+    pub description: String,
+    pub creator_id: String,
+    pub module_count: i64,
+    pub lesson_count: i64,
+    pub module_quiz_count: i64,
+    // Passing score of the final exam, if the course has one
+    pub final_exam_passing_score: Option<f64>,
+}
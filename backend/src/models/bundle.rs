@@ -0,0 +1,124 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::collections::BTreeMap;
+
+use crate::models::course::CreateQuizPayload;
+
+/// `course.json` of a course bundle. Lesson content and media are separate
+/// files of the bundle, referenced by their path.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleManifest {
+    pub format_version: u32,
+    pub exported_at: DateTime<Utc>,
+    // Id of the course in the environment it was exported from
+    pub source_course_id: Option<i64>,
+    pub title: String,
+    pub description: String,
+    pub modules: Vec<BundleModule>,
+    pub final_exam: Option<CreateQuizPayload>,
+    #[serde(default)]
+    pub media: Vec<BundleMedia>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleModule {
+    pub source_id: Option<i64>,
+    pub title: String,
+    pub position: i32,
+    pub lessons: Vec<BundleLesson>,
+    pub quiz: Option<CreateQuizPayload>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleLesson {
+    pub source_id: Option<i64>,
+    pub title: String,
+    pub position: i32,
+    // Path of the Markdown content file in the bundle
+    pub content_file: String,
+    pub video_url: Option<String>,
+    // SCORM packages stay in the environment they were uploaded to
+    #[serde(default)]
+    pub scorm_package_id: Option<i64>,
+    #[serde(default)]
+    pub min_watched_percent: Option<f64>,
+    #[serde(default)]
+    pub video_duration_seconds: Option<f64>,
+}
+
+/// A media file of the bundle, lesson content and video URLs refer to it by
+/// its path.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleMedia {
+    pub path: String,
+    pub content_type: String,
+    pub sha256: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportCourseQuery {
+    pub course_id: i64,
+    pub creator_id: String,
+    pub requested_at: i64,
+    // Creator wallet signature of the creator request message for the course
+    pub signature: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportCourseQuery {
+    // Creator of the imported course
+    pub creator_id: String,
+    pub requested_at: i64,
+    // Creator wallet signature of the creator request message, without a course
+    pub signature: String,
+    // Only validate the bundle
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedCourse {
+    // Not set on a dry run
+    pub course_id: Option<i64>,
+    pub title: String,
+    pub modules: usize,
+    pub lessons: usize,
+    pub questions: usize,
+    pub media: usize,
+    // Source ids in the bundle to the ids of the imported course
+    pub module_ids: BTreeMap<i64, i64>,
+    pub lesson_ids: BTreeMap<i64, i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MediaPath {
+    pub sha256: String,
+}
+
+#[derive(Debug, FromRow)]
+pub struct MediaRow {
+    pub sha256: String,
+    pub file_name: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct BundleQuizRow {
+    pub id: i64,
+    // Not set for the final exam
+    pub module_id: Option<i64>,
+    pub passing_score_percent: f64,
+    pub max_attempts: Option<i32>,
+    pub cooldown_seconds: i32,
+    pub scoring_policy: String,
+    pub shuffle_answers: bool,
+}
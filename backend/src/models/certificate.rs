@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateQuery {
+    pub learner_id: String,
+    pub course_id: i64,
+}
+
+#[derive(Debug, FromRow)]
+pub struct CertificateRow {
+    pub course_title: String,
+    pub creator_id: String,
+    pub display_name: Option<String>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub nft_token_id: Option<i64>,
+    pub nft_contract_address: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateVerification {
+    pub learner_id: String,
+    pub display_name: Option<String>,
+    pub course_id: i64,
+    pub course_title: String,
+    pub creator_id: String,
+    pub completed_at: DateTime<Utc>,
+    pub nft_token_id: Option<i64>,
+    pub credential_id: Option<i64>,
+    // Hex sha256 of the certificate PDF, to check a downloaded copy
+    pub certificate_hash: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetDisplayNameRequest {
+    pub learner_id: String,
+    pub display_name: Option<String>,
+    pub requested_at: i64,
+    // Learner wallet signature of the display name message
+    pub signature: String,
+}
@@ -0,0 +1,13 @@
+use serde::Serialize;
+
+use crate::models::quiz_format::FormatIssue;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedCartridge {
+    pub course_id: i64,
+    pub file_name: String,
+    // Base64 .imscc package
+    pub content: String,
+    pub issues: Vec<FormatIssue>,
+}
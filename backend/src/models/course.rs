@@ -8,6 +8,16 @@ pub struct CreateCoursePayload {
     pub description: String,
     pub creator_id: String,
     pub modules: Vec<CreateModulePayload>,
+    // Course-level exam, to be passed after all modules
+    pub final_exam: Option<CreateQuizPayload>,
+}
+
+/// Ids of a created course, modules and lessons in payload order.
+#[derive(Debug)]
+pub struct InsertedCourse {
+    pub course_id: i64,
+    pub module_ids: Vec<i64>,
+    pub lesson_ids: Vec<Vec<i64>>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -26,26 +36,146 @@ pub struct CreateLessonPayload {
     pub content: String,
     pub video_url: Option<String>,
     pub position: i32,
+    // Uploaded SCORM package the lesson launches, completed by the package
+    pub scorm_package_id: Option<i64>,
+    // Share of the video to watch before the lesson can be completed
+    pub min_watched_percent: Option<f64>,
+    // Length of the video file, progress reports must match it
+    pub video_duration_seconds: Option<f64>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateQuizPayload {
     pub questions: Vec<CreateQuestionPayload>,
+    #[serde(default = "default_passing_score_percent")]
+    pub passing_score_percent: f64,
+    // Unlimited when not set
+    pub max_attempts: Option<i32>,
+    #[serde(default)]
+    pub cooldown_seconds: i32,
+    #[serde(default)]
+    pub scoring_policy: ScoringPolicy,
+    // Questions drawn per attempt. Every question is served when empty
+    #[serde(default)]
+    pub draw_rules: Vec<CreateDrawRulePayload>,
+    #[serde(default = "default_shuffle_answers")]
+    pub shuffle_answers: bool,
+}
+
+fn default_passing_score_percent() -> f64 {
+    70.0
+}
+
+fn default_shuffle_answers() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateDrawRulePayload {
+    // Questions without a pool are drawn by a rule without a pool
+    pub pool: Option<String>,
+    pub count: i32,
+}
+
+/// Which attempt counts for a learner's quiz result.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoringPolicy {
+    #[default]
+    Best,
+    Latest,
+}
+
+impl ScoringPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScoringPolicy::Best => "best",
+            ScoringPolicy::Latest => "latest",
+        }
+    }
+
+    pub fn parse(scoring_policy: &str) -> Option<ScoringPolicy> {
+        match scoring_policy {
+            "best" => Some(ScoringPolicy::Best),
+            "latest" => Some(ScoringPolicy::Latest),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateQuestionPayload {
     pub question_text: String,
+    #[serde(default)]
+    pub question_type: QuestionType,
+    #[serde(default = "default_points")]
+    pub points: f64,
+    // Answers in display order. For ordering questions this is the correct order
+    #[serde(default)]
     pub answers: Vec<CreateAnswerOptionPayload>,
+    pub numeric_answer: Option<f64>,
+    pub numeric_tolerance: Option<f64>,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    // Question bank pool the question is drawn from
+    pub pool: Option<String>,
+}
+
+fn default_points() -> f64 {
+    1.0
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateAnswerOptionPayload {
     pub answer_text: String,
+    #[serde(default)]
     pub is_correct: bool,
+    // Right-hand side of the pair for matching questions
+    pub match_text: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestionType {
+    #[default]
+    SingleChoice,
+    MultipleChoice,
+    TrueFalse,
+    Ordering,
+    Matching,
+    Numeric,
+    ShortText,
+}
+
+impl QuestionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuestionType::SingleChoice => "single_choice",
+            QuestionType::MultipleChoice => "multiple_choice",
+            QuestionType::TrueFalse => "true_false",
+            QuestionType::Ordering => "ordering",
+            QuestionType::Matching => "matching",
+            QuestionType::Numeric => "numeric",
+            QuestionType::ShortText => "short_text",
+        }
+    }
+
+    pub fn parse(question_type: &str) -> Option<QuestionType> {
+        match question_type {
+            "single_choice" => Some(QuestionType::SingleChoice),
+            "multiple_choice" => Some(QuestionType::MultipleChoice),
+            "true_false" => Some(QuestionType::TrueFalse),
+            "ordering" => Some(QuestionType::Ordering),
+            "matching" => Some(QuestionType::Matching),
+            "numeric" => Some(QuestionType::Numeric),
+            "short_text" => Some(QuestionType::ShortText),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, FromRow)]
@@ -56,8 +186,19 @@ pub struct Course {
     pub description: String,
     pub creator_id: String,
     pub modules: Vec<Module>,
+    pub final_exam: Option<Quiz>,
     pub num_learners: i32,
     pub num_completed: i32,
+    // Video the learner watched last, when the course is viewed with a learner id
+    pub resume: Option<ResumePosition>,
+}
+
+#[derive(Debug, Deserialize, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ResumePosition {
+    pub lesson_id: i64,
+    pub module_id: i64,
+    pub position_seconds: f64,
 }
 
 #[derive(Deserialize, Debug)]
@@ -71,12 +212,15 @@ pub struct JoinCourseRequest {
 #[serde(rename_all = "camelCase")]
 pub struct CourseQuery {
     pub course_id: i64,
+    // Only read by /get-course, for the learner's resume position
+    pub learner_id: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct CreatorQuery {
-    pub creator_id: String,
+pub struct AttestedCourseQuery {
+    pub course_id: i64,
+    pub nonce: String,
 }
 
 #[derive(Debug, Deserialize, Serialize, FromRow)]
@@ -84,6 +228,8 @@ pub struct CreatorQuery {
 pub struct NumCompletedResponse {
     pub course_id: i64,
     pub num_completed: i64,
+    pub nonce: String,
+    pub expires_at: i64,
 }
 
 #[derive(Debug, Deserialize, Serialize, FromRow)]
@@ -91,6 +237,8 @@ pub struct NumCompletedResponse {
 pub struct CourseCreatorResponse {
     pub course_id: i64,
     pub creator_id: String,
+    pub nonce: String,
+    pub expires_at: i64,
 }
 
 #[derive(Debug, Deserialize, Serialize, FromRow)]
@@ -113,32 +261,48 @@ pub struct Lesson {
     pub content: String,
     pub video_url: Option<String>,
     pub position: i32,
+    pub scorm_package_id: Option<i64>,
+    pub min_watched_percent: Option<f64>,
+    pub video_duration_seconds: Option<f64>,
 }
 
 #[derive(Debug, Deserialize, Serialize, FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct Quiz {
     pub id: i64,
-    pub module_id: i64,
-    pub questions: Vec<Question>,
+    // Set for module quizzes
+    pub module_id: Option<i64>,
+    // Set for course final exams
+    pub course_id: Option<i64>,
+    pub passing_score_percent: f64,
+    pub max_attempts: Option<i32>,
+    pub cooldown_seconds: i32,
+    pub scoring_policy: ScoringPolicy,
+    // Questions served per attempt, drawn from the question bank
+    pub question_count: i64,
 }
 
-#[derive(Debug, Deserialize, Serialize, FromRow)]
-#[serde(rename_all = "camelCase")]
+// Server-side only, the answer key never leaves the backend
+#[derive(Debug, FromRow)]
 pub struct Question {
     pub id: i64,
-    pub quiz_id: i64,
     pub question_text: String,
+    pub question_type: QuestionType,
+    pub points: f64,
     pub answers: Vec<AnswerOption>,
+    pub numeric_answer: Option<f64>,
+    pub numeric_tolerance: f64,
+    pub case_sensitive: bool,
+    pub pool: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, FromRow)]
-#[serde(rename_all = "camelCase")]
+#[derive(Debug, FromRow)]
 pub struct AnswerOption {
     pub id: i64,
-    pub question_id: i64,
     pub answer_text: String,
     pub is_correct: bool,
+    pub position: i32,
+    pub match_text: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -219,12 +383,21 @@ pub struct LessonRow {
     pub content: String,
     pub video_url: Option<String>,
     pub position: i32,
+    pub scorm_package_id: Option<i64>,
+    pub min_watched_percent: Option<f64>,
+    pub video_duration_seconds: Option<f64>,
 }
 
 #[derive(Debug, FromRow)]
 pub struct QuizRow {
     pub id: i64,
-    pub module_id: i64,
+    pub module_id: Option<i64>,
+    pub course_id: Option<i64>,
+    pub passing_score_percent: f64,
+    pub max_attempts: Option<i32>,
+    pub cooldown_seconds: i32,
+    pub scoring_policy: String,
+    pub question_count: i64,
 }
 
 #[derive(Debug, FromRow)]
@@ -232,6 +405,18 @@ pub struct QuestionRow {
     pub id: i64,
     pub quiz_id: i64,
     pub question_text: String,
+    pub question_type: String,
+    pub points: f64,
+    pub numeric_answer: Option<f64>,
+    pub numeric_tolerance: f64,
+    pub case_sensitive: bool,
+    pub pool: Option<String>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct DrawRuleRow {
+    pub pool: Option<String>,
+    pub draw_count: i32,
 }
 
 #[derive(Debug, FromRow)]
@@ -240,11 +425,6 @@ pub struct AnswerOptionRow {
     pub question_id: i64,
     pub answer_text: String,
     pub is_correct: bool,
-}
-
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct CountsResponse {
-    pub num_learners: i64,
-    pub num_courses: i64,
+    pub position: i32,
+    pub match_text: Option<String>,
 }
@@ -0,0 +1,71 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialQuery {
+    pub learner_id: String,
+    pub course_id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CredentialPath {
+    pub credential_id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatusListPath {
+    pub list_id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyCredentialRequest {
+    pub jwt: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyCredentialResponse {
+    pub valid: bool,
+    pub revoked: bool,
+    pub issuer: String,
+    pub credential: Option<Value>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevokeCredentialRequest {
+    pub credential_id: i64,
+    pub creator_id: String,
+    pub reason: Option<String>,
+    // Creator wallet signature of the revocation message
+    pub signature: String,
+}
+
+#[derive(Debug, FromRow)]
+pub struct CredentialRow {
+    pub id: i64,
+    pub learner_id: String,
+    pub course_id: i64,
+    pub jwt: String,
+    pub issued_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub revocation_reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialResponse {
+    pub id: i64,
+    pub learner_id: String,
+    pub course_id: i64,
+    pub jwt: String,
+    pub credential: Value,
+    pub issued_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub revocation_reason: Option<String>,
+}
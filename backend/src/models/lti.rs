@@ -0,0 +1,55 @@
+use serde::Deserialize;
+use sqlx::FromRow;
+
+/// OIDC third party login initiation, sent by the platform as query or form.
+#[derive(Debug, Deserialize)]
+pub struct LtiLoginParams {
+    pub iss: String,
+    pub login_hint: String,
+    pub lti_message_hint: Option<String>,
+    pub client_id: Option<String>,
+}
+
+/// Authentication response the platform posts to the launch URL.
+#[derive(Debug, Deserialize)]
+pub struct LtiLaunchForm {
+    pub id_token: String,
+    pub state: String,
+}
+
+/// Course picked on the deep linking page.
+#[derive(Debug, Deserialize)]
+pub struct DeepLinkSelectForm {
+    pub deep_link_id: String,
+    pub course_id: i64,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct LtiPlatformRow {
+    pub id: i64,
+    pub issuer: String,
+    pub client_id: String,
+    pub auth_login_url: String,
+    pub auth_token_url: String,
+    pub jwks_url: String,
+}
+
+#[derive(Debug, FromRow)]
+pub struct DeepLinkRow {
+    pub platform_id: i64,
+    pub deployment_id: String,
+    pub return_url: String,
+    pub data: Option<String>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct LtiGradeSyncRow {
+    pub platform_id: i64,
+    pub lineitem: String,
+    pub sub: String,
+    pub learner_id: String,
+    pub course_id: i64,
+    pub synced_percent: Option<i32>,
+    pub synced_completed: bool,
+    pub attempts: i32,
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::course::CreateQuizPayload;
+
+/// Front matter of `course.md`, its body is the course description.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CourseFrontMatter {
+    pub title: String,
+    pub source_id: Option<i64>,
+    pub final_exam: Option<CreateQuizPayload>,
+}
+
+/// Front matter of the `module.md` of a module folder.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleFrontMatter {
+    // Folder name without its number when not set
+    pub title: Option<String>,
+    // Order of the folder when not set
+    pub position: Option<i32>,
+    pub source_id: Option<i64>,
+    pub quiz: Option<CreateQuizPayload>,
+}
+
+/// Front matter of a lesson file, its body is the lesson content.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LessonFrontMatter {
+    // File name without its number when not set
+    pub title: Option<String>,
+    // Order of the file when not set
+    pub position: Option<i32>,
+    pub source_id: Option<i64>,
+    pub video_url: Option<String>,
+    pub scorm_package_id: Option<i64>,
+    pub min_watched_percent: Option<f64>,
+    pub video_duration_seconds: Option<f64>,
+}
@@ -0,0 +1,66 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterCourseNftsRequest {
+    pub course_id: i64,
+    pub creator_nft_address: String,
+    pub learner_nft_address: String,
+    pub requested_at: i64,
+    // Course creator wallet signature of the course NFTs message
+    pub signature: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterLearnerNftRequest {
+    pub learner_id: String,
+    pub course_id: i64,
+    pub nft_token_id: i64,
+    pub requested_at: i64,
+    // Learner wallet signature of the learner NFT message
+    pub signature: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NftMetadataPath {
+    pub contract: String,
+    pub token_id: i64,
+}
+
+// ERC-721 metadata JSON schema, field names follow the OpenSea convention
+#[derive(Debug, Serialize)]
+pub struct NftMetadata {
+    pub name: String,
+    pub description: String,
+    pub image: String,
+    pub attributes: Vec<NftAttribute>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NftAttribute {
+    pub trait_type: String,
+    pub value: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_type: Option<String>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct LearnerNftRow {
+    pub learner_id: String,
+    pub course_id: i64,
+    pub course_title: String,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct CreatorNftRow {
+    pub course_id: i64,
+    pub course_title: String,
+    pub creator_id: String,
+    pub num_learners: i64,
+    pub num_completed: i64,
+}
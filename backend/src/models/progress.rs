@@ -1,5 +1,9 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlx::{FromRow, types::Json as SqlJson};
+use std::collections::HashMap;
+
+use crate::models::course::{QuestionType, ScoringPolicy};
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -16,6 +20,8 @@ pub struct CourseProgressResponse {
     pub completed_module_ids: Vec<i64>,
     pub progress_percent: f32,
     pub course_completed: bool,
+    // Active time on the course lessons, from viewer heartbeats
+    pub time_spent_seconds: i64,
 }
 
 #[derive(Deserialize, Debug)]
@@ -25,6 +31,75 @@ pub struct LessonCompleteRequest {
     pub lesson_id: i64,
 }
 
+/// Sent by the viewer while a lesson is open and the learner is active.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LessonHeartbeatRequest {
+    pub learner_id: String,
+    pub lesson_id: i64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LessonHeartbeatResponse {
+    pub lesson_id: i64,
+    // Time since the learner's previous heartbeat from any tab, 0 after an idle gap
+    pub credited_seconds: f64,
+    pub time_spent_seconds: i64,
+}
+
+/// Sent by the viewer's video player while playing, and when it pauses or ends.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoProgressRequest {
+    pub learner_id: String,
+    pub lesson_id: i64,
+    pub position_seconds: f64,
+    pub duration_seconds: f64,
+    // Played since the previous report, as [start, end] in seconds
+    #[serde(default)]
+    pub watched: Vec<[f64; 2]>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoProgressQuery {
+    pub learner_id: String,
+    pub lesson_id: i64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoProgress {
+    pub lesson_id: i64,
+    // Where playback continues, the start once the video was played to the end
+    pub resume_position_seconds: f64,
+    pub duration_seconds: f64,
+    // Parts watched, rewatching a part counts once
+    pub watched_seconds: f64,
+    pub watched_percent: Option<f64>,
+    // Needed to complete the lesson, when set
+    pub min_watched_percent: Option<f64>,
+}
+
+/// A lesson's video settings with the learner's playback, when started.
+#[derive(Debug, FromRow)]
+pub struct LessonVideoRow {
+    pub video_url: Option<String>,
+    pub min_watched_percent: Option<f64>,
+    pub position_seconds: Option<f64>,
+    pub duration_seconds: Option<f64>,
+    pub watched_seconds: Option<f64>,
+}
+
+/// The learner's recorded playback of a video, as the next report merges into it.
+#[derive(Debug, FromRow)]
+pub struct VideoProgressRow {
+    pub watched_intervals: SqlJson<Vec<[f64; 2]>>,
+    pub duration_seconds: f64,
+    pub seconds_since_update: f64,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct CompletedLessonsQuery {
@@ -58,8 +133,147 @@ pub struct CourseCompleteRequest {
 pub struct CompleteQuizPayload {
     pub quiz_id: i64,
     pub learner_id: String,
-    pub score: i32,
+    // The quiz instance served by /start-quiz-attempt
+    pub instance_id: i64,
+    pub responses: Vec<QuestionResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartQuizAttemptPayload {
+    pub quiz_id: i64,
+    pub learner_id: String,
+}
+
+/// The questions drawn for one attempt, without the answer key.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuizInstance {
+    pub instance_id: i64,
+    pub quiz_id: i64,
+    pub learner_id: String,
+    pub created_at: DateTime<Utc>,
+    pub questions: Vec<ServedQuestion>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServedQuestion {
+    pub id: i64,
+    pub question_text: String,
+    pub question_type: QuestionType,
+    pub points: f64,
+    pub answers: Vec<ServedAnswer>,
+    // Right-hand sides of matching questions, shuffled
+    pub match_options: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServedAnswer {
+    pub id: i64,
+    pub answer_text: String,
+}
+
+#[derive(Debug, FromRow)]
+pub struct QuizInstanceRow {
+    pub id: i64,
+    pub quiz_id: i64,
+    pub learner_id: String,
+    pub seed: i64,
+    pub question_ids: Vec<i64>,
+    pub created_at: DateTime<Utc>,
+    pub submitted_at: Option<DateTime<Utc>>,
+}
+
+// Only the field matching the question type is read when grading
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuestionResponse {
+    pub question_id: i64,
+    pub selected_answer_ids: Option<Vec<i64>>,
+    pub ordered_answer_ids: Option<Vec<i64>>,
+    // Answer id -> submitted match text
+    pub matches: Option<HashMap<i64, String>>,
+    pub numeric_answer: Option<f64>,
+    pub text_answer: Option<String>,
+    // Reported by the client, for item analytics
+    pub time_spent_seconds: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuestionResult {
+    pub question_id: i64,
+    // Fraction of the points earned, between 0 and 1
+    pub credit: f64,
+    pub points: f64,
+    pub earned: f64,
+    pub correct: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuizGradeResponse {
+    pub quiz_id: i64,
+    pub attempt_id: i64,
+    pub attempt_number: i32,
+    pub score: f64,
+    pub max_score: f64,
+    pub score_percent: f64,
+    pub total_questions: i32,
+    pub passed: bool,
+    pub passing_score_percent: f64,
+    // Whether the attempt that counts under the scoring policy passed
+    pub quiz_passed: bool,
+    pub attempts_remaining: Option<i32>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub results: Vec<QuestionResult>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuizAttemptsQuery {
+    pub quiz_id: i64,
+    pub learner_id: String,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct QuizAttempt {
+    pub id: i64,
+    pub attempt_number: i32,
+    pub score: f64,
+    pub max_score: f64,
+    pub score_percent: f64,
     pub total_questions: i32,
+    pub passed: bool,
+    pub submitted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuizAttemptsResponse {
+    pub quiz_id: i64,
+    pub learner_id: String,
+    pub passing_score_percent: f64,
+    pub max_attempts: Option<i32>,
+    pub cooldown_seconds: i32,
+    pub scoring_policy: ScoringPolicy,
+    pub attempts: Vec<QuizAttempt>,
+    pub result_attempt_id: Option<i64>,
+    pub quiz_passed: bool,
+    pub attempts_remaining: Option<i32>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct QuizSettingsRow {
+    pub passing_score_percent: f64,
+    pub max_attempts: Option<i32>,
+    pub cooldown_seconds: i32,
+    pub scoring_policy: String,
+    pub shuffle_answers: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -72,6 +286,7 @@ pub struct CourseProgressSummary {
     pub completed_module_ids: Vec<i64>,
     pub progress_percent: f32,
     pub course_completed: bool,
+    pub time_spent_seconds: i64,
 }
 
 #[derive(Deserialize)]
@@ -86,6 +301,17 @@ pub struct CourseProgressPercentage {
     pub course_id: i64,
     pub learner_id: String,
     pub progress_percent: u8,
+    pub nonce: String,
+    pub milestone: i32,
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestedProgressQuery {
+    pub learner_id: String,
+    pub course_id: i64,
+    pub nonce: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -95,10 +321,28 @@ pub struct EnrollmentQuery {
     pub learner_id: String,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestedEnrollmentQuery {
+    pub course_id: i64,
+    pub learner_id: String,
+    pub nonce: String,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EnrollmentResponse {
     pub course_id: i64,
     pub learner_id: String,
     pub is_enrolled: bool
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestedEnrollmentResponse {
+    pub course_id: i64,
+    pub learner_id: String,
+    pub is_enrolled: bool,
+    pub nonce: String,
+    pub expires_at: i64,
 }
\ No newline at end of file
@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::FromRow;
+
+/// File formats learner progress is exported as.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressExportFormat {
+    #[default]
+    Csv,
+    Parquet,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportProgressQuery {
+    pub course_id: i64,
+    #[serde(default)]
+    pub format: ProgressExportFormat,
+    pub creator_id: String,
+    pub requested_at: i64,
+    pub signature: String,
+}
+
+/// Quiz with a score column in the export, module quizzes in course order
+/// then the final exam.
+#[derive(Debug, FromRow)]
+pub struct ExportQuizRow {
+    pub id: i64,
+    pub final_exam: bool,
+}
+
+/// One enrolled learner of the export.
+#[derive(Debug, FromRow)]
+pub struct ProgressExportRow {
+    pub learner_id: String,
+    pub display_name: Option<String>,
+    pub enrolled_at: Option<DateTime<Utc>>,
+    pub lessons_completed: i64,
+    pub modules_completed: i64,
+    // Counting attempt score of each exported quiz, None when not attempted
+    pub quiz_scores: Vec<Option<f64>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
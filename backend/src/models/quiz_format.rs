@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::course::CreateQuestionPayload;
+
+/// Question bank interchange formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuizFormat {
+    // Moodle GIFT text
+    Gift,
+    // IMS QTI content package
+    Qti,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum QtiVersion {
+    #[default]
+    #[serde(rename = "2.1")]
+    V2p1,
+    #[serde(rename = "3.0")]
+    V3p0,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportQuizPayload {
+    pub format: QuizFormat,
+    // GIFT text, or a base64 QTI zip package. A single QTI item XML is accepted as is
+    pub content: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportQuizQuery {
+    pub quiz_id: i64,
+    pub format: QuizFormat,
+    #[serde(default)]
+    pub qti_version: QtiVersion,
+    pub creator_id: String,
+    pub requested_at: i64,
+    // Creator wallet signature of the creator request message for the quiz's course
+    pub signature: String,
+}
+
+/// A construct of an item that could not be carried over, the item is
+/// skipped when `skipped` is set.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatIssue {
+    // Title, identifier or position of the item in the source
+    pub item: String,
+    pub message: String,
+    pub skipped: bool,
+}
+
+/// Questions ready for the quiz of a create course payload.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedQuiz {
+    pub questions: Vec<CreateQuestionPayload>,
+    pub issues: Vec<FormatIssue>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedQuiz {
+    pub quiz_id: i64,
+    pub format: QuizFormat,
+    pub file_name: String,
+    // GIFT text, or a base64 QTI zip package
+    pub content: String,
+    pub issues: Vec<FormatIssue>,
+}
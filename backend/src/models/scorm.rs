@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::scorm::ScormVersion;
+use crate::scorm::runtime::CmiValues;
+
+/// An uploaded SCORM package, lessons launch it by its id.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScormPackage {
+    pub id: i64,
+    pub title: String,
+    pub scorm_version: ScormVersion,
+    pub launch_path: String,
+    pub files: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadScormQuery {
+    // Creator uploading the package
+    pub creator_id: String,
+    pub requested_at: i64,
+    // Creator wallet signature of the creator request message, without a course
+    pub signature: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScormPackagePath {
+    pub package_id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScormContentPath {
+    pub package_id: i64,
+    // Path of the file relative to the package root
+    pub path: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScormRuntimeQuery {
+    pub learner_id: String,
+    pub lesson_id: i64,
+}
+
+/// Data model values a package starts from for one learner.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScormRuntime {
+    pub learner_id: String,
+    pub lesson_id: i64,
+    pub package_id: i64,
+    pub scorm_version: ScormVersion,
+    pub launch_path: String,
+    pub values: CmiValues,
+    pub lesson_completed: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitScormPayload {
+    pub learner_id: String,
+    pub lesson_id: i64,
+    // Elements set by the package since the last commit
+    pub values: CmiValues,
+    // Sent on LMSFinish / Terminate, adds the session time to the total
+    #[serde(default)]
+    pub finished: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommittedScorm {
+    pub lesson_completed: bool,
+}
+
+#[derive(Debug, FromRow)]
+pub struct ScormLessonRow {
+    pub package_id: i64,
+    pub scorm_version: String,
+    pub launch_path: String,
+    pub course_id: i64,
+}
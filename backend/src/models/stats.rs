@@ -0,0 +1,66 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlatformStatsQuery {
+    // The last 30 days by default, ending now
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlatformStats {
+    pub generated_at: DateTime<Utc>,
+    pub totals: TotalStats,
+    pub range: RangeStats,
+    // The range of the same length just before `range`
+    pub previous_range: RangeStats,
+    pub growth: RangeGrowth,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct TotalStats {
+    pub learners: i64,
+    pub creators: i64,
+    pub courses: i64,
+    pub enrollments: i64,
+    pub completions: i64,
+    pub learner_nfts: i64,
+    pub creator_nfts: i64,
+    pub active_learners_7d: i64,
+    pub active_learners_30d: i64,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct RangeStats {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub new_learners: i64,
+    pub new_creators: i64,
+    pub new_courses: i64,
+    pub enrollments: i64,
+    pub completions: i64,
+    pub learner_nfts_minted: i64,
+    pub creator_nfts_minted: i64,
+    // Learners who enrolled, completed a lesson or attempted a quiz in the range
+    pub active_learners: i64,
+}
+
+/// Change from the previous range as a fraction, None when it had nothing.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RangeGrowth {
+    pub new_learners: Option<f64>,
+    pub new_creators: Option<f64>,
+    pub new_courses: Option<f64>,
+    pub enrollments: Option<f64>,
+    pub completions: Option<f64>,
+    pub learner_nfts_minted: Option<f64>,
+    pub creator_nfts_minted: Option<f64>,
+    pub active_learners: Option<f64>,
+}
@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XapiStatementsQuery {
+    pub course_id: i64,
+    pub learner_id: Option<String>,
+}
+
+/// A queued xAPI statement with its delivery status.
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct XapiStatementRow {
+    pub id: String,
+    pub learner_id: String,
+    pub verb: String,
+    pub activity_type: String,
+    pub activity_id: i64,
+    pub course_id: i64,
+    pub activity_name: String,
+    pub result: Option<Value>,
+    pub timestamp: DateTime<Utc>,
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub sent_at: Option<DateTime<Utc>>,
+}
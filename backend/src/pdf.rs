@@ -0,0 +1,355 @@
+use chrono::{DateTime, Utc};
+use lopdf::{
+    Document, Object, Stream, StringFormat,
+    content::{Content, Operation},
+    dictionary,
+};
+use qrcode::{Color, QrCode};
+use sha2::{Digest, Sha256};
+
+// A4 landscape, in points
+const PAGE_WIDTH: f32 = 842.0;
+const PAGE_HEIGHT: f32 = 595.0;
+const MARGIN: f32 = 40.0;
+const MAX_TEXT_WIDTH: f32 = 700.0;
+
+const QR_SIZE: f32 = 110.0;
+const QR_QUIET_ZONE_MODULES: usize = 2;
+
+const REGULAR_FONT: &str = "F1";
+const BOLD_FONT: &str = "F2";
+
+// Glyph widths of the standard Helvetica fonts for ASCII 32..=126, per 1000 units
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+const HELVETICA_BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+    611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+    278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+const DEFAULT_GLYPH_WIDTH: u16 = 556;
+
+/// Everything printed on a completion certificate. The PDF only depends on
+/// these fields, so the same completion always renders to the same bytes.
+pub struct CertificateData {
+    pub learner_id: String,
+    pub display_name: Option<String>,
+    pub course_title: String,
+    pub creator_id: String,
+    pub completed_at: DateTime<Utc>,
+    pub nft_token_id: Option<i64>,
+    pub nft_contract_address: Option<String>,
+    pub verification_url: String,
+}
+
+struct Page {
+    operations: Vec<Operation>,
+}
+
+impl Page {
+    fn text(&mut self, font: &str, size: f32, x: f32, y: f32, text: &str) {
+        self.operations.extend([
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec![font.into(), size.into()]),
+            Operation::new("Td", vec![x.into(), y.into()]),
+            Operation::new(
+                "Tj",
+                vec![Object::String(win_ansi(text), StringFormat::Literal)],
+            ),
+            Operation::new("ET", vec![]),
+        ]);
+    }
+
+    /// Centers `text` on the page, shrinking it until it fits `MAX_TEXT_WIDTH`.
+    fn centered_text(&mut self, font: &str, size: f32, y: f32, text: &str) {
+        let width = text_width(font, text, 1.0);
+        let size = size.min(MAX_TEXT_WIDTH / width.max(1.0));
+        let x = (PAGE_WIDTH - width * size) / 2.0;
+        self.text(font, size, x, y, text);
+    }
+
+    fn rectangle(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        self.operations.push(Operation::new(
+            "re",
+            vec![x.into(), y.into(), width.into(), height.into()],
+        ));
+    }
+
+    fn qr_code(&mut self, data: &str, x: f32, y: f32) -> Result<(), String> {
+        let code = QrCode::new(data).map_err(|e| format!("Failed to encode QR code: {}", e))?;
+        let width = code.width();
+        let module = QR_SIZE / (width + 2 * QR_QUIET_ZONE_MODULES) as f32;
+        let origin = module * QR_QUIET_ZONE_MODULES as f32;
+
+        for (i, color) in code.to_colors().into_iter().enumerate() {
+            if color == Color::Dark {
+                let (col, row) = (i % width, i / width);
+                // QR rows run top to bottom, PDF y runs bottom to top
+                self.rectangle(
+                    x + origin + col as f32 * module,
+                    y + QR_SIZE - origin - (row + 1) as f32 * module,
+                    module,
+                    module,
+                );
+            }
+        }
+        self.operations.push(Operation::new("f", vec![]));
+
+        Ok(())
+    }
+}
+
+/// Renders a single page A4 landscape completion certificate.
+pub fn render_certificate(data: &CertificateData) -> Result<Vec<u8>, String> {
+    let mut page = Page {
+        operations: Vec::new(),
+    };
+
+    // Border
+    page.operations.push(Operation::new("w", vec![2.into()]));
+    page.rectangle(
+        MARGIN,
+        MARGIN,
+        PAGE_WIDTH - 2.0 * MARGIN,
+        PAGE_HEIGHT - 2.0 * MARGIN,
+    );
+    page.operations.push(Operation::new("S", vec![]));
+
+    page.centered_text(BOLD_FONT, 34.0, 470.0, "Certificate of Completion");
+    page.centered_text(REGULAR_FONT, 14.0, 420.0, "This certifies that");
+
+    match &data.display_name {
+        Some(display_name) => {
+            page.centered_text(BOLD_FONT, 24.0, 385.0, display_name);
+            page.centered_text(REGULAR_FONT, 11.0, 365.0, &data.learner_id);
+        }
+        None => page.centered_text(BOLD_FONT, 18.0, 380.0, &data.learner_id),
+    }
+
+    page.centered_text(REGULAR_FONT, 14.0, 330.0, "has completed the course");
+    page.centered_text(BOLD_FONT, 26.0, 290.0, &data.course_title);
+    page.centered_text(
+        REGULAR_FONT,
+        12.0,
+        255.0,
+        &format!("Created by {}", data.creator_id),
+    );
+    page.centered_text(
+        REGULAR_FONT,
+        12.0,
+        235.0,
+        &format!("Completed on {}", data.completed_at.format("%B %-d, %Y")),
+    );
+
+    if let Some(token_id) = data.nft_token_id {
+        let token = match &data.nft_contract_address {
+            Some(contract) => format!("LearnerNFT #{} ({})", token_id, contract),
+            None => format!("LearnerNFT #{}", token_id),
+        };
+        page.centered_text(REGULAR_FONT, 10.0, 215.0, &token);
+    }
+
+    let qr_x = PAGE_WIDTH - MARGIN - 20.0 - QR_SIZE;
+    let qr_y = MARGIN + 20.0;
+    page.qr_code(&data.verification_url, qr_x, qr_y)?;
+    page.text(REGULAR_FONT, 8.0, MARGIN + 20.0, qr_y + 12.0, "Verify at");
+    page.text(
+        REGULAR_FONT,
+        8.0,
+        MARGIN + 20.0,
+        qr_y,
+        &data.verification_url,
+    );
+
+    build_document(data, page)
+}
+
+fn build_document(data: &CertificateData, page: Page) -> Result<Vec<u8>, String> {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+        "Encoding" => "WinAnsiEncoding",
+    });
+    let bold_font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica-Bold",
+        "Encoding" => "WinAnsiEncoding",
+    });
+    let resources_id = doc.add_object(dictionary! {
+        "Font" => dictionary! {
+            REGULAR_FONT => font_id,
+            BOLD_FONT => bold_font_id,
+        },
+    });
+
+    let content = Content {
+        operations: page.operations,
+    }
+    .encode()
+    .map_err(|e| format!("Failed to encode certificate content: {}", e))?;
+    let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+    let page_id = doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+        "Contents" => content_id,
+    });
+
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![page_id.into()],
+            "Count" => 1,
+            "Resources" => resources_id,
+            "MediaBox" => vec![0.into(), 0.into(), PAGE_WIDTH.into(), PAGE_HEIGHT.into()],
+        }),
+    );
+
+    // Dates and ids are derived from the certificate data instead of the clock
+    let info_id = doc.add_object(dictionary! {
+        "Title" => Object::string_literal(win_ansi(&format!("Certificate - {}", data.course_title))),
+        "Producer" => Object::string_literal("Aranya"),
+        "CreationDate" => Object::string_literal(
+            data.completed_at.format("D:%Y%m%d%H%M%SZ").to_string(),
+        ),
+    });
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+
+    let document_id = document_id(data);
+    doc.trailer.set("Root", catalog_id);
+    doc.trailer.set("Info", info_id);
+    doc.trailer.set(
+        "ID",
+        vec![
+            Object::String(document_id.clone(), StringFormat::Hexadecimal),
+            Object::String(document_id, StringFormat::Hexadecimal),
+        ],
+    );
+    doc.compress();
+
+    let mut bytes = Vec::new();
+    doc.save_to(&mut bytes)
+        .map_err(|e| format!("Failed to write certificate: {}", e))?;
+
+    Ok(bytes)
+}
+
+fn document_id(data: &CertificateData) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    for field in [
+        data.learner_id.as_str(),
+        data.display_name.as_deref().unwrap_or_default(),
+        data.course_title.as_str(),
+        data.creator_id.as_str(),
+        &data.completed_at.to_rfc3339(),
+        &data
+            .nft_token_id
+            .map(|id| id.to_string())
+            .unwrap_or_default(),
+        data.nft_contract_address.as_deref().unwrap_or_default(),
+        data.verification_url.as_str(),
+    ] {
+        hasher.update(field.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.finalize()[..16].to_vec()
+}
+
+fn text_width(font: &str, text: &str, size: f32) -> f32 {
+    let widths = if font == BOLD_FONT {
+        &HELVETICA_BOLD_WIDTHS
+    } else {
+        &HELVETICA_WIDTHS
+    };
+    let units: u32 = text
+        .chars()
+        .map(|c| match c {
+            ' '..='~' => widths[c as usize - 32],
+            _ => DEFAULT_GLYPH_WIDTH,
+        } as u32)
+        .sum();
+
+    units as f32 * size / 1000.0
+}
+
+// The standard fonts only cover Latin-1, anything else is replaced
+fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| u8::try_from(u32::from(c)).unwrap_or(b'?'))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn certificate() -> CertificateData {
+        CertificateData {
+            learner_id: "0x1111111111111111111111111111111111111111".to_string(),
+            display_name: Some("Zoë Learner".to_string()),
+            course_title: "Rust basics".to_string(),
+            creator_id: "0x2222222222222222222222222222222222222222".to_string(),
+            completed_at: Utc.with_ymd_and_hms(2025, 3, 14, 15, 9, 26).unwrap(),
+            nft_token_id: Some(7),
+            nft_contract_address: Some("0x3333333333333333333333333333333333333333".to_string()),
+            verification_url: "https://aranya.example/certificates/verify/42".to_string(),
+        }
+    }
+
+    #[test]
+    fn same_completion_renders_the_same_bytes() {
+        let first = render_certificate(&certificate()).unwrap();
+        let second = render_certificate(&certificate()).unwrap();
+        assert_eq!(Sha256::digest(&first), Sha256::digest(&second));
+        assert_eq!(first, second);
+
+        let other = render_certificate(&CertificateData {
+            display_name: None,
+            ..certificate()
+        })
+        .unwrap();
+        assert_ne!(other, first);
+    }
+
+    #[test]
+    fn certificate_is_a_single_page_pdf() {
+        let bytes = render_certificate(&certificate()).unwrap();
+        let doc = Document::load_mem(&bytes).unwrap();
+        let pages = doc.get_pages();
+        assert_eq!(pages.len(), 1);
+
+        let content = doc.get_page_content(pages[&1]).unwrap();
+        let content = Content::decode(&content).unwrap();
+        let texts: Vec<Vec<u8>> = content
+            .operations
+            .iter()
+            .filter(|op| op.operator == "Tj")
+            .filter_map(|op| op.operands[0].as_str().ok().map(<[u8]>::to_vec))
+            .collect();
+        for expected in [
+            win_ansi("Zoë Learner"),
+            b"Rust basics".to_vec(),
+            b"Completed on March 14, 2025".to_vec(),
+            b"LearnerNFT #7 (0x3333333333333333333333333333333333333333)".to_vec(),
+        ] {
+            assert!(texts.contains(&expected), "{:?}", expected);
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use arrow_array::{
+    ArrayRef, Float64Array, Int64Array, RecordBatch, StringArray, TimestampMicrosecondArray,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{DateTime, SecondsFormat, Utc};
+use parquet::arrow::ArrowWriter;
+use std::sync::Arc;
+
+use crate::models::progress_export::{ExportQuizRow, ProgressExportFormat, ProgressExportRow};
+
+// CSV bytes buffered before they are sent as a chunk
+const CSV_CHUNK_SIZE: usize = 64 * 1024;
+// Learners per Parquet row group, each group is sent once written
+const PARQUET_ROW_GROUP_SIZE: usize = 8192;
+
+/// Columns of a progress export besides the per learner ones.
+pub struct ProgressColumns {
+    pub quiz_columns: Vec<String>,
+    pub lessons_total: i64,
+    pub modules_total: i64,
+}
+
+impl ProgressColumns {
+    pub fn new(quizzes: &[ExportQuizRow], lessons_total: i64, modules_total: i64) -> Self {
+        ProgressColumns {
+            quiz_columns: quizzes
+                .iter()
+                .map(|quiz| {
+                    if quiz.final_exam {
+                        "final_exam_score_percent".to_string()
+                    } else {
+                        format!("quiz_{}_score_percent", quiz.id)
+                    }
+                })
+                .collect(),
+            lessons_total,
+            modules_total,
+        }
+    }
+
+    fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = [
+            "learner_id",
+            "display_name",
+            "enrolled_at",
+            "lessons_completed",
+            "lessons_total",
+            "modules_completed",
+            "modules_total",
+        ]
+        .map(String::from)
+        .to_vec();
+        names.extend(self.quiz_columns.iter().cloned());
+        names.push("completed_at".to_string());
+        names
+    }
+}
+
+/// Encodes learner rows as they are read from the database, handing back
+/// chunks of the file to stream so the whole export is never held in memory.
+pub enum ProgressWriter {
+    Csv {
+        writer: csv::Writer<Vec<u8>>,
+        columns: ProgressColumns,
+    },
+    Parquet {
+        writer: ArrowWriter<Vec<u8>>,
+        schema: SchemaRef,
+        columns: ProgressColumns,
+        rows: Vec<ProgressExportRow>,
+    },
+}
+
+impl ProgressWriter {
+    pub fn new(
+        format: ProgressExportFormat,
+        columns: ProgressColumns,
+    ) -> Result<ProgressWriter, String> {
+        match format {
+            ProgressExportFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(Vec::new());
+                writer
+                    .write_record(columns.names())
+                    .map_err(|e| e.to_string())?;
+                Ok(ProgressWriter::Csv { writer, columns })
+            }
+            ProgressExportFormat::Parquet => {
+                let schema = parquet_schema(&columns);
+                let writer = ArrowWriter::try_new(Vec::new(), schema.clone(), None)
+                    .map_err(|e| e.to_string())?;
+                Ok(ProgressWriter::Parquet {
+                    writer,
+                    schema,
+                    columns,
+                    rows: Vec::new(),
+                })
+            }
+        }
+    }
+
+    /// Adds a learner, returns the bytes ready to be sent if any.
+    pub fn write(&mut self, row: ProgressExportRow) -> Result<Option<Vec<u8>>, String> {
+        match self {
+            ProgressWriter::Csv { writer, columns } => {
+                writer
+                    .write_record(csv_record(&row, columns))
+                    .map_err(|e| e.to_string())?;
+                if writer.get_ref().len() < CSV_CHUNK_SIZE {
+                    return Ok(None);
+                }
+                let full = std::mem::replace(writer, csv::Writer::from_writer(Vec::new()));
+                full.into_inner().map(Some).map_err(|e| e.to_string())
+            }
+            ProgressWriter::Parquet {
+                writer,
+                schema,
+                columns,
+                rows,
+            } => {
+                rows.push(row);
+                if rows.len() < PARQUET_ROW_GROUP_SIZE {
+                    return Ok(None);
+                }
+                let batch = record_batch(schema, columns, &std::mem::take(rows))?;
+                writer.write(&batch).map_err(|e| e.to_string())?;
+                writer.flush().map_err(|e| e.to_string())?;
+                Ok(Some(std::mem::take(writer.inner_mut())))
+            }
+        }
+    }
+
+    /// The rest of the file, for Parquet the last row group and the footer.
+    pub fn finish(self) -> Result<Vec<u8>, String> {
+        match self {
+            ProgressWriter::Csv { writer, .. } => writer.into_inner().map_err(|e| e.to_string()),
+            ProgressWriter::Parquet {
+                mut writer,
+                schema,
+                columns,
+                rows,
+            } => {
+                if !rows.is_empty() {
+                    let batch = record_batch(&schema, &columns, &rows)?;
+                    writer.write(&batch).map_err(|e| e.to_string())?;
+                }
+                writer.into_inner().map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+fn csv_record(row: &ProgressExportRow, columns: &ProgressColumns) -> Vec<String> {
+    let mut record = vec![
+        row.learner_id.clone(),
+        row.display_name.clone().unwrap_or_default(),
+        row.enrolled_at.map(timestamp).unwrap_or_default(),
+        row.lessons_completed.to_string(),
+        columns.lessons_total.to_string(),
+        row.modules_completed.to_string(),
+        columns.modules_total.to_string(),
+    ];
+    record.extend(
+        row.quiz_scores
+            .iter()
+            .map(|score| score.map(|s| s.to_string()).unwrap_or_default()),
+    );
+    record.push(row.completed_at.map(timestamp).unwrap_or_default());
+    record
+}
+
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn parquet_schema(columns: &ProgressColumns) -> SchemaRef {
+    let time = DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()));
+    let mut fields = vec![
+        Field::new("learner_id", DataType::Utf8, false),
+        Field::new("display_name", DataType::Utf8, true),
+        Field::new("enrolled_at", time.clone(), true),
+        Field::new("lessons_completed", DataType::Int64, false),
+        Field::new("lessons_total", DataType::Int64, false),
+        Field::new("modules_completed", DataType::Int64, false),
+        Field::new("modules_total", DataType::Int64, false),
+    ];
+    fields.extend(
+        columns
+            .quiz_columns
+            .iter()
+            .map(|name| Field::new(name, DataType::Float64, true)),
+    );
+    fields.push(Field::new("completed_at", time, true));
+    Arc::new(Schema::new(fields))
+}
+
+fn record_batch(
+    schema: &SchemaRef,
+    columns: &ProgressColumns,
+    rows: &[ProgressExportRow],
+) -> Result<RecordBatch, String> {
+    let times = |time: fn(&ProgressExportRow) -> Option<DateTime<Utc>>| -> ArrayRef {
+        Arc::new(
+            TimestampMicrosecondArray::from(
+                rows.iter()
+                    .map(|row| time(row).map(|t| t.timestamp_micros()))
+                    .collect::<Vec<_>>(),
+            )
+            .with_timezone("UTC"),
+        )
+    };
+    let totals = |total: i64| -> ArrayRef { Arc::new(Int64Array::from(vec![total; rows.len()])) };
+
+    let mut arrays: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            rows.iter().map(|row| &row.learner_id),
+        )),
+        Arc::new(StringArray::from_iter(
+            rows.iter().map(|row| row.display_name.as_deref()),
+        )),
+        times(|row| row.enrolled_at),
+        Arc::new(Int64Array::from_iter_values(
+            rows.iter().map(|row| row.lessons_completed),
+        )),
+        totals(columns.lessons_total),
+        Arc::new(Int64Array::from_iter_values(
+            rows.iter().map(|row| row.modules_completed),
+        )),
+        totals(columns.modules_total),
+    ];
+    for i in 0..columns.quiz_columns.len() {
+        arrays.push(Arc::new(Float64Array::from_iter(
+            rows.iter()
+                .map(|row| row.quiz_scores.get(i).copied().flatten()),
+        )));
+    }
+    arrays.push(times(|row| row.completed_at));
+
+    RecordBatch::try_new(schema.clone(), arrays).map_err(|e| e.to_string())
+}
@@ -0,0 +1,195 @@
+use rand::seq::SliceRandom;
+use rand_chacha::{ChaCha8Rng, rand_core::SeedableRng};
+
+use crate::models::course::{DrawRuleRow, Question, QuestionType};
+use crate::models::progress::{ServedAnswer, ServedQuestion};
+
+// Questions are drawn from stream 0, answers of each question from the stream of its id
+const DRAW_STREAM: u64 = 0;
+
+/// Draws the questions of an attempt from the quiz question bank, in the
+/// order they are served. The same seed always draws the same questions.
+pub fn draw_questions(questions: &[Question], rules: &[DrawRuleRow], seed: i64) -> Vec<i64> {
+    let mut rng = seeded_rng(seed, DRAW_STREAM);
+
+    let mut drawn: Vec<i64> = if rules.is_empty() {
+        questions.iter().map(|q| q.id).collect()
+    } else {
+        rules
+            .iter()
+            .flat_map(|rule| {
+                let mut candidates: Vec<i64> = questions
+                    .iter()
+                    .filter(|q| q.pool == rule.pool)
+                    .map(|q| q.id)
+                    .collect();
+                candidates.sort_unstable();
+                candidates.shuffle(&mut rng);
+                candidates.truncate(rule.draw_count.max(0) as usize);
+                candidates
+            })
+            .collect()
+    };
+    drawn.shuffle(&mut rng);
+
+    drawn
+}
+
+/// Strips the answer key from a question and shuffles its answers for the
+/// attempt seeded with `seed`.
+pub fn serve_question(question: &Question, seed: i64, shuffle_answers: bool) -> ServedQuestion {
+    let mut rng = seeded_rng(seed, question.id as u64);
+
+    let mut answers: Vec<ServedAnswer> = match question.question_type {
+        // Accepted answers would give the answer away
+        QuestionType::ShortText | QuestionType::Numeric => Vec::new(),
+        _ => question
+            .answers
+            .iter()
+            .map(|a| ServedAnswer {
+                id: a.id,
+                answer_text: a.answer_text.clone(),
+            })
+            .collect(),
+    };
+    // Ordering questions are served in the correct order unless shuffled
+    let shuffle = match question.question_type {
+        QuestionType::Ordering => true,
+        QuestionType::TrueFalse => false,
+        _ => shuffle_answers,
+    };
+    if shuffle {
+        answers.shuffle(&mut rng);
+    }
+
+    let mut match_options: Vec<String> = match question.question_type {
+        QuestionType::Matching => question
+            .answers
+            .iter()
+            .filter_map(|a| a.match_text.clone())
+            .collect(),
+        _ => Vec::new(),
+    };
+    match_options.shuffle(&mut rng);
+
+    ServedQuestion {
+        id: question.id,
+        question_text: question.question_text.clone(),
+        question_type: question.question_type,
+        points: question.points,
+        answers,
+        match_options,
+    }
+}
+
+// ChaCha is used over StdRng because its output is stable across rand versions
+fn seeded_rng(seed: i64, stream: u64) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed as u64);
+    rng.set_stream(stream);
+    rng
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::models::course::AnswerOption;
+
+    // Questions 1-6 are in pool "a", 7-9 in pool "b" and 10 in no pool
+    fn bank() -> Vec<Question> {
+        (1..=10)
+            .map(|id| Question {
+                id,
+                question_text: format!("Question {}", id),
+                question_type: QuestionType::SingleChoice,
+                points: 1.0,
+                answers: (1..=4)
+                    .map(|n| AnswerOption {
+                        id: id * 10 + n,
+                        answer_text: format!("Answer {}", n),
+                        is_correct: n == 1,
+                        position: n as i32,
+                        match_text: None,
+                    })
+                    .collect(),
+                numeric_answer: None,
+                numeric_tolerance: 0.0,
+                case_sensitive: false,
+                pool: match id {
+                    1..=6 => Some("a".to_string()),
+                    7..=9 => Some("b".to_string()),
+                    _ => None,
+                },
+            })
+            .collect()
+    }
+
+    fn rule(pool: Option<&str>, draw_count: i32) -> DrawRuleRow {
+        DrawRuleRow {
+            pool: pool.map(str::to_string),
+            draw_count,
+        }
+    }
+
+    fn pool_of(questions: &[Question], id: i64) -> Option<String> {
+        questions.iter().find(|q| q.id == id).unwrap().pool.clone()
+    }
+
+    #[test]
+    fn same_seed_draws_the_same_questions() {
+        let questions = bank();
+        let rules = [rule(Some("a"), 3), rule(Some("b"), 2)];
+
+        let drawn = draw_questions(&questions, &rules, 42);
+        assert_eq!(drawn, draw_questions(&questions, &rules, 42));
+        assert!(
+            (0..20).any(|seed| draw_questions(&questions, &rules, seed) != drawn),
+            "Every seed drew the same questions"
+        );
+
+        let served = serve_question(&questions[0], 42, true);
+        let again = serve_question(&questions[0], 42, true);
+        let ids = |q: &ServedQuestion| q.answers.iter().map(|a| a.id).collect::<Vec<_>>();
+        assert_eq!(ids(&served), ids(&again));
+    }
+
+    #[test]
+    fn draws_have_no_duplicates() {
+        let questions = bank();
+        let rules = [rule(Some("a"), 4), rule(Some("b"), 3), rule(None, 1)];
+
+        for seed in 0..50 {
+            let drawn = draw_questions(&questions, &rules, seed);
+            let unique: HashSet<i64> = drawn.iter().copied().collect();
+            assert_eq!(unique.len(), drawn.len(), "seed {}", seed);
+
+            let from_a = drawn
+                .iter()
+                .filter(|id| pool_of(&questions, **id).as_deref() == Some("a"))
+                .count();
+            assert_eq!(from_a, 4, "seed {}", seed);
+            assert_eq!(drawn.len(), 8, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn small_pools_are_drawn_whole() {
+        let questions = bank();
+        // Pool "b" only has 3 questions and the "c" pool none
+        let rules = [rule(Some("b"), 5), rule(Some("c"), 2), rule(Some("a"), -1)];
+
+        let mut drawn = draw_questions(&questions, &rules, 7);
+        drawn.sort_unstable();
+        assert_eq!(drawn, [7, 8, 9]);
+    }
+
+    #[test]
+    fn without_rules_every_question_is_served() {
+        let questions = bank();
+
+        let mut drawn = draw_questions(&questions, &[], 3);
+        drawn.sort_unstable();
+        assert_eq!(drawn, (1..=10).collect::<Vec<i64>>());
+    }
+}
@@ -0,0 +1,784 @@
+use crate::models::course::{CreateAnswerOptionPayload, CreateQuestionPayload, QuestionType};
+use crate::models::quiz_format::{FormatIssue, ImportedQuiz};
+use crate::quiz_format::{Issues, item_label, keep_valid};
+
+// Characters with a meaning in GIFT, escaped with a backslash in text
+const SPECIAL_CHARS: [char; 6] = ['~', '=', '#', '{', '}', ':'];
+
+// Answer weights Moodle accepts, in percent
+const MOODLE_WEIGHTS: [f64; 20] = [
+    100.0, 90.0, 83.33333, 80.0, 75.0, 70.0, 66.66667, 60.0, 50.0, 40.0, 33.33333, 30.0, 25.0,
+    20.0, 16.66667, 14.28571, 12.5, 11.11111, 10.0, 5.0,
+];
+
+// Categories of a Moodle course are exported under this prefix
+const CATEGORY_PREFIX: &str = "$course$/top/";
+
+struct GiftAnswer {
+    // `=` for right and `~` for wrong answers, unless weighted
+    marker: u8,
+    weight: Option<f64>,
+    text: String,
+    match_text: Option<String>,
+}
+
+/// Parses Moodle GIFT text into questions. `$CATEGORY` sets the pool of the
+/// questions after it.
+pub fn import_gift(text: &str) -> ImportedQuiz {
+    let mut issues = Issues::default();
+    let mut questions = Vec::new();
+    let mut pool: Option<String> = None;
+    let mut position = 0;
+
+    for item in split_items(text) {
+        let mut rest = item.as_str();
+        while let Some(line) = rest
+            .lines()
+            .next()
+            .filter(|line| line.trim_start().starts_with("$CATEGORY:"))
+        {
+            let category = line.trim_start()["$CATEGORY:".len()..].trim();
+            let category = category
+                .strip_prefix(CATEGORY_PREFIX)
+                .or_else(|| category.strip_prefix("$course$/"))
+                .unwrap_or(category);
+            pool = Some(category.to_string()).filter(|c| !c.is_empty());
+            rest = rest[line.len()..].trim_start();
+        }
+        if rest.is_empty() {
+            continue;
+        }
+
+        if let Some(question) = parse_item(rest, position, &pool, &mut issues) {
+            questions.push(question);
+        }
+        position += 1;
+    }
+
+    ImportedQuiz {
+        questions,
+        issues: issues.into_vec(),
+    }
+}
+
+/// Writes questions as GIFT text, grouped by pool. Ordering questions have
+/// no GIFT equivalent and are left out.
+pub fn export_gift(questions: &[CreateQuestionPayload]) -> (String, Vec<FormatIssue>) {
+    let mut issues = Issues::default();
+    let mut out = String::from("// Exported from Aranya\n\n");
+
+    let mut order: Vec<usize> = (0..questions.len()).collect();
+    order.sort_by_key(|&i| &questions[i].pool);
+
+    let mut pool: Option<&String> = None;
+    for i in order {
+        let question = &questions[i];
+        let label = item_label(i);
+        let Some(block) = answer_block(question, &label, &mut issues) else {
+            continue;
+        };
+
+        if question.pool.as_ref() != pool {
+            pool = question.pool.as_ref();
+            out.push_str(&format!(
+                "$CATEGORY: {}{}\n\n",
+                CATEGORY_PREFIX,
+                pool.map(String::as_str).unwrap_or_default()
+            ));
+        }
+        if question.points != 1.0 {
+            issues.note(
+                &label,
+                "GIFT has no points, Moodle imports the question worth 1 point",
+            );
+        }
+
+        out.push_str(&format!(
+            "::{}::{} {}\n\n",
+            escape(&label),
+            escape(&question.question_text),
+            block
+        ));
+    }
+
+    (out, issues.into_vec())
+}
+
+// Items are separated by blank lines outside of answer blocks
+fn split_items(text: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if depth == 0 && trimmed.starts_with("//") {
+            continue;
+        }
+        if depth == 0 && trimmed.is_empty() {
+            if !current.trim().is_empty() {
+                items.push(std::mem::take(&mut current));
+            }
+            current.clear();
+            continue;
+        }
+
+        depth = brace_depth(line, depth);
+        current.push_str(line);
+        current.push('\n');
+    }
+    if !current.trim().is_empty() {
+        items.push(current);
+    }
+
+    items
+}
+
+fn brace_depth(line: &str, mut depth: usize) -> usize {
+    let mut bytes = line.bytes();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'\\' => {
+                bytes.next();
+            }
+            b'{' => depth += 1,
+            b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    depth
+}
+
+fn parse_item(
+    item: &str,
+    position: usize,
+    pool: &Option<String>,
+    issues: &mut Issues,
+) -> Option<CreateQuestionPayload> {
+    let mut rest = item.trim();
+    let mut label = item_label(position);
+    if let Some(after) = rest.strip_prefix("::")
+        && let Some(end) = find_unescaped(after, "::")
+    {
+        let title = unescape(after[..end].trim());
+        if !title.is_empty() {
+            label = title;
+        }
+        rest = after[end + 2..].trim_start();
+    }
+
+    let (rest, html) = strip_format_marker(rest);
+
+    let Some(open) = find_unescaped(rest, "{") else {
+        issues.skip(
+            &label,
+            "Descriptions without an answer block are not supported",
+        );
+        return None;
+    };
+    let Some(close) = find_unescaped(&rest[open + 1..], "}").map(|i| open + 1 + i) else {
+        issues.skip(&label, "Answer block is not closed");
+        return None;
+    };
+
+    let mut question_text = gift_text(&rest[..open], html);
+    let tail = gift_text(&rest[close + 1..], html);
+    if !tail.is_empty() {
+        question_text = format!("{} _____ {}", question_text, tail)
+            .trim()
+            .to_string();
+        issues.note(
+            &label,
+            "Missing word question imported with a blank in the question text",
+        );
+    }
+
+    let mut block = &rest[open + 1..close];
+    if let Some(i) = find_unescaped(block, "####") {
+        block = &block[..i];
+        issues.note(&label, "General feedback is not supported and was dropped");
+    }
+    let block = block.trim();
+
+    let question = if block.is_empty() {
+        issues.skip(&label, "Essay questions are not supported");
+        return None;
+    } else if let Some(is_true) = parse_true_false(block, &label, issues) {
+        question(
+            question_text,
+            QuestionType::TrueFalse,
+            vec![answer("True", is_true), answer("False", !is_true)],
+            pool,
+        )
+    } else if let Some(numeric) = block.strip_prefix('#') {
+        let (value, tolerance) = parse_numeric_block(numeric, &label, issues)?;
+        CreateQuestionPayload {
+            numeric_answer: Some(value),
+            numeric_tolerance: Some(tolerance),
+            ..question(question_text, QuestionType::Numeric, Vec::new(), pool)
+        }
+    } else {
+        parse_answer_block(block, question_text, html, pool, &label, issues)?
+    };
+
+    keep_valid(question, &label, issues)
+}
+
+fn parse_true_false(block: &str, label: &str, issues: &mut Issues) -> Option<bool> {
+    let (key, feedback) = match find_unescaped(block, "#") {
+        Some(i) => (&block[..i], true),
+        None => (block, false),
+    };
+    let is_true = match key.trim().to_uppercase().as_str() {
+        "T" | "TRUE" => true,
+        "F" | "FALSE" => false,
+        _ => return None,
+    };
+    if feedback {
+        issues.note(label, "Answer feedback is not supported and was dropped");
+    }
+    Some(is_true)
+}
+
+// The first fully correct answer counts, `value:tolerance` or `min..max`
+fn parse_numeric_block(block: &str, label: &str, issues: &mut Issues) -> Option<(f64, f64)> {
+    let answers = split_answers(block);
+    let raw = if answers.is_empty() {
+        block
+    } else {
+        if answers.len() > 1 {
+            issues.note(
+                label,
+                "Only the first fully correct numeric answer was kept",
+            );
+        }
+        let Some(raw) = answers
+            .iter()
+            .filter(|(marker, _)| *marker == b'=')
+            .map(|(_, raw)| strip_weight(raw))
+            .find(|(weight, _)| weight.is_none_or(|w| w >= 100.0))
+            .map(|(_, raw)| raw)
+        else {
+            issues.skip(label, "Numeric question has no fully correct answer");
+            return None;
+        };
+        raw
+    };
+
+    let raw = match find_unescaped(raw, "#") {
+        Some(i) => {
+            issues.note(label, "Answer feedback is not supported and was dropped");
+            &raw[..i]
+        }
+        None => raw,
+    };
+
+    let parsed = match raw.split_once("..") {
+        Some((min, max)) => min
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .zip(max.trim().parse::<f64>().ok())
+            .map(|(min, max)| ((min + max) / 2.0, (max - min).abs() / 2.0)),
+        None => match raw.split_once(':') {
+            Some((value, tolerance)) => value
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .zip(tolerance.trim().parse::<f64>().ok()),
+            None => raw.trim().parse::<f64>().ok().map(|value| (value, 0.0)),
+        },
+    };
+    if parsed.is_none() {
+        issues.skip(label, format!("\"{}\" is not a numeric answer", raw.trim()));
+    }
+    parsed
+}
+
+// Choice, matching and short answer questions
+fn parse_answer_block(
+    block: &str,
+    question_text: String,
+    html: bool,
+    pool: &Option<String>,
+    label: &str,
+    issues: &mut Issues,
+) -> Option<CreateQuestionPayload> {
+    let mut feedback = false;
+    let answers: Vec<GiftAnswer> = split_answers(block)
+        .into_iter()
+        .map(|(marker, raw)| {
+            let (weight, mut raw) = strip_weight(raw);
+            if let Some(i) = find_unescaped(raw, "#") {
+                raw = &raw[..i];
+                feedback = true;
+            }
+            let (text, match_text) = match find_unescaped(raw, "->") {
+                Some(i) => (&raw[..i], Some(gift_text(&raw[i + 2..], html))),
+                None => (raw, None),
+            };
+            GiftAnswer {
+                marker,
+                weight,
+                text: gift_text(text, html),
+                match_text,
+            }
+        })
+        .collect();
+
+    if feedback {
+        issues.note(label, "Answer feedback is not supported and was dropped");
+    }
+    if answers.is_empty() {
+        issues.skip(label, "Answer block has no answers");
+        return None;
+    }
+
+    if answers.iter().any(|a| a.match_text.is_some()) {
+        let (pairs, distractors): (Vec<GiftAnswer>, Vec<GiftAnswer>) =
+            answers.into_iter().partition(|a| !a.text.is_empty());
+        if !distractors.is_empty() {
+            issues.note(
+                label,
+                "Extra matching distractors are not supported and were dropped",
+            );
+        }
+        let answers = pairs
+            .into_iter()
+            .map(|a| CreateAnswerOptionPayload {
+                answer_text: a.text,
+                is_correct: false,
+                match_text: a.match_text,
+            })
+            .collect();
+        return Some(question(
+            question_text,
+            QuestionType::Matching,
+            answers,
+            pool,
+        ));
+    }
+
+    if answers.iter().any(|a| a.marker == b'~') {
+        let is_correct = |a: &GiftAnswer| a.weight.map_or(a.marker == b'=', |w| w > 0.0);
+        let num_correct = answers.iter().filter(|a| is_correct(a)).count();
+        let partial = answers
+            .iter()
+            .any(|a| a.weight.is_some_and(|w| w > 0.0 && w < 100.0));
+        let question_type = if num_correct == 1 && !partial {
+            QuestionType::SingleChoice
+        } else {
+            QuestionType::MultipleChoice
+        };
+        let answers = answers
+            .iter()
+            .map(|a| answer(&a.text, is_correct(a)))
+            .collect();
+        return Some(question(question_text, question_type, answers, pool));
+    }
+
+    let (accepted, partial): (Vec<GiftAnswer>, Vec<GiftAnswer>) = answers
+        .into_iter()
+        .partition(|a| a.weight.is_none_or(|w| w >= 100.0));
+    if !partial.is_empty() {
+        issues.note(
+            label,
+            "Answers with partial credit are not supported and were dropped",
+        );
+    }
+    let answers = accepted.iter().map(|a| answer(&a.text, true)).collect();
+    Some(question(
+        question_text,
+        QuestionType::ShortText,
+        answers,
+        pool,
+    ))
+}
+
+fn answer_block(
+    question: &CreateQuestionPayload,
+    label: &str,
+    issues: &mut Issues,
+) -> Option<String> {
+    let lines: Vec<String> = match question.question_type {
+        QuestionType::SingleChoice => choice_lines(question),
+        QuestionType::TrueFalse => {
+            let correct = question
+                .answers
+                .iter()
+                .find(|a| a.is_correct)
+                .map(|a| a.answer_text.trim().to_lowercase());
+            match correct.as_deref() {
+                Some("true") => return Some("{TRUE}".to_string()),
+                Some("false") => return Some("{FALSE}".to_string()),
+                _ => {
+                    issues.note(label, "True/false answers other than True and False are exported as a single choice question");
+                    choice_lines(question)
+                }
+            }
+        }
+        QuestionType::MultipleChoice => {
+            let num_correct = question.answers.iter().filter(|a| a.is_correct).count();
+            let num_wrong = question.answers.len() - num_correct;
+            question
+                .answers
+                .iter()
+                .map(|a| {
+                    let weight = if a.is_correct {
+                        moodle_weight(100.0 / num_correct as f64)
+                    } else {
+                        -moodle_weight(100.0 / num_wrong as f64)
+                    };
+                    format!("~%{}%{}", weight, escape(&a.answer_text))
+                })
+                .collect()
+        }
+        QuestionType::Matching => question
+            .answers
+            .iter()
+            .map(|a| {
+                format!(
+                    "={} -> {}",
+                    escape(&a.answer_text),
+                    escape(a.match_text.as_deref().unwrap_or_default())
+                )
+            })
+            .collect(),
+        QuestionType::Numeric => {
+            let Some(value) = question.numeric_answer else {
+                issues.skip(label, "Numeric question has no answer");
+                return None;
+            };
+            let tolerance = question.numeric_tolerance.unwrap_or(0.0);
+            return Some(if tolerance > 0.0 {
+                format!("{{#{}:{}}}", value, tolerance)
+            } else {
+                format!("{{#{}}}", value)
+            });
+        }
+        QuestionType::ShortText => {
+            if question.case_sensitive {
+                issues.note(label, "GIFT short answers are not case sensitive");
+            }
+            question
+                .answers
+                .iter()
+                .map(|a| format!("={}", escape(&a.answer_text)))
+                .collect()
+        }
+        QuestionType::Ordering => {
+            issues.skip(label, "Ordering questions are not supported by GIFT");
+            return None;
+        }
+    };
+
+    let lines: Vec<String> = lines.iter().map(|line| format!("\t{}", line)).collect();
+    Some(format!("{{\n{}\n}}", lines.join("\n")))
+}
+
+fn choice_lines(question: &CreateQuestionPayload) -> Vec<String> {
+    question
+        .answers
+        .iter()
+        .map(|a| {
+            format!(
+                "{}{}",
+                if a.is_correct { '=' } else { '~' },
+                escape(&a.answer_text)
+            )
+        })
+        .collect()
+}
+
+fn moodle_weight(percent: f64) -> f64 {
+    MOODLE_WEIGHTS
+        .into_iter()
+        .min_by(|a, b| (a - percent).abs().total_cmp(&(b - percent).abs()))
+        .unwrap_or(100.0)
+}
+
+fn question(
+    question_text: String,
+    question_type: QuestionType,
+    answers: Vec<CreateAnswerOptionPayload>,
+    pool: &Option<String>,
+) -> CreateQuestionPayload {
+    CreateQuestionPayload {
+        question_text,
+        question_type,
+        points: 1.0,
+        answers,
+        numeric_answer: None,
+        numeric_tolerance: None,
+        case_sensitive: false,
+        pool: pool.clone(),
+    }
+}
+
+fn answer(answer_text: &str, is_correct: bool) -> CreateAnswerOptionPayload {
+    CreateAnswerOptionPayload {
+        answer_text: answer_text.to_string(),
+        is_correct,
+        match_text: None,
+    }
+}
+
+// Answers start at every unescaped `=` or `~`
+fn split_answers(block: &str) -> Vec<(u8, &str)> {
+    let bytes = block.as_bytes();
+    let mut starts = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'=' | b'~' => starts.push(i),
+            _ => {}
+        }
+        i += 1;
+    }
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(n, &start)| {
+            let end = starts.get(n + 1).copied().unwrap_or(block.len());
+            (bytes[start], &block[start + 1..end])
+        })
+        .collect()
+}
+
+// Answer weight in percent, as in `~%50%answer`
+fn strip_weight(raw: &str) -> (Option<f64>, &str) {
+    let raw = raw.trim();
+    if let Some(after) = raw.strip_prefix('%')
+        && let Some(end) = after.find('%')
+    {
+        return (
+            after[..end].trim().parse().ok(),
+            after[end + 1..].trim_start(),
+        );
+    }
+    (None, raw)
+}
+
+fn find_unescaped(text: &str, pattern: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            i += 2;
+            continue;
+        }
+        if bytes[i..].starts_with(pattern.as_bytes()) {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+fn strip_format_marker(text: &str) -> (&str, bool) {
+    for (marker, html) in [
+        ("[html]", true),
+        ("[moodle]", false),
+        ("[plain]", false),
+        ("[markdown]", false),
+    ] {
+        if let Some(rest) = text.strip_prefix(marker) {
+            return (rest.trim_start(), html);
+        }
+    }
+    (text, false)
+}
+
+fn gift_text(raw: &str, html: bool) -> String {
+    let text = unescape(raw.trim());
+    if html {
+        strip_html(&text).trim().to_string()
+    } else {
+        text
+    }
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some(next) => out.push(next),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c if c == '\\' || SPECIAL_CHARS.contains(&c) => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn strip_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn choice(answer_text: &str, is_correct: bool) -> CreateAnswerOptionPayload {
+        answer(answer_text, is_correct)
+    }
+
+    fn questions() -> Vec<CreateQuestionPayload> {
+        let pool = Some("Week 1".to_string());
+        vec![
+            question(
+                "Which {brace} is = to ~ this?".to_string(),
+                QuestionType::SingleChoice,
+                vec![choice("Left: {", true), choice("Right: }", false)],
+                &pool,
+            ),
+            question(
+                "Pick the primes".to_string(),
+                QuestionType::MultipleChoice,
+                vec![choice("2", true), choice("3", true), choice("4", false)],
+                &pool,
+            ),
+            question(
+                "Rust has a borrow checker".to_string(),
+                QuestionType::TrueFalse,
+                vec![choice("True", true), choice("False", false)],
+                &pool,
+            ),
+            question(
+                "Match the keywords".to_string(),
+                QuestionType::Matching,
+                vec![
+                    CreateAnswerOptionPayload {
+                        match_text: Some("Binding".to_string()),
+                        ..choice("let", false)
+                    },
+                    CreateAnswerOptionPayload {
+                        match_text: Some("Function".to_string()),
+                        ..choice("fn", false)
+                    },
+                ],
+                &pool,
+            ),
+            CreateQuestionPayload {
+                numeric_answer: Some(3.5),
+                numeric_tolerance: Some(0.25),
+                ..question(
+                    "Half of seven".to_string(),
+                    QuestionType::Numeric,
+                    Vec::new(),
+                    &pool,
+                )
+            },
+            question(
+                "Name the package manager".to_string(),
+                QuestionType::ShortText,
+                vec![choice("cargo", true), choice("Cargo", true)],
+                &Some("Week 2".to_string()),
+            ),
+        ]
+    }
+
+    #[test]
+    fn export_then_import_keeps_every_question() {
+        let questions = questions();
+        let (text, issues) = export_gift(&questions);
+        assert!(issues.is_empty(), "{:?}", issues);
+
+        let imported = import_gift(&text);
+        assert!(imported.issues.is_empty(), "{:?}", imported.issues);
+        assert_eq!(
+            serde_json::to_value(&imported.questions).unwrap(),
+            serde_json::to_value(&questions).unwrap()
+        );
+
+        let (again, _) = export_gift(&imported.questions);
+        assert_eq!(again, text);
+    }
+
+    #[test]
+    fn malformed_items_are_skipped() {
+        for (text, message) in [
+            ("Unclosed {=yes ~no", "Answer block is not closed"),
+            ("}{", "Answer block is not closed"),
+            ("Dangling escape {=a\\", "Answer block is not closed"),
+            (
+                "::",
+                "Descriptions without an answer block are not supported",
+            ),
+            ("Numeric {#abc}", "\"abc\" is not a numeric answer"),
+            ("Numeric {#1..}", "\"1..\" is not a numeric answer"),
+            ("{#=%%}", "\"\" is not a numeric answer"),
+            (
+                "Matching {=a -> }",
+                "Question \"Matching\" is not a valid matching question",
+            ),
+        ] {
+            let imported = import_gift(text);
+            assert!(imported.questions.is_empty(), "{:?}", text);
+            assert_eq!(imported.issues.len(), 1, "{:?}", text);
+            assert!(imported.issues[0].skipped);
+            assert_eq!(imported.issues[0].message, message);
+        }
+
+        // Escapes before multi-byte characters and empty categories
+        let imported = import_gift("$CATEGORY:\n\nMulti-byte \\é {=ü ~ö}");
+        assert_eq!(imported.questions[0].question_text, "Multi-byte é");
+        assert_eq!(imported.questions[0].pool, None);
+    }
+
+    #[test]
+    fn unsupported_items_are_reported() {
+        let imported = import_gift("Essay {}\n\nDescription only\n\nOk {T}");
+        assert_eq!(imported.questions.len(), 1);
+        let skipped: Vec<&str> = imported
+            .issues
+            .iter()
+            .filter(|issue| issue.skipped)
+            .map(|issue| issue.message.as_str())
+            .collect();
+        assert_eq!(
+            skipped,
+            [
+                "Essay questions are not supported",
+                "Descriptions without an answer block are not supported"
+            ]
+        );
+    }
+}
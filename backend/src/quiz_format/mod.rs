@@ -0,0 +1,76 @@
+pub mod gift;
+pub mod qti;
+
+use crate::grading::validate_question;
+use crate::models::course::{CreateAnswerOptionPayload, CreateQuestionPayload, Question};
+use crate::models::quiz_format::FormatIssue;
+
+/// Issues found while converting the items of one question bank.
+#[derive(Default)]
+pub struct Issues(Vec<FormatIssue>);
+
+impl Issues {
+    /// Records a construct that was dropped or changed, the item is kept.
+    pub fn note(&mut self, item: &str, message: impl Into<String>) {
+        self.push(item, message.into(), false);
+    }
+
+    /// Records why an item is left out.
+    pub fn skip(&mut self, item: &str, message: impl Into<String>) {
+        self.push(item, message.into(), true);
+    }
+
+    fn push(&mut self, item: &str, message: String, skipped: bool) {
+        self.0.push(FormatIssue {
+            item: item.to_string(),
+            message,
+            skipped,
+        });
+    }
+
+    pub fn into_vec(self) -> Vec<FormatIssue> {
+        self.0
+    }
+}
+
+/// Keeps an imported question only if it can be graded, like questions of a
+/// create course payload.
+fn keep_valid(
+    question: CreateQuestionPayload,
+    item: &str,
+    issues: &mut Issues,
+) -> Option<CreateQuestionPayload> {
+    match validate_question(&question) {
+        Ok(()) => Some(question),
+        Err(e) => {
+            issues.skip(item, e);
+            None
+        }
+    }
+}
+
+/// The payload a stored question was created from, for exports.
+pub fn question_payload(question: &Question) -> CreateQuestionPayload {
+    CreateQuestionPayload {
+        question_text: question.question_text.clone(),
+        question_type: question.question_type,
+        points: question.points,
+        answers: question
+            .answers
+            .iter()
+            .map(|a| CreateAnswerOptionPayload {
+                answer_text: a.answer_text.clone(),
+                is_correct: a.is_correct,
+                match_text: a.match_text.clone(),
+            })
+            .collect(),
+        numeric_answer: question.numeric_answer,
+        numeric_tolerance: Some(question.numeric_tolerance),
+        case_sensitive: question.case_sensitive,
+        pool: question.pool.clone(),
+    }
+}
+
+fn item_label(position: usize) -> String {
+    format!("Question {}", position + 1)
+}
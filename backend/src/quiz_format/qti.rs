@@ -0,0 +1,1056 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Write};
+use zip::{CompressionMethod, ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use crate::models::course::{CreateAnswerOptionPayload, CreateQuestionPayload, QuestionType};
+use crate::models::quiz_format::{FormatIssue, ImportedQuiz, QtiVersion};
+use crate::quiz_format::{Issues, item_label, keep_valid};
+
+const MANIFEST: &str = "imsmanifest.xml";
+const RESPONSE: &str = "RESPONSE";
+// Inline text entries are shown as a blank in the question text
+const BLANK: &str = "_____";
+
+// Interactions of a question, other interactions are reported as unsupported
+const INTERACTIONS: [&str; 4] = [
+    "choiceInteraction",
+    "orderInteraction",
+    "matchInteraction",
+    "textEntryInteraction",
+];
+
+// Elements that only make sense rendered, their content is dropped
+const MEDIA: [&str; 4] = ["img", "math", "object", "audio"];
+
+// Elements closed with a line break in the question text
+const BLOCKS: [&str; 6] = ["p", "div", "br", "li", "h1", "h2"];
+
+/// Parses the items of a QTI 2.1 or 3.0 content package, or a single item
+/// document. An item becomes a question when it has exactly one supported
+/// interaction.
+pub fn import_qti(content: &str) -> Result<ImportedQuiz, String> {
+    let items = if content.trim_start().starts_with('<') {
+        vec![("item.xml".to_string(), content.to_string())]
+    } else {
+        let bytes = STANDARD
+            .decode(content.trim())
+            .map_err(|e| format!("QTI package is not valid base64: {}", e))?;
+        read_package(&bytes)?
+    };
+
+    let mut issues = Issues::default();
+    let mut questions = Vec::new();
+    for (position, (href, xml)) in items.iter().enumerate() {
+        let question = match Document::parse(xml) {
+            Ok(doc) => parse_item(&doc, href, position, &mut issues),
+            Err(e) => {
+                issues.skip(href, format!("Item is not valid XML: {}", e));
+                None
+            }
+        };
+        questions.extend(question);
+    }
+
+    Ok(ImportedQuiz {
+        questions,
+        issues: issues.into_vec(),
+    })
+}
+
+/// Writes questions as a QTI content package, one item per question with a
+/// manifest listing them. The pool of a question is kept as the item label.
+pub fn export_qti(
+    questions: &[CreateQuestionPayload],
+    version: QtiVersion,
+) -> Result<(Vec<u8>, Vec<FormatIssue>), String> {
+    let mut issues = Issues::default();
+    let mut files = Vec::new();
+    for (position, question) in questions.iter().enumerate() {
+        let identifier = format!("Q{}", position + 1);
+        let label = item_label(position);
+        if let Some(xml) = write_item(question, &identifier, &label, version, &mut issues) {
+            files.push((format!("items/{}.xml", identifier), identifier, xml));
+        }
+    }
+
+    let (manifest_ns, resource_type) = match version {
+        QtiVersion::V2p1 => (
+            "http://www.imsglobal.org/xsd/imscp_v1p1",
+            "imsqti_item_xmlv2p1",
+        ),
+        QtiVersion::V3p0 => (
+            "http://www.imsglobal.org/xsd/qti/qtiv3p0/imscp_v1p1",
+            "imsqti_item_xmlv3p0",
+        ),
+    };
+    let mut manifest = XmlWriter::new(None);
+    manifest.open(
+        "manifest",
+        &[("xmlns", manifest_ns), ("identifier", "MANIFEST-ARANYA")],
+    );
+    manifest.empty("organizations", &[]);
+    manifest.open("resources", &[]);
+    for (href, identifier, _) in &files {
+        manifest.open(
+            "resource",
+            &[
+                ("identifier", identifier),
+                ("type", resource_type),
+                ("href", href),
+            ],
+        );
+        manifest.empty("file", &[("href", href)]);
+        manifest.close();
+    }
+    manifest.close();
+    manifest.close();
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let entries = std::iter::once((MANIFEST.to_string(), manifest.finish()))
+        .chain(files.into_iter().map(|(href, _, xml)| (href, xml)));
+    for (name, xml) in entries {
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        zip.write_all(xml.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    let bytes = zip.finish().map_err(|e| e.to_string())?.into_inner();
+
+    Ok((bytes, issues.into_vec()))
+}
+
+// Item documents in the order of the manifest, or of the package without one
+fn read_package(bytes: &[u8]) -> Result<Vec<(String, String)>, String> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| format!("QTI package is not a zip file: {}", e))?;
+
+    let hrefs: Vec<String> = match read_entry(&mut archive, MANIFEST) {
+        Some(manifest) => {
+            let doc = Document::parse(&manifest)
+                .map_err(|e| format!("Manifest is not valid XML: {}", e))?;
+            doc.descendants()
+                .filter(|n| n.tag_name().name() == "resource")
+                .filter(|n| {
+                    n.attribute("type")
+                        .is_some_and(|t| t.starts_with("imsqti_item"))
+                })
+                .filter_map(|n| {
+                    n.attribute("href").or_else(|| {
+                        n.children()
+                            .find(|c| c.tag_name().name() == "file")
+                            .and_then(|f| f.attribute("href"))
+                    })
+                })
+                .map(str::to_string)
+                .collect()
+        }
+        None => {
+            let mut names: Vec<String> = archive
+                .file_names()
+                .filter(|name| name.ends_with(".xml"))
+                .map(str::to_string)
+                .collect();
+            names.sort();
+            names
+        }
+    };
+
+    Ok(hrefs
+        .into_iter()
+        .filter_map(|href| read_entry(&mut archive, &href).map(|xml| (href, xml)))
+        .collect())
+}
+
+fn read_entry(archive: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> Option<String> {
+    let mut file = archive.by_name(name).ok()?;
+    let mut xml = String::new();
+    file.read_to_string(&mut xml).ok()?;
+    Some(xml)
+}
+
+fn parse_item(
+    doc: &Document,
+    href: &str,
+    position: usize,
+    issues: &mut Issues,
+) -> Option<CreateQuestionPayload> {
+    let root = doc.root_element();
+    if name(root) != "assessmentItem" {
+        // Tests and other documents listed without a manifest
+        return None;
+    }
+    let label = attr(root, "title")
+        .or(attr(root, "identifier"))
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{} ({})", item_label(position), href));
+
+    let Some(body) = child(root, "itemBody") else {
+        issues.skip(&label, "Item has no item body");
+        return None;
+    };
+    let interactions: Vec<Node> = body
+        .descendants()
+        .filter(|n| n.is_element() && name(*n).ends_with("Interaction"))
+        .collect();
+    let interaction = match interactions.as_slice() {
+        [] => {
+            issues.skip(&label, "Item has no interaction");
+            return None;
+        }
+        [interaction] => *interaction,
+        _ => {
+            issues.skip(
+                &label,
+                "Items with more than one interaction are not supported",
+            );
+            return None;
+        }
+    };
+    let kind = name(interaction);
+    if !INTERACTIONS.contains(&kind.as_str()) {
+        issues.skip(&label, format!("{} is not supported", kind));
+        return None;
+    }
+
+    if body
+        .descendants()
+        .any(|n| n.is_element() && MEDIA.contains(&n.tag_name().name()))
+    {
+        issues.note(
+            &label,
+            "Images, math and media are not supported and were dropped",
+        );
+    }
+    if child(root, "modalFeedback").is_some() {
+        issues.note(&label, "Feedback is not supported and was dropped");
+    }
+
+    let mut question_text = String::new();
+    body_text(body, interaction, &mut question_text);
+    if let Some(prompt) = child(interaction, "prompt") {
+        question_text.push('\n');
+        question_text.push_str(&text_of(prompt));
+    }
+    let question_text = normalize_lines(&question_text);
+
+    let response_id = attr(interaction, "responseIdentifier").unwrap_or(RESPONSE);
+    let declaration = root
+        .children()
+        .find(|n| name(*n) == "responseDeclaration" && attr(*n, "identifier") == Some(response_id));
+    let correct: Vec<String> = declaration
+        .and_then(|d| child(d, "correctResponse"))
+        .map(|c| {
+            c.children()
+                .filter(|n| name(*n) == "value")
+                .map(|v| text_of(v))
+                .collect()
+        })
+        .unwrap_or_default();
+    // Mapped keys worth points, with whether they are case sensitive
+    let mapped: Vec<(String, bool)> = declaration
+        .and_then(|d| child(d, "mapping"))
+        .map(|m| {
+            m.children()
+                .filter(|n| name(*n) == "mapEntry")
+                .filter(|n| {
+                    attr(*n, "mappedValue")
+                        .and_then(|v| v.parse::<f64>().ok())
+                        .is_some_and(|v| v > 0.0)
+                })
+                .filter_map(|n| {
+                    Some((
+                        attr(n, "mapKey")?.to_string(),
+                        attr(n, "caseSensitive") == Some("true"),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let points = root
+        .children()
+        .find(|n| name(*n) == "outcomeDeclaration" && attr(*n, "identifier") == Some("MAXSCORE"))
+        .and_then(|d| child(d, "defaultValue"))
+        .and_then(|d| child(d, "value"))
+        .and_then(|v| text_of(v).parse::<f64>().ok())
+        .unwrap_or(1.0);
+
+    let mut question = CreateQuestionPayload {
+        question_text,
+        question_type: QuestionType::SingleChoice,
+        points,
+        answers: Vec::new(),
+        numeric_answer: None,
+        numeric_tolerance: None,
+        case_sensitive: false,
+        pool: attr(root, "label").map(str::to_string),
+    };
+
+    match kind.as_str() {
+        "choiceInteraction" => {
+            let correct_ids: Vec<&str> = if correct.is_empty() {
+                mapped.iter().map(|(key, _)| key.as_str()).collect()
+            } else {
+                correct.iter().map(String::as_str).collect()
+            };
+            question.answers = children(interaction, "simpleChoice")
+                .map(|c| CreateAnswerOptionPayload {
+                    answer_text: text_of(c),
+                    is_correct: attr(c, "identifier").is_some_and(|id| correct_ids.contains(&id)),
+                    match_text: None,
+                })
+                .collect();
+
+            let cardinality = declaration.and_then(|d| attr(d, "cardinality"));
+            let is_true_false = question.answers.len() == 2
+                && question.answers.iter().all(|a| {
+                    ["true", "false"].contains(&a.answer_text.trim().to_lowercase().as_str())
+                });
+            question.question_type = if cardinality == Some("multiple") {
+                QuestionType::MultipleChoice
+            } else if is_true_false {
+                QuestionType::TrueFalse
+            } else {
+                QuestionType::SingleChoice
+            };
+        }
+        "orderInteraction" => {
+            let choices: HashMap<&str, String> = children(interaction, "simpleChoice")
+                .filter_map(|c| Some((attr(c, "identifier")?, text_of(c))))
+                .collect();
+            if correct.len() < choices.len() {
+                issues.note(&label, "Choices outside of the correct order were dropped");
+            }
+            question.question_type = QuestionType::Ordering;
+            question.answers = correct
+                .iter()
+                .filter_map(|id| choices.get(id.as_str()))
+                .map(|text| CreateAnswerOptionPayload {
+                    answer_text: text.clone(),
+                    is_correct: true,
+                    match_text: None,
+                })
+                .collect();
+        }
+        "matchInteraction" => {
+            let sets: Vec<Node> = children(interaction, "simpleMatchSet").collect();
+            let [sources, targets] = sets.as_slice() else {
+                issues.skip(&label, "Match interaction needs two match sets");
+                return None;
+            };
+            let targets: HashMap<&str, String> = children(*targets, "simpleAssociableChoice")
+                .filter_map(|c| Some((attr(c, "identifier")?, text_of(c))))
+                .collect();
+            let pairs: Vec<(&str, &str)> = correct
+                .iter()
+                .filter_map(|pair| pair.split_once(char::is_whitespace))
+                .map(|(source, target)| (source.trim(), target.trim()))
+                .collect();
+
+            question.question_type = QuestionType::Matching;
+            for source in children(*sources, "simpleAssociableChoice") {
+                let id = attr(source, "identifier").unwrap_or_default();
+                let mut matches = pairs.iter().filter(|(s, _)| *s == id);
+                let Some(target) = matches.next().and_then(|(_, t)| targets.get(t)) else {
+                    issues.note(&label, format!("{} has no match and was dropped", id));
+                    continue;
+                };
+                if matches.next().is_some() {
+                    issues.note(&label, format!("Only the first match of {} was kept", id));
+                }
+                question.answers.push(CreateAnswerOptionPayload {
+                    answer_text: text_of(source),
+                    is_correct: false,
+                    match_text: Some(target.clone()),
+                });
+            }
+            let used = targets
+                .keys()
+                .filter(|t| pairs.iter().any(|(_, target)| target == *t))
+                .count();
+            if used < targets.len() {
+                issues.note(
+                    &label,
+                    "Unmatched targets are not supported and were dropped",
+                );
+            }
+        }
+        _ => {
+            let base_type = declaration
+                .and_then(|d| attr(d, "baseType"))
+                .unwrap_or("string");
+            if matches!(base_type, "float" | "integer") {
+                question.question_type = QuestionType::Numeric;
+                question.numeric_answer = correct.first().and_then(|v| v.parse().ok());
+                question.numeric_tolerance = Some(numeric_tolerance(
+                    root,
+                    question.numeric_answer.unwrap_or_default(),
+                ));
+            } else {
+                question.question_type = QuestionType::ShortText;
+                // Exact matching unless a mapping says otherwise
+                question.case_sensitive =
+                    mapped.is_empty() || mapped.iter().any(|(_, case_sensitive)| *case_sensitive);
+                let mut accepted: Vec<String> = correct.clone();
+                for (key, _) in &mapped {
+                    if !accepted.contains(key) {
+                        accepted.push(key.clone());
+                    }
+                }
+                question.answers = accepted
+                    .into_iter()
+                    .map(|answer_text| CreateAnswerOptionPayload {
+                        answer_text,
+                        is_correct: true,
+                        match_text: None,
+                    })
+                    .collect();
+            }
+        }
+    }
+
+    keep_valid(question, &label, issues)
+}
+
+// Tolerance of an `equal` in the response processing, 0 for exact answers
+fn numeric_tolerance(root: Node, answer: f64) -> f64 {
+    let Some(equal) = child(root, "responseProcessing").and_then(|rp| {
+        rp.descendants()
+            .find(|n| n.is_element() && name(*n) == "equal")
+    }) else {
+        return 0.0;
+    };
+    let tolerance = attr(equal, "tolerance")
+        .unwrap_or_default()
+        .split_whitespace()
+        .filter_map(|t| t.parse::<f64>().ok())
+        .fold(0.0, f64::max);
+    match attr(equal, "toleranceMode") {
+        Some("absolute") => tolerance,
+        Some("relative") => (answer * tolerance / 100.0).abs(),
+        _ => 0.0,
+    }
+}
+
+fn write_item(
+    question: &CreateQuestionPayload,
+    identifier: &str,
+    label: &str,
+    version: QtiVersion,
+    issues: &mut Issues,
+) -> Option<String> {
+    let (item_ns, templates) = match version {
+        QtiVersion::V2p1 => (
+            "http://www.imsglobal.org/xsd/imsqti_v2p1",
+            "http://www.imsglobal.org/question/qti_v2p1/rptemplates/",
+        ),
+        QtiVersion::V3p0 => (
+            "http://www.imsglobal.org/xsd/imsqtiasi_v3p0",
+            "https://purl.imsglobal.org/spec/qti/v3p0/rptemplates/",
+        ),
+    };
+    let template_suffix = match version {
+        QtiVersion::V2p1 => "",
+        QtiVersion::V3p0 => ".xml",
+    };
+
+    let answer_ids: Vec<String> = (1..=question.answers.len())
+        .map(|i| format!("A{}", i))
+        .collect();
+    // Matching targets, one per distinct match text
+    let mut targets: Vec<&str> = Vec::new();
+    for a in &question.answers {
+        let target = a.match_text.as_deref().unwrap_or_default();
+        if !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+    let target_id = |text: &str| {
+        format!(
+            "M{}",
+            targets.iter().position(|t| *t == text).unwrap_or_default() + 1
+        )
+    };
+
+    let tolerance = question.numeric_tolerance.unwrap_or(0.0);
+    let (cardinality, base_type, correct): (&str, &str, Vec<String>) = match question.question_type
+    {
+        QuestionType::SingleChoice | QuestionType::TrueFalse | QuestionType::MultipleChoice => (
+            if question.question_type == QuestionType::MultipleChoice {
+                "multiple"
+            } else {
+                "single"
+            },
+            "identifier",
+            question
+                .answers
+                .iter()
+                .zip(&answer_ids)
+                .filter(|(a, _)| a.is_correct)
+                .map(|(_, id)| id.clone())
+                .collect(),
+        ),
+        QuestionType::Ordering => ("ordered", "identifier", answer_ids.clone()),
+        QuestionType::Matching => (
+            "multiple",
+            "directedPair",
+            question
+                .answers
+                .iter()
+                .zip(&answer_ids)
+                .map(|(a, id)| {
+                    format!(
+                        "{} {}",
+                        id,
+                        target_id(a.match_text.as_deref().unwrap_or_default())
+                    )
+                })
+                .collect(),
+        ),
+        QuestionType::Numeric => {
+            let Some(value) = question.numeric_answer else {
+                issues.skip(label, "Numeric question has no answer");
+                return None;
+            };
+            ("single", "float", vec![value.to_string()])
+        }
+        QuestionType::ShortText => (
+            "single",
+            "string",
+            question
+                .answers
+                .first()
+                .map(|a| a.answer_text.clone())
+                .into_iter()
+                .collect(),
+        ),
+    };
+    if question.question_type == QuestionType::MultipleChoice {
+        issues.note(
+            label,
+            "Partial credit is not exported, the match correct template scores all or nothing",
+        );
+    }
+
+    let points = question.points.to_string();
+    let mut xml = XmlWriter::new(Some(version));
+    let mut root_attrs = vec![
+        ("xmlns", item_ns),
+        ("identifier", identifier),
+        ("title", label),
+        ("adaptive", "false"),
+        ("timeDependent", "false"),
+    ];
+    if let Some(pool) = &question.pool {
+        root_attrs.push(("label", pool));
+    }
+    xml.open("assessmentItem", &root_attrs);
+
+    xml.open(
+        "responseDeclaration",
+        &[
+            ("identifier", RESPONSE),
+            ("cardinality", cardinality),
+            ("baseType", base_type),
+        ],
+    );
+    xml.open("correctResponse", &[]);
+    for value in &correct {
+        xml.leaf("value", &[], value);
+    }
+    xml.close();
+    if question.question_type == QuestionType::ShortText {
+        let case_sensitive = question.case_sensitive.to_string();
+        xml.open("mapping", &[("defaultValue", "0")]);
+        for a in &question.answers {
+            xml.empty(
+                "mapEntry",
+                &[
+                    ("mapKey", &a.answer_text),
+                    ("mappedValue", &points),
+                    ("caseSensitive", &case_sensitive),
+                ],
+            );
+        }
+        xml.close();
+    }
+    xml.close();
+
+    xml.empty(
+        "outcomeDeclaration",
+        &[
+            ("identifier", "SCORE"),
+            ("cardinality", "single"),
+            ("baseType", "float"),
+        ],
+    );
+    xml.open(
+        "outcomeDeclaration",
+        &[
+            ("identifier", "MAXSCORE"),
+            ("cardinality", "single"),
+            ("baseType", "float"),
+        ],
+    );
+    xml.open("defaultValue", &[]);
+    xml.leaf("value", &[], &points);
+    xml.close();
+    xml.close();
+
+    xml.open("itemBody", &[]);
+    for line in question
+        .question_text
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+    {
+        xml.leaf("p", &[], line.trim());
+    }
+    match question.question_type {
+        QuestionType::SingleChoice | QuestionType::TrueFalse | QuestionType::MultipleChoice => {
+            let max_choices = if cardinality == "multiple" { "0" } else { "1" };
+            xml.open(
+                "choiceInteraction",
+                &[
+                    ("responseIdentifier", RESPONSE),
+                    ("shuffle", "true"),
+                    ("maxChoices", max_choices),
+                ],
+            );
+            for (a, id) in question.answers.iter().zip(&answer_ids) {
+                xml.leaf("simpleChoice", &[("identifier", id)], &a.answer_text);
+            }
+            xml.close();
+        }
+        QuestionType::Ordering => {
+            xml.open(
+                "orderInteraction",
+                &[("responseIdentifier", RESPONSE), ("shuffle", "true")],
+            );
+            for (a, id) in question.answers.iter().zip(&answer_ids) {
+                xml.leaf("simpleChoice", &[("identifier", id)], &a.answer_text);
+            }
+            xml.close();
+        }
+        QuestionType::Matching => {
+            let max_associations = question.answers.len().to_string();
+            xml.open(
+                "matchInteraction",
+                &[
+                    ("responseIdentifier", RESPONSE),
+                    ("shuffle", "true"),
+                    ("maxAssociations", &max_associations),
+                ],
+            );
+            xml.open("simpleMatchSet", &[]);
+            for (a, id) in question.answers.iter().zip(&answer_ids) {
+                xml.leaf(
+                    "simpleAssociableChoice",
+                    &[("identifier", id), ("matchMax", "1")],
+                    &a.answer_text,
+                );
+            }
+            xml.close();
+            xml.open("simpleMatchSet", &[]);
+            for target in &targets {
+                xml.leaf(
+                    "simpleAssociableChoice",
+                    &[("identifier", &target_id(target)), ("matchMax", "0")],
+                    target,
+                );
+            }
+            xml.close();
+            xml.close();
+        }
+        QuestionType::Numeric | QuestionType::ShortText => {
+            xml.open("p", &[]);
+            xml.empty("textEntryInteraction", &[("responseIdentifier", RESPONSE)]);
+            xml.close();
+        }
+    }
+    xml.close();
+
+    if question.question_type == QuestionType::Numeric && tolerance > 0.0 {
+        let tolerance = format!("{} {}", tolerance, tolerance);
+        xml.open("responseProcessing", &[]);
+        xml.open("responseCondition", &[]);
+        xml.open("responseIf", &[]);
+        xml.open(
+            "equal",
+            &[("toleranceMode", "absolute"), ("tolerance", &tolerance)],
+        );
+        xml.empty("variable", &[("identifier", RESPONSE)]);
+        xml.empty("correct", &[("identifier", RESPONSE)]);
+        xml.close();
+        xml.open("setOutcomeValue", &[("identifier", "SCORE")]);
+        xml.empty("variable", &[("identifier", "MAXSCORE")]);
+        xml.close();
+        xml.close();
+        xml.close();
+        xml.close();
+    } else {
+        let template = if question.question_type == QuestionType::ShortText {
+            "map_response"
+        } else {
+            "match_correct"
+        };
+        let template = format!("{}{}{}", templates, template, template_suffix);
+        xml.empty("responseProcessing", &[("template", &template)]);
+    }
+
+    xml.close();
+    Some(xml.finish())
+}
+
+/// Indented XML with QTI 3.0 names when writing 3.0 items.
+pub(crate) struct XmlWriter {
+    out: String,
+    version: Option<QtiVersion>,
+    open: Vec<String>,
+}
+
+impl XmlWriter {
+    pub(crate) fn new(version: Option<QtiVersion>) -> Self {
+        XmlWriter {
+            out: String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"),
+            version,
+            open: Vec::new(),
+        }
+    }
+
+    pub(crate) fn open(&mut self, element: &str, attrs: &[(&str, &str)]) {
+        let tag = self.start_tag(element, attrs);
+        self.out.push_str(&format!("{}>\n", tag));
+        self.open.push(self.element_name(element));
+    }
+
+    pub(crate) fn close(&mut self) {
+        if let Some(element) = self.open.pop() {
+            self.indent();
+            self.out.push_str(&format!("</{}>\n", element));
+        }
+    }
+
+    pub(crate) fn empty(&mut self, element: &str, attrs: &[(&str, &str)]) {
+        let tag = self.start_tag(element, attrs);
+        self.out.push_str(&format!("{}/>\n", tag));
+    }
+
+    pub(crate) fn leaf(&mut self, element: &str, attrs: &[(&str, &str)], text: &str) {
+        let tag = self.start_tag(element, attrs);
+        self.out.push_str(&format!(
+            "{}>{}</{}>\n",
+            tag,
+            escape_xml(text),
+            self.element_name(element)
+        ));
+    }
+
+    fn start_tag(&mut self, element: &str, attrs: &[(&str, &str)]) -> String {
+        self.indent();
+        let mut tag = format!("<{}", self.element_name(element));
+        for (attr, value) in attrs {
+            tag.push_str(&format!(
+                " {}=\"{}\"",
+                self.attr_name(attr),
+                escape_xml(value)
+            ));
+        }
+        tag
+    }
+
+    fn indent(&mut self) {
+        self.out.push_str(&"  ".repeat(self.open.len()));
+    }
+
+    fn element_name(&self, element: &str) -> String {
+        match self.version {
+            Some(QtiVersion::V3p0) if !BLOCKS.contains(&element) => {
+                format!("qti-{}", kebab_case(element))
+            }
+            _ => element.to_string(),
+        }
+    }
+
+    fn attr_name(&self, attr: &str) -> String {
+        match self.version {
+            Some(QtiVersion::V3p0) => kebab_case(attr),
+            _ => attr.to_string(),
+        }
+    }
+
+    pub(crate) fn finish(self) -> String {
+        self.out
+    }
+}
+
+// QTI 2.1 name of an element, `qti-choice-interaction` in 3.0 is `choiceInteraction`
+fn name(node: Node) -> String {
+    let name = node.tag_name().name();
+    match name.strip_prefix("qti-") {
+        Some(rest) => camel_case(rest),
+        None => name.to_string(),
+    }
+}
+
+fn attr<'a>(node: Node<'a, '_>, camel: &str) -> Option<&'a str> {
+    node.attribute(camel)
+        .or_else(|| node.attribute(kebab_case(camel).as_str()))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, camel: &str) -> Option<Node<'a, 'input>> {
+    node.children()
+        .find(|n| n.is_element() && name(*n) == camel)
+}
+
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    camel: &str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |n| n.is_element() && name(*n) == camel)
+}
+
+// Question text of the item body, the interaction left out or shown as a blank
+fn body_text(node: Node, interaction: Node, out: &mut String) {
+    for n in node.children() {
+        if n == interaction {
+            let inline = node
+                .children()
+                .any(|c| c.is_text() && !c.text().unwrap_or_default().trim().is_empty());
+            if inline {
+                out.push_str(&format!(" {} ", BLANK));
+            }
+        } else if n.is_text() {
+            out.push_str(n.text().unwrap_or_default());
+        } else if n.is_element() && !MEDIA.contains(&n.tag_name().name()) {
+            body_text(n, interaction, out);
+            if BLOCKS.contains(&n.tag_name().name()) {
+                out.push('\n');
+            }
+        }
+    }
+}
+
+fn text_of(node: Node) -> String {
+    let text: String = node
+        .descendants()
+        .filter(|n| n.is_text())
+        .filter_map(|n| n.text())
+        .collect();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn normalize_lines(text: &str) -> String {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn kebab_case(camel: &str) -> String {
+    let mut out = String::with_capacity(camel.len() + 4);
+    for c in camel.chars() {
+        if c.is_ascii_uppercase() {
+            out.push('-');
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn camel_case(kebab: &str) -> String {
+    let mut out = String::with_capacity(kebab.len());
+    let mut upper = false;
+    for c in kebab.chars() {
+        if c == '-' {
+            upper = true;
+        } else if upper {
+            out.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(answer_text: &str, is_correct: bool) -> CreateAnswerOptionPayload {
+        CreateAnswerOptionPayload {
+            answer_text: answer_text.to_string(),
+            is_correct,
+            match_text: None,
+        }
+    }
+
+    fn question(
+        question_text: &str,
+        question_type: QuestionType,
+        answers: Vec<CreateAnswerOptionPayload>,
+    ) -> CreateQuestionPayload {
+        CreateQuestionPayload {
+            question_text: question_text.to_string(),
+            question_type,
+            points: 2.0,
+            answers,
+            numeric_answer: None,
+            numeric_tolerance: None,
+            case_sensitive: false,
+            pool: Some("Week 1".to_string()),
+        }
+    }
+
+    fn questions() -> Vec<CreateQuestionPayload> {
+        vec![
+            question(
+                "Which is <less> & \"quoted\"?",
+                QuestionType::SingleChoice,
+                vec![answer("a < b", true), answer("a > b", false)],
+            ),
+            question(
+                "Pick the primes",
+                QuestionType::MultipleChoice,
+                vec![answer("2", true), answer("3", true), answer("4", false)],
+            ),
+            question(
+                "Rust has a borrow checker",
+                QuestionType::TrueFalse,
+                vec![answer("True", true), answer("False", false)],
+            ),
+            question(
+                "Order the build steps",
+                QuestionType::Ordering,
+                vec![
+                    answer("Parse", true),
+                    answer("Check", true),
+                    answer("Codegen", true),
+                ],
+            ),
+            question(
+                "Match the keywords",
+                QuestionType::Matching,
+                vec![
+                    CreateAnswerOptionPayload {
+                        match_text: Some("Binding".to_string()),
+                        ..answer("let", false)
+                    },
+                    CreateAnswerOptionPayload {
+                        match_text: Some("Function".to_string()),
+                        ..answer("fn", false)
+                    },
+                ],
+            ),
+            CreateQuestionPayload {
+                numeric_answer: Some(3.5),
+                numeric_tolerance: Some(0.25),
+                ..question("Half of seven", QuestionType::Numeric, Vec::new())
+            },
+            CreateQuestionPayload {
+                case_sensitive: true,
+                ..question(
+                    "Name the package manager",
+                    QuestionType::ShortText,
+                    vec![answer("cargo", true), answer("Cargo", true)],
+                )
+            },
+        ]
+    }
+
+    #[test]
+    fn export_then_import_keeps_every_question() {
+        let questions = questions();
+        for version in [QtiVersion::V2p1, QtiVersion::V3p0] {
+            let (package, issues) = export_qti(&questions, version).unwrap();
+            let skipped: Vec<&FormatIssue> = issues.iter().filter(|i| i.skipped).collect();
+            assert!(skipped.is_empty(), "{:?}", skipped);
+
+            let imported = import_qti(&STANDARD.encode(&package)).unwrap();
+            assert!(imported.issues.is_empty(), "{:?}", imported.issues);
+            assert_eq!(
+                serde_json::to_value(&imported.questions).unwrap(),
+                serde_json::to_value(&questions).unwrap()
+            );
+        }
+    }
+
+    fn package(entries: &[(&str, &str)]) -> String {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        STANDARD.encode(zip.finish().unwrap().into_inner())
+    }
+
+    #[test]
+    fn malformed_packages_are_rejected() {
+        for (content, error) in [
+            ("not base64!".to_string(), "QTI package is not valid base64"),
+            (
+                STANDARD.encode("plain text"),
+                "QTI package is not a zip file",
+            ),
+            (
+                package(&[(MANIFEST, "<manifest><resources>")]),
+                "Manifest is not valid XML",
+            ),
+        ] {
+            let e = import_qti(&content).err().unwrap();
+            assert!(e.starts_with(error), "{}", e);
+        }
+    }
+
+    #[test]
+    fn malformed_items_are_skipped() {
+        // Manifest entries outside of the package are ignored
+        let manifest = r#"<manifest><resources>
+            <resource type="imsqti_item_xmlv2p1" href="../outside.xml"/>
+            <resource type="imsqti_item_xmlv2p1" href="items/broken.xml"/>
+            <resource type="imsqti_item_xmlv2p1" href="items/empty.xml"/>
+            <resource type="imsqti_item_xmlv2p1" href="items/slider.xml"/>
+        </resources></manifest>"#;
+        let imported = import_qti(&package(&[
+            (MANIFEST, manifest),
+            ("items/broken.xml", "<assessmentItem><itemBody>"),
+            ("items/empty.xml", r#"<assessmentItem identifier="empty"/>"#),
+            (
+                "items/slider.xml",
+                r#"<assessmentItem identifier="slider"><itemBody><sliderInteraction/></itemBody></assessmentItem>"#,
+            ),
+        ]))
+        .unwrap();
+
+        assert!(imported.questions.is_empty());
+        let skipped: Vec<(&str, bool)> = imported
+            .issues
+            .iter()
+            .map(|issue| (issue.item.as_str(), issue.skipped))
+            .collect();
+        assert_eq!(
+            skipped,
+            [
+                ("items/broken.xml", true),
+                ("empty", true),
+                ("slider", true)
+            ]
+        );
+        assert_eq!(
+            imported.issues[2].message,
+            "sliderInteraction is not supported"
+        );
+    }
+}
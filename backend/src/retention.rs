@@ -0,0 +1,35 @@
+use sqlx::{Pool, Postgres};
+
+const REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// Background task that keeps the `cohort_retention` materialized view
+/// current, since cohorts are computed over every enrollment.
+pub struct RetentionRefresher {
+    pool: Pool<Postgres>,
+}
+
+impl RetentionRefresher {
+    pub fn new(pool: Pool<Postgres>) -> RetentionRefresher {
+        RetentionRefresher { pool }
+    }
+
+    pub fn spawn(self) {
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = self.refresh().await {
+                    eprintln!("Cohort retention refresh error: {}", e);
+                }
+                tokio::time::sleep(REFRESH_INTERVAL).await;
+            }
+        });
+    }
+
+    // Concurrently so retention stays readable during the refresh
+    async fn refresh(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("REFRESH MATERIALIZED VIEW CONCURRENTLY cohort_retention")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
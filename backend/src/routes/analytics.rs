@@ -0,0 +1,15 @@
+use axum::{Router, routing::get};
+use sqlx::{Pool, Postgres};
+
+use crate::handlers::analytics::{
+    get_cohort_retention, get_course_analytics, get_platform_retention, get_quiz_analytics,
+};
+
+pub fn analytics_routes(pool: Pool<Postgres>) -> Router {
+    Router::new()
+        .route("/get-cohort-retention", get(get_cohort_retention))
+        .route("/get-course-analytics", get(get_course_analytics))
+        .route("/get-platform-retention", get(get_platform_retention))
+        .route("/get-quiz-analytics", get(get_quiz_analytics))
+        .with_state(pool)
+}
@@ -0,0 +1,32 @@
+use axum::{
+    Router, middleware,
+    routing::{get, post},
+};
+use sqlx::{Pool, Postgres};
+
+use crate::handlers::attestation::{
+    AttestationState, get_attestation_public_key, get_attestation_snapshot, record_snapshot,
+    request_attestation_nonce,
+};
+use crate::handlers::course::{get_course_creator, get_num_completed};
+use crate::handlers::progress::{get_course_progress_percentage, is_enrolled};
+use crate::signing::ServerKey;
+
+// Endpoints queried by the FDC Web2Json verifier
+pub fn attestation_routes(pool: Pool<Postgres>, key: ServerKey) -> Router {
+    let state = AttestationState { pool, key };
+
+    let attested = Router::new()
+        .route("/get-course-creator", get(get_course_creator))
+        .route("/is-enrolled", get(is_enrolled))
+        .route("/get-num-completed", get(get_num_completed))
+        .route("/get-course-progress-percentage", get(get_course_progress_percentage))
+        .route_layer(middleware::from_fn_with_state(state.clone(), record_snapshot));
+
+    Router::new()
+        .merge(attested)
+        .route("/request-attestation-nonce", post(request_attestation_nonce))
+        .route("/get-attestation-snapshot", get(get_attestation_snapshot))
+        .route("/get-attestation-public-key", get(get_attestation_public_key))
+        .with_state(state)
+}
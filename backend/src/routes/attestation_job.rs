@@ -0,0 +1,17 @@
+use axum::{
+    Router,
+    routing::{get, post},
+};
+use sqlx::{Pool, Postgres};
+
+use crate::handlers::attestation_job::{
+    create_attestation_job, get_attestation_job, get_attestation_jobs,
+};
+
+pub fn attestation_job_routes(pool: Pool<Postgres>) -> Router {
+    Router::new()
+        .route("/create-attestation-job", post(create_attestation_job))
+        .route("/get-attestation-job", get(get_attestation_job))
+        .route("/get-attestation-jobs", get(get_attestation_jobs))
+        .with_state(pool)
+}
@@ -0,0 +1,31 @@
+use axum::{
+    Router,
+    routing::{get, post},
+};
+
+use crate::handlers::badge::{
+    get_badge_assertion, get_badge_issuer, get_badges, get_course_achievement,
+    get_creator_milestone_achievement, verify_badge,
+};
+use crate::handlers::credential::CredentialState;
+
+// Open Badges 3.0 achievements and assertions, served at stable URLs
+pub fn badge_routes(state: CredentialState) -> Router {
+    Router::new()
+        .route("/badges/issuer", get(get_badge_issuer))
+        .route(
+            "/badges/achievements/course/{course_id}",
+            get(get_course_achievement),
+        )
+        .route(
+            "/badges/achievements/course/{course_id}/creator-milestone/{milestone}",
+            get(get_creator_milestone_achievement),
+        )
+        .route(
+            "/badges/assertions/{assertion_id}",
+            get(get_badge_assertion),
+        )
+        .route("/get-badges", get(get_badges))
+        .route("/verify-badge", post(verify_badge))
+        .with_state(state)
+}
@@ -0,0 +1,28 @@
+use axum::{
+    Router,
+    extract::DefaultBodyLimit,
+    routing::{get, post},
+};
+
+use crate::bundle::MAX_BUNDLE_BYTES;
+use crate::handlers::bundle::{export_course, get_media, import_course};
+use crate::handlers::common_cartridge::export_common_cartridge;
+use crate::handlers::markdown_course::{export_markdown_course, import_markdown_course};
+use crate::state::AppState;
+
+pub fn bundle_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/export-course", get(export_course))
+        .route(
+            "/import-course",
+            post(import_course).layer(DefaultBodyLimit::max(MAX_BUNDLE_BYTES)),
+        )
+        .route("/export-markdown-course", get(export_markdown_course))
+        .route(
+            "/import-markdown-course",
+            post(import_markdown_course).layer(DefaultBodyLimit::max(MAX_BUNDLE_BYTES)),
+        )
+        .route("/export-common-cartridge", get(export_common_cartridge))
+        .route("/media/{sha256}", get(get_media))
+        .with_state(state)
+}
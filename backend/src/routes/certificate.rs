@@ -0,0 +1,15 @@
+use axum::{
+    Router,
+    routing::{get, post},
+};
+
+use crate::handlers::certificate::{get_certificate, set_learner_display_name, verify_certificate};
+use crate::state::AppState;
+
+pub fn certificate_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/get-certificate", get(get_certificate))
+        .route("/verify-certificate", get(verify_certificate))
+        .route("/set-learner-display-name", post(set_learner_display_name))
+        .with_state(state)
+}
@@ -0,0 +1,19 @@
+use axum::{
+    Router,
+    routing::{get, post},
+};
+
+use crate::handlers::credential::{
+    CredentialState, get_credential, get_credential_jwt, get_status_list, revoke_credential,
+    verify_credential,
+};
+
+pub fn credential_routes(state: CredentialState) -> Router {
+    Router::new()
+        .route("/get-credential", get(get_credential))
+        .route("/verify-credential", post(verify_credential))
+        .route("/revoke-credential", post(revoke_credential))
+        .route("/credentials/{credential_id}", get(get_credential_jwt))
+        .route("/credentials/status/{list_id}", get(get_status_list))
+        .with_state(state)
+}
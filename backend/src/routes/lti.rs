@@ -0,0 +1,17 @@
+use axum::{
+    Router,
+    routing::{get, post},
+};
+
+use crate::handlers::lti::{
+    LtiState, get_lti_jwks, lti_deep_link, lti_launch, lti_login, lti_login_form,
+};
+
+pub fn lti_routes(state: LtiState) -> Router {
+    Router::new()
+        .route("/lti/jwks", get(get_lti_jwks))
+        .route("/lti/login", get(lti_login).post(lti_login_form))
+        .route("/lti/launch", post(lti_launch))
+        .route("/lti/deep-link", post(lti_deep_link))
+        .with_state(state)
+}
@@ -1,2 +1,16 @@
+pub mod analytics;
+pub mod attestation;
+pub mod attestation_job;
+pub mod badge;
+pub mod bundle;
+pub mod certificate;
 pub mod course;
-pub mod progress;
\ No newline at end of file
+pub mod credential;
+pub mod lti;
+pub mod nft;
+pub mod progress;
+pub mod progress_export;
+pub mod quiz_format;
+pub mod scorm;
+pub mod stats;
+pub mod xapi;
\ No newline at end of file
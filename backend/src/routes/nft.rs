@@ -0,0 +1,15 @@
+use axum::{
+    Router,
+    routing::{get, post},
+};
+use sqlx::{Pool, Postgres};
+
+use crate::handlers::nft::{get_nft_metadata, register_course_nfts, register_learner_nft};
+
+pub fn nft_routes(pool: Pool<Postgres>) -> Router {
+    Router::new()
+        .route("/register-course-nfts", post(register_course_nfts))
+        .route("/register-learner-nft", post(register_learner_nft))
+        .route("/nft/{contract}/{token_id}", get(get_nft_metadata))
+        .with_state(pool)
+}
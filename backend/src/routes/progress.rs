@@ -1,17 +1,27 @@
 use axum::{routing::{get, post}, Router};
 use sqlx::{Pool, Postgres};
-use crate::handlers::progress::{complete_course, complete_lesson, complete_module, complete_quiz, get_all_course_progress, get_completed_lesson_ids, get_course_progress, get_course_progress_percentage, is_enrolled};
+use crate::handlers::credential::CredentialState;
+use crate::handlers::progress::{complete_course, complete_lesson, complete_module, complete_quiz, get_all_course_progress, get_completed_lesson_ids, get_course_progress, get_enrollment, get_quiz_attempts, get_video_progress, record_lesson_heartbeat, record_video_progress, start_quiz_attempt};
+
+pub fn progress_routes(pool: Pool<Postgres>, credential_state: CredentialState) -> Router {
+    // Completing a course issues its credential, the other routes only need the pool
+    let completion = Router::new()
+        .route("/complete-course", post(complete_course))
+        .with_state(credential_state);
 
-pub fn progress_routes(pool: Pool<Postgres>) -> Router {
     Router::new()
         .route("/complete-lesson", post(complete_lesson))
+        .route("/lesson-heartbeat", post(record_lesson_heartbeat))
+        .route("/video-progress", post(record_video_progress))
         .route("/complete-module", post(complete_module))
-        .route("/complete-course", post(complete_course))
+        .route("/start-quiz-attempt", post(start_quiz_attempt))
         .route("/complete-quiz", post(complete_quiz))
+        .route("/get-enrollment", get(get_enrollment))
         .route("/get-course-progress", get(get_course_progress))
-        .route("/get-course-progress-percentage", get(get_course_progress_percentage))
-        .route("/is-enrolled", get(is_enrolled))
         .route("/get-completed-lesson-ids", get(get_completed_lesson_ids))
         .route("/get-all-course-progress", get(get_all_course_progress))
+        .route("/get-quiz-attempts", get(get_quiz_attempts))
+        .route("/get-video-progress", get(get_video_progress))
         .with_state(pool)
+        .merge(completion)
 }
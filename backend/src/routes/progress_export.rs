@@ -0,0 +1,10 @@
+use axum::{Router, routing::get};
+use sqlx::{Pool, Postgres};
+
+use crate::handlers::progress_export::export_progress;
+
+pub fn progress_export_routes(pool: Pool<Postgres>) -> Router {
+    Router::new()
+        .route("/export-progress", get(export_progress))
+        .with_state(pool)
+}
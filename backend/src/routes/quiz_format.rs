@@ -0,0 +1,14 @@
+use axum::{
+    Router,
+    routing::{get, post},
+};
+use sqlx::{Pool, Postgres};
+
+use crate::handlers::quiz_format::{export_quiz, import_quiz};
+
+pub fn quiz_format_routes(pool: Pool<Postgres>) -> Router {
+    Router::new()
+        .route("/import-quiz", post(import_quiz))
+        .route("/export-quiz", get(export_quiz))
+        .with_state(pool)
+}
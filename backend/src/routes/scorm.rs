@@ -0,0 +1,28 @@
+use axum::{
+    Router,
+    extract::DefaultBodyLimit,
+    routing::{get, post},
+};
+use sqlx::{Pool, Postgres};
+
+use crate::bundle::MAX_BUNDLE_BYTES;
+use crate::handlers::scorm::{
+    commit_scorm_runtime, get_scorm_content, get_scorm_runtime, launch_scorm_package,
+    upload_scorm_package,
+};
+
+pub fn scorm_routes(pool: Pool<Postgres>) -> Router {
+    Router::new()
+        .route(
+            "/upload-scorm-package",
+            post(upload_scorm_package).layer(DefaultBodyLimit::max(MAX_BUNDLE_BYTES)),
+        )
+        .route("/scorm/{package_id}/launch", get(launch_scorm_package))
+        .route(
+            "/scorm/{package_id}/content/{*path}",
+            get(get_scorm_content),
+        )
+        .route("/get-scorm-runtime", get(get_scorm_runtime))
+        .route("/commit-scorm-runtime", post(commit_scorm_runtime))
+        .with_state(pool)
+}
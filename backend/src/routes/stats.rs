@@ -0,0 +1,11 @@
+use axum::{Router, routing::get};
+use std::sync::Arc;
+
+use crate::handlers::stats::get_platform_stats;
+use crate::stats::StatsService;
+
+pub fn stats_routes(stats: Arc<StatsService>) -> Router {
+    Router::new()
+        .route("/get-platform-stats", get(get_platform_stats))
+        .with_state(stats)
+}
@@ -0,0 +1,10 @@
+use axum::{Router, routing::get};
+use sqlx::{Pool, Postgres};
+
+use crate::handlers::xapi::get_xapi_statements;
+
+pub fn xapi_routes(pool: Pool<Postgres>) -> Router {
+    Router::new()
+        .route("/get-xapi-statements", get(get_xapi_statements))
+        .with_state(pool)
+}
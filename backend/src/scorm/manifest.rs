@@ -0,0 +1,289 @@
+use roxmltree::{Document, Node};
+use std::collections::BTreeMap;
+
+use super::{MANIFEST_FILE, ScormVersion};
+
+/// What the backend needs from `imsmanifest.xml` to launch a package.
+#[derive(Debug)]
+pub struct ScormManifest {
+    pub version: ScormVersion,
+    pub title: String,
+    // Href of the first SCO with its parameters, relative to the package root
+    pub launch_path: String,
+}
+
+/// Reads the manifest of a package zip and returns it with the files of the
+/// package, by their path relative to the folder of the manifest.
+pub fn read_package(
+    files: BTreeMap<String, Vec<u8>>,
+) -> Result<(ScormManifest, BTreeMap<String, Vec<u8>>), String> {
+    // Packages zipped with their folder keep the manifest one level down
+    let root = files
+        .keys()
+        .filter(|path| *path == MANIFEST_FILE || path.ends_with(&format!("/{}", MANIFEST_FILE)))
+        .min_by_key(|path| path.len())
+        .map(|path| path[..path.len() - MANIFEST_FILE.len()].to_string())
+        .ok_or_else(|| format!("Package has no {}", MANIFEST_FILE))?;
+
+    let mut package = BTreeMap::new();
+    for (path, data) in files {
+        let Some(path) = path.strip_prefix(&root) else {
+            continue;
+        };
+        if path.split('/').any(|part| part == "..") {
+            return Err(format!("Package file {} is outside the package", path));
+        }
+        package.insert(path.to_string(), data);
+    }
+
+    let xml = std::str::from_utf8(&package[MANIFEST_FILE])
+        .map_err(|_| format!("{} is not UTF-8", MANIFEST_FILE))?;
+    let manifest = parse_manifest(xml)?;
+
+    let launch_file = manifest
+        .launch_path
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default();
+    if !package.contains_key(launch_file) {
+        return Err(format!("Launch file {} is missing", launch_file));
+    }
+
+    Ok((manifest, package))
+}
+
+pub fn parse_manifest(xml: &str) -> Result<ScormManifest, String> {
+    let doc =
+        Document::parse(xml).map_err(|e| format!("{} is not valid XML: {}", MANIFEST_FILE, e))?;
+    let root = doc.root_element();
+    let version =
+        detect_version(root).ok_or_else(|| "Package is not SCORM 1.2 or SCORM 2004".to_string())?;
+
+    let organizations = child(root, "organizations");
+    let organization = organizations.and_then(|orgs| {
+        let default = orgs.attribute("default");
+        children(orgs, "organization")
+            .find(|o| default.is_some() && o.attribute("identifier") == default)
+            .or_else(|| children(orgs, "organization").next())
+    });
+
+    let resources =
+        child(root, "resources").ok_or_else(|| format!("{} has no resources", MANIFEST_FILE))?;
+    let resources_base = xml_base(resources);
+    let resource_href = |resource: Node| {
+        resource
+            .attribute("href")
+            .map(|href| format!("{}{}{}", resources_base, xml_base(resource), href))
+    };
+
+    // The first item of the organization that launches a resource
+    let launch = organization.and_then(|org| {
+        org.descendants()
+            .filter(|n| n.tag_name().name() == "item")
+            .find_map(|item| {
+                let identifierref = item.attribute("identifierref")?;
+                let resource = children(resources, "resource")
+                    .find(|r| r.attribute("identifier") == Some(identifierref))?;
+                let href = resource_href(resource)?;
+                Some(with_parameters(href, item.attribute("parameters")))
+            })
+    });
+    // Packages without an organization launch their first SCO
+    let launch = launch.or_else(|| {
+        children(resources, "resource")
+            .filter(|r| {
+                r.attributes()
+                    .find(|a| a.name().eq_ignore_ascii_case("scormtype"))
+                    .is_some_and(|a| a.value().eq_ignore_ascii_case("sco"))
+            })
+            .find_map(resource_href)
+    });
+    let launch_path = launch.ok_or_else(|| "Package has nothing to launch".to_string())?;
+
+    let title = organization
+        .and_then(|org| child(org, "title"))
+        .and_then(|t| t.text())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .unwrap_or("SCORM package")
+        .to_string();
+
+    Ok(ScormManifest {
+        version,
+        title,
+        launch_path: launch_path.trim_start_matches("./").to_string(),
+    })
+}
+
+fn detect_version(root: Node) -> Option<ScormVersion> {
+    let schema_version = root
+        .descendants()
+        .find(|n| n.tag_name().name() == "schemaversion")
+        .and_then(|n| n.text())
+        .map(str::trim);
+    match schema_version {
+        Some("1.2") => return Some(ScormVersion::V1p2),
+        Some(v) if v.contains("2004") || v.contains("1.3") => return Some(ScormVersion::V2004),
+        _ => {}
+    }
+
+    // Older exports leave out the schema version, their namespaces still tell
+    let namespaces: Vec<&str> = root.namespaces().map(|ns| ns.uri()).collect();
+    if namespaces.iter().any(|ns| ns.contains("adlcp_rootv1p2")) {
+        Some(ScormVersion::V1p2)
+    } else if namespaces.iter().any(|ns| ns.contains("adlcp_v1p3")) {
+        Some(ScormVersion::V2004)
+    } else {
+        None
+    }
+}
+
+fn with_parameters(href: String, parameters: Option<&str>) -> String {
+    match parameters.map(str::trim).filter(|p| !p.is_empty()) {
+        Some(p) if p.starts_with('#') => format!("{}{}", href, p),
+        Some(p) => {
+            let p = p.trim_start_matches(['?', '&']);
+            let separator = if href.contains('?') { '&' } else { '?' };
+            format!("{}{}{}", href, separator, p)
+        }
+        None => href,
+    }
+}
+
+fn xml_base<'a>(node: Node<'a, '_>) -> &'a str {
+    node.attribute(("http://www.w3.org/XML/1998/namespace", "base"))
+        .unwrap_or_default()
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &'a str) -> Option<Node<'a, 'input>> {
+    children(node, name).next()
+}
+
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |n| n.is_element() && n.tag_name().name() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCORM_12: &str = include_str!("testdata/scorm12_imsmanifest.xml");
+    const SCORM_2004: &str = include_str!("testdata/scorm2004_imsmanifest.xml");
+
+    fn package(files: &[(&str, &str)]) -> BTreeMap<String, Vec<u8>> {
+        files
+            .iter()
+            .map(|(path, data)| (path.to_string(), data.as_bytes().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn scorm_12_launches_the_default_organization() {
+        let manifest = parse_manifest(SCORM_12).unwrap();
+        assert_eq!(manifest.version, ScormVersion::V1p2);
+        assert_eq!(manifest.title, "Golf Explained");
+        assert_eq!(
+            manifest.launch_path,
+            "content/playing/index.html?lang=en&chapter=1"
+        );
+    }
+
+    #[test]
+    fn scorm_2004_launches_the_first_item() {
+        let manifest = parse_manifest(SCORM_2004).unwrap();
+        assert_eq!(manifest.version, ScormVersion::V2004);
+        assert_eq!(manifest.title, "Workplace safety");
+        assert_eq!(manifest.launch_path, "intro/index.html#start");
+    }
+
+    #[test]
+    fn packages_without_organizations_launch_their_first_sco() {
+        // No schema version either, the namespace tells the version
+        let manifest = parse_manifest(
+            r#"<manifest xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_v1p3">
+  <organizations/>
+  <resources>
+    <resource identifier="a" adlcp:scormType="asset" href="style.css"/>
+    <resource identifier="b" adlcp:scormType="sco" href="lesson.html"/>
+  </resources>
+</manifest>"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.version, ScormVersion::V2004);
+        assert_eq!(manifest.title, "SCORM package");
+        assert_eq!(manifest.launch_path, "lesson.html");
+    }
+
+    #[test]
+    fn malformed_manifests_are_rejected() {
+        for (xml, message) in [
+            ("<manifest>", "imsmanifest.xml is not valid XML"),
+            (
+                "<manifest><metadata><schemaversion>CAM 1.0</schemaversion></metadata></manifest>",
+                "Package is not SCORM 1.2 or SCORM 2004",
+            ),
+            (
+                "<manifest><metadata><schemaversion>1.2</schemaversion></metadata></manifest>",
+                "imsmanifest.xml has no resources",
+            ),
+            (
+                r#"<manifest>
+  <metadata><schemaversion>1.2</schemaversion></metadata>
+  <resources><resource identifier="a" href="notes.txt"/></resources>
+</manifest>"#,
+                "Package has nothing to launch",
+            ),
+        ] {
+            let error = parse_manifest(xml).unwrap_err();
+            assert!(error.starts_with(message), "{:?}: {}", xml, error);
+        }
+    }
+
+    #[test]
+    fn packages_zipped_with_their_folder_are_read() {
+        let (manifest, files) = read_package(package(&[
+            ("golf/imsmanifest.xml", SCORM_12),
+            ("golf/content/playing/index.html", "<html></html>"),
+            ("golf/extras/imsmanifest.xml", SCORM_2004),
+            ("readme.txt", "Outside the package"),
+        ]))
+        .unwrap();
+        assert_eq!(manifest.version, ScormVersion::V1p2);
+        assert_eq!(
+            files.keys().collect::<Vec<_>>(),
+            [
+                "content/playing/index.html",
+                "extras/imsmanifest.xml",
+                "imsmanifest.xml"
+            ]
+        );
+    }
+
+    #[test]
+    fn incomplete_packages_are_rejected() {
+        for (files, message) in [
+            (
+                package(&[("index.html", "<html></html>")]),
+                "Package has no imsmanifest.xml",
+            ),
+            (
+                package(&[("imsmanifest.xml", SCORM_2004)]),
+                "Launch file intro/index.html is missing",
+            ),
+            (
+                package(&[
+                    ("imsmanifest.xml", SCORM_2004),
+                    ("intro/index.html", "<html></html>"),
+                    ("intro/../../escape.html", "<html></html>"),
+                ]),
+                "Package file intro/../../escape.html is outside the package",
+            ),
+        ] {
+            assert_eq!(read_package(files).unwrap_err(), message);
+        }
+    }
+}
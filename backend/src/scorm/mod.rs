@@ -0,0 +1,67 @@
+pub mod manifest;
+pub mod runtime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bundle::content_type;
+
+pub const MANIFEST_FILE: &str = "imsmanifest.xml";
+
+// What package files may be served as, the pages and assets of a course.
+// Anything else is only offered as a download
+const PACKAGE_CONTENT_TYPES: [&str; 20] = [
+    "text/html; charset=utf-8",
+    "text/javascript",
+    "text/css",
+    "application/json",
+    "application/xml",
+    "text/plain; charset=utf-8",
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/svg+xml",
+    "image/webp",
+    "video/mp4",
+    "video/webm",
+    "video/ogg",
+    "audio/mpeg",
+    "audio/ogg",
+    "audio/wav",
+    "font/woff",
+    "font/woff2",
+    "font/ttf",
+];
+
+/// Content type a package file is served as, by the extension of its path in
+/// the package. `None` for files a package has no reason to show.
+pub fn package_content_type(path: &str) -> Option<&'static str> {
+    let content_type = content_type(path);
+    PACKAGE_CONTENT_TYPES
+        .contains(&content_type)
+        .then_some(content_type)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ScormVersion {
+    #[serde(rename = "1.2")]
+    V1p2,
+    #[serde(rename = "2004")]
+    V2004,
+}
+
+impl ScormVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScormVersion::V1p2 => "1.2",
+            ScormVersion::V2004 => "2004",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "1.2" => Some(ScormVersion::V1p2),
+            "2004" => Some(ScormVersion::V2004),
+            _ => None,
+        }
+    }
+}
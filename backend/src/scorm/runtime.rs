@@ -0,0 +1,598 @@
+use std::collections::BTreeMap;
+
+use super::ScormVersion;
+
+/// Data model elements of one learner in one SCORM lesson, by element name.
+pub type CmiValues = BTreeMap<String, String>;
+
+// Elements a package can set besides the ones validated below, stored as sent
+const FREE_FORM_1P2: &[&str] = &[
+    "cmi.objectives.",
+    "cmi.interactions.",
+    "cmi.student_preference.",
+];
+const FREE_FORM_2004: &[&str] = &[
+    "cmi.objectives.",
+    "cmi.interactions.",
+    "cmi.comments_from_learner.",
+    "cmi.learner_preference.",
+];
+const MAX_FREE_FORM_CHARS: usize = 4000;
+
+/// Names of the elements in a version of the data model.
+struct Elements {
+    status: &'static str,
+    location: &'static str,
+    exit: &'static str,
+    session_time: &'static str,
+    total_time: &'static str,
+    entry: &'static str,
+    learner_id: &'static str,
+    learner_name: &'static str,
+    credit: &'static str,
+    mode: &'static str,
+}
+
+const ELEMENTS_1P2: Elements = Elements {
+    status: "cmi.core.lesson_status",
+    location: "cmi.core.lesson_location",
+    exit: "cmi.core.exit",
+    session_time: "cmi.core.session_time",
+    total_time: "cmi.core.total_time",
+    entry: "cmi.core.entry",
+    learner_id: "cmi.core.student_id",
+    learner_name: "cmi.core.student_name",
+    credit: "cmi.core.credit",
+    mode: "cmi.core.lesson_mode",
+};
+
+const ELEMENTS_2004: Elements = Elements {
+    status: "cmi.completion_status",
+    location: "cmi.location",
+    exit: "cmi.exit",
+    session_time: "cmi.session_time",
+    total_time: "cmi.total_time",
+    entry: "cmi.entry",
+    learner_id: "cmi.learner_id",
+    learner_name: "cmi.learner_name",
+    credit: "cmi.credit",
+    mode: "cmi.mode",
+};
+
+fn elements(version: ScormVersion) -> &'static Elements {
+    match version {
+        ScormVersion::V1p2 => &ELEMENTS_1P2,
+        ScormVersion::V2004 => &ELEMENTS_2004,
+    }
+}
+
+/// Applies the values a package committed to the stored ones. Session time
+/// is only added to the total when the package finishes, since packages may
+/// commit the running session time more than once.
+pub fn commit(
+    version: ScormVersion,
+    stored: &mut CmiValues,
+    values: CmiValues,
+    finished: bool,
+) -> Result<(), String> {
+    for (element, value) in &values {
+        validate(version, element, value)?;
+    }
+
+    let names = elements(version);
+    for (element, value) in values {
+        if element == names.session_time {
+            if finished {
+                let total = stored
+                    .get(names.total_time)
+                    .and_then(|t| parse_time(version, t))
+                    .unwrap_or_default();
+                let session = parse_time(version, &value).unwrap_or_default();
+                stored.insert(
+                    names.total_time.to_string(),
+                    format_time(version, total + session),
+                );
+            }
+        } else {
+            stored.insert(element, value);
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the package reported the lesson as done. SCORM 1.2 has a single
+/// status, SCORM 2004 separates completion from success.
+pub fn is_completed(version: ScormVersion, stored: &CmiValues) -> bool {
+    let value = |element: &str| stored.get(element).map(String::as_str);
+    match version {
+        ScormVersion::V1p2 => matches!(
+            value("cmi.core.lesson_status"),
+            Some("passed" | "completed")
+        ),
+        ScormVersion::V2004 => {
+            value("cmi.completion_status") == Some("completed")
+                || value("cmi.success_status") == Some("passed")
+        }
+    }
+}
+
+/// The values a package reads when it is launched: the stored ones with the
+/// learner and the read-only elements the LMS provides.
+pub fn launch_values(
+    version: ScormVersion,
+    stored: Option<CmiValues>,
+    learner_id: &str,
+    learner_name: &str,
+) -> CmiValues {
+    let names = elements(version);
+    let entry = match &stored {
+        None => "ab-initio",
+        Some(stored) if stored.get(names.exit).map(String::as_str) == Some("suspend") => "resume",
+        Some(_) => "",
+    };
+
+    let mut values = stored.unwrap_or_default();
+    // Write-only elements
+    values.remove(names.exit);
+
+    let defaults = match version {
+        ScormVersion::V1p2 => [(names.status, "not attempted"), (names.location, "")],
+        ScormVersion::V2004 => [(names.status, "unknown"), ("cmi.success_status", "unknown")],
+    };
+    for (element, value) in defaults {
+        values
+            .entry(element.to_string())
+            .or_insert_with(|| value.to_string());
+    }
+    values
+        .entry(names.total_time.to_string())
+        .or_insert_with(|| format_time(version, 0.0));
+
+    for (element, value) in [
+        (names.entry, entry),
+        (names.learner_id, learner_id),
+        (names.learner_name, learner_name),
+        (names.credit, "credit"),
+        (names.mode, "normal"),
+    ] {
+        values.insert(element.to_string(), value.to_string());
+    }
+
+    values
+}
+
+fn validate(version: ScormVersion, element: &str, value: &str) -> Result<(), String> {
+    let one_of = |allowed: &[&str]| {
+        if allowed.contains(&value) {
+            Ok(())
+        } else {
+            Err(format!("{} can't be \"{}\"", element, value))
+        }
+    };
+    let max_chars = |max: usize| {
+        if value.chars().count() <= max {
+            Ok(())
+        } else {
+            Err(format!("{} is longer than {} characters", element, max))
+        }
+    };
+    let number_in = |min: f64, max: f64| match value.parse::<f64>() {
+        Ok(n) if n.is_finite() && (min..=max).contains(&n) => Ok(()),
+        _ => Err(format!(
+            "{} must be a number from {} to {}, got \"{}\"",
+            element, min, max, value
+        )),
+    };
+    let number = || match value.parse::<f64>() {
+        Ok(n) if n.is_finite() => Ok(()),
+        _ => Err(format!("{} must be a number, got \"{}\"", element, value)),
+    };
+    let time = || {
+        parse_time(version, value)
+            .map(|_| ())
+            .ok_or_else(|| format!("{} is not a valid time span: \"{}\"", element, value))
+    };
+
+    match (version, element) {
+        (ScormVersion::V1p2, "cmi.core.lesson_status") => {
+            one_of(&["passed", "completed", "failed", "incomplete", "browsed"])
+        }
+        (ScormVersion::V1p2, "cmi.core.lesson_location") => max_chars(255),
+        // Scores may be cleared with an empty string
+        (
+            ScormVersion::V1p2,
+            "cmi.core.score.raw" | "cmi.core.score.min" | "cmi.core.score.max",
+        ) => {
+            if value.is_empty() {
+                Ok(())
+            } else {
+                number()
+            }
+        }
+        (ScormVersion::V1p2, "cmi.core.exit") => one_of(&["", "time-out", "suspend", "logout"]),
+        (ScormVersion::V1p2, "cmi.core.session_time") => time(),
+        (ScormVersion::V1p2, "cmi.suspend_data" | "cmi.comments") => max_chars(4096),
+        (ScormVersion::V2004, "cmi.completion_status") => {
+            one_of(&["completed", "incomplete", "not attempted", "unknown"])
+        }
+        (ScormVersion::V2004, "cmi.success_status") => one_of(&["passed", "failed", "unknown"]),
+        (ScormVersion::V2004, "cmi.score.scaled") => number_in(-1.0, 1.0),
+        (ScormVersion::V2004, "cmi.score.raw" | "cmi.score.min" | "cmi.score.max") => number(),
+        (ScormVersion::V2004, "cmi.progress_measure") => number_in(0.0, 1.0),
+        (ScormVersion::V2004, "cmi.location") => max_chars(1000),
+        (ScormVersion::V2004, "cmi.exit") => {
+            one_of(&["", "timeout", "suspend", "logout", "normal"])
+        }
+        (ScormVersion::V2004, "cmi.session_time") => time(),
+        (ScormVersion::V2004, "cmi.suspend_data") => max_chars(64000),
+        _ => {
+            let free_form = match version {
+                ScormVersion::V1p2 => FREE_FORM_1P2,
+                ScormVersion::V2004 => FREE_FORM_2004,
+            };
+            if free_form.iter().any(|prefix| element.starts_with(prefix)) {
+                max_chars(MAX_FREE_FORM_CHARS)
+            } else {
+                Err(format!(
+                    "{} can't be set by a SCORM {} package",
+                    element,
+                    version.as_str()
+                ))
+            }
+        }
+    }
+}
+
+/// Seconds of a SCORM 1.2 `HHHH:MM:SS.SS` time span or a SCORM 2004 ISO 8601
+/// duration like `PT1H30M5.5S`.
+fn parse_time(version: ScormVersion, value: &str) -> Option<f64> {
+    match version {
+        ScormVersion::V1p2 => {
+            let mut parts = value.split(':');
+            let (hours, minutes, seconds) = (parts.next()?, parts.next()?, parts.next()?);
+            if parts.next().is_some() || hours.is_empty() || hours.len() > 4 || minutes.len() != 2 {
+                return None;
+            }
+            let hours: u32 = hours.parse().ok()?;
+            let minutes: u32 = minutes.parse().ok()?;
+            let seconds: f64 = seconds.parse().ok()?;
+            (minutes < 60 && (0.0..60.0).contains(&seconds))
+                .then(|| f64::from(hours) * 3600.0 + f64::from(minutes) * 60.0 + seconds)
+        }
+        ScormVersion::V2004 => {
+            let rest = value.strip_prefix('P')?;
+            let (date, time) = rest.split_once('T').unwrap_or((rest, ""));
+            if rest.is_empty() || rest.ends_with('T') {
+                return None;
+            }
+            let total = duration_part(
+                date,
+                &[
+                    ('Y', 365.0 * 86400.0),
+                    ('M', 30.0 * 86400.0),
+                    ('D', 86400.0),
+                ],
+            )? + duration_part(time, &[('H', 3600.0), ('M', 60.0), ('S', 1.0)])?;
+            Some(total)
+        }
+    }
+}
+
+// Sum of the number-unit pairs of a duration, units in their required order
+fn duration_part(text: &str, units: &[(char, f64)]) -> Option<f64> {
+    let mut total = 0.0;
+    let mut rest = text;
+    let mut units = units.iter();
+    while !rest.is_empty() {
+        let end = rest.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+        let number: f64 = rest[..end].parse().ok()?;
+        let unit = rest[end..].chars().next()?;
+        let (_, seconds) = units.find(|(u, _)| *u == unit)?;
+        total += number * seconds;
+        rest = &rest[end + 1..];
+    }
+    Some(total)
+}
+
+fn format_time(version: ScormVersion, seconds: f64) -> String {
+    let centiseconds = (seconds * 100.0).round() as u64;
+    let (hours, minutes) = (centiseconds / 360_000, centiseconds / 6000 % 60);
+    let seconds = centiseconds % 6000;
+    match version {
+        ScormVersion::V1p2 => format!(
+            "{:04}:{:02}:{:02}.{:02}",
+            hours,
+            minutes,
+            seconds / 100,
+            seconds % 100
+        ),
+        ScormVersion::V2004 => format!(
+            "PT{}H{}M{}.{:02}S",
+            hours,
+            minutes,
+            seconds / 100,
+            seconds % 100
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> CmiValues {
+        pairs
+            .iter()
+            .map(|(element, value)| (element.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn valid_values_are_accepted() {
+        let long_location = "x".repeat(255);
+        let free_form = "x".repeat(MAX_FREE_FORM_CHARS);
+        for (version, element, value) in [
+            (ScormVersion::V1p2, "cmi.core.lesson_status", "passed"),
+            (ScormVersion::V1p2, "cmi.core.lesson_status", "browsed"),
+            (
+                ScormVersion::V1p2,
+                "cmi.core.lesson_location",
+                long_location.as_str(),
+            ),
+            (ScormVersion::V1p2, "cmi.core.score.raw", "87.5"),
+            (ScormVersion::V1p2, "cmi.core.score.min", ""),
+            (ScormVersion::V1p2, "cmi.core.exit", "suspend"),
+            (ScormVersion::V1p2, "cmi.core.session_time", "0001:30:05.25"),
+            (ScormVersion::V1p2, "cmi.core.session_time", "12:00:00"),
+            (
+                ScormVersion::V1p2,
+                "cmi.interactions.0.id",
+                free_form.as_str(),
+            ),
+            (
+                ScormVersion::V2004,
+                "cmi.completion_status",
+                "not attempted",
+            ),
+            (ScormVersion::V2004, "cmi.success_status", "failed"),
+            (ScormVersion::V2004, "cmi.score.scaled", "-1"),
+            (ScormVersion::V2004, "cmi.score.scaled", "0.75"),
+            (ScormVersion::V2004, "cmi.score.raw", "-3"),
+            (ScormVersion::V2004, "cmi.progress_measure", "1"),
+            (ScormVersion::V2004, "cmi.exit", "normal"),
+            (ScormVersion::V2004, "cmi.session_time", "PT1H30M5.5S"),
+            (ScormVersion::V2004, "cmi.session_time", "P1DT2M"),
+            (ScormVersion::V2004, "cmi.learner_preference.language", "fr"),
+        ] {
+            assert_eq!(
+                validate(version, element, value),
+                Ok(()),
+                "{} = {:?}",
+                element,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        let long_location = "x".repeat(256);
+        let free_form = "x".repeat(MAX_FREE_FORM_CHARS + 1);
+        for (version, element, value, message) in [
+            (
+                ScormVersion::V1p2,
+                "cmi.core.lesson_status",
+                "not attempted",
+                "cmi.core.lesson_status can't be \"not attempted\"",
+            ),
+            (
+                ScormVersion::V1p2,
+                "cmi.core.lesson_location",
+                long_location.as_str(),
+                "cmi.core.lesson_location is longer than 255 characters",
+            ),
+            (
+                ScormVersion::V1p2,
+                "cmi.core.score.raw",
+                "high",
+                "cmi.core.score.raw must be a number, got \"high\"",
+            ),
+            (
+                ScormVersion::V1p2,
+                "cmi.core.exit",
+                "timeout",
+                "cmi.core.exit can't be \"timeout\"",
+            ),
+            (
+                ScormVersion::V1p2,
+                "cmi.core.session_time",
+                "PT5S",
+                "cmi.core.session_time is not a valid time span: \"PT5S\"",
+            ),
+            (
+                ScormVersion::V1p2,
+                "cmi.core.session_time",
+                "00:60:00",
+                "cmi.core.session_time is not a valid time span: \"00:60:00\"",
+            ),
+            (
+                ScormVersion::V1p2,
+                "cmi.core.total_time",
+                "0000:00:10",
+                "cmi.core.total_time can't be set by a SCORM 1.2 package",
+            ),
+            (
+                ScormVersion::V1p2,
+                "cmi.completion_status",
+                "completed",
+                "cmi.completion_status can't be set by a SCORM 1.2 package",
+            ),
+            (
+                ScormVersion::V1p2,
+                "cmi.objectives.0.id",
+                free_form.as_str(),
+                "cmi.objectives.0.id is longer than 4000 characters",
+            ),
+            (
+                ScormVersion::V2004,
+                "cmi.completion_status",
+                "passed",
+                "cmi.completion_status can't be \"passed\"",
+            ),
+            (
+                ScormVersion::V2004,
+                "cmi.score.scaled",
+                "1.5",
+                "cmi.score.scaled must be a number from -1 to 1, got \"1.5\"",
+            ),
+            (
+                ScormVersion::V2004,
+                "cmi.score.raw",
+                "NaN",
+                "cmi.score.raw must be a number, got \"NaN\"",
+            ),
+            (
+                ScormVersion::V2004,
+                "cmi.progress_measure",
+                "-0.1",
+                "cmi.progress_measure must be a number from 0 to 1, got \"-0.1\"",
+            ),
+            (
+                ScormVersion::V2004,
+                "cmi.session_time",
+                "PT",
+                "cmi.session_time is not a valid time span: \"PT\"",
+            ),
+            (
+                ScormVersion::V2004,
+                "cmi.session_time",
+                "PT5S1M",
+                "cmi.session_time is not a valid time span: \"PT5S1M\"",
+            ),
+            (
+                ScormVersion::V2004,
+                "cmi.core.lesson_status",
+                "passed",
+                "cmi.core.lesson_status can't be set by a SCORM 2004 package",
+            ),
+            (
+                ScormVersion::V2004,
+                "cmi.learner_name",
+                "Mallory",
+                "cmi.learner_name can't be set by a SCORM 2004 package",
+            ),
+        ] {
+            assert_eq!(
+                validate(version, element, value).unwrap_err(),
+                message,
+                "{} = {:?}",
+                element,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn one_invalid_value_rejects_the_whole_commit() {
+        let mut stored = values(&[("cmi.location", "page-1")]);
+        let error = commit(
+            ScormVersion::V2004,
+            &mut stored,
+            values(&[("cmi.location", "page-2"), ("cmi.score.scaled", "2")]),
+            false,
+        )
+        .unwrap_err();
+        assert!(error.starts_with("cmi.score.scaled"), "{}", error);
+        assert_eq!(stored, values(&[("cmi.location", "page-1")]));
+    }
+
+    #[test]
+    fn session_time_is_totalled_when_the_package_finishes() {
+        for (version, session_time, total_time, after_two) in [
+            (
+                ScormVersion::V1p2,
+                "0000:10:30.5",
+                "cmi.core.total_time",
+                "0000:21:01.00",
+            ),
+            (
+                ScormVersion::V2004,
+                "PT10M30.5S",
+                "cmi.total_time",
+                "PT0H21M1.00S",
+            ),
+        ] {
+            let names = elements(version);
+            let mut stored = CmiValues::new();
+            let session = values(&[(names.session_time, session_time)]);
+
+            // Running commits don't count yet
+            commit(version, &mut stored, session.clone(), false).unwrap();
+            assert_eq!(stored.get(total_time), None);
+
+            commit(version, &mut stored, session.clone(), true).unwrap();
+            commit(version, &mut stored, session, true).unwrap();
+            assert_eq!(stored.get(total_time).unwrap(), after_two);
+            assert_eq!(stored.get(names.session_time), None);
+        }
+    }
+
+    #[test]
+    fn completion_follows_each_version() {
+        assert!(is_completed(
+            ScormVersion::V1p2,
+            &values(&[("cmi.core.lesson_status", "passed")])
+        ));
+        assert!(!is_completed(
+            ScormVersion::V1p2,
+            &values(&[("cmi.core.lesson_status", "failed")])
+        ));
+        assert!(is_completed(
+            ScormVersion::V2004,
+            &values(&[
+                ("cmi.completion_status", "incomplete"),
+                ("cmi.success_status", "passed")
+            ])
+        ));
+        assert!(!is_completed(
+            ScormVersion::V2004,
+            &values(&[("cmi.completion_status", "incomplete")])
+        ));
+    }
+
+    #[test]
+    fn launch_values_resume_suspended_attempts() {
+        let first = launch_values(ScormVersion::V1p2, None, "0xabc", "Ada");
+        assert_eq!(
+            first,
+            values(&[
+                ("cmi.core.credit", "credit"),
+                ("cmi.core.entry", "ab-initio"),
+                ("cmi.core.lesson_location", ""),
+                ("cmi.core.lesson_mode", "normal"),
+                ("cmi.core.lesson_status", "not attempted"),
+                ("cmi.core.student_id", "0xabc"),
+                ("cmi.core.student_name", "Ada"),
+                ("cmi.core.total_time", "0000:00:00.00"),
+            ])
+        );
+
+        let stored = values(&[
+            ("cmi.exit", "suspend"),
+            ("cmi.completion_status", "incomplete"),
+            ("cmi.learner_id", "0xstale"),
+        ]);
+        let resumed = launch_values(ScormVersion::V2004, Some(stored), "0xabc", "Ada");
+        assert_eq!(resumed["cmi.entry"], "resume");
+        assert_eq!(resumed["cmi.completion_status"], "incomplete");
+        assert_eq!(resumed["cmi.success_status"], "unknown");
+        assert_eq!(resumed["cmi.total_time"], "PT0H0M0.00S");
+        assert_eq!(resumed["cmi.learner_id"], "0xabc");
+        assert!(!resumed.contains_key("cmi.exit"));
+
+        let ended = values(&[("cmi.exit", "normal")]);
+        let relaunched = launch_values(ScormVersion::V2004, Some(ended), "0xabc", "Ada");
+        assert_eq!(relaunched["cmi.entry"], "");
+    }
+}
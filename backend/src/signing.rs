@@ -0,0 +1,292 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use dotenv::dotenv;
+use ed25519_dalek::{Signature as Ed25519Signature, Signer, SigningKey, Verifier};
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use serde_json::{Value, json};
+use sha3::{Digest, Keccak256};
+use std::env;
+
+// Multicodec prefix of an ed25519 public key in a did:key identifier
+const ED25519_MULTICODEC: [u8; 2] = [0xed, 0x01];
+
+/// Server ed25519 key used to sign records that third parties may audit.
+#[derive(Clone)]
+pub struct ServerKey {
+    signing_key: SigningKey,
+}
+
+impl ServerKey {
+    /// Loads the key from `SIGNING_KEY`, a hex encoded 32 byte seed.
+    pub fn from_env() -> Result<ServerKey, String> {
+        dotenv().ok();
+        let seed_hex = env::var("SIGNING_KEY").map_err(|_| "SIGNING_KEY not set".to_string())?;
+        ServerKey::from_hex(&seed_hex)
+    }
+
+    pub fn from_hex(seed_hex: &str) -> Result<ServerKey, String> {
+        let seed: [u8; 32] = hex::decode(seed_hex.trim_start_matches("0x"))
+            .map_err(|_| "SIGNING_KEY is not valid hex".to_string())?
+            .try_into()
+            .map_err(|_| "SIGNING_KEY must be 32 bytes".to_string())?;
+
+        Ok(ServerKey {
+            signing_key: SigningKey::from_bytes(&seed),
+        })
+    }
+
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(self.signing_key.verifying_key().to_bytes())
+    }
+
+    pub fn sign_hex(&self, message: &[u8]) -> String {
+        hex::encode(self.signing_key.sign(message).to_bytes())
+    }
+
+    /// did:key identifier of the public key, used as credential issuer.
+    pub fn did(&self) -> String {
+        let mut bytes = ED25519_MULTICODEC.to_vec();
+        bytes.extend_from_slice(&self.signing_key.verifying_key().to_bytes());
+        format!("did:key:z{}", bs58::encode(bytes).into_string())
+    }
+
+    /// Signs `claims` as a compact EdDSA JWS.
+    pub fn sign_jwt(&self, typ: &str, claims: &Value) -> String {
+        let did = self.did();
+        let key_id = format!("{}#{}", did, did.trim_start_matches("did:key:"));
+        let header = json!({ "alg": "EdDSA", "typ": typ, "kid": key_id });
+
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature = self.signing_key.sign(signing_input.as_bytes());
+
+        format!(
+            "{}.{}",
+            signing_input,
+            URL_SAFE_NO_PAD.encode(signature.to_bytes())
+        )
+    }
+
+    /// Verifies a compact JWS signed by this key and returns its claims.
+    pub fn verify_jwt(&self, jwt: &str) -> Result<Value, String> {
+        let parts: Vec<&str> = jwt.split('.').collect();
+        let [header, claims, signature] = parts[..] else {
+            return Err("Malformed JWT".to_string());
+        };
+
+        let header: Value = decode_jwt_part(header)?;
+        if header["alg"] != "EdDSA" {
+            return Err("Unsupported JWT algorithm".to_string());
+        }
+
+        let signature: [u8; 64] = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| "Malformed JWT signature".to_string())?
+            .try_into()
+            .map_err(|_| "JWT signature must be 64 bytes".to_string())?;
+        self.signing_key
+            .verifying_key()
+            .verify(
+                format!("{}.{}", parts[0], claims).as_bytes(),
+                &Ed25519Signature::from_bytes(&signature),
+            )
+            .map_err(|_| "Invalid JWT signature".to_string())?;
+
+        decode_jwt_part(claims)
+    }
+}
+
+pub(crate) fn decode_jwt_part(part: &str) -> Result<Value, String> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|_| "Malformed JWT".to_string())?;
+    serde_json::from_slice(&bytes).map_err(|_| "Malformed JWT".to_string())
+}
+
+/// Recovers the lowercase 0x address that produced an EIP-191 `personal_sign`
+/// signature over `message`.
+pub fn recover_eth_address(message: &str, signature_hex: &str) -> Result<String, String> {
+    let bytes = hex::decode(signature_hex.trim_start_matches("0x"))
+        .map_err(|_| "Signature is not valid hex".to_string())?;
+    if bytes.len() != 65 {
+        return Err("Signature must be 65 bytes".to_string());
+    }
+
+    let v = bytes[64];
+    let recovery_id = RecoveryId::from_byte(if v >= 27 { v - 27 } else { v })
+        .ok_or_else(|| "Invalid signature recovery id".to_string())?;
+    let signature =
+        Signature::from_slice(&bytes[..64]).map_err(|_| "Invalid signature".to_string())?;
+
+    let prefixed = format!("\x19Ethereum Signed Message:\n{}{}", message.len(), message);
+    let digest = Keccak256::digest(prefixed.as_bytes());
+
+    let verifying_key = VerifyingKey::recover_from_prehash(&digest, &signature, recovery_id)
+        .map_err(|_| "Failed to recover signer".to_string())?;
+    let public_key = verifying_key.to_encoded_point(false);
+    let address_hash = Keccak256::digest(&public_key.as_bytes()[1..]);
+
+    Ok(format!("0x{}", hex::encode(&address_hash[12..])))
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::VerifyingKey as Ed25519VerifyingKey;
+
+    use super::*;
+    use crate::test_support::{personal_sign, wallet_address};
+
+    // Test 1 of RFC 8032, section 7.1
+    const RFC8032_SEED: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+    const RFC8032_PUBLIC_KEY: &str =
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const RFC8032_SIGNATURE: &str = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+
+    // `personal_sign` of "Some data" from the web3.js account documentation
+    const EIP191_ADDRESS: &str = "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23";
+    const EIP191_SIGNATURE: &str = "0xb91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c";
+
+    fn verify_hex(public_key_hex: &str, message: &[u8], signature_hex: &str) -> bool {
+        let public_key: [u8; 32] = hex::decode(public_key_hex).unwrap().try_into().unwrap();
+        let signature: [u8; 64] = hex::decode(signature_hex).unwrap().try_into().unwrap();
+        Ed25519VerifyingKey::from_bytes(&public_key)
+            .unwrap()
+            .verify(message, &Ed25519Signature::from_bytes(&signature))
+            .is_ok()
+    }
+
+    #[test]
+    fn server_key_matches_the_rfc_8032_vector() {
+        let key = ServerKey::from_hex(&format!("0x{}", RFC8032_SEED)).unwrap();
+        assert_eq!(key.public_key_hex(), RFC8032_PUBLIC_KEY);
+        assert_eq!(key.sign_hex(b""), RFC8032_SIGNATURE);
+    }
+
+    #[test]
+    fn signatures_verify_until_the_message_changes() {
+        let key = ServerKey::from_hex(RFC8032_SEED).unwrap();
+        let message = br#"{"courseId":1,"numCompleted":3}"#;
+        let signature = key.sign_hex(message);
+        assert!(verify_hex(&key.public_key_hex(), message, &signature));
+        assert!(!verify_hex(
+            &key.public_key_hex(),
+            br#"{"courseId":1,"numCompleted":4}"#,
+            &signature
+        ));
+
+        let other = ServerKey::from_hex(&"11".repeat(32)).unwrap();
+        assert!(!verify_hex(&other.public_key_hex(), message, &signature));
+    }
+
+    #[test]
+    fn malformed_seeds_are_rejected() {
+        assert_eq!(
+            ServerKey::from_hex("not hex").err().unwrap(),
+            "SIGNING_KEY is not valid hex"
+        );
+        assert_eq!(
+            ServerKey::from_hex("abcd").err().unwrap(),
+            "SIGNING_KEY must be 32 bytes"
+        );
+    }
+
+    #[test]
+    fn jwt_round_trips_under_the_did_key() {
+        let key = ServerKey::from_hex(RFC8032_SEED).unwrap();
+        let claims = json!({ "sub": "0xabc", "courseId": 7 });
+        let jwt = key.sign_jwt("vc+jwt", &claims);
+
+        let header = decode_jwt_part(jwt.split('.').next().unwrap()).unwrap();
+        assert_eq!(header["alg"], "EdDSA");
+        assert_eq!(header["typ"], "vc+jwt");
+        assert!(header["kid"].as_str().unwrap().starts_with(&key.did()));
+        assert!(key.did().starts_with("did:key:z6Mk"));
+
+        assert_eq!(key.verify_jwt(&jwt).unwrap(), claims);
+    }
+
+    #[test]
+    fn tampered_jwts_are_rejected() {
+        let key = ServerKey::from_hex(RFC8032_SEED).unwrap();
+        let jwt = key.sign_jwt("vc+jwt", &json!({ "sub": "0xabc" }));
+        let [header, _, signature]: [&str; 3] =
+            jwt.split('.').collect::<Vec<_>>().try_into().unwrap();
+
+        let forged_claims = URL_SAFE_NO_PAD.encode(json!({ "sub": "0xdef" }).to_string());
+        let none_header = URL_SAFE_NO_PAD.encode(json!({ "alg": "none" }).to_string());
+        let other_key = ServerKey::from_hex(&"11".repeat(32)).unwrap();
+        for (jwt, message) in [
+            (
+                format!("{}.{}.{}", header, forged_claims, signature),
+                "Invalid JWT signature",
+            ),
+            (
+                other_key.sign_jwt("vc+jwt", &json!({ "sub": "0xabc" })),
+                "Invalid JWT signature",
+            ),
+            (
+                format!("{}.{}.{}", none_header, forged_claims, signature),
+                "Unsupported JWT algorithm",
+            ),
+            (
+                format!("{}.{}.AAAA", header, forged_claims),
+                "JWT signature must be 64 bytes",
+            ),
+            (format!("{}.{}", header, forged_claims), "Malformed JWT"),
+        ] {
+            assert_eq!(key.verify_jwt(&jwt).unwrap_err(), message, "{}", jwt);
+        }
+    }
+
+    #[test]
+    fn eth_address_is_recovered_from_a_known_signature() {
+        assert_eq!(
+            recover_eth_address("Some data", EIP191_SIGNATURE).unwrap(),
+            EIP191_ADDRESS
+        );
+        // Recovery ids without the 27 offset are accepted too
+        let mut bytes = hex::decode(&EIP191_SIGNATURE[2..]).unwrap();
+        bytes[64] -= 27;
+        assert_eq!(
+            recover_eth_address("Some data", &hex::encode(bytes)).unwrap(),
+            EIP191_ADDRESS
+        );
+    }
+
+    #[test]
+    fn eth_signatures_recover_another_address_once_tampered() {
+        let secret = [7; 32];
+        let signature = personal_sign(&secret, "Enroll in course 1");
+        assert_eq!(
+            recover_eth_address("Enroll in course 1", &signature).unwrap(),
+            wallet_address(&secret)
+        );
+        assert_ne!(
+            recover_eth_address("Enroll in course 2", &signature).ok(),
+            Some(wallet_address(&secret))
+        );
+        let tampered = EIP191_SIGNATURE.replace("0xb914", "0xb915");
+        assert_ne!(
+            recover_eth_address("Some data", &tampered).ok().as_deref(),
+            Some(EIP191_ADDRESS)
+        );
+
+        for (signature, message) in [
+            ("0xzz", "Signature is not valid hex"),
+            ("0x1234", "Signature must be 65 bytes"),
+        ] {
+            assert_eq!(
+                recover_eth_address("Some data", signature).unwrap_err(),
+                message
+            );
+        }
+        let mut bytes = hex::decode(&EIP191_SIGNATURE[2..]).unwrap();
+        bytes[64] = 31;
+        assert_eq!(
+            recover_eth_address("Some data", &hex::encode(bytes)).unwrap_err(),
+            "Invalid signature recovery id"
+        );
+    }
+}
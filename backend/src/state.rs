@@ -0,0 +1,23 @@
+use axum::extract::FromRef;
+use sqlx::{Pool, Postgres};
+
+/// State of the routes that need the public URL of this backend besides the
+/// database, like links to media, certificates and the LTI endpoints.
+#[derive(Clone)]
+pub struct AppState {
+    pub pool: Pool<Postgres>,
+    pub public_api_url: String,
+}
+
+impl FromRef<AppState> for Pool<Postgres> {
+    fn from_ref(state: &AppState) -> Pool<Postgres> {
+        state.pool.clone()
+    }
+}
+
+impl AppState {
+    /// Absolute URL of `path` on this backend.
+    pub fn public_url(&self, path: &str) -> String {
+        format!("{}{}", self.public_api_url.trim_end_matches('/'), path)
+    }
+}
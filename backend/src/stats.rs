@@ -0,0 +1,175 @@
+use chrono::{DateTime, Duration, Utc};
+use dotenv::dotenv;
+use sqlx::{Pool, Postgres};
+use std::{collections::HashMap, env, time::Instant};
+use tokio::sync::RwLock;
+
+use crate::models::stats::{
+    PlatformStats, PlatformStatsQuery, RangeGrowth, RangeStats, TotalStats,
+};
+
+const DEFAULT_REFRESH_SECONDS: u64 = 300;
+const DEFAULT_RANGE_DAYS: i64 = 30;
+// Distinct ranges kept at once, requests with explicit dates can vary freely
+const MAX_CACHED_RANGES: usize = 64;
+
+// Learners with any activity between $1 and $2
+const ACTIVE_LEARNERS: &str = r#"
+    SELECT COUNT(DISTINCT learner_id) FROM (
+        SELECT learner_id FROM learner_course_enrollment WHERE enrolled_at >= $1 AND enrolled_at < $2
+        UNION ALL
+        SELECT learner_id FROM lesson_completion WHERE completed_at >= $1 AND completed_at < $2
+        UNION ALL
+        SELECT learner_id FROM quiz_attempt WHERE submitted_at >= $1 AND submitted_at < $2
+    ) activity
+"#;
+
+/// Platform-wide statistics, computed on demand and cached per requested
+/// range for `STATS_REFRESH_SECONDS` (default 300).
+pub struct StatsService {
+    pool: Pool<Postgres>,
+    refresh_interval: std::time::Duration,
+    cache: RwLock<HashMap<PlatformStatsQuery, (Instant, PlatformStats)>>,
+}
+
+impl StatsService {
+    pub fn from_env(pool: Pool<Postgres>) -> StatsService {
+        dotenv().ok();
+        let refresh_seconds = env::var("STATS_REFRESH_SECONDS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_REFRESH_SECONDS);
+
+        StatsService {
+            pool,
+            refresh_interval: std::time::Duration::from_secs(refresh_seconds),
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Statistics for a range, from the cache while they are fresh. Ranges
+    /// left open are cached as such so they keep ending now.
+    pub async fn stats(&self, query: PlatformStatsQuery) -> Result<PlatformStats, String> {
+        if let Some((computed_at, stats)) = self.cache.read().await.get(&query)
+            && computed_at.elapsed() < self.refresh_interval
+        {
+            return Ok(stats.clone());
+        }
+
+        let now = Utc::now();
+        let to = query.to.unwrap_or(now);
+        let from = query
+            .from
+            .unwrap_or(to - Duration::days(DEFAULT_RANGE_DAYS));
+        if from >= to {
+            return Err("from must be before to".to_string());
+        }
+
+        let range = self.range_stats(from, to).await?;
+        let previous_range = self.range_stats(from - (to - from), from).await?;
+        let stats = PlatformStats {
+            generated_at: now,
+            totals: self.total_stats(now).await?,
+            growth: growth(&range, &previous_range),
+            range,
+            previous_range,
+        };
+
+        let mut cache = self.cache.write().await;
+        if cache.len() >= MAX_CACHED_RANGES {
+            cache.retain(|_, (computed_at, _)| computed_at.elapsed() < self.refresh_interval);
+            if cache.len() >= MAX_CACHED_RANGES {
+                cache.clear();
+            }
+        }
+        cache.insert(query, (Instant::now(), stats.clone()));
+
+        Ok(stats)
+    }
+
+    async fn total_stats(&self, now: DateTime<Utc>) -> Result<TotalStats, String> {
+        let sql = format!(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM learner) AS learners,
+                (SELECT COUNT(*) FROM creator) AS creators,
+                (SELECT COUNT(*) FROM course) AS courses,
+                (SELECT COUNT(*) FROM learner_course_enrollment) AS enrollments,
+                (SELECT COUNT(*) FROM course_completion) AS completions,
+                (
+                    SELECT COUNT(*) FROM learner_course_enrollment WHERE nft_token_id IS NOT NULL
+                ) AS learner_nfts,
+                (SELECT COUNT(*) FROM course WHERE creator_nft_address IS NOT NULL) AS creator_nfts,
+                ({active}) AS active_learners_7d,
+                ({active_30d}) AS active_learners_30d
+            "#,
+            active = ACTIVE_LEARNERS,
+            active_30d = ACTIVE_LEARNERS.replace("$1", "$3"),
+        );
+
+        sqlx::query_as::<_, TotalStats>(&sql)
+            .bind(now - Duration::days(7))
+            .bind(now)
+            .bind(now - Duration::days(30))
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn range_stats(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<RangeStats, String> {
+        let sql = format!(
+            r#"
+            SELECT $1 AS "from", $2 AS "to",
+                (SELECT COUNT(*) FROM learner WHERE created_at >= $1 AND created_at < $2) AS new_learners,
+                (SELECT COUNT(*) FROM creator WHERE created_at >= $1 AND created_at < $2) AS new_creators,
+                (SELECT COUNT(*) FROM course WHERE created_at >= $1 AND created_at < $2) AS new_courses,
+                (
+                    SELECT COUNT(*) FROM learner_course_enrollment
+                    WHERE enrolled_at >= $1 AND enrolled_at < $2
+                ) AS enrollments,
+                (
+                    SELECT COUNT(*) FROM course_completion
+                    WHERE completed_at >= $1 AND completed_at < $2
+                ) AS completions,
+                (
+                    SELECT COUNT(*) FROM learner_course_enrollment
+                    WHERE nft_minted_at >= $1 AND nft_minted_at < $2
+                ) AS learner_nfts_minted,
+                (
+                    SELECT COUNT(*) FROM course
+                    WHERE nfts_registered_at >= $1 AND nfts_registered_at < $2
+                ) AS creator_nfts_minted,
+                ({active}) AS active_learners
+            "#,
+            active = ACTIVE_LEARNERS,
+        );
+
+        sqlx::query_as::<_, RangeStats>(&sql)
+            .bind(from)
+            .bind(to)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+fn growth(range: &RangeStats, previous: &RangeStats) -> RangeGrowth {
+    let change = |current: i64, previous: i64| {
+        (previous > 0).then(|| (current - previous) as f64 / previous as f64)
+    };
+
+    RangeGrowth {
+        new_learners: change(range.new_learners, previous.new_learners),
+        new_creators: change(range.new_creators, previous.new_creators),
+        new_courses: change(range.new_courses, previous.new_courses),
+        enrollments: change(range.enrollments, previous.enrollments),
+        completions: change(range.completions, previous.completions),
+        learner_nfts_minted: change(range.learner_nfts_minted, previous.learner_nfts_minted),
+        creator_nfts_minted: change(range.creator_nfts_minted, previous.creator_nfts_minted),
+        active_learners: change(range.active_learners, previous.active_learners),
+    }
+}
@@ -0,0 +1,165 @@
+use axum::Router;
+use chrono::Utc;
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+use std::collections::BTreeMap;
+use tokio::net::TcpListener;
+
+use crate::bundle::{BUNDLE_FORMAT_VERSION, Bundle, media_path};
+use crate::models::bundle::{BundleLesson, BundleManifest, BundleMedia, BundleModule};
+use crate::models::course::{
+    CreateAnswerOptionPayload, CreateQuestionPayload, CreateQuizPayload, QuestionType,
+    ScoringPolicy,
+};
+
+/// Serves a mock HTTP server on a free local port and returns its base url.
+pub async fn serve(router: Router) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind mock server");
+    let addr = listener.local_addr().expect("Mock server has no address");
+    tokio::spawn(async move {
+        axum::serve(listener, router)
+            .await
+            .expect("Mock server failed")
+    });
+    format!("http://{}", addr)
+}
+
+/// Signs `message` with EIP-191 `personal_sign` as the wallet of `secret`,
+/// the way the webapp signs with the learner's or creator's wallet.
+pub fn personal_sign(secret: &[u8; 32], message: &str) -> String {
+    let signing_key = k256::ecdsa::SigningKey::from_bytes(secret.into()).unwrap();
+    let prefixed = format!("\x19Ethereum Signed Message:\n{}{}", message.len(), message);
+    let digest = Keccak256::digest(prefixed.as_bytes());
+    let (signature, recovery_id) = signing_key.sign_prehash_recoverable(&digest).unwrap();
+
+    let mut bytes = signature.to_bytes().to_vec();
+    bytes.push(27 + recovery_id.to_byte());
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Lowercase 0x address of the wallet of `secret`.
+pub fn wallet_address(secret: &[u8; 32]) -> String {
+    let signing_key = k256::ecdsa::SigningKey::from_bytes(secret.into()).unwrap();
+    let public_key = signing_key.verifying_key().to_encoded_point(false);
+    let hash = Keccak256::digest(&public_key.as_bytes()[1..]);
+    format!("0x{}", hex::encode(&hash[12..]))
+}
+
+/// A course bundle with two modules, a quiz, a final exam and a media file
+/// linked from a lesson and used as a lesson video.
+pub fn sample_bundle() -> Bundle {
+    let png = b"\x89PNG\r\n\x1a\nnot really an image".to_vec();
+    let mp4 = b"\x00\x00\x00\x18ftypmp42".to_vec();
+    let (media, media_data): (Vec<BundleMedia>, Vec<Vec<u8>>) = [
+        ("diagram.png", "image/png", png),
+        ("intro.mp4", "video/mp4", mp4),
+    ]
+    .into_iter()
+    .map(|(name, content_type, data)| {
+        let sha256 = hex::encode(Sha256::digest(&data));
+        let media = BundleMedia {
+            path: media_path(&sha256, name),
+            content_type: content_type.to_string(),
+            sha256,
+        };
+        (media, data)
+    })
+    .unzip();
+    let (diagram, video) = (media[0].path.clone(), media[1].path.clone());
+
+    let lesson = |source_id: i64, position: i32, title: &str| BundleLesson {
+        source_id: Some(source_id),
+        title: title.to_string(),
+        position,
+        content_file: format!("lessons/{}.md", source_id),
+        video_url: None,
+        scorm_package_id: None,
+        min_watched_percent: None,
+        video_duration_seconds: None,
+    };
+    let quiz = |question_text: &str| CreateQuizPayload {
+        questions: vec![CreateQuestionPayload {
+            question_text: question_text.to_string(),
+            question_type: QuestionType::SingleChoice,
+            points: 2.0,
+            answers: [("Yes", true), ("No", false)]
+                .into_iter()
+                .map(|(answer_text, is_correct)| CreateAnswerOptionPayload {
+                    answer_text: answer_text.to_string(),
+                    is_correct,
+                    match_text: None,
+                })
+                .collect(),
+            numeric_answer: None,
+            numeric_tolerance: None,
+            case_sensitive: false,
+            pool: None,
+        }],
+        passing_score_percent: 50.0,
+        max_attempts: Some(3),
+        cooldown_seconds: 0,
+        scoring_policy: ScoringPolicy::Best,
+        draw_rules: Vec::new(),
+        shuffle_answers: true,
+    };
+
+    let manifest = BundleManifest {
+        format_version: BUNDLE_FORMAT_VERSION,
+        exported_at: Utc::now(),
+        source_course_id: Some(1),
+        title: "Rust basics".to_string(),
+        description: "Ownership & borrowing".to_string(),
+        modules: vec![
+            BundleModule {
+                source_id: Some(10),
+                title: "Getting started".to_string(),
+                position: 1,
+                lessons: vec![
+                    lesson(1, 1, "Welcome"),
+                    BundleLesson {
+                        video_url: Some(video),
+                        min_watched_percent: Some(80.0),
+                        video_duration_seconds: Some(12.5),
+                        ..lesson(2, 2, "Installing")
+                    },
+                ],
+                quiz: Some(quiz("Did you install Rust?")),
+            },
+            BundleModule {
+                source_id: Some(11),
+                title: "Ownership".to_string(),
+                position: 2,
+                lessons: vec![lesson(3, 1, "Moves")],
+                quiz: None,
+            },
+        ],
+        final_exam: Some(quiz("Do values have one owner?")),
+        media,
+    };
+
+    let mut files: BTreeMap<String, Vec<u8>> = BTreeMap::from([
+        (
+            "lessons/1.md".to_string(),
+            b"# Welcome\n\nLet's *start*.".to_vec(),
+        ),
+        (
+            "lessons/2.md".to_string(),
+            format!("![Diagram]({})\n\nRun `rustup`.", diagram).into_bytes(),
+        ),
+        (
+            "lessons/3.md".to_string(),
+            "Values move <b>once</b>.".as_bytes().to_vec(),
+        ),
+    ]);
+    files.extend(
+        manifest
+            .media
+            .iter()
+            .map(|m| m.path.clone())
+            .zip(media_data),
+    );
+
+    Bundle { manifest, files }
+}
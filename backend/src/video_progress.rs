@@ -0,0 +1,181 @@
+// Playback this close to the end counts as finished, the video resumes from the start
+const RESUME_END_MARGIN_SECONDS: f64 = 5.0;
+// Fastest playback rate of the player, reports can't credit more video than this
+const MAX_PLAYBACK_RATE: f64 = 2.0;
+// Players report slightly different durations for the same file
+const DURATION_TOLERANCE_SECONDS: f64 = 1.0;
+// Extensions the lesson player plays itself and reports progress for
+const VIDEO_FILE_EXTENSIONS: [&str; 6] = ["mp4", "webm", "ogv", "ogg", "mov", "m4v"];
+
+/// Whether the lesson player plays the URL as a video file and reports how
+/// much was watched, like uploaded media. Embeds such as YouTube report nothing.
+pub fn is_video_file(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    let mut segments = url.path().rsplit('/');
+    let last = segments.next().unwrap_or_default();
+    let uploaded_media = segments.next() == Some("media")
+        && last.len() == 64
+        && last.bytes().all(|b| b.is_ascii_hexdigit());
+    let extension = last.rsplit_once('.').map(|(_, extension)| extension);
+    uploaded_media
+        || extension.is_some_and(|extension| {
+            VIDEO_FILE_EXTENSIONS
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(extension))
+        })
+}
+
+/// Whether a duration reported by the player is the one set on the lesson.
+pub fn duration_matches(reported: f64, lesson_duration: f64) -> bool {
+    (reported - lesson_duration).abs() <= DURATION_TOLERANCE_SECONDS
+}
+
+/// Adds the intervals played since the last report to the watched intervals,
+/// clamped to the video duration. The result is sorted and intervals that
+/// overlap or touch are merged, so rewatching a part never counts twice.
+/// At most `max_new_seconds` of not yet watched video is credited, in the
+/// order it was played.
+pub fn merge_watched(
+    watched: &[[f64; 2]],
+    played: &[[f64; 2]],
+    duration: f64,
+    max_new_seconds: f64,
+) -> Vec<[f64; 2]> {
+    let mut merged = normalize(watched.iter().copied(), duration);
+    let mut budget = max_new_seconds.max(0.0);
+    for &interval in played {
+        let mut credited = Vec::new();
+        for [start, end] in unwatched_parts(&merged, interval) {
+            if budget <= 0.0 {
+                break;
+            }
+            let end = end.min(start + budget);
+            budget -= end - start;
+            credited.push([start, end]);
+        }
+        merged = normalize(merged.into_iter().chain(credited), duration);
+    }
+    merged
+}
+
+/// The watched seconds a report may add, bounded by how fast the video can
+/// play since the previous report. The first report only starts the clock.
+pub fn max_new_watched_seconds(seconds_since_last_report: Option<f64>) -> f64 {
+    seconds_since_last_report.map_or(0.0, |seconds| seconds.max(0.0) * MAX_PLAYBACK_RATE)
+}
+
+fn normalize(intervals: impl Iterator<Item = [f64; 2]>, duration: f64) -> Vec<[f64; 2]> {
+    let mut intervals: Vec<[f64; 2]> = intervals
+        .filter(|[start, end]| start.is_finite() && end.is_finite())
+        .map(|[start, end]| [start.max(0.0), end.min(duration)])
+        .filter(|[start, end]| start < end)
+        .collect();
+    intervals.sort_by(|a, b| a[0].total_cmp(&b[0]));
+
+    let mut merged: Vec<[f64; 2]> = Vec::with_capacity(intervals.len());
+    for [start, end] in intervals {
+        match merged.last_mut() {
+            Some(last) if start <= last[1] => last[1] = last[1].max(end),
+            _ => merged.push([start, end]),
+        }
+    }
+    merged
+}
+
+// Parts of `[start, end]` outside the sorted, merged `watched` intervals
+fn unwatched_parts(watched: &[[f64; 2]], [start, end]: [f64; 2]) -> Vec<[f64; 2]> {
+    if !(start.is_finite() && end.is_finite()) {
+        return Vec::new();
+    }
+    let mut parts = Vec::new();
+    let mut cursor = start;
+    for &[watched_start, watched_end] in watched {
+        if watched_start >= end {
+            break;
+        }
+        if watched_start > cursor {
+            parts.push([cursor, watched_start]);
+        }
+        cursor = cursor.max(watched_end);
+    }
+    if cursor < end {
+        parts.push([cursor, end]);
+    }
+    parts
+}
+
+pub fn watched_seconds(intervals: &[[f64; 2]]) -> f64 {
+    // Folded from 0.0, an empty float sum is -0.0
+    intervals
+        .iter()
+        .fold(0.0, |total, [start, end]| total + end - start)
+}
+
+/// Share of the video watched, unknown until the player reported the duration.
+pub fn watched_percent(watched_seconds: f64, duration: f64) -> Option<f64> {
+    (duration > 0.0).then(|| (watched_seconds / duration * 100.0).min(100.0))
+}
+
+/// Where the video continues from, the start again once it was played to the end.
+pub fn resume_position(position: f64, duration: f64) -> f64 {
+    if duration > 0.0 && position >= duration - RESUME_END_MARGIN_SECONDS {
+        0.0
+    } else {
+        position.max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_intervals_count_once() {
+        let merged = merge_watched(&[[0.0, 10.0]], &[[5.0, 15.0], [14.0, 20.0]], 60.0, 60.0);
+
+        assert_eq!(merged, vec![[0.0, 20.0]]);
+        assert_eq!(watched_seconds(&merged), 20.0);
+    }
+
+    #[test]
+    fn intervals_are_clamped_to_the_duration() {
+        let merged = merge_watched(&[], &[[-5.0, 10.0], [50.0, 90.0]], 60.0, 100.0);
+
+        assert_eq!(merged, vec![[0.0, 10.0], [50.0, 60.0]]);
+    }
+
+    #[test]
+    fn new_seconds_are_capped_in_the_order_played() {
+        let merged = merge_watched(&[[0.0, 10.0]], &[[5.0, 20.0], [40.0, 60.0]], 60.0, 15.0);
+
+        // Rewatching 5-10 is free, 10-20 uses 10 seconds and 40-45 the rest
+        assert_eq!(merged, vec![[0.0, 20.0], [40.0, 45.0]]);
+    }
+
+    #[test]
+    fn first_report_credits_nothing() {
+        assert_eq!(max_new_watched_seconds(None), 0.0);
+        assert_eq!(max_new_watched_seconds(Some(10.0)), 20.0);
+        assert!(merge_watched(&[], &[[0.0, 60.0]], 60.0, max_new_watched_seconds(None)).is_empty());
+    }
+
+    #[test]
+    fn only_video_files_report_progress() {
+        let media = format!("https://api.example.com/media/{}", "ab".repeat(32));
+
+        assert!(is_video_file(&media));
+        assert!(is_video_file("https://cdn.example.com/intro.MP4?token=1"));
+        assert!(is_video_file("https://cdn.example.com/intro.webm#t=10"));
+        assert!(!is_video_file("https://www.youtube.com/embed/abc123"));
+        assert!(!is_video_file("https://cdn.example.com/media/intro"));
+        assert!(!is_video_file("intro.mp4"));
+    }
+
+    #[test]
+    fn reported_duration_must_match_the_lesson() {
+        assert!(duration_matches(120.4, 120.0));
+        assert!(!duration_matches(1.0, 120.0));
+    }
+}
@@ -0,0 +1,205 @@
+use reqwest::StatusCode;
+use serde_json::Value;
+
+const XAPI_VERSION: &str = "1.0.3";
+
+/// Client for the statements resource of a Learning Record Store.
+#[derive(Clone)]
+pub struct LrsClient {
+    http: reqwest::Client,
+    endpoint: String,
+    username: String,
+    password: String,
+}
+
+impl LrsClient {
+    pub fn new(http: reqwest::Client, endpoint: &str, username: &str, password: &str) -> LrsClient {
+        LrsClient {
+            http,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            username: username.to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    /// Stores a statement under its id. The PUT is idempotent, so a statement
+    /// is sent again safely when the response of an earlier try was lost.
+    pub async fn put_statement(&self, id: &str, statement: &Value) -> Result<(), String> {
+        let mut request = self
+            .http
+            .put(format!("{}/statements", self.endpoint))
+            .query(&[("statementId", id)])
+            .header("X-Experience-API-Version", XAPI_VERSION)
+            .json(statement);
+        if !self.username.is_empty() {
+            request = request.basic_auth(&self.username, Some(&self.password));
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("LRS request failed: {}", e))?;
+
+        // 409 means the LRS already has a statement with this id
+        match response.status() {
+            status if status.is_success() || status == StatusCode::CONFLICT => Ok(()),
+            status => {
+                let body = response.text().await.unwrap_or_default();
+                Err(format!(
+                    "LRS returned status {}: {}",
+                    status,
+                    body.chars().take(200).collect::<String>()
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Json, Router,
+        extract::{Query, State},
+        http::HeaderMap,
+        routing::put,
+    };
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use super::*;
+    use crate::models::xapi::XapiStatementRow;
+    use crate::test_support::serve;
+    use crate::xapi::statement::{quiz_result, render_statement};
+
+    // Basic auth header of lrs:secret
+    const AUTHORIZATION: &str = "Basic bHJzOnNlY3JldA==";
+
+    type Stored = Arc<Mutex<HashMap<String, Value>>>;
+
+    /// LRS that stores statements by id and rejects unversioned, unauthorized
+    /// and malformed requests the way the xAPI spec asks.
+    fn mock_lrs(stored: Stored) -> Router {
+        Router::new()
+            .route(
+                "/statements",
+                put(
+                    |State(stored): State<Stored>,
+                     Query(query): Query<HashMap<String, String>>,
+                     headers: HeaderMap,
+                     Json(statement): Json<Value>| async move {
+                        let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+                        if header("Authorization") != Some(AUTHORIZATION) {
+                            return StatusCode::UNAUTHORIZED;
+                        }
+                        if header("X-Experience-API-Version") != Some(XAPI_VERSION) {
+                            return StatusCode::BAD_REQUEST;
+                        }
+                        let Some(id) = query.get("statementId") else {
+                            return StatusCode::BAD_REQUEST;
+                        };
+                        if statement["id"] != json!(id) {
+                            return StatusCode::BAD_REQUEST;
+                        }
+
+                        let mut stored = stored.lock().unwrap();
+                        if stored.contains_key(id) {
+                            return StatusCode::CONFLICT;
+                        }
+                        stored.insert(id.clone(), statement);
+                        StatusCode::NO_CONTENT
+                    },
+                ),
+            )
+            .with_state(stored)
+    }
+
+    async fn lrs(username: &str) -> (LrsClient, Stored) {
+        let stored = Stored::default();
+        let endpoint = serve(mock_lrs(stored.clone())).await;
+        let client = LrsClient::new(
+            reqwest::Client::new(),
+            &format!("{}/", endpoint),
+            username,
+            "secret",
+        );
+        (client, stored)
+    }
+
+    fn quiz_statement() -> XapiStatementRow {
+        XapiStatementRow {
+            id: "6f1c2a4e-8d3b-4c5a-9e7f-0a1b2c3d4e5f".to_string(),
+            learner_id: "0xabc".to_string(),
+            verb: "passed".to_string(),
+            activity_type: "quiz".to_string(),
+            activity_id: 3,
+            course_id: 4,
+            activity_name: "Basics quiz".to_string(),
+            result: Some(quiz_result(8.0, 10.0, 80.0, true)),
+            timestamp: Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap(),
+            status: "pending".to_string(),
+            attempts: 0,
+            last_error: None,
+            sent_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn put_statement_stores_the_rendered_statement() {
+        let (lrs, stored) = lrs("lrs").await;
+        let row = quiz_statement();
+
+        lrs.put_statement(&row.id, &render_statement(&row, "https://api.aranya.test/"))
+            .await
+            .unwrap();
+
+        let stored = stored.lock().unwrap();
+        let statement = &stored[&row.id];
+        assert_eq!(
+            statement["actor"]["account"],
+            json!({ "homePage": "https://api.aranya.test", "name": "0xabc" })
+        );
+        assert_eq!(
+            statement["verb"]["id"],
+            "http://adlnet.gov/expapi/verbs/passed"
+        );
+        assert_eq!(
+            statement["object"]["id"],
+            "https://api.aranya.test/courses/4/quizzes/3"
+        );
+        assert_eq!(
+            statement["context"]["contextActivities"]["parent"][0]["id"],
+            "https://api.aranya.test/courses/4"
+        );
+        assert_eq!(statement["result"]["score"]["scaled"], 0.8);
+        assert_eq!(statement["timestamp"], "2026-01-02T03:04:05.000Z");
+    }
+
+    #[tokio::test]
+    async fn statement_already_stored_counts_as_sent() {
+        let (lrs, stored) = lrs("lrs").await;
+        let row = quiz_statement();
+        let statement = render_statement(&row, "https://api.aranya.test");
+
+        lrs.put_statement(&row.id, &statement).await.unwrap();
+        lrs.put_statement(&row.id, &statement).await.unwrap();
+
+        assert_eq!(stored.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn put_statement_reports_rejected_statements() {
+        let (lrs, stored) = lrs("wrong-user").await;
+        let row = quiz_statement();
+
+        let error = lrs
+            .put_statement(&row.id, &render_statement(&row, "https://api.aranya.test"))
+            .await
+            .unwrap_err();
+
+        assert_eq!(error, "LRS returned status 401 Unauthorized: ");
+        assert!(stored.lock().unwrap().is_empty());
+    }
+}
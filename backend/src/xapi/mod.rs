@@ -0,0 +1,151 @@
+pub mod lrs;
+pub mod statement;
+pub mod worker;
+
+use dotenv::dotenv;
+use serde_json::Value;
+use sqlx::{Executor, Postgres};
+use std::env;
+
+/// Learning Record Store the xAPI statements are delivered to. The endpoint
+/// is configurable so statements can be sent to a local mock LRS.
+#[derive(Clone, Debug)]
+pub struct XapiConfig {
+    pub lrs_endpoint: String,
+    pub lrs_username: String,
+    pub lrs_password: String,
+    // Prefix of activity ids and home page of learner accounts
+    pub activity_base_url: String,
+}
+
+impl XapiConfig {
+    /// `None` when no LRS is configured, statements then stay queued.
+    pub fn from_env(public_api_url: &str) -> Option<XapiConfig> {
+        dotenv().ok();
+        let lrs_endpoint = env::var("XAPI_LRS_ENDPOINT")
+            .ok()
+            .filter(|e| !e.is_empty())?;
+        Some(XapiConfig {
+            lrs_endpoint,
+            lrs_username: env::var("XAPI_LRS_USERNAME").unwrap_or_default(),
+            lrs_password: env::var("XAPI_LRS_PASSWORD").unwrap_or_default(),
+            activity_base_url: env::var("XAPI_ACTIVITY_BASE_URL")
+                .unwrap_or_else(|_| public_api_url.to_string()),
+        })
+    }
+}
+
+/// The ADL verbs statements are emitted with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verb {
+    Launched,
+    Experienced,
+    Completed,
+    Passed,
+    Failed,
+    Scored,
+}
+
+impl Verb {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Verb::Launched => "launched",
+            Verb::Experienced => "experienced",
+            Verb::Completed => "completed",
+            Verb::Passed => "passed",
+            Verb::Failed => "failed",
+            Verb::Scored => "scored",
+        }
+    }
+
+    pub fn parse(verb: &str) -> Option<Verb> {
+        match verb {
+            "launched" => Some(Verb::Launched),
+            "experienced" => Some(Verb::Experienced),
+            "completed" => Some(Verb::Completed),
+            "passed" => Some(Verb::Passed),
+            "failed" => Some(Verb::Failed),
+            "scored" => Some(Verb::Scored),
+            _ => None,
+        }
+    }
+
+    pub fn iri(&self) -> String {
+        format!("http://adlnet.gov/expapi/verbs/{}", self.as_str())
+    }
+}
+
+/// The course item a statement is about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Activity {
+    Course(i64),
+    Lesson(i64),
+    Quiz(i64),
+}
+
+impl Activity {
+    fn kind(&self) -> &'static str {
+        match self {
+            Activity::Course(_) => "course",
+            Activity::Lesson(_) => "lesson",
+            Activity::Quiz(_) => "quiz",
+        }
+    }
+
+    fn id(&self) -> i64 {
+        match self {
+            Activity::Course(id) | Activity::Lesson(id) | Activity::Quiz(id) => *id,
+        }
+    }
+
+    // Course id and display name of the activity, by the activity id
+    fn lookup(&self) -> &'static str {
+        match self {
+            Activity::Course(_) => "SELECT id, title FROM course WHERE id = $1",
+            Activity::Lesson(_) => {
+                "SELECT m.course_id, l.title FROM lesson l JOIN module m ON m.id = l.module_id WHERE l.id = $1"
+            }
+            Activity::Quiz(_) => {
+                r#"
+                SELECT COALESCE(q.course_id, m.course_id), COALESCE(m.title || ' quiz', 'Final exam')
+                FROM quiz q
+                LEFT JOIN module m ON m.id = q.module_id
+                WHERE q.id = $1
+                "#
+            }
+        }
+    }
+}
+
+/// Queues a statement for the LRS. Called in the transaction that records
+/// the learning event, so a statement is queued if and only if the event is.
+/// Activity names are kept so statements outlive a deleted course.
+pub async fn enqueue_statement<'e, E>(
+    executor: E,
+    learner_id: &str,
+    verb: Verb,
+    activity: Activity,
+    result: Option<Value>,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let query = format!(
+        r#"
+        INSERT INTO xapi_statement (learner_id, verb, activity_type, activity_id, course_id, activity_name, result)
+        SELECT $2, $3, $4, $1, activity.course_id, activity.name, $5
+        FROM ({}) AS activity (course_id, name)
+        "#,
+        activity.lookup()
+    );
+    sqlx::query(&query)
+        .bind(activity.id())
+        .bind(learner_id)
+        .bind(verb.as_str())
+        .bind(activity.kind())
+        .bind(result)
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
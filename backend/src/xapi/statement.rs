@@ -0,0 +1,70 @@
+use chrono::SecondsFormat;
+use serde_json::{Value, json};
+
+use crate::models::xapi::XapiStatementRow;
+use crate::xapi::Verb;
+
+/// Result of a graded quiz attempt, scaled by the maximum score.
+pub fn quiz_result(score: f64, max_score: f64, score_percent: f64, passed: bool) -> Value {
+    json!({
+        "score": {
+            "scaled": (score_percent / 100.0).clamp(0.0, 1.0),
+            "raw": score,
+            "min": 0.0,
+            "max": max_score,
+        },
+        "success": passed,
+        "completion": true,
+    })
+}
+
+/// The xAPI 1.0.3 statement of a queued event. Learners are identified by
+/// an account on this platform, their ids are wallet addresses.
+pub fn render_statement(row: &XapiStatementRow, activity_base_url: &str) -> Value {
+    let base = activity_base_url.trim_end_matches('/');
+    let course_iri = format!("{}/courses/{}", base, row.course_id);
+    let (object_iri, activity_type) = match row.activity_type.as_str() {
+        "lesson" => (
+            format!("{}/lessons/{}", course_iri, row.activity_id),
+            "lesson",
+        ),
+        "quiz" => (
+            format!("{}/quizzes/{}", course_iri, row.activity_id),
+            "assessment",
+        ),
+        _ => (course_iri.clone(), "course"),
+    };
+    let verb = Verb::parse(&row.verb).map(|v| v.iri()).unwrap_or_default();
+
+    let mut statement = json!({
+        "id": row.id,
+        "actor": {
+            "objectType": "Agent",
+            "account": { "homePage": base, "name": row.learner_id },
+        },
+        "verb": {
+            "id": verb,
+            "display": { "en-US": row.verb },
+        },
+        "object": {
+            "objectType": "Activity",
+            "id": object_iri,
+            "definition": {
+                "type": format!("http://adlnet.gov/expapi/activities/{}", activity_type),
+                "name": { "en-US": row.activity_name },
+            },
+        },
+        "context": { "platform": "Aranya" },
+        "timestamp": row.timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+    });
+    if row.activity_type != "course" {
+        statement["context"]["contextActivities"] = json!({
+            "parent": [{ "objectType": "Activity", "id": course_iri }],
+        });
+    }
+    if let Some(result) = &row.result {
+        statement["result"] = result.clone();
+    }
+
+    statement
+}
@@ -0,0 +1,144 @@
+use chrono::{Duration, Utc};
+use sqlx::{Pool, Postgres};
+
+use crate::models::xapi::XapiStatementRow;
+use crate::xapi::{XapiConfig, lrs::LrsClient, statement::render_statement};
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_SENT: &str = "sent";
+pub const STATUS_FAILED: &str = "failed";
+
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+const BATCH_SIZE: i64 = 50;
+const MAX_ATTEMPTS: i32 = 10;
+const BASE_BACKOFF_SECONDS: i64 = 10;
+const MAX_BACKOFF_SECONDS: i64 = 3600;
+
+/// Background worker that delivers queued xAPI statements to the LRS, oldest
+/// first, retrying with backoff.
+pub struct XapiWorker {
+    pool: Pool<Postgres>,
+    activity_base_url: String,
+    lrs: LrsClient,
+}
+
+impl XapiWorker {
+    pub fn new(pool: Pool<Postgres>, config: &XapiConfig) -> XapiWorker {
+        XapiWorker {
+            pool,
+            activity_base_url: config.activity_base_url.clone(),
+            lrs: LrsClient::new(
+                reqwest::Client::new(),
+                &config.lrs_endpoint,
+                &config.lrs_username,
+                &config.lrs_password,
+            ),
+        }
+    }
+
+    pub fn spawn(self) {
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = self.send_due_statements().await {
+                    eprintln!("xAPI worker error: {}", e);
+                }
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        });
+    }
+
+    async fn send_due_statements(&self) -> Result<(), sqlx::Error> {
+        let statements: Vec<XapiStatementRow> = sqlx::query_as::<_, XapiStatementRow>(
+            r#"
+            SELECT id::TEXT, learner_id, verb, activity_type, activity_id, course_id, activity_name,
+                result, timestamp, status, attempts, last_error, sent_at
+            FROM xapi_statement
+            WHERE status = $1 AND next_attempt_at <= now()
+            ORDER BY timestamp
+            LIMIT $2
+            "#,
+        )
+        .bind(STATUS_PENDING)
+        .bind(BATCH_SIZE)
+        .fetch_all(&self.pool)
+        .await?;
+
+        for statement in statements {
+            let sent = self
+                .lrs
+                .put_statement(
+                    &statement.id,
+                    &render_statement(&statement, &self.activity_base_url),
+                )
+                .await;
+            match sent {
+                Ok(()) => {
+                    sqlx::query(
+                        r#"
+                        UPDATE xapi_statement
+                        SET status = $2, attempts = attempts + 1, last_error = NULL,
+                            sent_at = now()
+                        WHERE id = $1::UUID
+                        "#,
+                    )
+                    .bind(&statement.id)
+                    .bind(STATUS_SENT)
+                    .execute(&self.pool)
+                    .await?;
+                }
+                Err(e) => {
+                    eprintln!("xAPI statement {} not sent: {}", statement.id, e);
+                    self.retry(&statement, e).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn retry(&self, statement: &XapiStatementRow, error: String) -> Result<(), sqlx::Error> {
+        let attempts = statement.attempts + 1;
+        let status = if attempts >= MAX_ATTEMPTS {
+            STATUS_FAILED
+        } else {
+            STATUS_PENDING
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE xapi_statement
+            SET status = $2, attempts = $3, last_error = $4, next_attempt_at = $5
+            WHERE id = $1::UUID
+            "#,
+        )
+        .bind(&statement.id)
+        .bind(status)
+        .bind(attempts)
+        .bind(error)
+        .bind(Utc::now() + backoff(attempts))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+fn backoff(attempts: i32) -> Duration {
+    let seconds = BASE_BACKOFF_SECONDS.saturating_mul(1 << attempts.clamp(0, 16));
+    Duration::seconds(seconds.min(MAX_BACKOFF_SECONDS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        assert_eq!(backoff(1), Duration::seconds(20));
+        assert_eq!(backoff(3), Duration::seconds(80));
+        assert_eq!(
+            backoff(MAX_ATTEMPTS),
+            Duration::seconds(MAX_BACKOFF_SECONDS)
+        );
+    }
+}
@@ -37,16 +37,21 @@ contract CourseManager {
     string[5] public learnerMilestoneURIs;
     uint16[5] public creatorMilestoneThresholds;
     mapping(uint256 => mapping(address => bool)) public isEnrolled;
+    mapping(bytes32 => bool) public usedNonces;
 
     struct CreateCourseDTO {
         uint256 course_id;
         address user_id;
+        bytes32 nonce;
+        uint256 expires_at;
     }
 
     struct EnrollDTO {
         uint256 course_id;
         address learner_id;
         bool is_enrolled;
+        bytes32 nonce;
+        uint256 expires_at;
     }
 
     struct Course {
@@ -111,6 +116,10 @@ contract CourseManager {
         require(dto.course_id == courseId, "courseId doesn't match");
         require(dto.user_id == msg.sender, "Sender is not course creator");
 
+        require(block.timestamp <= dto.expires_at, "Attestation expired");
+        require(!usedNonces[dto.nonce], "Attestation already used");
+        usedNonces[dto.nonce] = true;
+
         address creatorNFTClone = creatorNFTImplementation.clone();
         CreatorNFT(creatorNFTClone).initialize(
             courseName,
@@ -164,6 +173,10 @@ contract CourseManager {
         require(dto.is_enrolled, "Sender is not saved to database");
         require(!isEnrolled[courseId][msg.sender], "Already enrolled");
 
+        require(block.timestamp <= dto.expires_at, "Attestation expired");
+        require(!usedNonces[dto.nonce], "Attestation already used");
+        usedNonces[dto.nonce] = true;
+
         LearnerNFT learnerNFT = LearnerNFT(course.learnerNFT);
         uint256 tokenId = learnerNFT.mint(msg.sender);
 
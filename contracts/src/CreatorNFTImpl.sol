@@ -25,12 +25,15 @@ contract CreatorNFT is
     string[5] public milestoneURIs;
     uint16[5] public milestoneThresholds;
     uint256 public currentMilestone;
+    mapping(bytes32 => bool) public usedNonces;
 
     event MilestoneUpdated(uint256 newMilestone);
 
     struct DataTransportObject {
         uint256 course_id;
         uint256 num_completed;
+        bytes32 nonce;
+        uint256 expires_at;
     }
 
     constructor() {
@@ -74,6 +77,9 @@ contract CreatorNFT is
         );
 
         require(dto.course_id == courseId, "CourseId doesn't match");
+        require(block.timestamp <= dto.expires_at, "Attestation expired");
+        require(!usedNonces[dto.nonce], "Attestation already used");
+        usedNonces[dto.nonce] = true;
 
         uint16[5] memory thresholds = milestoneThresholds;
         uint256 milestone = 0;
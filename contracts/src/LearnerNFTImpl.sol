@@ -33,11 +33,15 @@ contract LearnerNFT is
     mapping(uint256 => uint256) public tokenMilestones;
 
     mapping(uint256 => bool) public tokenExists;
+    mapping(bytes32 => bool) public usedNonces;
 
     struct DataTransportObject {
         uint256 course_id;
         address learner_id;
         uint8 progress_percent;
+        bytes32 nonce;
+        uint8 milestone;
+        uint256 expires_at;
     }
 
     event LearnerNFTMinted(address indexed learner, uint256 indexed tokenId);
@@ -108,7 +112,15 @@ contract LearnerNFT is
             "LearnerId doesn't match with sender"
         );
 
-        uint256 newMilestone = dto.progress_percent / 25;
+        require(block.timestamp <= dto.expires_at, "Attestation expired");
+        require(!usedNonces[dto.nonce], "Attestation already used");
+        require(
+            dto.milestone <= dto.progress_percent / 25,
+            "Milestone not reached"
+        );
+        usedNonces[dto.nonce] = true;
+
+        uint256 newMilestone = dto.milestone;
         uint256 currentMilestone = tokenMilestones[tokenId];
 
         if (newMilestone > currentMilestone) {
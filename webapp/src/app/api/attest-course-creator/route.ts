@@ -0,0 +1,17 @@
+import { requestAttestationNonce, requestAttestationProof, jsonResponse } from "../utils/attestation";
+
+export async function POST(request: Request) {
+    const { courseId, wallet, requestedAt, signature } = await request.json();
+
+    const nonce = await requestAttestationNonce({
+        kind: "course_creator",
+        courseId,
+        wallet,
+        requestedAt,
+        signature,
+    });
+
+    const proof = await requestAttestationProof({ kind: "course_creator", courseId, nonce });
+
+    return jsonResponse({ proof });
+}
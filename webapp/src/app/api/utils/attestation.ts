@@ -0,0 +1,109 @@
+import IWeb2JsonVerification from "../../abis/fdc/IWeb2JsonVerification.json";
+import { decodeAbiParameters } from "viem";
+import { sleep } from "./core";
+
+export const BACKEND_URL = process.env.BACKEND_URL ?? "http://localhost:4000";
+
+const POLL_INTERVAL_MS = 5000;
+const MAX_POLL_TIME_MS = 30 * 60 * 1000;
+
+type AttestationKind = "course_creator" | "enrollment" | "creator_milestone" | "learner_milestone";
+
+type AttestationNonceRequest = {
+    kind: AttestationKind;
+    courseId: number | string;
+    wallet: string;
+    milestone?: number;
+    requestedAt: number;
+    signature: string;
+};
+
+type AttestationJobRequest = {
+    kind: AttestationKind;
+    courseId: number | string;
+    learnerId?: string;
+    nonce: string;
+};
+
+// Exchanges a wallet signed nonce request for the nonce the attested endpoint is queried with
+export async function requestAttestationNonce(nonceRequest: AttestationNonceRequest): Promise<string> {
+    const nonceResponse = await fetch(`${BACKEND_URL}/request-attestation-nonce`, {
+        method: "POST",
+        headers: {
+            "Content-Type": "application/json",
+        },
+        body: JSON.stringify({ ...nonceRequest, courseId: Number(nonceRequest.courseId) }),
+    });
+
+    if (!nonceResponse.ok) {
+        throw new Error(`Failed to request attestation nonce: ${await nonceResponse.text()}`);
+    }
+
+    const { nonce } = await nonceResponse.json();
+    return nonce;
+}
+
+// Creates an attestation job on the backend, waits for its proof and decodes the response
+export async function requestAttestationProof(jobRequest: AttestationJobRequest) {
+    const createResponse = await fetch(`${BACKEND_URL}/create-attestation-job`, {
+        method: "POST",
+        headers: {
+            "Content-Type": "application/json",
+        },
+        body: JSON.stringify({ ...jobRequest, courseId: Number(jobRequest.courseId) }),
+    });
+
+    if (!createResponse.ok) {
+        throw new Error(`Failed to create attestation job: ${await createResponse.text()}`);
+    }
+
+    let job = await createResponse.json();
+    const startedAt = Date.now();
+
+    while (job.status !== "proof_ready") {
+        if (job.status === "failed") {
+            throw new Error(`Attestation job ${job.id} failed: ${job.lastError}`);
+        }
+        if (Date.now() - startedAt > MAX_POLL_TIME_MS) {
+            throw new Error(`Attestation job ${job.id} timed out in status ${job.status}`);
+        }
+
+        await sleep(POLL_INTERVAL_MS);
+
+        const jobResponse = await fetch(`${BACKEND_URL}/get-attestation-job?jobId=${job.id}`);
+        if (!jobResponse.ok) {
+            throw new Error(`Failed to get attestation job: ${await jobResponse.text()}`);
+        }
+        job = await jobResponse.json();
+    }
+
+    const responseType = IWeb2JsonVerification.abi[0].inputs[0].components[1];
+
+    if (!responseType) throw new Error("Response ABI not found");
+
+    const decoded = decodeAbiParameters(
+        [
+            {
+                type: responseType.type,
+                name: responseType.name,
+                components: responseType.components,
+            },
+        ],
+        job.proof.responseHex as `0x${string}`
+    );
+
+    return {
+        merkleProof: job.proof.merkleProof,
+        data: decoded[0],
+    };
+}
+
+export function jsonResponse(body: any) {
+    return new Response(
+        JSON.stringify(body, (_, value) => (typeof value === "bigint" ? value.toString() : value)),
+        {
+            status: 200,
+            headers: { "Content-Type": "application/json" },
+        }
+    );
+}
@@ -6,6 +6,7 @@ import CoursePreviewSection from '../components/Landing/CoursePreviewSection';
 import FAQSection from '../components/Landing/FAQSection';
 import Footer from '../components/Layout/Footer';
 import { CoursePreview } from '@/types/course';
+import { PlatformStats } from '@/types/stats';
 interface LandingPageProps {
   isLoggedIn: boolean;
   onLogin: () => void;
@@ -17,25 +18,25 @@ const LandingPage: React.FC<LandingPageProps> = async ({
   onLogout
 }) => {
 
-  const [coursesResponse, countsResponse] = await Promise.all([
+  const [coursesResponse, statsResponse] = await Promise.all([
     fetch("http://localhost:4000/get-top-courses"),
-    fetch("http://localhost:4000/get-counts"),
+    fetch("http://localhost:4000/get-platform-stats"),
   ]);
 
-  if (!coursesResponse.ok || !countsResponse.ok) {
+  if (!coursesResponse.ok || !statsResponse.ok) {
     throw new Error("Failed to fetch data");
   }
 
-  const [courses, counts] = await Promise.all([
+  const [courses, stats]: [CoursePreview[], PlatformStats] = await Promise.all([
     coursesResponse.json(),
-    countsResponse.json(),
+    statsResponse.json(),
   ]);
 
   return (
     <div className="min-h-screen flex flex-col bg-stone-50">
       <Navbar isLoggedIn={isLoggedIn} onLogin={onLogin} onLogout={onLogout} />
       <main>
-        <HeroSection isLoggedIn={isLoggedIn} onLogin={onLogin} counts={counts} />
+        <HeroSection isLoggedIn={isLoggedIn} onLogin={onLogin} stats={stats} />
         <HowItWorks />
         <CoursePreviewSection courses={courses} />
         <FAQSection />
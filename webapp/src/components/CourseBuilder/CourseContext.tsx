@@ -20,6 +20,8 @@ export type Lesson = {
   content: string;
   position: number;
   videoUrl?: string;
+  minWatchedPercent?: number;
+  videoDurationSeconds?: number;
 };
 export type Module = {
   id: string;
@@ -1,6 +1,7 @@
 import React, { useEffect, useState } from 'react';
 import { useCourseBuilder, Lesson } from './CourseContext';
 import { TrashIcon, PencilIcon, LeafIcon } from 'lucide-react';
+import { isVideoFile } from '../CourseViewer/LessonContent';
 const LessonForm: React.FC = () => {
   const {
     course,
@@ -12,9 +13,14 @@ const LessonForm: React.FC = () => {
   const [title, setTitle] = useState('');
   const [content, setContent] = useState('');
   const [videoUrl, setVideoUrl] = useState('');
+  const [minWatchedPercent, setMinWatchedPercent] = useState('');
+  // Read from the video file, the backend checks progress reports against it
+  const [videoDuration, setVideoDuration] = useState<number | undefined>();
   const [isEditing, setIsEditing] = useState(false);
   const [lessonPosition, setLessonPosition] = useState(1);
   const currentModule = course.modules.find(mod => mod.id === activeModule);
+  // Only video files report what was watched, embeds can't require a share
+  const tracksVideo = !!videoUrl && isVideoFile(videoUrl);
   useEffect(() => {
     if (!currentModule) {
       return;
@@ -25,6 +31,8 @@ const LessonForm: React.FC = () => {
         setTitle(lesson.title);
         setContent(lesson.content);
         setVideoUrl(lesson.videoUrl || '');
+        setMinWatchedPercent(lesson.minWatchedPercent?.toString() ?? '');
+        setVideoDuration(lesson.videoDurationSeconds);
         setIsEditing(true);
       }
     }
@@ -45,7 +53,9 @@ const LessonForm: React.FC = () => {
                 ...lesson,
                 title,
                 content,
-                videoUrl: videoUrl || undefined
+                videoUrl: videoUrl || undefined,
+                minWatchedPercent: tracksVideo && minWatchedPercent ? Number(minWatchedPercent) : undefined,
+                videoDurationSeconds: tracksVideo ? videoDuration : undefined
               } : lesson)
             };
           }
@@ -59,7 +69,9 @@ const LessonForm: React.FC = () => {
         title,
         content,
         position: lessonPosition,
-        videoUrl: videoUrl || undefined
+        videoUrl: videoUrl || undefined,
+        minWatchedPercent: tracksVideo && minWatchedPercent ? Number(minWatchedPercent) : undefined,
+        videoDurationSeconds: tracksVideo ? videoDuration : undefined
       };
 
       setLessonPosition(lessonPosition + 1);
@@ -79,12 +91,16 @@ const LessonForm: React.FC = () => {
     setTitle('');
     setContent('');
     setVideoUrl('');
+    setMinWatchedPercent('');
+    setVideoDuration(undefined);
     setActiveLesson(null);
   };
   const handleEditLesson = (lesson: Lesson) => {
     setTitle(lesson.title);
     setContent(lesson.content);
     setVideoUrl(lesson.videoUrl || '');
+    setMinWatchedPercent(lesson.minWatchedPercent?.toString() ?? '');
+    setVideoDuration(lesson.videoDurationSeconds);
     setActiveLesson(lesson.id);
     setIsEditing(true);
   };
@@ -135,11 +151,23 @@ const LessonForm: React.FC = () => {
               <label htmlFor="videoUrl" className="block text-sm font-medium text-stone-700 mb-1">
                 Video URL (optional)
               </label>
-              <input type="url" id="videoUrl" value={videoUrl} onChange={e => setVideoUrl(e.target.value)} className="block w-full text-stone-800 focus:outline-none rounded-md border-stone-200 shadow-sm focus:border-amber-500 focus:ring-amber-500 sm:text-sm p-2 border" placeholder="https://www.youtube.com/embed/video-id" />
+              <input type="url" id="videoUrl" value={videoUrl} onChange={e => {
+              setVideoUrl(e.target.value);
+              setVideoDuration(undefined);
+            }} className="block w-full text-stone-800 focus:outline-none rounded-md border-stone-200 shadow-sm focus:border-amber-500 focus:ring-amber-500 sm:text-sm p-2 border" placeholder="https://www.youtube.com/embed/video-id" />
               {videoUrl && <div className="mt-2 border border-stone-200 rounded-md overflow-hidden">
-                  <iframe width="100%" height="150" src={videoUrl} title="Video preview" frameBorder="0" allow="accelerometer; autoplay; clipboard-write; encrypted-media; gyroscope; picture-in-picture" allowFullScreen />
+                  {tracksVideo ? <video width="100%" height="150" src={videoUrl} controls preload="metadata" onLoadedMetadata={e => setVideoDuration(Number.isFinite(e.currentTarget.duration) ? e.currentTarget.duration : undefined)} /> : <iframe width="100%" height="150" src={videoUrl} title="Video preview" frameBorder="0" allow="accelerometer; autoplay; clipboard-write; encrypted-media; gyroscope; picture-in-picture" allowFullScreen />}
                 </div>}
             </div>
+            {tracksVideo && <div>
+              <label htmlFor="minWatchedPercent" className="block text-sm font-medium text-stone-700 mb-1">
+                Minimum watched % to complete (optional)
+              </label>
+              <input type="number" id="minWatchedPercent" min={1} max={100} value={minWatchedPercent} onChange={e => setMinWatchedPercent(e.target.value)} disabled={videoDuration === undefined} className="block w-full text-stone-800 focus:outline-none rounded-md border-stone-200 shadow-sm focus:border-amber-500 focus:ring-amber-500 sm:text-sm p-2 border" placeholder="80" />
+              {videoDuration === undefined && <p className="mt-1 text-xs text-stone-500">
+                  Waiting for the video to load its duration
+                </p>}
+            </div>}
             <div className="pt-2 flex gap-2">
               <button type="submit" className="inline-flex justify-center rounded-md border border-transparent bg-amber-700 py-2 px-4 text-sm font-medium text-white shadow-sm hover:bg-amber-800 focus:outline-none focus:ring-2 focus:ring-amber-500 focus:ring-offset-2">
                 {isEditing ? 'Update Lesson' : 'Add Lesson'}
@@ -149,6 +177,8 @@ const LessonForm: React.FC = () => {
               setTitle('');
               setContent('');
               setVideoUrl('');
+              setMinWatchedPercent('');
+              setVideoDuration(undefined);
               setActiveLesson(null);
             }} className="inline-flex justify-center rounded-md border border-stone-200 bg-white py-2 px-4 text-sm font-medium text-stone-700 shadow-sm hover:bg-stone-50 focus:outline-none focus:ring-2 focus:ring-amber-500 focus:ring-offset-2">
                   Cancel
@@ -13,8 +13,9 @@ import {
     SendIcon,
     LeafIcon,
 } from "lucide-react";
-import { useAccount, useWriteContract } from "wagmi";
+import { useAccount, useSignMessage, useWriteContract } from "wagmi";
 import ICourseManager from "../../app/abis/aranya/ICourseManager.json";
+import { nonceRequestMessage } from "../utils/utils";
 const COURSE_MANAGER_ADDRESS =
     process.env.NEXT_PUBLIC_COURSE_MANAGER_ADDRESS || "";
 
@@ -72,6 +73,7 @@ export default function SubmitCourseModal({
     const [error, setError] = useState<string | null>(null);
     const { isConnected, chainId, address } = useAccount();
     const { writeContractAsync, isPending: isWritePending } = useWriteContract();
+    const { signMessageAsync } = useSignMessage();
     const [serverData, setServerData] = useState<ServerResponse | null>(null);
     const { course } = useCourseBuilder();
 
@@ -87,8 +89,10 @@ export default function SubmitCourseModal({
                 content: lesson.content,
                 videoUrl: lesson.videoUrl,
                 position: lesson.position,
+                minWatchedPercent: lesson.videoUrl ? lesson.minWatchedPercent : undefined,
+                videoDurationSeconds: lesson.videoUrl ? lesson.videoDurationSeconds : undefined,
             })),
-            quiz: mod.quiz ? toQuizPayload(mod.quiz) : undefined
+            quiz: mod.quiz?.questions.length ? toQuizPayload(mod.quiz) : undefined
         }))
     };
 
@@ -111,6 +115,10 @@ export default function SubmitCourseModal({
 
         const runServerFlow = async () => {
             try {
+                if (!address) {
+                    throw new Error("Please connect your wallet to continue.");
+                }
+
                 const res = await fetch("/api/create-course", {
                     method: "POST",
                     headers: { "Content-Type": "application/json" },
@@ -121,10 +129,29 @@ export default function SubmitCourseModal({
                     throw new Error(`Server returned ${res.status}`);
                 }
 
-                const data = (await res.json()) as { proof?: any; courseId?: string | number; creatorId?: string; message?: string };
+                const created = (await res.json()) as { courseId: string | number; title?: string };
+                if (cancelled) return;
+
+                // The creator signs for the attestation once the course id is known
+                const requestedAt = Math.floor(Date.now() / 1000);
+                const signature = await signMessageAsync({
+                    message: nonceRequestMessage("course_creator", address, created.courseId, requestedAt),
+                });
+
+                const attestRes = await fetch("/api/attest-course-creator", {
+                    method: "POST",
+                    headers: { "Content-Type": "application/json" },
+                    body: JSON.stringify({ courseId: created.courseId, wallet: address, requestedAt, signature }),
+                });
+
+                if (!attestRes.ok) {
+                    throw new Error(`Server returned ${attestRes.status}`);
+                }
+
+                const { proof } = (await attestRes.json()) as { proof?: any };
                 if (cancelled) return;
 
-                setServerData(data);
+                setServerData({ ...created, proof });
                 setCurrentStep("pending");
             } catch (e: any) {
                 if (cancelled) return;
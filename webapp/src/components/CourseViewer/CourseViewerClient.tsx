@@ -4,26 +4,27 @@ import React, { useEffect, useState } from 'react';
 import { CourseViewerProvider } from '../CourseViewer/CourseContext';
 import CourseViewerLayout from './CourseViewerLayout';
 import { Course, CourseProgress } from '../../types/course';
-import { useAccount } from 'wagmi';
+import { useLearnerId } from './useLearnerId';
 
 interface Props {
     course: Course;
 }
 
-const CourseViewerClient: React.FC<Props> = ({ course }) => {
-    const { address, isConnected } = useAccount();
+const CourseViewerClient: React.FC<Props> = ({ course: initialCourse }) => {
+    const learnerId = useLearnerId();
+    const [course, setCourse] = useState<Course>(initialCourse);
     const [progress, setProgress] = useState<CourseProgress | null>(null);
     const [loading, setLoading] = useState(true);
     const [isEnrolled, setIsEnrolled] = useState(false);
 
     useEffect(() => {
         const fetchEnrollmentAndProgress = async () => {
-            if (!address) return;
+            if (!learnerId) return;
 
             try {
-                const enrollUrl = new URL("http://localhost:4000/is-enrolled");
+                const enrollUrl = new URL("http://localhost:4000/get-enrollment");
                 enrollUrl.searchParams.set("courseId", String(course.id));
-                enrollUrl.searchParams.set("learnerId", address);
+                enrollUrl.searchParams.set("learnerId", learnerId);
 
                 const enrollRes = await fetch(enrollUrl.toString());
                 if (!enrollRes.ok) throw new Error("Failed to check enrollment");
@@ -34,13 +35,21 @@ const CourseViewerClient: React.FC<Props> = ({ course }) => {
                 if (json.isEnrolled) {
                     const progressUrl = new URL("http://localhost:4000/get-course-progress");
                     progressUrl.searchParams.set("courseId", String(course.id));
-                    progressUrl.searchParams.set("learnerId", address);
+                    progressUrl.searchParams.set("learnerId", learnerId);
 
                     const progressRes = await fetch(progressUrl.toString());
                     if (!progressRes.ok) throw new Error("Failed to fetch progress");
 
                     const data = await progressRes.json();
                     setProgress(data);
+
+                    // Fetched again for the learner, with where to resume
+                    const courseUrl = new URL("http://localhost:4000/get-course");
+                    courseUrl.searchParams.set("courseId", String(course.id));
+                    courseUrl.searchParams.set("learnerId", learnerId);
+
+                    const courseRes = await fetch(courseUrl.toString());
+                    if (courseRes.ok) setCourse(await courseRes.json());
                 } else {
                     setProgress({
                         completedLessonIds: [],
@@ -48,6 +57,7 @@ const CourseViewerClient: React.FC<Props> = ({ course }) => {
                         completedModuleIds: [],
                         progressPercent: 0,
                         courseCompleted: false,
+                        timeSpentSeconds: 0,
                     });
                 }
             } catch (err) {
@@ -59,16 +69,17 @@ const CourseViewerClient: React.FC<Props> = ({ course }) => {
                     completedModuleIds: [],
                     progressPercent: 0,
                     courseCompleted: false,
+                    timeSpentSeconds: 0,
                 });
             } finally {
                 setLoading(false);
             }
         };
 
-        if (isConnected) {
+        if (learnerId) {
             fetchEnrollmentAndProgress();
         }
-    }, [address, isConnected, course.id]);
+    }, [learnerId, course.id]);
 
     console.log(progress);
 
@@ -3,9 +3,10 @@ import { useCourseViewer } from './CourseContext';
 import CourseSidebar from './CourseSidebar';
 import LessonContent from './LessonContent';
 import QuizContent from './QuizContent';
-import { QuizResult } from '@/types/course';
+import { QuestionResponse, QuizResult } from '@/types/course';
 import { useAccount } from 'wagmi';
 import EnrollModal from './EnrollModal';
+import { useLearnerId } from './useLearnerId';
 import { ArrowRightIcon, BookOpenIcon, LeafIcon } from 'lucide-react';
 import { ConnectButton } from '@rainbow-me/rainbowkit';
 
@@ -16,11 +17,18 @@ const CourseViewerLayout: React.FC = () => {
   const [activeLessonId, setActiveLessonId] = useState<number | null>(null);
   const [activeQuizId, setActiveQuizId] = useState<number | null>(null);
   const [isSubmitting, setIsSubmitting] = useState(false);
-  const { isConnected, chainId, address } = useAccount();
+  const { isConnected, chainId } = useAccount();
+  const learnerId = useLearnerId();
   const [isEnrolltModalOpen, setIsEnrollModalOpen] = useState(false);
 
   useEffect(() => {
-    if (course.modules.length > 0 && !activeModuleId) {
+    // Learners continue with the video they watched last
+    const resumeModule = course.resume && course.modules.find(m => m.id === course.resume?.moduleId);
+    if (course.resume && resumeModule && !activeModuleId) {
+      setActiveModuleId(resumeModule.id);
+      setActiveLessonId(course.resume.lessonId);
+      setActiveQuizId(null);
+    } else if (course.modules.length > 0 && !activeModuleId) {
       const firstModule = course.modules[0];
       setActiveModuleId(firstModule.id);
       if (firstModule.lessons.length > 0) {
@@ -63,9 +71,20 @@ const CourseViewerLayout: React.FC = () => {
     if (!activeLesson || !activeModule || isSubmitting) return;
 
     setIsSubmitting(true);
-    markLessonComplete(activeLesson.id);
 
     try {
+      const lessonRes = await fetch('http://localhost:4000/complete-lesson', {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ lessonId: activeLesson.id, learnerId }),
+      });
+      // Rejected until enough of the lesson video was watched
+      if (!lessonRes.ok) {
+        alert(await lessonRes.text());
+        return;
+      }
+      markLessonComplete(activeLesson.id);
+
       const newCompletedLessons = {
         ...progress.completedLessons,
         [activeLesson.id]: true,
@@ -85,17 +104,11 @@ const CourseViewerLayout: React.FC = () => {
 
       console.log("module complete: ", moduleComplete);
 
-      await fetch('http://localhost:4000/complete-lesson', {
-        method: 'POST',
-        headers: { 'Content-Type': 'application/json' },
-        body: JSON.stringify({ lessonId: activeLesson.id, learnerId: address }),
-      });
-
       if (moduleComplete) {
         const res = await fetch('http://localhost:4000/complete-module', {
           method: 'POST',
           headers: { 'Content-Type': 'application/json' },
-          body: JSON.stringify({ moduleId: activeModule.id, learnerId: address, courseId: course.id }),
+          body: JSON.stringify({ moduleId: activeModule.id, learnerId, courseId: course.id }),
         });
 
         const data = await res.json();
@@ -105,7 +118,7 @@ const CourseViewerLayout: React.FC = () => {
           const res = await fetch('http://localhost:4000/complete-course', {
             method: 'POST',
             headers: { 'Content-Type': 'application/json' },
-            body: JSON.stringify({ learnerId: address, courseId: course.id }),
+            body: JSON.stringify({ learnerId, courseId: course.id }),
           });
 
           const data = await res.json();
@@ -128,43 +141,45 @@ const CourseViewerLayout: React.FC = () => {
   };
 
 
-  const handleQuizComplete = async (result: QuizResult) => {
-    if (activeQuiz && activeModule) {
-
-      const percentScore = Math.round(
-        (result.score / result.totalQuestions) * 100,
-      )
-      const passed = percentScore >= 70
+  const handleQuizComplete = async (instanceId: number, responses: QuestionResponse[]): Promise<QuizResult> => {
+    if (!activeQuiz || !activeModule) {
+      throw new Error("No active quiz");
+    }
+    // Every submission is an attempt, graded against the instance the backend served
+    const res = await fetch('http://localhost:4000/complete-quiz', {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({
+        quizId: activeQuiz.id,
+        learnerId: learnerId,
+        instanceId,
+        responses,
+      }),
+    });
+
+    if (!res.ok) {
+      throw new Error(await res.text());
+    }
 
-      if (passed) {
-        markQuizComplete(activeQuiz.id, result);
+    const result: QuizResult = await res.json();
+    if (result.quizPassed) {
+      markQuizComplete(activeQuiz.id, result);
 
-        const learnerId = address;
-        try {
-          await fetch('http://localhost:4000/complete-quiz', {
+      try {
+        const moduleComplete = isModuleCompleted(activeModule.id);
+        if (moduleComplete) {
+          await fetch('http://localhost:4000/complete-module', {
             method: 'POST',
             headers: { 'Content-Type': 'application/json' },
-            body: JSON.stringify({
-              quizId: activeQuiz.id,
-              learnerId: learnerId,
-              score: result.score,
-              totalQuestions: result.totalQuestions,
-            }),
+            body: JSON.stringify({ moduleId: activeModule.id, learnerId, courseId: course.id }),
           });
-
-          const moduleComplete = isModuleCompleted(activeModule.id);
-          if (moduleComplete) {
-            await fetch('http://localhost:4000/complete-module', {
-              method: 'POST',
-              headers: { 'Content-Type': 'application/json' },
-              body: JSON.stringify({ moduleId: activeModule.id, learnerId }),
-            });
-          }
-        } catch (err) {
-          console.error("Failed to sync quiz/module completion:", err);
         }
+      } catch (err) {
+        console.error("Failed to sync module completion:", err);
       }
     }
+
+    return result;
   }
 
   const getNextLesson = () => {
@@ -283,11 +298,13 @@ const CourseViewerLayout: React.FC = () => {
               onNavigate={handleLessonChange}
               onComplete={handleLessonComplete}
               isPreview={!isEnrolled}
+              learnerId={learnerId}
             />
           ) : activeQuiz ? (
             <QuizContent
               quiz={activeQuiz}
               module={activeModule!}
+              learnerId={learnerId}
               onComplete={handleQuizComplete}
               existingResult={getQuizResult(activeQuiz.id)}
               isPreview={!isEnrolled}
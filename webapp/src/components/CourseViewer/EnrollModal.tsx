@@ -13,8 +13,9 @@ import {
     SendIcon,
     LeafIcon,
 } from "lucide-react";
-import { useAccount, useWriteContract } from "wagmi";
+import { useAccount, useSignMessage, useWriteContract } from "wagmi";
 import ICourseManager from "../../app/abis/aranya/ICourseManager.json";
+import { nonceRequestMessage } from "../utils/utils";
 
 const COURSE_MANAGER_ADDRESS = process.env.NEXT_PUBLIC_COURSE_MANAGER_ADDRESS;
 
@@ -55,6 +56,7 @@ export default function EnrollModal({
     const [error, setError] = useState<string | null>(null);
     const { isConnected, chainId, address } = useAccount();
     const { writeContractAsync, isPending: isWritePending } = useWriteContract();
+    const { signMessageAsync } = useSignMessage();
     const [serverData, setServerData] = useState<ServerResponse | null>(null);
 
     const bumpIntermediates = () => {
@@ -76,10 +78,19 @@ export default function EnrollModal({
 
         const runServerFlow = async () => {
             try {
+                if (!address) {
+                    throw new Error("Please connect your wallet to continue.");
+                }
+
+                const requestedAt = Math.floor(Date.now() / 1000);
+                const signature = await signMessageAsync({
+                    message: nonceRequestMessage("enrollment", address, courseId, requestedAt),
+                });
+
                 const res = await fetch("/api/enroll", {
                     method: "POST",
                     headers: { "Content-Type": "application/json" },
-                    body: JSON.stringify({ courseId, learnerId: address }),
+                    body: JSON.stringify({ courseId, learnerId: address, requestedAt, signature }),
                 });
 
                 if (!res.ok) {
@@ -1,5 +1,6 @@
-import React from 'react';
+import React, { useEffect, useRef } from 'react';
 import { Lesson, Module } from '../../types/course';
+import { useVideoProgress } from './useVideoProgress';
 import { ArrowLeftIcon, ArrowRightIcon, BookOpenIcon, CheckIcon, GraduationCap } from 'lucide-react';
 interface LessonContentProps {
   lesson: Lesson;
@@ -17,7 +18,18 @@ interface LessonContentProps {
   onNavigate: (moduleId: number, lessonId: number) => void;
   onComplete: () => void;
   isPreview: boolean;
+  learnerId?: string;
 }
+
+const BACKEND_URL = 'http://localhost:4000';
+// The backend counts gaps between heartbeats longer than a minute as idle
+const HEARTBEAT_INTERVAL_MS = 15_000;
+const IDLE_AFTER_MS = 60_000;
+
+// Video files play in the viewer's own player, which reports what was watched.
+// Embedded players such as YouTube are shown in an iframe and can't be tracked
+export const isVideoFile = (url: string) =>
+  url.startsWith(`${BACKEND_URL}/media/`) || /\.(mp4|webm|ogv|ogg|mov|m4v)([?#]|$)/i.test(url);
 const LessonContent: React.FC<LessonContentProps> = ({
   lesson,
   module,
@@ -27,10 +39,57 @@ const LessonContent: React.FC<LessonContentProps> = ({
   isLessonCompleted,
   onNavigate,
   onComplete,
-  isPreview
+  isPreview,
+  learnerId
 }) => {
 
   const lessonCompleted = isLessonCompleted(lesson.id);
+  const isScorm = lesson.scormPackageId != null;
+  const videoRef = useRef<HTMLVideoElement>(null);
+  const playsVideoFile = !!lesson.videoUrl && isVideoFile(lesson.videoUrl);
+  const videoProgress = useVideoProgress(videoRef, lesson.id, learnerId, playsVideoFile && !isPreview);
+  const minWatchedPercent = lesson.videoUrl ? lesson.minWatchedPercent : null;
+  const watchedEnough = minWatchedPercent == null || (videoProgress?.watchedPercent ?? 0) >= minWatchedPercent;
+
+  // The SCORM launcher reports each commit, the package decides when the lesson is complete
+  useEffect(() => {
+    if (!isScorm || lessonCompleted) return;
+    const onMessage = (event: MessageEvent) => {
+      if (event.origin !== BACKEND_URL) return;
+      const { type, lessonId, lessonCompleted } = event.data ?? {};
+      if (type === 'aranya-scorm-commit' && lessonId === lesson.id && lessonCompleted) {
+        onComplete();
+      }
+    };
+    window.addEventListener('message', onMessage);
+    return () => window.removeEventListener('message', onMessage);
+  }, [isScorm, lessonCompleted, lesson.id, onComplete]);
+
+  // Reports active time while the lesson is visible and the learner is interacting with it,
+  // or watching an embedded video or SCORM package
+  useEffect(() => {
+    if (isPreview || !learnerId) return;
+    let lastInputAt = Date.now();
+    const onInput = () => { lastInputAt = Date.now(); };
+    const inputEvents = ['mousemove', 'keydown', 'scroll', 'touchstart'];
+    inputEvents.forEach(name => window.addEventListener(name, onInput, { passive: true }));
+    const sendHeartbeat = () => {
+      const iframeFocused = document.activeElement instanceof HTMLIFrameElement;
+      if (document.visibilityState !== 'visible') return;
+      if (!iframeFocused && Date.now() - lastInputAt > IDLE_AFTER_MS) return;
+      fetch(`${BACKEND_URL}/lesson-heartbeat`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ learnerId, lessonId: lesson.id })
+      }).catch(() => {});
+    };
+    sendHeartbeat();
+    const interval = window.setInterval(sendHeartbeat, HEARTBEAT_INTERVAL_MS);
+    return () => {
+      window.clearInterval(interval);
+      inputEvents.forEach(name => window.removeEventListener(name, onInput));
+    };
+  }, [isPreview, learnerId, lesson.id]);
 
   return <div className="max-w-3xl mx-auto px-6 py-8">
     <div className="mb-6">
@@ -39,7 +98,21 @@ const LessonContent: React.FC<LessonContentProps> = ({
         {lesson.title}
       </h1>
       {lesson.videoUrl && <div className="aspect-video mb-8 border border-stone-200 rounded-lg overflow-hidden bg-black">
-        <iframe width="100%" height="100%" src={lesson.videoUrl} title={lesson.title} frameBorder="0" allow="accelerometer; autoplay; clipboard-write; encrypted-media; gyroscope; picture-in-picture" allowFullScreen className="w-full h-full" />
+        {playsVideoFile
+          ? <video key={lesson.id} ref={videoRef} src={lesson.videoUrl} title={lesson.title} controls preload="metadata" className="w-full h-full" />
+          : <iframe width="100%" height="100%" src={lesson.videoUrl} title={lesson.title} frameBorder="0" allow="accelerometer; autoplay; clipboard-write; encrypted-media; gyroscope; picture-in-picture" allowFullScreen className="w-full h-full" />}
+      </div>}
+      {minWatchedPercent != null && !lessonCompleted && !isPreview && <div className="-mt-6 mb-8 text-sm text-stone-600">
+        Watched {Math.floor(videoProgress?.watchedPercent ?? 0)}% of the video, {minWatchedPercent}% needed to complete the lesson
+      </div>}
+      {isScorm && !isPreview && learnerId && <div className="aspect-video mb-8 border border-stone-200 rounded-lg overflow-hidden">
+        <iframe
+          src={`${BACKEND_URL}/scorm/${lesson.scormPackageId}/launch?lessonId=${lesson.id}&learnerId=${encodeURIComponent(learnerId)}`}
+          title={lesson.title}
+          allow="autoplay; fullscreen"
+          allowFullScreen
+          className="w-full h-full"
+        />
       </div>}
       <div className="prose prose-stone max-w-none">
         {lesson.content.split('\n\n').map((paragraph, i) => <p key={i} className="mb-4 text-stone-700 leading-relaxed">
@@ -54,11 +127,11 @@ const LessonContent: React.FC<LessonContentProps> = ({
       </button> : <div />
 
       }
-      {!lessonCompleted && !isPreview &&
+      {!lessonCompleted && !isPreview && !isScorm &&
         <button
           onClick={onComplete}
-          disabled={isSubmitting}
-          className="flex items-center px-4 py-2 text-sm font-medium text-white bg-emerald-700 rounded-md hover:bg-emerald-800"
+          disabled={isSubmitting || !watchedEnough}
+          className="flex items-center px-4 py-2 text-sm font-medium text-white bg-emerald-700 rounded-md hover:bg-emerald-800 disabled:opacity-50 disabled:cursor-not-allowed"
         >
           <CheckIcon className="h-4 w-4 mr-2" />
           Mark as Complete
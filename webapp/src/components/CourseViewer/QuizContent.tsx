@@ -1,5 +1,12 @@
-import React, { useState } from 'react'
-import { Quiz, Module, QuizResult } from '../../types/course';
+import React, { useEffect, useState } from 'react'
+import {
+  Quiz,
+  Module,
+  QuizInstance,
+  QuizResult,
+  QuestionResponse,
+  ServedQuestion,
+} from '../../types/course';
 import {
   CheckCircleIcon,
   XCircleIcon,
@@ -9,244 +16,380 @@ import {
 interface QuizContentProps {
   quiz: Quiz | null
   module: Module
-  onComplete: (result: QuizResult) => void
+  learnerId?: string
+  onComplete: (instanceId: number, responses: QuestionResponse[]) => Promise<QuizResult>
   existingResult?: QuizResult | null
   isPreview: boolean;
 }
+// Whether the learner has given enough of an answer to move on
+const isAnswered = (question: ServedQuestion, response?: QuestionResponse) => {
+  if (!response) return false
+  switch (question.questionType) {
+    case 'multiple_choice':
+    case 'single_choice':
+    case 'true_false':
+      return !!response.selectedAnswerIds?.length
+    case 'ordering':
+      return true
+    case 'matching':
+      return Object.keys(response.matches ?? {}).length === question.answers.length
+    case 'numeric':
+      return response.numericAnswer !== undefined && !isNaN(response.numericAnswer)
+    case 'short_text':
+      return !!response.textAnswer?.trim()
+  }
+}
+const QuestionInput: React.FC<{
+  question: ServedQuestion
+  response?: QuestionResponse
+  onChange: (response: QuestionResponse) => void
+}> = ({ question, response, onChange }) => {
+  const selected = response?.selectedAnswerIds ?? []
+  switch (question.questionType) {
+    case 'single_choice':
+    case 'true_false':
+    case 'multiple_choice': {
+      const multiple = question.questionType === 'multiple_choice'
+      const toggle = (optionId: number) => {
+        const selectedAnswerIds = multiple
+          ? selected.includes(optionId)
+            ? selected.filter((id) => id !== optionId)
+            : [...selected, optionId]
+          : [optionId]
+        onChange({ questionId: question.id, selectedAnswerIds })
+      }
+      return (
+        <div className="space-y-3">
+          {question.answers.map((option) => {
+            const isSelected = selected.includes(option.id)
+            return (
+              <button
+                key={option.id}
+                onClick={() => toggle(option.id)}
+                className={`w-full text-left p-3 rounded-md border ${isSelected ? 'border-amber-500 bg-amber-50' : 'border-stone-200 hover:border-amber-200 hover:bg-amber-50/30'}`}
+              >
+                <div className="flex items-center">
+                  <div
+                    className={`h-5 w-5 ${multiple ? 'rounded' : 'rounded-full'} border flex items-center justify-center mr-3 ${isSelected ? 'border-amber-500 bg-amber-500' : 'border-stone-300'}`}
+                  >
+                    {isSelected && (
+                      <div className={`h-2 w-2 bg-white ${multiple ? '' : 'rounded-full'}`}></div>
+                    )}
+                  </div>
+                  <span className="text-stone-700">{option.answerText}</span>
+                </div>
+              </button>
+            )
+          })}
+        </div>
+      )
+    }
+    case 'ordering': {
+      const order = response?.orderedAnswerIds ?? question.answers.map((a) => a.id)
+      const move = (index: number, offset: number) => {
+        const orderedAnswerIds = [...order]
+        const [item] = orderedAnswerIds.splice(index, 1)
+        orderedAnswerIds.splice(index + offset, 0, item)
+        onChange({ questionId: question.id, orderedAnswerIds })
+      }
+      return (
+        <div className="space-y-2">
+          {order.map((answerId, index) => (
+            <div
+              key={answerId}
+              className="flex items-center justify-between p-3 rounded-md border border-stone-200"
+            >
+              <span className="text-stone-700">
+                {question.answers.find((a) => a.id === answerId)?.answerText}
+              </span>
+              <div className="space-x-2 text-sm">
+                <button disabled={index === 0} onClick={() => move(index, -1)} className="text-amber-700 disabled:text-stone-300">Up</button>
+                <button disabled={index === order.length - 1} onClick={() => move(index, 1)} className="text-amber-700 disabled:text-stone-300">Down</button>
+              </div>
+            </div>
+          ))}
+        </div>
+      )
+    }
+    case 'matching':
+      return (
+        <div className="space-y-2">
+          {question.answers.map((option) => (
+            <div key={option.id} className="flex items-center justify-between p-3 rounded-md border border-stone-200">
+              <span className="text-stone-700">{option.answerText}</span>
+              <select
+                value={response?.matches?.[option.id] ?? ''}
+                onChange={(e) =>
+                  onChange({
+                    questionId: question.id,
+                    matches: { ...response?.matches, [option.id]: e.target.value },
+                  })
+                }
+                className="border border-stone-300 rounded-md px-2 py-1 text-stone-700"
+              >
+                <option value="" disabled>Select a match</option>
+                {question.matchOptions.map((matchOption) => (
+                  <option key={matchOption} value={matchOption}>{matchOption}</option>
+                ))}
+              </select>
+            </div>
+          ))}
+        </div>
+      )
+    case 'numeric':
+    case 'short_text':
+      return (
+        <input
+          type={question.questionType === 'numeric' ? 'number' : 'text'}
+          value={
+            question.questionType === 'numeric'
+              ? response?.numericAnswer ?? ''
+              : response?.textAnswer ?? ''
+          }
+          onChange={(e) =>
+            onChange(
+              question.questionType === 'numeric'
+                ? { questionId: question.id, numericAnswer: parseFloat(e.target.value) }
+                : { questionId: question.id, textAnswer: e.target.value },
+            )
+          }
+          className="w-full p-3 rounded-md border border-stone-300 text-stone-700"
+        />
+      )
+  }
+}
 const QuizContent: React.FC<QuizContentProps> = ({
   quiz,
   module,
+  learnerId,
   onComplete,
   existingResult,
   isPreview
 }) => {
-  if (quiz) {
-    const [currentQuestionIndex, setCurrentQuestionIndex] = useState(0)
-    const [selectedAnswers, setSelectedAnswers] = useState<
-      Record<number, number>
-    >({})
-    const [showResults, setShowResults] = useState(!!existingResult)
-    const [quizResult, setQuizResult] = useState<QuizResult | null>(
-      existingResult || null,
-    )
-    const currentQuestion = quiz.questions[currentQuestionIndex]
-    const isLastQuestion = currentQuestionIndex === quiz.questions.length - 1
-    const hasSelectedAnswer = !!selectedAnswers[currentQuestion?.id]
-    const handleSelectAnswer = (questionId: number, optionId: number) => {
-      setSelectedAnswers((prev) => ({
-        ...prev,
-        [questionId]: optionId,
-      }))
-    }
-    const handleNextQuestion = () => {
-      if (isLastQuestion) {
-        calculateResults()
-      } else {
-        setCurrentQuestionIndex((prev) => prev + 1)
-      }
+  const [instance, setInstance] = useState<QuizInstance | null>(null)
+  const [currentQuestionIndex, setCurrentQuestionIndex] = useState(0)
+  const [responses, setResponses] = useState<Record<number, QuestionResponse>>({})
+  const [quizResult, setQuizResult] = useState<QuizResult | null>(
+    existingResult || null,
+  )
+  const [error, setError] = useState<string | null>(null)
+  const [isSubmitting, setIsSubmitting] = useState(false)
+  const [questionShownAt, setQuestionShownAt] = useState(Date.now())
+
+  // Every attempt gets its own questions, drawn and shuffled by the backend
+  const startAttempt = async () => {
+    if (!quiz || !learnerId || isPreview) return
+    setError(null)
+    const res = await fetch('http://localhost:4000/start-quiz-attempt', {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ quizId: quiz.id, learnerId }),
+    })
+    if (!res.ok) {
+      setError(await res.text())
+      return
     }
-    const calculateResults = () => {
-      let correctAnswers = 0
-      quiz.questions.forEach((question) => {
-        const selectedOptionId = selectedAnswers[question.id]
-        if (selectedOptionId) {
-          const selectedOption = question.answers.find(
-            (opt) => opt.id === selectedOptionId,
-          )
-          if (selectedOption?.isCorrect) {
-            correctAnswers++
-          }
-        }
-      })
-      const result: QuizResult = {
-        score: correctAnswers,
-        totalQuestions: quiz.questions.length,
-        answers: {
-          ...selectedAnswers,
-        },
-      }
-      setQuizResult(result);
-      setShowResults(true);
-      onComplete(result);
-    }
-    const resetQuiz = () => {
-      setSelectedAnswers({})
-      setCurrentQuestionIndex(0)
-      setShowResults(false)
-      setQuizResult(null)
+    setInstance(await res.json())
+    setResponses({})
+    setCurrentQuestionIndex(0)
+    setQuestionShownAt(Date.now())
+  }
+
+  useEffect(() => {
+    if (!quizResult) {
+      startAttempt()
     }
-    if (showResults && quizResult) {
-      const percentScore = Math.round(
-        (quizResult.score / quizResult.totalQuestions) * 100,
-      )
-      const passed = percentScore >= 70
-      return (
-        <div className="max-w-3xl mx-auto px-6 py-8">
-          <div className="mb-6">
-            <div className="text-sm text-amber-700 mb-1">{module.title}</div>
-            <h1 className="text-2xl font-bold text-stone-800 mb-4">
-              Quiz
-            </h1>
-            <div className="bg-white border border-stone-200 rounded-lg p-6 mb-6">
-              <div className="text-center mb-6">
-                <div
-                  className={`inline-flex items-center justify-center w-24 h-24 rounded-full ${passed ? 'bg-emerald-50 text-emerald-700' : 'bg-red-50 text-red-700'} mb-4`}
-                >
-                  {passed ? (
-                    <AwardIcon className="h-12 w-12" />
-                  ) : (
-                    <XCircleIcon className="h-12 w-12" />
-                  )}
-                </div>
-                <h2 className="text-xl font-bold text-stone-800">
-                  {passed ? 'Quiz Passed!' : 'Try Again'}
-                </h2>
-                <p className="text-stone-600">
-                  You scored {quizResult.score} out of {quizResult.totalQuestions}{' '}
-                  ({percentScore}%)
-                </p>
-              </div>
-              <div className="space-y-4 mb-6">
-                {quiz.questions.map((question, idx) => {
-                  const selectedOptionId = quizResult.answers[question.id]
-                  const selectedOption = question.answers.find(
-                    (opt) => opt.id === selectedOptionId,
-                  )
-                  const correctOption = question.answers.find(
-                    (opt) => opt.isCorrect,
-                  )
-                  return (
-                    <div
-                      key={question.id}
-                      className="border border-stone-200 rounded-md p-4"
-                    >
-                      <div className="flex items-start">
-                        <div className="bg-stone-100 text-stone-700 rounded-full h-6 w-6 flex items-center justify-center text-sm font-medium mr-2 mt-0.5">
-                          {idx + 1}
-                        </div>
-                        <div className="flex-1">
-                          <p className="font-medium text-stone-800 mb-2">
-                            {question.questionText}
-                          </p>
-                          <div className="space-y-2">
-                            {question.answers.map((option) => {
-                              const isSelected = option.id === selectedOptionId
-                              const isCorrect = option.isCorrect
-                              let bgColor = 'bg-white'
-                              if (isSelected && isCorrect) {
-                                bgColor = 'bg-emerald-50'
-                              } else if (isSelected && !isCorrect) {
-                                bgColor = 'bg-red-50'
-                              } else if (isCorrect) {
-                                bgColor = 'bg-emerald-50'
-                              }
-                              return (
-                                <div
-                                  key={option.id}
-                                  className={`flex items-center p-2 rounded-md ${bgColor} border border-stone-200`}
-                                >
-                                  {isSelected && isCorrect && (
-                                    <CheckCircleIcon className="h-5 w-5 text-emerald-600 mr-2 flex-shrink-0" />
-                                  )}
-                                  {isSelected && !isCorrect && (
-                                    <XCircleIcon className="h-5 w-5 text-red-600 mr-2 flex-shrink-0" />
-                                  )}
-                                  {!isSelected && isCorrect && (
-                                    <CheckCircleIcon className="h-5 w-5 text-emerald-600 mr-2 flex-shrink-0" />
-                                  )}
-                                  {!isSelected && !isCorrect && (
-                                    <div className="w-5 h-5 mr-2" />
-                                  )}
-                                  <span
-                                    className={`${isCorrect ? 'text-emerald-700' : isSelected ? 'text-red-700' : 'text-stone-700'}`}
-                                  >
-                                    {option.answerText}
-                                  </span>
-                                </div>
-                              )
-                            })}
-                          </div>
-                        </div>
-                      </div>
-                    </div>
-                  )
-                })}
-              </div>
-              {!passed && <div className="flex justify-center">
-                <button
-                  onClick={resetQuiz}
-                  className="flex items-center px-4 py-2 text-sm font-medium text-white bg-amber-700 rounded-md hover:bg-amber-800"
-                >
-                  <div className="h-4 w-4 mr-2" />
-                  Retake Quiz
-                </button>
-              </div>}
-            </div>
-          </div>
-        </div>
-      )
+  }, [quiz?.id, learnerId, isPreview])
+
+  if (!quiz) return null
+
+  const submit = async (submitted: Record<number, QuestionResponse>) => {
+    if (!instance) return
+    setIsSubmitting(true)
+    try {
+      const result = await onComplete(instance.instanceId, Object.values(submitted))
+      setQuizResult(result)
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err))
+    } finally {
+      setIsSubmitting(false)
     }
+  }
+
+  const resetQuiz = () => {
+    setQuizResult(null)
+    setInstance(null)
+    startAttempt()
+  }
+
+  if (quizResult) {
+    const percentScore = Math.round(quizResult.scorePercent)
+    const passed = quizResult.passed
+    const canRetake = quizResult.attemptsRemaining !== 0
     return (
       <div className="max-w-3xl mx-auto px-6 py-8">
         <div className="mb-6">
           <div className="text-sm text-amber-700 mb-1">{module.title}</div>
-          <h1 className="text-2xl font-bold text-stone-800 mb-4">Quiz</h1>
-          <p className="text-stone-600 mb-6">Quiz Description?</p>
-          <div className="bg-white border border-stone-200 rounded-lg p-6">
-            <div className="flex justify-between items-center mb-4">
-              <div className="text-sm text-stone-500">
-                Question {currentQuestionIndex + 1} of {quiz.questions.length}
-              </div>
-              <div className="h-2 bg-stone-100 rounded-full w-48">
-                <div
-                  className="h-2 bg-amber-600 rounded-full"
-                  style={{
-                    width: `${((currentQuestionIndex + 1) / quiz.questions.length) * 100}%`,
-                  }}
-                ></div>
+          <h1 className="text-2xl font-bold text-stone-800 mb-4">
+            Quiz
+          </h1>
+          <div className="bg-white border border-stone-200 rounded-lg p-6 mb-6">
+            <div className="text-center mb-6">
+              <div
+                className={`inline-flex items-center justify-center w-24 h-24 rounded-full ${passed ? 'bg-emerald-50 text-emerald-700' : 'bg-red-50 text-red-700'} mb-4`}
+              >
+                {passed ? (
+                  <AwardIcon className="h-12 w-12" />
+                ) : (
+                  <XCircleIcon className="h-12 w-12" />
+                )}
               </div>
+              <h2 className="text-xl font-bold text-stone-800">
+                {passed ? 'Quiz Passed!' : 'Try Again'}
+              </h2>
+              <p className="text-stone-600">
+                You scored {quizResult.score} out of {quizResult.maxScore}{' '}
+                ({percentScore}%)
+              </p>
+              {quizResult.attemptsRemaining != null && (
+                <p className="text-sm text-stone-500">
+                  {quizResult.attemptsRemaining} attempt{quizResult.attemptsRemaining !== 1 ? 's' : ''} remaining
+                </p>
+              )}
             </div>
-            <div className="mb-6">
-              <h3 className="text-lg font-medium text-stone-800 mb-4">
-                {currentQuestion.questionText}
-              </h3>
-              <div className="space-y-3">
-                {currentQuestion.answers.map((option) => (
-                  <button
-                    key={option.id}
-                    onClick={() =>
-                      handleSelectAnswer(currentQuestion.id, option.id)
-                    }
-                    className={`w-full text-left p-3 rounded-md border ${selectedAnswers[currentQuestion.id] === option.id ? 'border-amber-500 bg-amber-50' : 'border-stone-200 hover:border-amber-200 hover:bg-amber-50/30'}`}
+            <div className="space-y-4 mb-6">
+              {quizResult.results.map((result, idx) => {
+                const question = instance?.questions.find((q) => q.id === result.questionId)
+                return (
+                  <div
+                    key={result.questionId}
+                    className="border border-stone-200 rounded-md p-4"
                   >
-                    <div className="flex items-center">
-                      <div
-                        className={`h-5 w-5 rounded-full border flex items-center justify-center mr-3 ${selectedAnswers[currentQuestion.id] === option.id ? 'border-amber-500 bg-amber-500' : 'border-stone-300'}`}
-                      >
-                        {selectedAnswers[currentQuestion.id] === option.id && (
-                          <div className="h-2 w-2 bg-white rounded-full"></div>
-                        )}
+                    <div className="flex items-start">
+                      <div className="bg-stone-100 text-stone-700 rounded-full h-6 w-6 flex items-center justify-center text-sm font-medium mr-2 mt-0.5">
+                        {idx + 1}
+                      </div>
+                      <div className="flex-1 flex items-center justify-between">
+                        <p className="font-medium text-stone-800">
+                          {question?.questionText ?? `Question ${idx + 1}`}
+                        </p>
+                        <div className="flex items-center">
+                          {result.correct ? (
+                            <CheckCircleIcon className="h-5 w-5 text-emerald-600 mr-2 flex-shrink-0" />
+                          ) : (
+                            <XCircleIcon className="h-5 w-5 text-red-600 mr-2 flex-shrink-0" />
+                          )}
+                          <span className="text-sm text-stone-600">
+                            {result.earned} / {result.points}
+                          </span>
+                        </div>
                       </div>
-                      <span className="text-stone-700">{option.answerText}</span>
                     </div>
-                  </button>
-                ))}
-              </div>
+                  </div>
+                )
+              })}
             </div>
-            <div className="flex justify-end">
+            {error && <p className="text-sm text-red-700 text-center mb-4">{error}</p>}
+            {!passed && canRetake && <div className="flex justify-center">
               <button
-                onClick={handleNextQuestion}
-                disabled={!hasSelectedAnswer}
-                className={`flex items-center px-4 py-2 text-sm font-medium text-white rounded-md ${hasSelectedAnswer ? 'bg-amber-700 hover:bg-amber-800' : 'bg-stone-300 cursor-not-allowed'}`}
+                onClick={resetQuiz}
+                className="flex items-center px-4 py-2 text-sm font-medium text-white bg-amber-700 rounded-md hover:bg-amber-800"
               >
-                {isLastQuestion ? 'Submit Quiz' : 'Next Question'}
-                <ArrowRightIcon className="h-4 w-4 ml-2" />
+                <div className="h-4 w-4 mr-2" />
+                Retake Quiz
               </button>
-            </div>
+            </div>}
           </div>
         </div>
       </div>
     )
   }
+
+  if (isPreview || !instance) {
+    return (
+      <div className="max-w-3xl mx-auto px-6 py-8">
+        <div className="text-sm text-amber-700 mb-1">{module.title}</div>
+        <h1 className="text-2xl font-bold text-stone-800 mb-4">Quiz</h1>
+        <p className="text-stone-600">
+          {isPreview
+            ? `Enroll in the course to take this ${quiz.questionCount} question quiz.`
+            : error ?? 'Loading quiz...'}
+        </p>
+      </div>
+    )
+  }
+
+  const currentQuestion = instance.questions[currentQuestionIndex]
+  const isLastQuestion = currentQuestionIndex === instance.questions.length - 1
+  const hasAnswered = isAnswered(currentQuestion, responses[currentQuestion.id])
+  const handleNextQuestion = () => {
+    // Time on each question is reported for the creator's item analytics
+    const timeSpentSeconds = (Date.now() - questionShownAt) / 1000
+    const updated = {
+      ...responses,
+      [currentQuestion.id]: {
+        // An ordering left untouched is submitted in the order it was served
+        questionId: currentQuestion.id,
+        ...(currentQuestion.questionType === 'ordering' && {
+          orderedAnswerIds: currentQuestion.answers.map((a) => a.id),
+        }),
+        ...responses[currentQuestion.id],
+        timeSpentSeconds,
+      },
+    }
+    setResponses(updated)
+    setQuestionShownAt(Date.now())
+    if (isLastQuestion) {
+      submit(updated)
+    } else {
+      setCurrentQuestionIndex((prev) => prev + 1)
+    }
+  }
+  return (
+    <div className="max-w-3xl mx-auto px-6 py-8">
+      <div className="mb-6">
+        <div className="text-sm text-amber-700 mb-1">{module.title}</div>
+        <h1 className="text-2xl font-bold text-stone-800 mb-4">Quiz</h1>
+        <div className="bg-white border border-stone-200 rounded-lg p-6">
+          <div className="flex justify-between items-center mb-4">
+            <div className="text-sm text-stone-500">
+              Question {currentQuestionIndex + 1} of {instance.questions.length}
+            </div>
+            <div className="h-2 bg-stone-100 rounded-full w-48">
+              <div
+                className="h-2 bg-amber-600 rounded-full"
+                style={{
+                  width: `${((currentQuestionIndex + 1) / instance.questions.length) * 100}%`,
+                }}
+              ></div>
+            </div>
+          </div>
+          <div className="mb-6">
+            <h3 className="text-lg font-medium text-stone-800 mb-4">
+              {currentQuestion.questionText}
+            </h3>
+            <QuestionInput
+              question={currentQuestion}
+              response={responses[currentQuestion.id]}
+              onChange={(response) =>
+                setResponses((prev) => ({ ...prev, [currentQuestion.id]: response }))
+              }
+            />
+          </div>
+          {error && <p className="text-sm text-red-700 mb-4">{error}</p>}
+          <div className="flex justify-end">
+            <button
+              onClick={handleNextQuestion}
+              disabled={!hasAnswered || isSubmitting}
+              className={`flex items-center px-4 py-2 text-sm font-medium text-white rounded-md ${hasAnswered && !isSubmitting ? 'bg-amber-700 hover:bg-amber-800' : 'bg-stone-300 cursor-not-allowed'}`}
+            >
+              {isLastQuestion ? 'Submit Quiz' : 'Next Question'}
+              <ArrowRightIcon className="h-4 w-4 ml-2" />
+            </button>
+          </div>
+        </div>
+      </div>
+    </div>
+  )
 }
 export default QuizContent
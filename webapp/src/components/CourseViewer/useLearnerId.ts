@@ -0,0 +1,23 @@
+"use client";
+
+import { useEffect, useState } from 'react';
+import { useAccount } from 'wagmi';
+
+const LTI_LEARNER_KEY = 'ltiLearnerId';
+
+// Learners launched from an LMS through LTI have no wallet, the backend hands
+// their learner id to the viewer as `?ltiLearnerId=` and it is kept for the tab
+export function useLearnerId(): string | undefined {
+    const { address } = useAccount();
+    const [ltiLearnerId, setLtiLearnerId] = useState<string | null>(null);
+
+    useEffect(() => {
+        const fromUrl = new URLSearchParams(window.location.search).get(LTI_LEARNER_KEY);
+        if (fromUrl) {
+            sessionStorage.setItem(LTI_LEARNER_KEY, fromUrl);
+        }
+        setLtiLearnerId(fromUrl ?? sessionStorage.getItem(LTI_LEARNER_KEY));
+    }, []);
+
+    return ltiLearnerId ?? address;
+}
@@ -0,0 +1,123 @@
+"use client";
+
+import { RefObject, useEffect, useRef, useState } from 'react';
+import { VideoProgress } from '../../types/course';
+
+const BACKEND_URL = 'http://localhost:4000';
+const REPORT_INTERVAL_MS = 10_000;
+// Larger jumps between time updates are seeks, not playback
+const MAX_PLAYBACK_STEP_SECONDS = 2;
+
+// Tracks which parts of a lesson video the learner played and where they
+// stopped, and seeks to the resume position once the video has loaded
+export function useVideoProgress(
+    videoRef: RefObject<HTMLVideoElement | null>,
+    lessonId: number,
+    learnerId: string | undefined,
+    enabled: boolean
+): VideoProgress | null {
+    const [progress, setProgress] = useState<VideoProgress | null>(null);
+    const resumed = useRef(false);
+
+    useEffect(() => {
+        setProgress(null);
+        resumed.current = false;
+        if (!enabled || !learnerId) return;
+
+        const url = new URL(`${BACKEND_URL}/get-video-progress`);
+        url.searchParams.set("lessonId", String(lessonId));
+        url.searchParams.set("learnerId", learnerId);
+        fetch(url.toString())
+            .then(res => res.ok ? res.json() : null)
+            .then(data => data && setProgress(data))
+            .catch(console.error);
+    }, [enabled, lessonId, learnerId]);
+
+    useEffect(() => {
+        const video = videoRef.current;
+        if (!video || !progress || resumed.current) return;
+        const resume = () => {
+            resumed.current = true;
+            if (progress.resumePositionSeconds > 0) {
+                video.currentTime = progress.resumePositionSeconds;
+            }
+        };
+        if (video.readyState >= HTMLMediaElement.HAVE_METADATA) {
+            resume();
+            return;
+        }
+        video.addEventListener('loadedmetadata', resume, { once: true });
+        return () => video.removeEventListener('loadedmetadata', resume);
+    }, [videoRef, progress]);
+
+    useEffect(() => {
+        const video = videoRef.current;
+        if (!video || !enabled || !learnerId) return;
+
+        let segment: [number, number] | null = null;
+        let played: [number, number][] = [];
+
+        const closeSegment = () => {
+            if (segment && segment[1] > segment[0]) played.push(segment);
+            segment = null;
+        };
+        // Reported on play too, the backend credits at most the time since the last report
+        const onPlay = () => {
+            report();
+            segment = [video.currentTime, video.currentTime];
+        };
+        const onTimeUpdate = () => {
+            const time = video.currentTime;
+            if (video.paused) return;
+            if (segment && time >= segment[1] && time - segment[1] <= MAX_PLAYBACK_STEP_SECONDS) {
+                segment[1] = time;
+            } else {
+                closeSegment();
+                segment = [time, time];
+            }
+        };
+        const report = (keepalive = false) => {
+            const current = segment;
+            closeSegment();
+            if (current && !video.paused) segment = [current[1], current[1]];
+            if (!Number.isFinite(video.duration) || video.duration <= 0) return;
+
+            const watched = played;
+            played = [];
+            fetch(`${BACKEND_URL}/video-progress`, {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({
+                    learnerId,
+                    lessonId,
+                    positionSeconds: video.currentTime,
+                    durationSeconds: video.duration,
+                    watched
+                }),
+                keepalive
+            })
+                .then(res => res.ok ? res.json() : null)
+                .then(data => data && !keepalive && setProgress(data))
+                .catch(() => {});
+        };
+        const onPause = () => report();
+
+        video.addEventListener('play', onPlay);
+        video.addEventListener('timeupdate', onTimeUpdate);
+        video.addEventListener('pause', onPause);
+        video.addEventListener('ended', onPause);
+        const interval = window.setInterval(() => {
+            if (!video.paused) report();
+        }, REPORT_INTERVAL_MS);
+        return () => {
+            window.clearInterval(interval);
+            video.removeEventListener('play', onPlay);
+            video.removeEventListener('timeupdate', onTimeUpdate);
+            video.removeEventListener('pause', onPause);
+            video.removeEventListener('ended', onPause);
+            if (resumed.current) report(true);
+        };
+    }, [videoRef, enabled, lessonId, learnerId]);
+
+    return progress;
+}
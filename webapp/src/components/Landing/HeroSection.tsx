@@ -5,15 +5,16 @@ import Link from "next/link"
 import Image from 'next/image';
 import { useAccount } from "wagmi"
 import { BookOpenIcon, PlusIcon } from 'lucide-react';
+import { PlatformStats } from '@/types/stats';
 interface HeroSectionProps {
   isLoggedIn: boolean;
   onLogin: () => void;
-  counts: any;
+  stats: PlatformStats;
 }
 const HeroSection: React.FC<HeroSectionProps> = ({
   isLoggedIn,
   onLogin,
-  counts
+  stats
 }) => {
   const { isConnected, address } = useAccount();
   return <div className="bg-gradient-to-br from-amber-50 to-stone-100 py-16 sm:py-24">
@@ -58,7 +59,7 @@ const HeroSection: React.FC<HeroSectionProps> = ({
                 <div className="mt-4 grid grid-cols-2 gap-4">
                   <div className="bg-white p-4 rounded-lg shadow border border-stone-200">
                     <div className="text-amber-700 font-bold text-3xl">
-                      {counts.numLearners}
+                      {stats.totals.learners}
                     </div>
                     <div className="text-stone-600 text-sm">
                       Learners
@@ -66,7 +67,7 @@ const HeroSection: React.FC<HeroSectionProps> = ({
                   </div>
                   <div className="bg-white p-4 rounded-lg shadow border border-stone-200">
                     <div className="text-amber-700 font-bold text-3xl">
-                      {counts.numCourses}
+                      {stats.totals.courses}
                     </div>
                     <div className="text-stone-600 text-sm">
                       Courses
@@ -82,7 +82,7 @@ const LearnerCourseCard: React.FC<LearnerCourseCardProps> = ({
                         {canUpgrade && <ArrowRightIcon className="h-3 w-3 ml-1" />}
                     </button>}
                 </div>
-                <UpgradeNFTModal isOpen={isModalOpen} onClose={() => setIsModalOpen(false)} nftAddress={nft.address} courseId={course.courseId} />
+                <UpgradeNFTModal isOpen={isModalOpen} onClose={() => setIsModalOpen(false)} nftAddress={nft.address} courseId={course.courseId} milestone={Math.floor(course.progressPercent / 25)} />
             </div>
         </div>
     )
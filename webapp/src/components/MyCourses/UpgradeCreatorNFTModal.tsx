@@ -13,9 +13,10 @@ import {
     SendIcon,
     LeafIcon,
 } from "lucide-react";
-import { useAccount, useWriteContract } from "wagmi";
+import { useAccount, useSignMessage, useWriteContract } from "wagmi";
 // If your ABI lives elsewhere, adjust the path:
 import ICreatorNFT from "../../app/abis/aranya/ICreatorNFT.json";
+import { nonceRequestMessage } from "../utils/utils";
 // If you keep CourseManager address in env, surface it to the client via NEXT_PUBLIC_*
 
 interface UpgradeNFTModalProps {
@@ -57,6 +58,7 @@ export default function UpgradeNFTModal({
     const [error, setError] = useState<string | null>(null);
     const { isConnected, chainId, address } = useAccount();
     const { writeContractAsync, isPending: isWritePending } = useWriteContract();
+    const { signMessageAsync } = useSignMessage();
     const [serverData, setServerData] = useState<ServerResponse | null>(null);
 
     const bumpIntermediates = () => {
@@ -78,10 +80,19 @@ export default function UpgradeNFTModal({
 
         const runServerFlow = async () => {
             try {
+                if (!address) {
+                    throw new Error("Please connect your wallet to continue.");
+                }
+
+                const requestedAt = Math.floor(Date.now() / 1000);
+                const signature = await signMessageAsync({
+                    message: nonceRequestMessage("creator_milestone", address, courseId, requestedAt),
+                });
+
                 const res = await fetch("/api/upgrade-creator-nft", {
                     method: "POST",
                     headers: { "Content-Type": "application/json" },
-                    body: JSON.stringify({ courseId }),
+                    body: JSON.stringify({ courseId, wallet: address, requestedAt, signature }),
                 });
 
                 if (!res.ok) {
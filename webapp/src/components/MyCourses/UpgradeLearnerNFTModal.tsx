@@ -13,9 +13,10 @@ import {
     SendIcon,
     LeafIcon,
 } from "lucide-react";
-import { useAccount, useWriteContract } from "wagmi";
+import { useAccount, useSignMessage, useWriteContract } from "wagmi";
 // If your ABI lives elsewhere, adjust the path:
 import ILearnerNFT from "../../app/abis/aranya/ILearnerNFT.json";
+import { nonceRequestMessage } from "../utils/utils";
 // If you keep CourseManager address in env, surface it to the client via NEXT_PUBLIC_*
 
 interface UpgradeNFTModalProps {
@@ -23,8 +24,10 @@ interface UpgradeNFTModalProps {
     onClose: () => void;
     courseId: number;
     nftAddress: string;
+    milestone: number;
 }
 
+
 type SubmissionStep =
     | "attestation"
     | "proof"
@@ -51,12 +54,14 @@ export default function UpgradeNFTModal({
     isOpen,
     onClose,
     courseId,
-    nftAddress
+    nftAddress,
+    milestone
 }: UpgradeNFTModalProps) {
     const [currentStep, setCurrentStep] = useState<SubmissionStep>("attestation");
     const [error, setError] = useState<string | null>(null);
     const { isConnected, chainId, address } = useAccount();
     const { writeContractAsync, isPending: isWritePending } = useWriteContract();
+    const { signMessageAsync } = useSignMessage();
     const [serverData, setServerData] = useState<ServerResponse | null>(null);
 
     const bumpIntermediates = () => {
@@ -78,10 +83,19 @@ export default function UpgradeNFTModal({
 
         const runServerFlow = async () => {
             try {
+                if (!address) {
+                    throw new Error("Please connect your wallet to continue.");
+                }
+
+                const requestedAt = Math.floor(Date.now() / 1000);
+                const signature = await signMessageAsync({
+                    message: nonceRequestMessage("learner_milestone", address, courseId, requestedAt, milestone),
+                });
+
                 const res = await fetch("/api/upgrade-learner-nft", {
                     method: "POST",
                     headers: { "Content-Type": "application/json" },
-                    body: JSON.stringify({ courseId, learnerId: address }),
+                    body: JSON.stringify({ courseId, learnerId: address, milestone, requestedAt, signature }),
                 });
 
                 if (!res.ok) {
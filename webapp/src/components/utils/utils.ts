@@ -22,6 +22,20 @@ export function getNextLearnerMilestone(progressPercentage: number): number | nu
 }
 
 
+export type AttestationKind = "course_creator" | "enrollment" | "creator_milestone" | "learner_milestone";
+
+// Must match nonce_request_message in the backend
+export function nonceRequestMessage(
+    kind: AttestationKind,
+    wallet: string,
+    courseId: number | string,
+    requestedAt: number,
+    milestone?: number
+): string {
+    const milestoneLine = milestone === undefined ? "" : `Milestone: ${milestone}\n`;
+    return `Aranya attestation request\nKind: ${kind}\nWallet: ${wallet.toLowerCase()}\nCourse: ${courseId}\n${milestoneLine}Requested at: ${requestedAt}`;
+}
+
 export function getAttribute(nft: CreatorNFT, trait: string): number {
     const attr = nft.attributes.find(a => a.trait_type === trait)
     return attr ? Number(attr.value) : 0
@@ -1,27 +1,71 @@
-export interface AnswerOption {
+export type QuestionType =
+  | "single_choice"
+  | "multiple_choice"
+  | "true_false"
+  | "ordering"
+  | "matching"
+  | "numeric"
+  | "short_text";
+
+export type ScoringPolicy = "best" | "latest";
+
+export interface Quiz {
   id: number,
-  questionId: number,
-  answerText: string;
-  isCorrect: boolean;
+  moduleId?: number | null; // set for module quizzes
+  courseId?: number | null; // set for course final exams
+  passingScorePercent: number;
+  maxAttempts?: number | null;
+  cooldownSeconds: number;
+  scoringPolicy: ScoringPolicy;
+  questionCount: number; // questions drawn per attempt
 }
 
-export interface Question {
-  id: number,
-  quizId: number,
+// Questions served for one attempt, without the answer key
+export type QuizInstance = {
+  instanceId: number;
+  quizId: number;
+  learnerId: string;
+  createdAt: string;
+  questions: ServedQuestion[];
+}
+
+export type ServedQuestion = {
+  id: number;
   questionText: string;
-  answers: AnswerOption[];
+  questionType: QuestionType;
+  points: number;
+  answers: { id: number; answerText: string }[];
+  matchOptions: string[];
 }
 
-export interface Quiz {
-  id: number,
-  moduleId: number,
-  questions: Question[];
+export type QuestionResponse = {
+  questionId: number;
+  selectedAnswerIds?: number[];
+  orderedAnswerIds?: number[];
+  matches?: Record<number, string>; // answerId -> matchText
+  numericAnswer?: number;
+  textAnswer?: string;
+  timeSpentSeconds?: number;
+}
+
+export type QuestionResult = {
+  questionId: number;
+  credit: number;
+  points: number;
+  earned: number;
+  correct: boolean;
 }
 
 export type QuizResult = {
-  score: number
-  totalQuestions: number
-  answers: Record<number, number> // questionId -> selectedOptionId
+  attemptNumber: number;
+  score: number;
+  maxScore: number;
+  scorePercent: number;
+  passed: boolean;
+  quizPassed: boolean;
+  attemptsRemaining?: number | null;
+  nextAttemptAt?: string | null;
+  results: QuestionResult[];
 }
 
 export interface Lesson {
@@ -31,6 +75,9 @@ export interface Lesson {
   content: string;
   videoUrl?: string | null;
   position: number;
+  scormPackageId?: number | null; // SCORM lessons launch the package instead of showing content
+  minWatchedPercent?: number | null; // share of the video to watch before completing
+  videoDurationSeconds?: number | null; // length of the video file, progress reports must match it
 }
 
 export interface Module {
@@ -50,6 +97,23 @@ export interface Course {
   numLearners: number;
   numCompleted: number;
   modules: Module[];
+  finalExam?: Quiz | null;
+  resume?: ResumePosition | null; // only when fetched with a learner id
+}
+
+export type ResumePosition = {
+  lessonId: number;
+  moduleId: number;
+  positionSeconds: number;
+}
+
+export type VideoProgress = {
+  lessonId: number;
+  resumePositionSeconds: number;
+  durationSeconds: number;
+  watchedSeconds: number;
+  watchedPercent: number | null;
+  minWatchedPercent: number | null;
 }
 
 export type Progress = {
@@ -65,6 +129,7 @@ export type CourseProgress = {
   completedModuleIds: number[];
   progressPercent: number;
   courseCompleted: boolean;
+  timeSpentSeconds: number;
 }
 
 export type CoursePayload = {
@@ -72,6 +137,7 @@ export type CoursePayload = {
   description: string;
   creatorId: string;
   modules: ModulePayload[];
+  finalExam?: QuizPayload;
 };
 
 export type ModulePayload = {
@@ -86,20 +152,41 @@ export type LessonPayload = {
   content: string;
   position: number;
   videoUrl?: string;
+  scormPackageId?: number;
+  minWatchedPercent?: number;
+  videoDurationSeconds?: number;
 };
 
 export type QuizPayload = {
   questions: QuestionPayload[];
+  drawRules?: DrawRulePayload[];
+  shuffleAnswers?: boolean;
+  passingScorePercent?: number;
+  maxAttempts?: number;
+  cooldownSeconds?: number;
+  scoringPolicy?: ScoringPolicy;
+};
+
+export type DrawRulePayload = {
+  pool?: string;
+  count: number;
 };
 
 export type QuestionPayload = {
   questionText: string;
+  pool?: string;
+  questionType?: QuestionType;
+  points?: number;
   answers: AnswerOptionPayload[];
+  numericAnswer?: number;
+  numericTolerance?: number;
+  caseSensitive?: boolean;
 };
 
 export type AnswerOptionPayload = {
   answerText: string;
   isCorrect: boolean;
+  matchText?: string;
 };
 
 export type CreatorCourseSummary = {
@@ -0,0 +1,35 @@
+export interface TotalStats {
+  learners: number;
+  creators: number;
+  courses: number;
+  enrollments: number;
+  completions: number;
+  learnerNfts: number;
+  creatorNfts: number;
+  activeLearners7d: number;
+  activeLearners30d: number;
+}
+
+export interface RangeStats {
+  from: string;
+  to: string;
+  newLearners: number;
+  newCreators: number;
+  newCourses: number;
+  enrollments: number;
+  completions: number;
+  learnerNftsMinted: number;
+  creatorNftsMinted: number;
+  activeLearners: number;
+}
+
+// Change from the previous range as a fraction, null when it had nothing
+export type RangeGrowth = { [K in Exclude<keyof RangeStats, 'from' | 'to'>]: number | null };
+
+export interface PlatformStats {
+  generatedAt: string;
+  totals: TotalStats;
+  range: RangeStats;
+  previousRange: RangeStats;
+  growth: RangeGrowth;
+}